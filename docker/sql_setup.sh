@@ -64,6 +64,9 @@ port = 5433
 ssl = on
 ssl_cert_file = 'server.crt'
 ssl_key_file = 'server.key'
+wal_level = logical
+max_wal_senders = 10
+max_replication_slots = 10
 EOCONF
 
 cat > "$PGDATA/pg_hba.conf" <<-EOCONF
@@ -84,6 +87,9 @@ host    all             ssl_user        ::0/0                reject
 host    all             postgres        0.0.0.0/0            trust
 # IPv6 local connections:
 host    all             postgres        ::0/0                trust
+# Replication connections:
+host    replication     postgres        0.0.0.0/0            trust
+host    replication     postgres        ::0/0                trust
 # Unix socket connections:
 local   all             postgres                             trust
 EOCONF
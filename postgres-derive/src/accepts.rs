@@ -0,0 +1,63 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Type;
+
+use crate::composites::Field;
+
+pub fn enum_body(name: &str) -> TokenStream {
+    // The set of labels is checked when values are actually converted so that a mismatch can be reported with the
+    // offending labels rather than as a generic type mismatch.
+    quote! {
+        if !type_.matches_name(#name) {
+            return false;
+        }
+
+        match *type_.kind() {
+            ::tokio_postgres::types::Kind::Enum(_) => true,
+            _ => false,
+        }
+    }
+}
+
+pub fn transparent_body(name: &str, trait_: TokenStream, inner: &Type) -> TokenStream {
+    quote! {
+        match *type_.kind() {
+            ::tokio_postgres::types::Kind::Domain(ref base) => {
+                type_.matches_name(#name) && <#inner as #trait_>::accepts(base)
+            }
+            _ => <#inner as #trait_>::accepts(type_),
+        }
+    }
+}
+
+pub fn composite_body(name: &str, trait_: TokenStream, fields: &[Field]) -> TokenStream {
+    let num_fields = fields.len();
+    let field_names = fields.iter().map(|f| &f.name);
+    let field_types = fields.iter().map(|f| &f.type_);
+
+    quote! {
+        if !type_.matches_name(#name) {
+            return false;
+        }
+
+        match *type_.kind() {
+            ::tokio_postgres::types::Kind::Composite(ref fields) => {
+                if fields.len() != #num_fields {
+                    return false;
+                }
+
+                fields.iter().all(|f| {
+                    match f.name() {
+                        #(
+                            #field_names => {
+                                <#field_types as #trait_>::accepts(f.type_())
+                            }
+                        )*
+                        _ => false,
+                    }
+                })
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields};
+
+use crate::overrides::Overrides;
+
+pub fn expand_derive_fromrow(input: DeriveInput) -> Result<TokenStream, Error> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input,
+                    "#[derive(FromRow)] may only be applied to structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input,
+                "#[derive(FromRow)] may only be applied to structs",
+            ))
+        }
+    };
+
+    let mut inits = vec![];
+    for field in fields {
+        let overrides = Overrides::extract(&field.attrs)?;
+        if let Some(path) = &overrides.transparent {
+            return Err(Error::new_spanned(
+                path,
+                "#[postgres(transparent)] may not be applied to #[derive(FromRow)] fields",
+            ));
+        }
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+
+        let init = if overrides.flatten {
+            if overrides.name.is_some() {
+                return Err(Error::new_spanned(
+                    field,
+                    "#[postgres(flatten)] fields cannot be renamed",
+                ));
+            }
+
+            quote! {
+                #ident: <#ty as ::tokio_postgres::FromRow>::from_row(row)?
+            }
+        } else {
+            let name = overrides.name.unwrap_or_else(|| ident.to_string());
+            quote! {
+                #ident: row.try_get::<_, #ty>(#name)?
+            }
+        };
+        inits.push(init);
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let out = quote! {
+        impl #impl_generics ::tokio_postgres::FromRow for #ident #ty_generics #where_clause {
+            fn from_row(row: &::tokio_postgres::Row)
+                        -> ::std::result::Result<Self, ::tokio_postgres::Error> {
+                ::std::result::Result::Ok(#ident {
+                    #(#inits,)*
+                })
+            }
+        }
+    };
+
+    Ok(out)
+}
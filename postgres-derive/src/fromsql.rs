@@ -0,0 +1,171 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use std::iter;
+use syn::{Data, DataStruct, DeriveInput, Error, Fields, Ident};
+
+use crate::accepts;
+use crate::composites::Field;
+use crate::enums::Variant;
+use crate::overrides::Overrides;
+use crate::transparent::Newtype;
+
+pub fn expand_derive_fromsql(input: DeriveInput) -> Result<TokenStream, Error> {
+    let overrides = Overrides::extract(&input.attrs)?;
+
+    let name = overrides.name.unwrap_or_else(|| input.ident.to_string());
+
+    let (accepts_body, from_sql_body) = match input.data {
+        Data::Enum(ref data) => {
+            let variants = data
+                .variants
+                .iter()
+                .map(Variant::parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            (
+                accepts::enum_body(&name),
+                enum_body(&input.ident, &variants),
+            )
+        }
+        Data::Struct(ref data) if overrides.transparent.is_some() => {
+            let newtype = Newtype::parse(&input, data)?;
+            (
+                accepts::transparent_body(
+                    &name,
+                    quote!(::tokio_postgres::types::FromSql<'a>),
+                    &newtype.ty,
+                ),
+                transparent_body(&input.ident, &newtype),
+            )
+        }
+        Data::Struct(DataStruct {
+            fields: Fields::Named(ref fields),
+            ..
+        }) => {
+            let fields = fields
+                .named
+                .iter()
+                .map(Field::parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            (
+                accepts::composite_body(
+                    &name,
+                    quote!(::tokio_postgres::types::FromSql<'a>),
+                    &fields,
+                ),
+                composite_body(&input.ident, &fields),
+            )
+        }
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "#[derive(FromSql)] may only be applied to enums, structs with named fields, and \
+                 #[postgres(transparent)] structs",
+            ));
+        }
+    };
+
+    let ident = &input.ident;
+    let out = quote! {
+        impl<'a> ::tokio_postgres::types::FromSql<'a> for #ident {
+            fn from_sql(type_: &::tokio_postgres::types::Type,
+                        buf: &'a [u8])
+                        -> ::std::result::Result<#ident,
+                                                 ::std::boxed::Box<dyn ::std::error::Error +
+                                                                   ::std::marker::Sync +
+                                                                   ::std::marker::Send>> {
+                #from_sql_body
+            }
+
+            fn accepts(type_: &::tokio_postgres::types::Type) -> bool {
+                #accepts_body
+            }
+        }
+    };
+
+    Ok(out)
+}
+
+fn enum_body(ident: &Ident, variants: &[Variant]) -> TokenStream {
+    let variant_names = variants.iter().map(|v| &v.name);
+    let idents = iter::repeat(ident);
+    let variant_idents = variants.iter().map(|v| &v.ident);
+    let known_names = variants.iter().map(|v| &v.name);
+
+    quote! {
+        match ::std::str::from_utf8(buf)? {
+            #(
+                #variant_names => ::std::result::Result::Ok(#idents::#variant_idents),
+            )*
+            s => {
+                ::std::result::Result::Err(
+                    ::tokio_postgres::types::__unknown_enum_label(type_, s, &[#(#known_names),*]),
+                )
+            }
+        }
+    }
+}
+
+fn transparent_body(ident: &Ident, newtype: &Newtype) -> TokenStream {
+    let member = &newtype.member;
+    let ty = &newtype.ty;
+
+    quote! {
+        let type_ = match *type_.kind() {
+            ::tokio_postgres::types::Kind::Domain(ref base) => base,
+            _ => type_,
+        };
+
+        <#ty as ::tokio_postgres::types::FromSql<'a>>::from_sql(type_, buf)
+            .map(|v| #ident { #member: v })
+    }
+}
+
+fn composite_body(ident: &Ident, fields: &[Field]) -> TokenStream {
+    let temp_vars = &fields
+        .iter()
+        .map(|f| Ident::new(&format!("__{}", f.ident), Span::call_site()))
+        .collect::<Vec<_>>();
+    let field_names = fields.iter().map(|f| &f.name);
+    let field_idents = fields.iter().map(|f| &f.ident);
+    let field_types = fields.iter().map(|f| &f.type_);
+
+    quote! {
+        let fields = match *type_.kind() {
+            ::tokio_postgres::types::Kind::Composite(ref fields) => fields,
+            _ => unreachable!(),
+        };
+
+        let values = ::tokio_postgres::types::__composite_values(buf)?;
+        if values.len() != fields.len() {
+            return ::std::result::Result::Err(
+                ::std::convert::Into::into(::std::format!("invalid field count: {} vs {}",
+                                                          values.len(),
+                                                          fields.len())));
+        }
+
+        #(
+            let mut #temp_vars = ::std::option::Option::None;
+        )*
+
+        for (field, value) in fields.iter().zip(values) {
+            match field.name() {
+                #(
+                    #field_names => {
+                        #temp_vars = ::std::option::Option::Some(
+                            <#field_types as ::tokio_postgres::types::FromSql<'a>>::from_sql_nullable(
+                                field.type_(),
+                                value,
+                            )?);
+                    }
+                )*
+                _ => unreachable!(),
+            }
+        }
+
+        ::std::result::Result::Ok(#ident {
+            #(
+                #field_idents: #temp_vars.unwrap(),
+            )*
+        })
+    }
+}
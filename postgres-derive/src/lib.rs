@@ -0,0 +1,43 @@
+//! An internal crate for `tokio-postgres` derives.
+//!
+//! This crate should not be used directly. Instead, enable the `derive` Cargo feature of `tokio-postgres` and use the
+//! `ToSql` and `FromSql` macros exported from its `types` module and the `FromRow` macro exported from its root.
+#![recursion_limit = "256"]
+#![warn(clippy::all, rust_2018_idioms)]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+
+mod accepts;
+mod composites;
+mod enums;
+mod fromrow;
+mod fromsql;
+mod overrides;
+mod tosql;
+mod transparent;
+
+#[proc_macro_derive(ToSql, attributes(postgres))]
+pub fn derive_tosql(input: TokenStream) -> TokenStream {
+    let input = syn::parse(input).unwrap();
+    tosql::expand_derive_tosql(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(FromSql, attributes(postgres))]
+pub fn derive_fromsql(input: TokenStream) -> TokenStream {
+    let input = syn::parse(input).unwrap();
+    fromsql::expand_derive_fromsql(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(FromRow, attributes(postgres))]
+pub fn derive_fromrow(input: TokenStream) -> TokenStream {
+    let input = syn::parse(input).unwrap();
+    fromrow::expand_derive_fromrow(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
@@ -0,0 +1,60 @@
+use syn::{Attribute, Error, Lit, Meta, NestedMeta, Path};
+
+pub struct Overrides {
+    pub name: Option<String>,
+    // the path is kept so that derives which don't support the override can point at it
+    pub transparent: Option<Path>,
+    pub flatten: bool,
+}
+
+impl Overrides {
+    pub fn extract(attrs: &[Attribute]) -> Result<Overrides, Error> {
+        let mut overrides = Overrides {
+            name: None,
+            transparent: None,
+            flatten: false,
+        };
+
+        for attr in attrs {
+            if !attr.path.is_ident("postgres") {
+                continue;
+            }
+
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                bad => return Err(Error::new_spanned(bad, "expected a #[postgres(...)]")),
+            };
+
+            for item in &list.nested {
+                match item {
+                    NestedMeta::Meta(Meta::NameValue(meta)) => {
+                        if !meta.path.is_ident("name") {
+                            return Err(Error::new_spanned(&meta.path, "unknown override"));
+                        }
+
+                        let value = match &meta.lit {
+                            Lit::Str(s) => s.value(),
+                            bad => {
+                                return Err(Error::new_spanned(bad, "expected a string literal"))
+                            }
+                        };
+
+                        overrides.name = Some(value);
+                    }
+                    NestedMeta::Meta(Meta::Path(path)) => {
+                        if path.is_ident("transparent") {
+                            overrides.transparent = Some(path.clone());
+                        } else if path.is_ident("flatten") {
+                            overrides.flatten = true;
+                        } else {
+                            return Err(Error::new_spanned(path, "unknown override"));
+                        }
+                    }
+                    bad => return Err(Error::new_spanned(bad, "unknown override")),
+                }
+            }
+        }
+
+        Ok(overrides)
+    }
+}
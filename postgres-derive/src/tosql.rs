@@ -0,0 +1,175 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use std::iter;
+use syn::{Data, DataStruct, DeriveInput, Error, Fields, Ident};
+
+use crate::accepts;
+use crate::composites::Field;
+use crate::enums::Variant;
+use crate::overrides::Overrides;
+use crate::transparent::Newtype;
+
+pub fn expand_derive_tosql(input: DeriveInput) -> Result<TokenStream, Error> {
+    let overrides = Overrides::extract(&input.attrs)?;
+
+    let name = overrides.name.unwrap_or_else(|| input.ident.to_string());
+
+    let (accepts_body, to_sql_body) = match input.data {
+        Data::Enum(ref data) => {
+            let variants = data
+                .variants
+                .iter()
+                .map(Variant::parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            (
+                accepts::enum_body(&name),
+                enum_body(&input.ident, &variants),
+            )
+        }
+        Data::Struct(ref data) if overrides.transparent.is_some() => {
+            let newtype = Newtype::parse(&input, data)?;
+            (
+                accepts::transparent_body(
+                    &name,
+                    quote!(::tokio_postgres::types::ToSql),
+                    &newtype.ty,
+                ),
+                transparent_body(&newtype),
+            )
+        }
+        Data::Struct(DataStruct {
+            fields: Fields::Named(ref fields),
+            ..
+        }) => {
+            let fields = fields
+                .named
+                .iter()
+                .map(Field::parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            (
+                accepts::composite_body(&name, quote!(::tokio_postgres::types::ToSql), &fields),
+                composite_body(&fields),
+            )
+        }
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "#[derive(ToSql)] may only be applied to enums, structs with named fields, and \
+                 #[postgres(transparent)] structs",
+            ));
+        }
+    };
+
+    let ident = &input.ident;
+    let out = quote! {
+        impl ::tokio_postgres::types::ToSql for #ident {
+            fn to_sql(&self,
+                      type_: &::tokio_postgres::types::Type,
+                      buf: &mut ::std::vec::Vec<u8>)
+                      -> ::std::result::Result<::tokio_postgres::types::IsNull,
+                                               ::std::boxed::Box<dyn ::std::error::Error +
+                                                                 ::std::marker::Sync +
+                                                                 ::std::marker::Send>> {
+                #to_sql_body
+            }
+
+            fn accepts(type_: &::tokio_postgres::types::Type) -> bool {
+                #accepts_body
+            }
+
+            ::tokio_postgres::to_sql_checked!();
+        }
+    };
+
+    Ok(out)
+}
+
+fn enum_body(ident: &Ident, variants: &[Variant]) -> TokenStream {
+    let idents = iter::repeat(ident);
+    let variant_idents = variants.iter().map(|v| &v.ident);
+    let variant_names = variants.iter().map(|v| &v.name);
+    let s = Ident::new("s", Span::call_site());
+
+    quote! {
+        let #s = match *self {
+            #(
+                #idents::#variant_idents => #variant_names,
+            )*
+        };
+
+        if let ::tokio_postgres::types::Kind::Enum(ref labels) = *type_.kind() {
+            if !labels.iter().any(|l| l == #s) {
+                let msg = ::std::format!(
+                    "label `{}` does not exist in enum type `{}`",
+                    #s,
+                    type_.name(),
+                );
+                return ::std::result::Result::Err(::std::convert::Into::into(msg));
+            }
+        }
+
+        buf.extend_from_slice(#s.as_bytes());
+        ::std::result::Result::Ok(::tokio_postgres::types::IsNull::No)
+    }
+}
+
+fn transparent_body(newtype: &Newtype) -> TokenStream {
+    let member = &newtype.member;
+    let ty = &newtype.ty;
+
+    quote! {
+        let type_ = match *type_.kind() {
+            ::tokio_postgres::types::Kind::Domain(ref base) => base,
+            _ => type_,
+        };
+
+        <#ty as ::tokio_postgres::types::ToSql>::to_sql(&self.#member, type_, buf)
+    }
+}
+
+fn composite_body(fields: &[Field]) -> TokenStream {
+    let field_names = fields.iter().map(|f| &f.name);
+    let field_idents = fields.iter().map(|f| &f.ident);
+
+    quote! {
+        let fields = match *type_.kind() {
+            ::tokio_postgres::types::Kind::Composite(ref fields) => fields,
+            _ => unreachable!(),
+        };
+
+        buf.extend_from_slice(&(fields.len() as i32).to_be_bytes());
+
+        for field in fields {
+            buf.extend_from_slice(&field.type_().oid().to_be_bytes());
+
+            let base = buf.len();
+            buf.extend_from_slice(&[0; 4]);
+            let r = match field.name() {
+                #(
+                    #field_names => {
+                        ::tokio_postgres::types::ToSql::to_sql(&self.#field_idents,
+                                                               field.type_(),
+                                                               buf)
+                    }
+                )*
+                _ => unreachable!(),
+            };
+
+            let count = match r? {
+                ::tokio_postgres::types::IsNull::Yes => -1,
+                ::tokio_postgres::types::IsNull::No => {
+                    let len = buf.len() - base - 4;
+                    if len > i32::max_value() as usize {
+                        return ::std::result::Result::Err(
+                            ::std::convert::Into::into("value too large to transmit"));
+                    }
+                    len as i32
+                },
+            };
+
+            buf[base..base + 4].copy_from_slice(&count.to_be_bytes());
+        }
+
+        ::std::result::Result::Ok(::tokio_postgres::types::IsNull::No)
+    }
+}
@@ -0,0 +1,29 @@
+use syn::{DataStruct, Error, Index, Member, Type};
+
+pub struct Newtype {
+    pub member: Member,
+    pub ty: Type,
+}
+
+impl Newtype {
+    pub fn parse(input: &syn::DeriveInput, data: &DataStruct) -> Result<Newtype, Error> {
+        let fields = data.fields.iter().collect::<Vec<_>>();
+        if fields.len() != 1 {
+            return Err(Error::new_spanned(
+                input,
+                "#[postgres(transparent)] may only be applied to structs with a single field",
+            ));
+        }
+
+        let field = fields[0];
+        let member = match field.ident {
+            Some(ref ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(0)),
+        };
+
+        Ok(Newtype {
+            member,
+            ty: field.ty.clone(),
+        })
+    }
+}
@@ -22,6 +22,7 @@ pub const DATA_ROW_TAG: u8 = b'D';
 pub const ERROR_RESPONSE_TAG: u8 = b'E';
 pub const COPY_IN_RESPONSE_TAG: u8 = b'G';
 pub const COPY_OUT_RESPONSE_TAG: u8 = b'H';
+pub const COPY_BOTH_RESPONSE_TAG: u8 = b'W';
 pub const EMPTY_QUERY_RESPONSE_TAG: u8 = b'I';
 pub const BACKEND_KEY_DATA_TAG: u8 = b'K';
 pub const NO_DATA_TAG: u8 = b'n';
@@ -92,6 +93,7 @@ pub enum Message {
     CopyDone,
     CopyInResponse(CopyInResponseBody),
     CopyOutResponse(CopyOutResponseBody),
+    CopyBothResponse(CopyBothResponseBody),
     DataRow(DataRowBody),
     EmptyQueryResponse,
     ErrorResponse(ErrorResponseBody),
@@ -191,6 +193,16 @@ impl Message {
                     storage,
                 })
             }
+            COPY_BOTH_RESPONSE_TAG => {
+                let format = buf.read_u8()?;
+                let len = buf.read_u16::<BigEndian>()?;
+                let storage = buf.read_all();
+                Message::CopyBothResponse(CopyBothResponseBody {
+                    format,
+                    len,
+                    storage,
+                })
+            }
             EMPTY_QUERY_RESPONSE_TAG => Message::EmptyQueryResponse,
             BACKEND_KEY_DATA_TAG => {
                 let process_id = buf.read_i32::<BigEndian>()?;
@@ -312,6 +324,20 @@ impl Buffer {
         }
     }
 
+    #[inline]
+    fn read_buf(&mut self, len: usize) -> io::Result<Bytes> {
+        if self.slice().len() < len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "unexpected EOF",
+            ));
+        }
+
+        let start = self.idx;
+        self.idx += len;
+        Ok(self.bytes.slice(start, self.idx))
+    }
+
     #[inline]
     fn read_all(&mut self) -> Bytes {
         let buf = self.bytes.slice_from(self.idx);
@@ -525,6 +551,27 @@ impl CopyOutResponseBody {
     }
 }
 
+pub struct CopyBothResponseBody {
+    storage: Bytes,
+    len: u16,
+    format: u8,
+}
+
+impl CopyBothResponseBody {
+    #[inline]
+    pub fn format(&self) -> u8 {
+        self.format
+    }
+
+    #[inline]
+    pub fn column_formats(&self) -> ColumnFormats<'_> {
+        ColumnFormats {
+            remaining: self.len,
+            buf: &self.storage,
+        }
+    }
+}
+
 pub struct DataRowBody {
     storage: Bytes,
     len: u16,
@@ -544,6 +591,11 @@ impl DataRowBody {
     pub fn buffer(&self) -> &[u8] {
         &self.storage
     }
+
+    #[inline]
+    pub fn buffer_bytes(&self) -> &Bytes {
+        &self.storage
+    }
 }
 
 pub struct DataRowRanges<'a> {
@@ -869,6 +921,703 @@ impl<'a> Field<'a> {
     }
 }
 
+pub const XLOG_DATA_TAG: u8 = b'w';
+pub const PRIMARY_KEEPALIVE_TAG: u8 = b'k';
+
+/// An enum representing the messages sent by the server in the `CopyData` messages of a streaming replication
+/// connection.
+pub enum ReplicationMessage<D> {
+    XLogData(XLogDataBody<D>),
+    PrimaryKeepAlive(PrimaryKeepAliveBody),
+    #[doc(hidden)]
+    __ForExtensibility,
+}
+
+impl ReplicationMessage<Bytes> {
+    #[inline]
+    pub fn parse(buf: &Bytes) -> io::Result<ReplicationMessage<Bytes>> {
+        let mut buf = Buffer {
+            bytes: buf.clone(),
+            idx: 0,
+        };
+
+        let tag = buf.read_u8()?;
+
+        let message = match tag {
+            XLOG_DATA_TAG => {
+                let wal_start = buf.read_u64::<BigEndian>()?;
+                let wal_end = buf.read_u64::<BigEndian>()?;
+                let timestamp = buf.read_i64::<BigEndian>()?;
+                let data = buf.read_all();
+                ReplicationMessage::XLogData(XLogDataBody {
+                    wal_start,
+                    wal_end,
+                    timestamp,
+                    data,
+                })
+            }
+            PRIMARY_KEEPALIVE_TAG => {
+                let wal_end = buf.read_u64::<BigEndian>()?;
+                let timestamp = buf.read_i64::<BigEndian>()?;
+                let reply = buf.read_u8()?;
+                ReplicationMessage::PrimaryKeepAlive(PrimaryKeepAliveBody {
+                    wal_end,
+                    timestamp,
+                    reply,
+                })
+            }
+            tag => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown replication message tag `{}`", tag),
+                ));
+            }
+        };
+
+        Ok(message)
+    }
+}
+
+pub struct XLogDataBody<D> {
+    wal_start: u64,
+    wal_end: u64,
+    timestamp: i64,
+    data: D,
+}
+
+impl<D> XLogDataBody<D> {
+    #[inline]
+    pub fn wal_start(&self) -> u64 {
+        self.wal_start
+    }
+
+    #[inline]
+    pub fn wal_end(&self) -> u64 {
+        self.wal_end
+    }
+
+    #[inline]
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    #[inline]
+    pub fn data(&self) -> &D {
+        &self.data
+    }
+
+    #[inline]
+    pub fn into_data(self) -> D {
+        self.data
+    }
+
+    /// Converts the data of the message, e.g. by decoding it as a `LogicalReplicationMessage`.
+    pub fn map_data<F, D2, E>(self, f: F) -> Result<XLogDataBody<D2>, E>
+    where
+        F: FnOnce(D) -> Result<D2, E>,
+    {
+        let data = f(self.data)?;
+        Ok(XLogDataBody {
+            wal_start: self.wal_start,
+            wal_end: self.wal_end,
+            timestamp: self.timestamp,
+            data,
+        })
+    }
+}
+
+pub struct PrimaryKeepAliveBody {
+    wal_end: u64,
+    timestamp: i64,
+    reply: u8,
+}
+
+impl PrimaryKeepAliveBody {
+    #[inline]
+    pub fn wal_end(&self) -> u64 {
+        self.wal_end
+    }
+
+    #[inline]
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// Returns 1 if the server requests an immediate status update, and 0 otherwise.
+    #[inline]
+    pub fn reply(&self) -> u8 {
+        self.reply
+    }
+}
+
+pub const BEGIN_TAG: u8 = b'B';
+pub const COMMIT_TAG: u8 = b'C';
+pub const ORIGIN_TAG: u8 = b'O';
+pub const RELATION_TAG: u8 = b'R';
+pub const TYPE_TAG: u8 = b'Y';
+pub const INSERT_TAG: u8 = b'I';
+pub const UPDATE_TAG: u8 = b'U';
+pub const DELETE_TAG: u8 = b'D';
+pub const TRUNCATE_TAG: u8 = b'T';
+pub const MESSAGE_TAG: u8 = b'M';
+pub const TUPLE_NEW_TAG: u8 = b'N';
+pub const TUPLE_KEY_TAG: u8 = b'K';
+pub const TUPLE_OLD_TAG: u8 = b'O';
+pub const TUPLE_DATA_NULL_TAG: u8 = b'n';
+pub const TUPLE_DATA_TOAST_TAG: u8 = b'u';
+pub const TUPLE_DATA_TEXT_TAG: u8 = b't';
+pub const TUPLE_DATA_BINARY_TAG: u8 = b'b';
+
+pub const REPLICA_IDENTITY_DEFAULT_TAG: u8 = b'd';
+pub const REPLICA_IDENTITY_NOTHING_TAG: u8 = b'n';
+pub const REPLICA_IDENTITY_FULL_TAG: u8 = b'f';
+pub const REPLICA_IDENTITY_INDEX_TAG: u8 = b'i';
+
+/// An enum representing the messages of version 1 of the `pgoutput` logical decoding plugin's protocol, which are
+/// carried in the data of `XLogData` messages.
+pub enum LogicalReplicationMessage {
+    Begin(BeginBody),
+    Commit(CommitBody),
+    Origin(OriginBody),
+    Relation(RelationBody),
+    Type(TypeBody),
+    Insert(InsertBody),
+    Update(UpdateBody),
+    Delete(DeleteBody),
+    Truncate(TruncateBody),
+    Message(MessageBody),
+    #[doc(hidden)]
+    __ForExtensibility,
+}
+
+impl LogicalReplicationMessage {
+    #[inline]
+    pub fn parse(buf: &Bytes) -> io::Result<LogicalReplicationMessage> {
+        let mut buf = Buffer {
+            bytes: buf.clone(),
+            idx: 0,
+        };
+
+        let tag = buf.read_u8()?;
+
+        let message = match tag {
+            BEGIN_TAG => LogicalReplicationMessage::Begin(BeginBody {
+                final_lsn: buf.read_u64::<BigEndian>()?,
+                timestamp: buf.read_i64::<BigEndian>()?,
+                xid: buf.read_u32::<BigEndian>()?,
+            }),
+            COMMIT_TAG => LogicalReplicationMessage::Commit(CommitBody {
+                flags: buf.read_i8()?,
+                commit_lsn: buf.read_u64::<BigEndian>()?,
+                end_lsn: buf.read_u64::<BigEndian>()?,
+                timestamp: buf.read_i64::<BigEndian>()?,
+            }),
+            ORIGIN_TAG => LogicalReplicationMessage::Origin(OriginBody {
+                commit_lsn: buf.read_u64::<BigEndian>()?,
+                name: buf.read_cstr()?,
+            }),
+            RELATION_TAG => {
+                let rel_id = buf.read_u32::<BigEndian>()?;
+                let namespace = buf.read_cstr()?;
+                let name = buf.read_cstr()?;
+                let replica_identity = match buf.read_u8()? {
+                    REPLICA_IDENTITY_DEFAULT_TAG => ReplicaIdentity::Default,
+                    REPLICA_IDENTITY_NOTHING_TAG => ReplicaIdentity::Nothing,
+                    REPLICA_IDENTITY_FULL_TAG => ReplicaIdentity::Full,
+                    REPLICA_IDENTITY_INDEX_TAG => ReplicaIdentity::Index,
+                    tag => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("unknown replica identity tag `{}`", tag),
+                        ));
+                    }
+                };
+                let column_len = buf.read_u16::<BigEndian>()?;
+
+                // each column takes at least a byte, so the count is capped by what's left of the message
+                let mut columns =
+                    Vec::with_capacity(cmp::min(column_len as usize, buf.slice().len()));
+                for _ in 0..column_len {
+                    columns.push(Column::parse(&mut buf)?);
+                }
+
+                LogicalReplicationMessage::Relation(RelationBody {
+                    rel_id,
+                    namespace,
+                    name,
+                    replica_identity,
+                    columns,
+                })
+            }
+            TYPE_TAG => LogicalReplicationMessage::Type(TypeBody {
+                id: buf.read_u32::<BigEndian>()?,
+                namespace: buf.read_cstr()?,
+                name: buf.read_cstr()?,
+            }),
+            INSERT_TAG => {
+                let rel_id = buf.read_u32::<BigEndian>()?;
+                let tag = buf.read_u8()?;
+                if tag != TUPLE_NEW_TAG {
+                    return Err(unexpected_tuple_tag(tag));
+                }
+                let tuple = Tuple::parse(&mut buf)?;
+
+                LogicalReplicationMessage::Insert(InsertBody { rel_id, tuple })
+            }
+            UPDATE_TAG => {
+                let rel_id = buf.read_u32::<BigEndian>()?;
+                let mut key_tuple = None;
+                let mut old_tuple = None;
+
+                let mut tag = buf.read_u8()?;
+                match tag {
+                    TUPLE_KEY_TAG => key_tuple = Some(Tuple::parse(&mut buf)?),
+                    TUPLE_OLD_TAG => old_tuple = Some(Tuple::parse(&mut buf)?),
+                    _ => {}
+                }
+                if key_tuple.is_some() || old_tuple.is_some() {
+                    tag = buf.read_u8()?;
+                }
+                if tag != TUPLE_NEW_TAG {
+                    return Err(unexpected_tuple_tag(tag));
+                }
+                let new_tuple = Tuple::parse(&mut buf)?;
+
+                LogicalReplicationMessage::Update(UpdateBody {
+                    rel_id,
+                    key_tuple,
+                    old_tuple,
+                    new_tuple,
+                })
+            }
+            DELETE_TAG => {
+                let rel_id = buf.read_u32::<BigEndian>()?;
+                let mut key_tuple = None;
+                let mut old_tuple = None;
+
+                match buf.read_u8()? {
+                    TUPLE_KEY_TAG => key_tuple = Some(Tuple::parse(&mut buf)?),
+                    TUPLE_OLD_TAG => old_tuple = Some(Tuple::parse(&mut buf)?),
+                    tag => return Err(unexpected_tuple_tag(tag)),
+                }
+
+                LogicalReplicationMessage::Delete(DeleteBody {
+                    rel_id,
+                    key_tuple,
+                    old_tuple,
+                })
+            }
+            TRUNCATE_TAG => {
+                let relation_len = buf.read_u32::<BigEndian>()?;
+                let options = buf.read_i8()?;
+
+                let mut rel_ids =
+                    Vec::with_capacity(cmp::min(relation_len as usize, buf.slice().len() / 4));
+                for _ in 0..relation_len {
+                    rel_ids.push(buf.read_u32::<BigEndian>()?);
+                }
+
+                LogicalReplicationMessage::Truncate(TruncateBody { options, rel_ids })
+            }
+            MESSAGE_TAG => {
+                let flags = buf.read_i8()?;
+                let message_lsn = buf.read_u64::<BigEndian>()?;
+                let prefix = buf.read_cstr()?;
+                let len = buf.read_i32::<BigEndian>()?;
+                let content = buf.read_buf(len as usize)?;
+
+                LogicalReplicationMessage::Message(MessageBody {
+                    flags,
+                    message_lsn,
+                    prefix,
+                    content,
+                })
+            }
+            tag => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown logical replication message tag `{}`", tag),
+                ));
+            }
+        };
+
+        if !buf.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid message length",
+            ));
+        }
+
+        Ok(message)
+    }
+}
+
+pub struct BeginBody {
+    final_lsn: u64,
+    timestamp: i64,
+    xid: u32,
+}
+
+impl BeginBody {
+    #[inline]
+    pub fn final_lsn(&self) -> u64 {
+        self.final_lsn
+    }
+
+    #[inline]
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    #[inline]
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+}
+
+pub struct CommitBody {
+    flags: i8,
+    commit_lsn: u64,
+    end_lsn: u64,
+    timestamp: i64,
+}
+
+impl CommitBody {
+    #[inline]
+    pub fn flags(&self) -> i8 {
+        self.flags
+    }
+
+    #[inline]
+    pub fn commit_lsn(&self) -> u64 {
+        self.commit_lsn
+    }
+
+    #[inline]
+    pub fn end_lsn(&self) -> u64 {
+        self.end_lsn
+    }
+
+    #[inline]
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+pub struct OriginBody {
+    commit_lsn: u64,
+    name: Bytes,
+}
+
+impl OriginBody {
+    #[inline]
+    pub fn commit_lsn(&self) -> u64 {
+        self.commit_lsn
+    }
+
+    #[inline]
+    pub fn name(&self) -> io::Result<&str> {
+        get_str(&self.name)
+    }
+}
+
+/// The replica identity setting of a relation, which determines the old values included in its updates and deletes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReplicaIdentity {
+    /// The values of the primary key columns, if any.
+    Default,
+    /// No old values.
+    Nothing,
+    /// The values of all columns.
+    Full,
+    /// The values of the columns of a specific index.
+    Index,
+}
+
+pub struct RelationBody {
+    rel_id: Oid,
+    namespace: Bytes,
+    name: Bytes,
+    replica_identity: ReplicaIdentity,
+    columns: Vec<Column>,
+}
+
+impl RelationBody {
+    #[inline]
+    pub fn rel_id(&self) -> Oid {
+        self.rel_id
+    }
+
+    #[inline]
+    pub fn namespace(&self) -> io::Result<&str> {
+        get_str(&self.namespace)
+    }
+
+    #[inline]
+    pub fn name(&self) -> io::Result<&str> {
+        get_str(&self.name)
+    }
+
+    #[inline]
+    pub fn replica_identity(&self) -> ReplicaIdentity {
+        self.replica_identity
+    }
+
+    #[inline]
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+}
+
+pub struct Column {
+    flags: i8,
+    name: Bytes,
+    type_id: Oid,
+    type_modifier: i32,
+}
+
+impl Column {
+    #[inline]
+    fn parse(buf: &mut Buffer) -> io::Result<Column> {
+        Ok(Column {
+            flags: buf.read_i8()?,
+            name: buf.read_cstr()?,
+            type_id: buf.read_u32::<BigEndian>()?,
+            type_modifier: buf.read_i32::<BigEndian>()?,
+        })
+    }
+
+    /// Returns 1 if the column is part of the relation's replica identity key, and 0 otherwise.
+    #[inline]
+    pub fn flags(&self) -> i8 {
+        self.flags
+    }
+
+    #[inline]
+    pub fn name(&self) -> io::Result<&str> {
+        get_str(&self.name)
+    }
+
+    #[inline]
+    pub fn type_id(&self) -> Oid {
+        self.type_id
+    }
+
+    #[inline]
+    pub fn type_modifier(&self) -> i32 {
+        self.type_modifier
+    }
+}
+
+pub struct TypeBody {
+    id: Oid,
+    namespace: Bytes,
+    name: Bytes,
+}
+
+impl TypeBody {
+    #[inline]
+    pub fn id(&self) -> Oid {
+        self.id
+    }
+
+    #[inline]
+    pub fn namespace(&self) -> io::Result<&str> {
+        get_str(&self.namespace)
+    }
+
+    #[inline]
+    pub fn name(&self) -> io::Result<&str> {
+        get_str(&self.name)
+    }
+}
+
+pub struct InsertBody {
+    rel_id: Oid,
+    tuple: Tuple,
+}
+
+impl InsertBody {
+    #[inline]
+    pub fn rel_id(&self) -> Oid {
+        self.rel_id
+    }
+
+    #[inline]
+    pub fn tuple(&self) -> &Tuple {
+        &self.tuple
+    }
+}
+
+pub struct UpdateBody {
+    rel_id: Oid,
+    key_tuple: Option<Tuple>,
+    old_tuple: Option<Tuple>,
+    new_tuple: Tuple,
+}
+
+impl UpdateBody {
+    #[inline]
+    pub fn rel_id(&self) -> Oid {
+        self.rel_id
+    }
+
+    /// Returns the old values of the relation's replica identity key, if the update changed it.
+    #[inline]
+    pub fn key_tuple(&self) -> Option<&Tuple> {
+        self.key_tuple.as_ref()
+    }
+
+    /// Returns the old values of all columns, if the relation's replica identity is `Full`.
+    #[inline]
+    pub fn old_tuple(&self) -> Option<&Tuple> {
+        self.old_tuple.as_ref()
+    }
+
+    #[inline]
+    pub fn new_tuple(&self) -> &Tuple {
+        &self.new_tuple
+    }
+}
+
+pub struct DeleteBody {
+    rel_id: Oid,
+    key_tuple: Option<Tuple>,
+    old_tuple: Option<Tuple>,
+}
+
+impl DeleteBody {
+    #[inline]
+    pub fn rel_id(&self) -> Oid {
+        self.rel_id
+    }
+
+    /// Returns the old values of the relation's replica identity key, unless its replica identity is `Full`.
+    #[inline]
+    pub fn key_tuple(&self) -> Option<&Tuple> {
+        self.key_tuple.as_ref()
+    }
+
+    /// Returns the old values of all columns, if the relation's replica identity is `Full`.
+    #[inline]
+    pub fn old_tuple(&self) -> Option<&Tuple> {
+        self.old_tuple.as_ref()
+    }
+}
+
+pub const TRUNCATE_CASCADE: i8 = 1;
+pub const TRUNCATE_RESTART_IDENTITY: i8 = 2;
+
+pub struct TruncateBody {
+    options: i8,
+    rel_ids: Vec<Oid>,
+}
+
+impl TruncateBody {
+    /// Returns the options of the `TRUNCATE`, a combination of `TRUNCATE_CASCADE` and `TRUNCATE_RESTART_IDENTITY`.
+    #[inline]
+    pub fn options(&self) -> i8 {
+        self.options
+    }
+
+    #[inline]
+    pub fn rel_ids(&self) -> &[Oid] {
+        &self.rel_ids
+    }
+}
+
+pub const MESSAGE_TRANSACTIONAL: i8 = 1;
+
+/// A message written with `pg_logical_emit_message`, which is only sent if the `messages` option is enabled.
+pub struct MessageBody {
+    flags: i8,
+    message_lsn: u64,
+    prefix: Bytes,
+    content: Bytes,
+}
+
+impl MessageBody {
+    /// Returns the flags of the message, which include `MESSAGE_TRANSACTIONAL` if it was part of a transaction.
+    #[inline]
+    pub fn flags(&self) -> i8 {
+        self.flags
+    }
+
+    #[inline]
+    pub fn message_lsn(&self) -> u64 {
+        self.message_lsn
+    }
+
+    #[inline]
+    pub fn prefix(&self) -> io::Result<&str> {
+        get_str(&self.prefix)
+    }
+
+    #[inline]
+    pub fn content(&self) -> &Bytes {
+        &self.content
+    }
+}
+
+pub struct Tuple(Vec<TupleData>);
+
+impl Tuple {
+    #[inline]
+    fn parse(buf: &mut Buffer) -> io::Result<Tuple> {
+        let column_len = buf.read_u16::<BigEndian>()?;
+
+        // each value takes at least a byte, so the count is capped by what's left of the message
+        let mut data = Vec::with_capacity(cmp::min(column_len as usize, buf.slice().len()));
+        for _ in 0..column_len {
+            let value = match buf.read_u8()? {
+                TUPLE_DATA_NULL_TAG => TupleData::Null,
+                TUPLE_DATA_TOAST_TAG => TupleData::UnchangedToast,
+                TUPLE_DATA_TEXT_TAG => {
+                    let len = buf.read_i32::<BigEndian>()?;
+                    TupleData::Text(buf.read_buf(len as usize)?)
+                }
+                TUPLE_DATA_BINARY_TAG => {
+                    let len = buf.read_i32::<BigEndian>()?;
+                    TupleData::Binary(buf.read_buf(len as usize)?)
+                }
+                tag => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("unknown tuple data tag `{}`", tag),
+                    ));
+                }
+            };
+            data.push(value);
+        }
+
+        Ok(Tuple(data))
+    }
+
+    #[inline]
+    pub fn tuple_data(&self) -> &[TupleData] {
+        &self.0
+    }
+}
+
+/// The value of a column in a `Tuple`.
+pub enum TupleData {
+    Null,
+    /// A TOASTed value which was not changed by an update, and whose actual value is not sent.
+    UnchangedToast,
+    /// A value in the text format.
+    Text(Bytes),
+    /// A value in the binary format.
+    Binary(Bytes),
+}
+
+#[inline]
+fn unexpected_tuple_tag(tag: u8) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("unexpected tuple tag `{}`", tag),
+    )
+}
+
 #[inline]
 fn find_null(buf: &[u8], start: usize) -> io::Result<usize> {
     match memchr(0, &buf[start..]) {
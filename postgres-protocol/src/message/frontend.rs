@@ -2,6 +2,7 @@
 #![allow(missing_docs)]
 
 use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+use bytes::buf::Chain;
 use bytes::{Buf, BufMut, BytesMut, IntoBuf};
 use std::convert::TryFrom;
 use std::error::Error;
@@ -297,6 +298,13 @@ where
         out.put_i32_be(self.len);
         out.put(self.buf);
     }
+
+    /// Returns a buffer of the serialized message which refers to the data rather than copying it.
+    pub fn into_buf(self) -> Chain<io::Cursor<[u8; 5]>, T> {
+        let mut header = [b'd', 0, 0, 0, 0];
+        BigEndian::write_i32(&mut header[1..], self.len);
+        io::Cursor::new(header).chain(self.buf)
+    }
 }
 
 #[inline]
@@ -382,6 +390,28 @@ pub fn ssl_request(buf: &mut Vec<u8>) {
     write_body(buf, |buf| buf.write_i32::<BigEndian>(80_877_103)).unwrap();
 }
 
+/// Writes the payload of a standby status update, which is sent to the server in a `CopyData` message during
+/// streaming replication.
+///
+/// The positions are the WAL locations which have been written, flushed to disk, and applied by the client, and the
+/// timestamp is in microseconds since midnight on 2000-01-01.
+#[inline]
+pub fn standby_status_update(
+    write_lsn: u64,
+    flush_lsn: u64,
+    apply_lsn: u64,
+    timestamp: i64,
+    reply: bool,
+    buf: &mut Vec<u8>,
+) {
+    buf.push(b'r');
+    buf.write_u64::<BigEndian>(write_lsn).unwrap();
+    buf.write_u64::<BigEndian>(flush_lsn).unwrap();
+    buf.write_u64::<BigEndian>(apply_lsn).unwrap();
+    buf.write_i64::<BigEndian>(timestamp).unwrap();
+    buf.push(reply as u8);
+}
+
 #[inline]
 pub fn startup_message<'a, I>(parameters: I, buf: &mut Vec<u8>) -> io::Result<()>
 where
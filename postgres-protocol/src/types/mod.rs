@@ -141,6 +141,22 @@ pub fn int8_from_sql(mut buf: &[u8]) -> Result<i64, StdBox<dyn Error + Sync + Se
     Ok(v)
 }
 
+/// Serializes a `PG_LSN` value.
+#[inline]
+pub fn pg_lsn_to_sql(v: u64, buf: &mut Vec<u8>) {
+    buf.write_u64::<BigEndian>(v).unwrap();
+}
+
+/// Deserializes a `PG_LSN` value.
+#[inline]
+pub fn pg_lsn_from_sql(mut buf: &[u8]) -> Result<u64, StdBox<dyn Error + Sync + Send>> {
+    let v = buf.read_u64::<BigEndian>()?;
+    if !buf.is_empty() {
+        return Err("invalid buffer size".into());
+    }
+    Ok(v)
+}
+
 /// Serializes a `FLOAT4` value.
 #[inline]
 pub fn float4_to_sql(v: f32, buf: &mut Vec<u8>) {
@@ -386,7 +402,7 @@ pub fn date_from_sql(mut buf: &[u8]) -> Result<i32, StdBox<dyn Error + Sync + Se
     Ok(v)
 }
 
-/// Serializes a `TIME` or `TIMETZ` value.
+/// Serializes a `TIME` value.
 ///
 /// The value should represent the number of microseconds since midnight.
 #[inline]
@@ -394,7 +410,7 @@ pub fn time_to_sql(v: i64, buf: &mut Vec<u8>) {
     buf.write_i64::<BigEndian>(v).unwrap();
 }
 
-/// Deserializes a `TIME` or `TIMETZ` value.
+/// Deserializes a `TIME` value.
 ///
 /// The value represents the number of microseconds since midnight.
 #[inline]
@@ -406,6 +422,30 @@ pub fn time_from_sql(mut buf: &[u8]) -> Result<i64, StdBox<dyn Error + Sync + Se
     Ok(v)
 }
 
+/// Serializes a `TIMETZ` value.
+///
+/// The time should represent the number of microseconds since midnight, and the zone the offset from UTC in seconds,
+/// with positive values west of Greenwich, as Postgres represents it internally.
+#[inline]
+pub fn timetz_to_sql(time: i64, zone: i32, buf: &mut Vec<u8>) {
+    buf.write_i64::<BigEndian>(time).unwrap();
+    buf.write_i32::<BigEndian>(zone).unwrap();
+}
+
+/// Deserializes a `TIMETZ` value.
+///
+/// The first value represents the number of microseconds since midnight, and the second the offset from UTC in
+/// seconds, with positive values west of Greenwich.
+#[inline]
+pub fn timetz_from_sql(mut buf: &[u8]) -> Result<(i64, i32), StdBox<dyn Error + Sync + Send>> {
+    let time = buf.read_i64::<BigEndian>()?;
+    let zone = buf.read_i32::<BigEndian>()?;
+    if !buf.is_empty() {
+        return Err("invalid message length".into());
+    }
+    Ok((time, zone))
+}
+
 /// Serializes a `MACADDR` value.
 #[inline]
 pub fn macaddr_to_sql(v: [u8; 6], buf: &mut Vec<u8>) {
@@ -1044,3 +1084,376 @@ impl Inet {
         self.netmask
     }
 }
+
+/// Serializes a composite or record value.
+///
+/// Each field is provided along with the OID of its type.
+#[inline]
+pub fn record_to_sql<T, I, F>(
+    fields: I,
+    mut serializer: F,
+    buf: &mut Vec<u8>,
+) -> Result<(), StdBox<dyn Error + Sync + Send>>
+where
+    I: IntoIterator<Item = (Oid, T)>,
+    F: FnMut(T, &mut Vec<u8>) -> Result<IsNull, StdBox<dyn Error + Sync + Send>>,
+{
+    let count_idx = buf.len();
+    buf.extend_from_slice(&[0; 4]);
+
+    let mut count = 0;
+    for (oid, field) in fields {
+        count += 1;
+        buf.write_u32::<BigEndian>(oid).unwrap();
+        write_nullable(|buf| serializer(field, buf), buf)?;
+    }
+
+    let count = i32::from_usize(count)?;
+    BigEndian::write_i32(&mut buf[count_idx..], count);
+
+    Ok(())
+}
+
+/// Deserializes a composite or record value.
+#[inline]
+pub fn record_from_sql<'a>(
+    mut buf: &'a [u8],
+) -> Result<Record<'a>, StdBox<dyn Error + Sync + Send>> {
+    let len = buf.read_i32::<BigEndian>()?;
+    if len < 0 {
+        return Err("invalid field count".into());
+    }
+
+    Ok(Record { len, buf })
+}
+
+/// A Postgres composite or record value.
+pub struct Record<'a> {
+    len: i32,
+    buf: &'a [u8],
+}
+
+impl<'a> Record<'a> {
+    /// Returns the number of fields in the record.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Determines if the record has no fields.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the fields of the record.
+    #[inline]
+    pub fn fields(&self) -> RecordFields<'a> {
+        RecordFields {
+            remaining: self.len,
+            buf: self.buf,
+        }
+    }
+}
+
+/// An iterator over the fields of a record.
+///
+/// Each field is returned as the OID of its type along with its raw value.
+pub struct RecordFields<'a> {
+    remaining: i32,
+    buf: &'a [u8],
+}
+
+impl<'a> FallibleIterator for RecordFields<'a> {
+    type Item = (Oid, Option<&'a [u8]>);
+    type Error = StdBox<dyn Error + Sync + Send>;
+
+    #[inline]
+    fn next(&mut self) -> Result<Option<(Oid, Option<&'a [u8]>)>, StdBox<dyn Error + Sync + Send>> {
+        if self.remaining == 0 {
+            if !self.buf.is_empty() {
+                return Err("invalid message length".into());
+            }
+            return Ok(None);
+        }
+        self.remaining -= 1;
+
+        let oid = self.buf.read_u32::<BigEndian>()?;
+        let len = self.buf.read_i32::<BigEndian>()?;
+        let val = if len < 0 {
+            None
+        } else {
+            if self.buf.len() < len as usize {
+                return Err("invalid value length".into());
+            }
+
+            let (val, buf) = self.buf.split_at(len as usize);
+            self.buf = buf;
+            Some(val)
+        };
+
+        Ok(Some((oid, val)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.remaining as usize;
+        (len, Some(len))
+    }
+}
+
+/// The sign of a `NUMERIC` value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NumericSign {
+    /// A positive value (or zero).
+    Positive,
+    /// A negative value.
+    Negative,
+    /// Not-a-number.
+    NaN,
+    /// Positive infinity.
+    PositiveInfinity,
+    /// Negative infinity.
+    NegativeInfinity,
+}
+
+const NUMERIC_POS: u16 = 0x0000;
+const NUMERIC_NEG: u16 = 0x4000;
+const NUMERIC_NAN: u16 = 0xC000;
+const NUMERIC_PINF: u16 = 0xD000;
+const NUMERIC_NINF: u16 = 0xF000;
+
+/// Serializes a `NUMERIC` value.
+///
+/// `digits` are base-10000 digits, the first of which is multiplied by 10000^`weight`. `scale` is the number of
+/// decimal digits after the decimal point to display.
+#[inline]
+pub fn numeric_to_sql<I>(
+    sign: NumericSign,
+    weight: i16,
+    scale: u16,
+    digits: I,
+    buf: &mut Vec<u8>,
+) -> Result<(), StdBox<dyn Error + Sync + Send>>
+where
+    I: IntoIterator<Item = i16>,
+{
+    let count_idx = buf.len();
+    buf.extend_from_slice(&[0; 2]);
+    buf.write_i16::<BigEndian>(weight).unwrap();
+    let sign = match sign {
+        NumericSign::Positive => NUMERIC_POS,
+        NumericSign::Negative => NUMERIC_NEG,
+        NumericSign::NaN => NUMERIC_NAN,
+        NumericSign::PositiveInfinity => NUMERIC_PINF,
+        NumericSign::NegativeInfinity => NUMERIC_NINF,
+    };
+    buf.write_u16::<BigEndian>(sign).unwrap();
+    buf.write_u16::<BigEndian>(scale).unwrap();
+
+    let mut count = 0;
+    for digit in digits {
+        if digit < 0 || digit >= 10_000 {
+            return Err("invalid NUMERIC digit".into());
+        }
+        count += 1;
+        buf.write_i16::<BigEndian>(digit).unwrap();
+    }
+
+    if count > i16::max_value() as usize {
+        return Err("too many NUMERIC digits".into());
+    }
+    BigEndian::write_i16(&mut buf[count_idx..], count as i16);
+
+    Ok(())
+}
+
+/// Deserializes a `NUMERIC` value.
+#[inline]
+pub fn numeric_from_sql<'a>(
+    mut buf: &'a [u8],
+) -> Result<Numeric<'a>, StdBox<dyn Error + Sync + Send>> {
+    let count = buf.read_i16::<BigEndian>()?;
+    if count < 0 {
+        return Err("invalid digit count".into());
+    }
+    let weight = buf.read_i16::<BigEndian>()?;
+    let sign = match buf.read_u16::<BigEndian>()? {
+        NUMERIC_POS => NumericSign::Positive,
+        NUMERIC_NEG => NumericSign::Negative,
+        NUMERIC_NAN => NumericSign::NaN,
+        NUMERIC_PINF => NumericSign::PositiveInfinity,
+        NUMERIC_NINF => NumericSign::NegativeInfinity,
+        _ => return Err("invalid NUMERIC sign".into()),
+    };
+    let scale = buf.read_u16::<BigEndian>()?;
+
+    if buf.len() != count as usize * 2 {
+        return Err("invalid buffer size".into());
+    }
+
+    Ok(Numeric {
+        sign,
+        weight,
+        scale,
+        digits: buf,
+    })
+}
+
+/// A Postgres `NUMERIC` value.
+pub struct Numeric<'a> {
+    sign: NumericSign,
+    weight: i16,
+    scale: u16,
+    digits: &'a [u8],
+}
+
+impl<'a> Numeric<'a> {
+    /// Returns the sign of the value.
+    #[inline]
+    pub fn sign(&self) -> NumericSign {
+        self.sign
+    }
+
+    /// Returns the weight of the first digit, as a power of 10000.
+    #[inline]
+    pub fn weight(&self) -> i16 {
+        self.weight
+    }
+
+    /// Returns the number of decimal digits after the decimal point to display.
+    #[inline]
+    pub fn scale(&self) -> u16 {
+        self.scale
+    }
+
+    /// Returns an iterator over the base-10000 digits of the value.
+    #[inline]
+    pub fn digits(&self) -> NumericDigits<'a> {
+        NumericDigits(self.digits)
+    }
+}
+
+/// An iterator over the base-10000 digits of a `NUMERIC` value.
+pub struct NumericDigits<'a>(&'a [u8]);
+
+impl<'a> FallibleIterator for NumericDigits<'a> {
+    type Item = i16;
+    type Error = StdBox<dyn Error + Sync + Send>;
+
+    #[inline]
+    fn next(&mut self) -> Result<Option<i16>, StdBox<dyn Error + Sync + Send>> {
+        if self.0.is_empty() {
+            return Ok(None);
+        }
+
+        let digit = self.0.read_i16::<BigEndian>()?;
+        if digit < 0 || digit >= 10_000 {
+            return Err("invalid NUMERIC digit".into());
+        }
+
+        Ok(Some(digit))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.len() / 2;
+        (len, Some(len))
+    }
+}
+
+/// Serializes a `TXID_SNAPSHOT` or `PG_SNAPSHOT` value.
+#[inline]
+pub fn snapshot_to_sql<I>(
+    xmin: u64,
+    xmax: u64,
+    xip: I,
+    buf: &mut Vec<u8>,
+) -> Result<(), StdBox<dyn Error + Sync + Send>>
+where
+    I: IntoIterator<Item = u64>,
+{
+    let count_idx = buf.len();
+    buf.extend_from_slice(&[0; 4]);
+    buf.write_u64::<BigEndian>(xmin).unwrap();
+    buf.write_u64::<BigEndian>(xmax).unwrap();
+
+    let mut count = 0;
+    for xid in xip {
+        count += 1;
+        buf.write_u64::<BigEndian>(xid).unwrap();
+    }
+
+    let count = i32::from_usize(count)?;
+    BigEndian::write_i32(&mut buf[count_idx..], count);
+
+    Ok(())
+}
+
+/// Deserializes a `TXID_SNAPSHOT` or `PG_SNAPSHOT` value.
+#[inline]
+pub fn snapshot_from_sql<'a>(
+    mut buf: &'a [u8],
+) -> Result<Snapshot<'a>, StdBox<dyn Error + Sync + Send>> {
+    let len = buf.read_i32::<BigEndian>()?;
+    if len < 0 {
+        return Err("invalid xip count".into());
+    }
+    let xmin = buf.read_u64::<BigEndian>()?;
+    let xmax = buf.read_u64::<BigEndian>()?;
+    if buf.len() != len as usize * 8 {
+        return Err("invalid message length".into());
+    }
+
+    Ok(Snapshot { xmin, xmax, buf })
+}
+
+/// A Postgres transaction snapshot.
+pub struct Snapshot<'a> {
+    xmin: u64,
+    xmax: u64,
+    buf: &'a [u8],
+}
+
+impl<'a> Snapshot<'a> {
+    /// Returns the earliest transaction ID that is still active.
+    #[inline]
+    pub fn xmin(&self) -> u64 {
+        self.xmin
+    }
+
+    /// Returns the first as-yet-unassigned transaction ID.
+    #[inline]
+    pub fn xmax(&self) -> u64 {
+        self.xmax
+    }
+
+    /// Returns an iterator over the transaction IDs in progress at the time of the snapshot.
+    #[inline]
+    pub fn xip(&self) -> SnapshotXip<'a> {
+        SnapshotXip(self.buf)
+    }
+}
+
+/// An iterator over the in-progress transaction IDs of a snapshot.
+pub struct SnapshotXip<'a>(&'a [u8]);
+
+impl<'a> FallibleIterator for SnapshotXip<'a> {
+    type Item = u64;
+    type Error = StdBox<dyn Error + Sync + Send>;
+
+    #[inline]
+    fn next(&mut self) -> Result<Option<u64>, StdBox<dyn Error + Sync + Send>> {
+        if self.0.is_empty() {
+            return Ok(None);
+        }
+
+        self.0.read_u64::<BigEndian>().map(Some).map_err(Into::into)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.len() / 8;
+        (len, Some(len))
+    }
+}
@@ -2,7 +2,7 @@ use fallible_iterator::FallibleIterator;
 use std::collections::HashMap;
 
 use super::*;
-use crate::IsNull;
+use crate::{IsNull, Oid};
 
 #[test]
 fn bool() {
@@ -36,6 +36,14 @@ fn int8() {
     assert_eq!(int8_from_sql(&buf).unwrap(), 0x0102_0304_0506_0708);
 }
 
+#[test]
+fn pg_lsn() {
+    let mut buf = vec![];
+    pg_lsn_to_sql(0x0000_0016_B374_D848, &mut buf);
+    assert_eq!(buf, [0, 0, 0, 0x16, 0xb3, 0x74, 0xd8, 0x48]);
+    assert_eq!(pg_lsn_from_sql(&buf).unwrap(), 0x0000_0016_B374_D848);
+}
+
 #[test]
 #[allow(clippy::float_cmp)]
 fn float4() {
@@ -154,3 +162,65 @@ fn non_null_array() {
     assert_eq!(array.dimensions().collect::<Vec<_>>().unwrap(), dimensions);
     assert_eq!(array.values().collect::<Vec<_>>().unwrap(), values);
 }
+
+#[test]
+fn record() {
+    let fields: Vec<(Oid, Option<i32>)> = vec![(23, Some(1)), (23, None)];
+
+    let mut buf = vec![];
+    record_to_sql(
+        fields,
+        |v, buf| match v {
+            Some(v) => {
+                int4_to_sql(v, buf);
+                Ok(IsNull::No)
+            }
+            None => Ok(IsNull::Yes),
+        },
+        &mut buf,
+    )
+    .unwrap();
+
+    let record = record_from_sql(&buf).unwrap();
+    assert_eq!(record.len(), 2);
+    let fields = record.fields().collect::<Vec<_>>().unwrap();
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields[0].0, 23);
+    assert_eq!(int4_from_sql(fields[0].1.unwrap()).unwrap(), 1);
+    assert_eq!(fields[1], (23, None));
+}
+
+#[test]
+fn numeric() {
+    let mut buf = vec![];
+    numeric_to_sql(NumericSign::Negative, 1, 2, vec![12, 3456, 7800], &mut buf).unwrap();
+
+    let numeric = numeric_from_sql(&buf).unwrap();
+    assert_eq!(numeric.sign(), NumericSign::Negative);
+    assert_eq!(numeric.weight(), 1);
+    assert_eq!(numeric.scale(), 2);
+    assert_eq!(
+        numeric.digits().collect::<Vec<_>>().unwrap(),
+        vec![12, 3456, 7800]
+    );
+}
+
+#[test]
+fn timetz() {
+    let mut buf = vec![];
+    timetz_to_sql(14_706_000_000, -7200, &mut buf);
+    assert_eq!(buf.len(), 12);
+    assert_eq!(timetz_from_sql(&buf).unwrap(), (14_706_000_000, -7200));
+    assert!(time_from_sql(&buf).is_err());
+}
+
+#[test]
+fn snapshot() {
+    let mut buf = vec![];
+    snapshot_to_sql(10, 20, vec![12, 15], &mut buf).unwrap();
+
+    let snapshot = snapshot_from_sql(&buf).unwrap();
+    assert_eq!(snapshot.xmin(), 10);
+    assert_eq!(snapshot.xmax(), 20);
+    assert_eq!(snapshot.xip().collect::<Vec<_>>().unwrap(), vec![12, 15]);
+}
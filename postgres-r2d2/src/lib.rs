@@ -0,0 +1,78 @@
+//! Connection pooling for the `postgres` crate via `r2d2`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use postgres::NoTls;
+//! use postgres_r2d2::PostgresConnectionManager;
+//! use std::thread;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let manager = PostgresConnectionManager::new("host=localhost user=postgres".parse()?, NoTls);
+//! let pool = r2d2::Pool::new(manager)?;
+//!
+//! for i in 0..10i32 {
+//!     let pool = pool.clone();
+//!     thread::spawn(move || {
+//!         let mut client = pool.get().unwrap();
+//!         client.execute("INSERT INTO foo (bar) VALUES ($1)", &[&i]).unwrap();
+//!     });
+//! }
+//! # Ok(())
+//! # }
+//! ```
+#![doc(html_root_url = "https://docs.rs/postgres-r2d2/0.1.0-rc.1")]
+#![warn(rust_2018_idioms, clippy::all, missing_docs)]
+
+use postgres::tls::{MakeTlsConnect, TlsConnect};
+use postgres::{Client, Config, Error, Socket};
+use r2d2::ManageConnection;
+
+#[cfg(test)]
+mod test;
+
+/// An `r2d2::ManageConnection` implementation for `postgres::Client`s.
+///
+/// Connections are checked with `Client::ping` when r2d2 tests them, and connections which have closed are discarded
+/// when they are returned to the pool.
+#[derive(Debug)]
+pub struct PostgresConnectionManager<T> {
+    config: Config,
+    tls: T,
+}
+
+impl<T> PostgresConnectionManager<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + 'static + Sync + Send,
+    T::TlsConnect: Send,
+    T::Stream: Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    /// Creates a new manager which opens connections with the specified configuration.
+    pub fn new(config: Config, tls: T) -> PostgresConnectionManager<T> {
+        PostgresConnectionManager { config, tls }
+    }
+}
+
+impl<T> ManageConnection for PostgresConnectionManager<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + 'static + Sync + Send,
+    T::TlsConnect: Send,
+    T::Stream: Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    type Connection = Client;
+    type Error = Error;
+
+    fn connect(&self) -> Result<Client, Error> {
+        self.config.connect(self.tls.clone())
+    }
+
+    fn is_valid(&self, client: &mut Client) -> Result<(), Error> {
+        client.ping()
+    }
+
+    fn has_broken(&self, client: &mut Client) -> bool {
+        client.is_closed()
+    }
+}
@@ -0,0 +1,49 @@
+use postgres::NoTls;
+use std::thread;
+
+use crate::PostgresConnectionManager;
+
+fn pool(max_size: u32) -> r2d2::Pool<PostgresConnectionManager<NoTls>> {
+    let config = "host=localhost port=5433 user=postgres".parse().unwrap();
+    let manager = PostgresConnectionManager::new(config, NoTls);
+    r2d2::Pool::builder()
+        .max_size(max_size)
+        .build(manager)
+        .unwrap()
+}
+
+#[test]
+fn smoke_test() {
+    let pool = pool(2);
+
+    let threads = (0..4i32)
+        .map(|i| {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                let mut client = pool.get().unwrap();
+                let rows = client.query("SELECT $1::INT4", &[&i]).unwrap();
+                assert_eq!(rows[0].get::<_, i32>(0), i);
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
+#[test]
+fn broken_connection() {
+    let pool = pool(1);
+
+    let mut client = pool.get().unwrap();
+    let pid = client.backend_pid();
+    client
+        .execute("SELECT pg_terminate_backend(pg_backend_pid())", &[])
+        .unwrap_err();
+    drop(client);
+
+    let mut client = pool.get().unwrap();
+    assert_ne!(client.backend_pid(), pid);
+    client.ping().unwrap();
+}
@@ -0,0 +1,55 @@
+#[cfg(feature = "runtime")]
+use std::sync::mpsc;
+#[cfg(feature = "runtime")]
+use tokio_postgres::tls::{MakeTlsConnect, TlsConnect};
+#[cfg(feature = "runtime")]
+use tokio_postgres::{Error, Socket};
+
+use crate::client::ClientExecutor;
+
+/// The capability to request cancellation of in-progress queries on a connection.
+///
+/// Tokens are obtained from `Client::cancel_token`, and can be cloned and moved to other threads independently of the
+/// client they were created from, for example to one which cancels a long-running query when the user presses Ctrl-C.
+#[derive(Clone)]
+pub struct CancelToken {
+    token: tokio_postgres::CancelToken,
+    #[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+    executor: ClientExecutor,
+}
+
+impl CancelToken {
+    pub(crate) fn new(token: tokio_postgres::CancelToken, executor: ClientExecutor) -> CancelToken {
+        CancelToken { token, executor }
+    }
+
+    /// Attempts to cancel the in-progress query on the connection associated with this `CancelToken`.
+    ///
+    /// A new connection is opened to the same server as the original, using the same TLS mode. The server provides no
+    /// information about whether a cancellation attempt was successful or not. An error will only be returned if the
+    /// client was unable to connect to the database. The request is made on the executor the client's connection
+    /// runs on, set with `Config::executor` or `Config::runtime`.
+    ///
+    /// Cancellation is inherently racy. There is no guarantee that the cancellation request will reach the server
+    /// before the query terminates normally, or that the connection associated with this token is still active.
+    ///
+    /// Requires the `runtime` Cargo feature (enabled by default).
+    #[cfg(feature = "runtime")]
+    pub fn cancel_query<T>(&self, tls: T) -> Result<(), Error>
+    where
+        T: MakeTlsConnect<Socket> + 'static + Send,
+        T::TlsConnect: Send,
+        T::Stream: Send,
+        <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+    {
+        // the request is made on the client's executor rather than with `Runtime::block_on`, which panics if called
+        // from within another runtime
+        let (tx, rx) = mpsc::channel();
+        let token = self.token.clone();
+        self.executor.spawn(Box::pin(async move {
+            let r = token.cancel_query(tls).await;
+            let _ = tx.send(r);
+        }));
+        rx.recv().unwrap()
+    }
+}
@@ -1,25 +1,79 @@
 use fallible_iterator::FallibleIterator;
+use futures::channel::mpsc;
 use futures::executor;
+#[cfg(feature = "runtime")]
+use std::future::Future;
 use std::io::{BufRead, Read};
+#[cfg(feature = "runtime")]
+use std::pin::Pin;
+#[cfg(feature = "runtime")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "runtime")]
+use tokio::runtime::Runtime;
+#[cfg(feature = "runtime")]
+use tokio_executor::Executor;
 use tokio_postgres::tls::{MakeTlsConnect, TlsConnect};
-use tokio_postgres::types::{ToSql, Type};
+use tokio_postgres::types::{FromSqlOwned, ToSql, Type};
 #[cfg(feature = "runtime")]
 use tokio_postgres::Socket;
-use tokio_postgres::{Error, Row, SimpleQueryMessage};
+use tokio_postgres::{BatchOutcome, Error, FromRow, ParamScope, Row, SimpleQueryMessage};
 
 use crate::copy_in_stream::CopyInStream;
 use crate::copy_out_reader::CopyOutReader;
 use crate::iter::Iter;
+use crate::{CancelToken, CopyInWriter, Pipeline, RowIter, Statement, ToStatement, Transaction};
 #[cfg(feature = "runtime")]
-use crate::Config;
-use crate::{Statement, ToStatement, Transaction};
+use crate::{Config, RUNTIME};
 
 /// A synchronous PostgreSQL client.
 ///
 /// This is a lightweight wrapper over the asynchronous tokio_postgres `Client`.
-pub struct Client(tokio_postgres::Client);
+pub struct Client {
+    client: tokio_postgres::Client,
+    executor: ClientExecutor,
+}
+
+// The executor running a client's connection, which its cancellation requests are made on as well.
+//
+// This also keeps a runtime set with `Config::runtime` alive for as long as the client and its cancellation tokens.
+// Clients created from a nonblocking client don't know the executor, and fall back to the default runtime.
+#[derive(Clone, Default)]
+pub(crate) struct ClientExecutor {
+    #[cfg(feature = "runtime")]
+    executor: Option<Arc<Mutex<dyn Executor + Send>>>,
+    #[cfg(feature = "runtime")]
+    _runtime: Option<Arc<Runtime>>,
+}
+
+impl ClientExecutor {
+    #[cfg(feature = "runtime")]
+    pub(crate) fn new(
+        executor: Arc<Mutex<dyn Executor + Send>>,
+        runtime: Option<Arc<Runtime>>,
+    ) -> ClientExecutor {
+        ClientExecutor {
+            executor: Some(executor),
+            _runtime: runtime,
+        }
+    }
+
+    #[cfg(feature = "runtime")]
+    pub(crate) fn spawn(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>) {
+        match &self.executor {
+            Some(executor) => executor.lock().unwrap().spawn(future).unwrap(),
+            None => {
+                RUNTIME.spawn(future);
+            }
+        }
+    }
+}
 
 impl Client {
+    #[cfg(feature = "runtime")]
+    pub(crate) fn new(client: tokio_postgres::Client, executor: ClientExecutor) -> Client {
+        Client { client, executor }
+    }
+
     /// A convenience function which parses a configuration string into a `Config` and then connects to the database.
     ///
     /// See the documentation for [`Config`] for information about the connection syntax.
@@ -85,7 +139,39 @@ impl Client {
         T: ?Sized + ToStatement,
     {
         let statement = query.__statement(self)?;
-        executor::block_on(self.0.execute(&statement, params))
+        executor::block_on(self.client.execute(&statement, params))
+    }
+
+    /// Like `execute`, but also returns the rows produced by the statement, such as by a `RETURNING` clause.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    pub fn execute_returning<T>(
+        &mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<(u64, Vec<Row>), Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        let statement = query.__statement(self)?;
+        executor::block_on(self.client.execute_returning(&statement, params))
+    }
+
+    /// Like `execute`, but takes an iterator of parameters rather than a slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    pub fn execute_raw<'a, T, I>(&mut self, query: &T, params: I) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement,
+        I: IntoIterator<Item = &'a dyn ToSql>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let statement = query.__statement(self)?;
+        executor::block_on(self.client.execute_iter(&statement, params))
     }
 
     /// Executes a statement, returning the resulting rows.
@@ -126,6 +212,59 @@ impl Client {
         self.query_iter(query, params)?.collect()
     }
 
+    /// Like `query`, except that each row is converted into a value implementing `FromRow`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    pub fn query_as<T, R>(
+        &mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<R>, Error>
+    where
+        T: ?Sized + ToStatement,
+        R: FromRow,
+    {
+        self.query_iter(query, params)?
+            .map(|row| row.try_into())
+            .collect()
+    }
+
+    /// Like `query`, but returns the single value of a query which returns one row with one column.
+    ///
+    /// An error is returned if the query does not return exactly one row and one column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use postgres::{Client, NoTls};
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    ///
+    /// let count: i64 = client.query_scalar("SELECT count(*) FROM foo", &[])?;
+    /// println!("{} rows", count);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query_scalar<T, R>(
+        &mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<R, Error>
+    where
+        T: ?Sized + ToStatement,
+        R: FromSqlOwned,
+    {
+        let statement = query.__statement(self)?;
+        executor::block_on(self.client.query_scalar(&statement, params))
+    }
+
     /// Like `query`, except that it returns a fallible iterator over the resulting rows rather than buffering the
     /// response in memory.
     ///
@@ -156,12 +295,77 @@ impl Client {
         &'a mut self,
         query: &T,
         params: &[&(dyn ToSql + Sync)],
-    ) -> Result<impl FallibleIterator<Item = Row, Error = Error> + 'a, Error>
+    ) -> Result<RowIter<'a>, Error>
     where
         T: ?Sized + ToStatement,
     {
         let statement = query.__statement(self)?;
-        Ok(Iter::new(self.0.query(&statement, params)))
+        Ok(RowIter::new(self.client.query(&statement, params)))
+    }
+
+    /// Like `query_iter`, but takes an iterator of parameters rather than a slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use postgres::{Client, NoTls};
+    /// use postgres::types::ToSql;
+    /// use fallible_iterator::FallibleIterator;
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    ///
+    /// let ids = vec![1i32, 2, 3];
+    /// let mut it = client.query_raw(
+    ///     "SELECT foo FROM bar WHERE id IN ($1, $2, $3)",
+    ///     ids.iter().map(|id| id as &dyn ToSql),
+    /// )?;
+    ///
+    /// while let Some(row) = it.next()? {
+    ///     let foo: i32 = row.get("foo");
+    ///     println!("foo: {}", foo);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query_raw<'a, 'b, T, I>(&'a mut self, query: &T, params: I) -> Result<RowIter<'a>, Error>
+    where
+        T: ?Sized + ToStatement,
+        I: IntoIterator<Item = &'b dyn ToSql>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let statement = query.__statement(self)?;
+        Ok(RowIter::new(self.client.query_iter(&statement, params)))
+    }
+
+    /// Executes a query without first preparing it, returning the resulting rows.
+    ///
+    /// The type of each parameter is specified alongside its value, which allows the query to be executed in a
+    /// single round trip.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use postgres::{Client, NoTls};
+    /// use postgres::types::Type;
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    ///
+    /// let rows = client.query_typed("SELECT foo FROM bar WHERE baz = $1", &[(&true, Type::BOOL)])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query_typed(
+        &mut self,
+        query: &str,
+        params: &[(&(dyn ToSql + Sync), Type)],
+    ) -> Result<Vec<Row>, Error> {
+        Iter::new(self.client.query_typed(query, params)).collect()
     }
 
     /// Creates a new prepared statement.
@@ -188,7 +392,7 @@ impl Client {
     /// # }
     /// ```
     pub fn prepare(&mut self, query: &str) -> Result<Statement, Error> {
-        executor::block_on(self.0.prepare(query))
+        executor::block_on(self.client.prepare(query))
     }
 
     /// Like `prepare`, but allows the types of query parameters to be explicitly specified.
@@ -219,7 +423,52 @@ impl Client {
     /// # }
     /// ```
     pub fn prepare_typed(&mut self, query: &str, types: &[Type]) -> Result<Statement, Error> {
-        executor::block_on(self.0.prepare_typed(query, types))
+        executor::block_on(self.client.prepare_typed(query, types))
+    }
+
+    /// Like `prepare`, but reuses a statement previously prepared for the same query if one is in the client's
+    /// statement cache.
+    ///
+    /// The cache is disabled by default, in which case this is equivalent to `prepare`. Use
+    /// `set_statement_cache_capacity` to enable it. Methods taking a raw query string, such as `query` and `execute`,
+    /// go through the cache as well.
+    pub fn prepare_cached(&mut self, query: &str) -> Result<Statement, Error> {
+        executor::block_on(self.client.prepare_cached(query))
+    }
+
+    /// Sets the maximum number of statements held in the client's statement cache, evicting the least recently used
+    /// statements if it currently holds more.
+    ///
+    /// A capacity of 0, the default, disables the cache.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use postgres::{Client, NoTls};
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    /// client.set_statement_cache_capacity(100);
+    ///
+    /// for id in 0..10i32 {
+    ///     // only prepared on the first iteration
+    ///     client.execute("INSERT INTO foo (id) VALUES ($1)", &[&id])?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_statement_cache_capacity(&mut self, capacity: usize) {
+        self.client.set_statement_cache_capacity(capacity)
+    }
+
+    /// Returns the maximum number of statements held in the client's statement cache.
+    pub fn statement_cache_capacity(&self) -> usize {
+        self.client.statement_cache_capacity()
+    }
+
+    /// Removes all statements from the client's statement cache.
+    pub fn clear_statement_cache(&mut self) {
+        self.client.clear_statement_cache()
     }
 
     /// Executes a `COPY FROM STDIN` statement, returning the number of rows created.
@@ -250,7 +499,45 @@ impl Client {
         R: Read + Unpin,
     {
         let statement = query.__statement(self)?;
-        executor::block_on(self.0.copy_in(&statement, params, CopyInStream(reader)))
+        executor::block_on(
+            self.client
+                .copy_in(&statement, params, CopyInStream(reader)),
+        )
+    }
+
+    /// Executes a `COPY FROM STDIN` statement, returning a writer to which the data is written.
+    ///
+    /// The `query` argument can either be a `Statement`, or a raw query string. The data written is passed along to the
+    /// server verbatim; it is the caller's responsibility to ensure it uses the proper format. The writer's `finish`
+    /// method must be called to complete the `COPY`, which returns the number of rows created.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use postgres::{Client, NoTls};
+    /// use std::io::Write;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    ///
+    /// let mut writer = client.copy_in_writer("COPY people FROM stdin", &[])?;
+    /// writer.write_all(b"1\tjohn\n2\tjane\n")?;
+    /// writer.finish()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn copy_in_writer<'a, T>(
+        &'a mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<CopyInWriter<'a>, Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        let statement = query.__statement(self)?;
+        let (sender, receiver) = mpsc::channel(1);
+        let future = self.client.copy_in(&statement, params, receiver);
+        Ok(CopyInWriter::new(future, sender))
     }
 
     /// Executes a `COPY TO STDOUT` statement, returning a reader of the resulting data.
@@ -281,7 +568,7 @@ impl Client {
         T: ?Sized + ToStatement,
     {
         let statement = query.__statement(self)?;
-        let stream = self.0.copy_out(&statement, params);
+        let stream = self.client.copy_out(&statement, params);
         CopyOutReader::new(stream)
     }
 
@@ -316,7 +603,7 @@ impl Client {
         &'a mut self,
         query: &str,
     ) -> Result<impl FallibleIterator<Item = SimpleQueryMessage, Error = Error> + 'a, Error> {
-        Ok(Iter::new(self.0.simple_query(query)))
+        Ok(Iter::new(self.client.simple_query(query)))
     }
 
     /// Executes a sequence of SQL statements using the simple query protocol.
@@ -330,7 +617,67 @@ impl Client {
     /// functionality to safely embed that data in the request. Do not form statements via string concatenation and pass
     /// them to this method!
     pub fn batch_execute(&mut self, query: &str) -> Result<(), Error> {
-        executor::block_on(self.0.batch_execute(query))
+        executor::block_on(self.client.batch_execute(query))
+    }
+
+    /// Like `batch_execute`, but returns the outcome of each statement in the sequence.
+    ///
+    /// The result contains one entry per statement executed, in order. If a statement fails, its error is the last
+    /// entry, so its index identifies the failed statement. An error is only returned directly if the request itself
+    /// failed, for example because the connection was closed.
+    pub fn batch_execute_results(&mut self, query: &str) -> Result<Vec<BatchOutcome>, Error> {
+        executor::block_on(self.client.batch_execute_results(query))
+    }
+
+    /// Sets the value of a runtime parameter.
+    ///
+    /// The parameter is set with the `set_config` function, so neither its name nor its value need to be escaped. With
+    /// `ParamScope::Local`, the change only lasts until the end of the current transaction, like `SET LOCAL`.
+    pub fn set_param(&mut self, name: &str, value: &str, scope: ParamScope) -> Result<(), Error> {
+        executor::block_on(self.client.set_param(name, value, scope))
+    }
+
+    /// Resets a runtime parameter to its default value, like `RESET`.
+    ///
+    /// The parameter's name is quoted as an identifier.
+    pub fn reset_param(&mut self, name: &str) -> Result<(), Error> {
+        executor::block_on(self.client.reset_param(name))
+    }
+
+    /// Resets all runtime parameters to their default values, like `RESET ALL`.
+    pub fn reset_all_params(&mut self) -> Result<(), Error> {
+        executor::block_on(self.client.reset_all_params())
+    }
+
+    /// Creates a new pipeline of statements to be sent to the server together.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use postgres::{Client, NoTls, PipelineResponse};
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    ///
+    /// let insert = client.prepare("INSERT INTO foo (bar) VALUES ($1)")?;
+    /// let select = client.prepare("SELECT bar FROM foo")?;
+    ///
+    /// let mut pipeline = client.pipeline();
+    /// pipeline.execute(&insert, &[&1i32]);
+    /// pipeline.query(&select, &[]);
+    ///
+    /// for response in pipeline.send()? {
+    ///     match response? {
+    ///         PipelineResponse::Rows(rows) => println!("{} rows", rows.len()),
+    ///         PipelineResponse::RowsAffected(n) => println!("{} rows affected", n),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pipeline(&mut self) -> Pipeline {
+        Pipeline::new(self.client.pipeline())
     }
 
     /// Begins a new database transaction.
@@ -354,35 +701,114 @@ impl Client {
     /// # }
     /// ```
     pub fn transaction(&mut self) -> Result<Transaction<'_>, Error> {
-        let transaction = executor::block_on(self.0.transaction())?;
-        Ok(Transaction::new(transaction))
+        let transaction = executor::block_on(self.client.transaction())?;
+        Ok(Transaction::new(transaction, self.executor.clone()))
+    }
+
+    /// Runs a closure within a database transaction.
+    ///
+    /// The transaction is committed if the closure returns `Ok`, and rolled back if it returns `Err` or panics.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use postgres::{Client, NoTls};
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    ///
+    /// let updated = client.with_transaction(|transaction| {
+    ///     transaction.execute("UPDATE foo SET bar = 10", &[])?;
+    ///     transaction.execute("UPDATE foo SET baz = 20", &[])
+    /// })?;
+    /// println!("{} rows updated", updated);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_transaction<F, T, E>(&mut self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Transaction<'_>) -> Result<T, E>,
+        E: From<Error>,
+    {
+        let transaction = self.transaction()?;
+        transaction.run(f)
+    }
+
+    /// Constructs a cancellation token that can later be used to request cancellation of a query running on the
+    /// connection associated with this client.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use postgres::{Client, NoTls};
+    /// use std::thread;
+    /// use std::time::Duration;
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    ///
+    /// let cancel_token = client.cancel_token();
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_secs(5));
+    ///     let _ = cancel_token.cancel_query(NoTls);
+    /// });
+    ///
+    /// match client.simple_query("SELECT long_running_query()") {
+    ///     Err(e) if e.code() == Some(&postgres::error::SqlState::QUERY_CANCELED) => {
+    ///         println!("query was canceled");
+    ///     }
+    ///     Err(e) => return Err(e),
+    ///     Ok(_) => {}
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cancel_token(&self) -> CancelToken {
+        CancelToken::new(self.client.cancel_token(), self.executor.clone())
+    }
+
+    /// Returns the process ID of the backend server process handling this connection.
+    ///
+    /// This can be used to identify the connection in `pg_stat_activity` and in the server's logs.
+    pub fn backend_pid(&self) -> i32 {
+        self.client.backend_pid()
     }
 
     /// Determines if the client's connection has already closed.
     ///
     /// If this returns `true`, the client is no longer usable.
     pub fn is_closed(&self) -> bool {
-        self.0.is_closed()
+        self.client.is_closed()
+    }
+
+    /// Checks that the connection is usable by performing a round trip to the server.
+    ///
+    /// Unlike `is_closed`, this detects connections which appear open but no longer reach a responsive server.
+    pub fn ping(&mut self) -> Result<(), Error> {
+        executor::block_on(self.client.ping())
     }
 
     /// Returns a shared reference to the inner nonblocking client.
     pub fn get_ref(&self) -> &tokio_postgres::Client {
-        &self.0
+        &self.client
     }
 
     /// Returns a mutable reference to the inner nonblocking client.
     pub fn get_mut(&mut self) -> &mut tokio_postgres::Client {
-        &mut self.0
+        &mut self.client
     }
 
     /// Consumes the client, returning the inner nonblocking client.
     pub fn into_inner(self) -> tokio_postgres::Client {
-        self.0
+        self.client
     }
 }
 
 impl From<tokio_postgres::Client> for Client {
     fn from(c: tokio_postgres::Client) -> Client {
-        Client(c)
+        Client {
+            client: c,
+            executor: ClientExecutor::default(),
+        }
     }
 }
@@ -2,20 +2,25 @@
 //!
 //! Requires the `runtime` Cargo feature (enabled by default).
 
-use futures::FutureExt;
+use futures::{executor, FutureExt};
 use log::error;
 use std::fmt;
+use std::net::IpAddr;
 use std::path::Path;
 use std::str::FromStr;
 use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
 use std::time::Duration;
+use tokio::runtime::Runtime;
 use tokio_executor::Executor;
+use tokio_postgres::error::DbError;
 use tokio_postgres::tls::{MakeTlsConnect, TlsConnect};
-use tokio_postgres::{Error, Socket};
+use tokio_postgres::{Error, Interceptor, Notification, OverflowPolicy, Socket};
 
 #[doc(inline)]
-pub use tokio_postgres::config::{SslMode, TargetSessionAttrs, ChannelBinding};
+pub use tokio_postgres::config::{ChannelBinding, LoadBalanceHosts, SslMode, TargetSessionAttrs};
 
+use crate::client::ClientExecutor;
 use crate::{Client, RUNTIME};
 
 /// Connection configuration.
@@ -34,24 +39,37 @@ use crate::{Client, RUNTIME};
 /// * `dbname` - The name of the database to connect to. Defaults to the username.
 /// * `options` - Command line options used to configure the server.
 /// * `application_name` - Sets the `application_name` parameter on the server.
+/// * `fallback_application_name` - An `application_name` to use if one isn't otherwise specified.
 /// * `sslmode` - Controls usage of TLS. If set to `disable`, TLS will not be used. If set to `prefer`, TLS will be used
 ///     if available, but not used otherwise. If set to `require`, TLS will be forced to be used. Defaults to `prefer`.
 /// * `host` - The host to connect to. On Unix platforms, if the host starts with a `/` character it is treated as the
-///     path to the directory containing Unix domain sockets. Otherwise, it is treated as a hostname. Multiple hosts
+///     path to the directory containing Unix domain sockets, and the connection is made to the socket named
+///     `.s.PGSQL.<port>` in it. Otherwise, it is treated as a hostname. Multiple hosts
 ///     can be specified, separated by commas. Each host will be tried in turn when connecting. Required if connecting
 ///     with the `connect` method.
+/// * `hostaddr` - The IP address to connect to, bypassing the lookup of the hostname. If both `host` and `hostaddr` are
+///     specified, the connection is made to `hostaddr` while `host` is still used to verify the server's TLS
+///     certificate. Multiple addresses can be specified, separated by commas, in which case there must be the same
+///     number of addresses as hosts.
 /// * `port` - The port to connect to. Multiple ports can be specified, separated by commas. The number of ports must be
 ///     either 1, in which case it will be used for all hosts, or the same as the number of hosts. Defaults to 5432 if
 ///     omitted or the empty string.
-/// * `connect_timeout` - The time limit in seconds applied to each socket-level connection attempt. Note that hostnames
-///     can resolve to multiple IP addresses, and this limit is applied to each address. Defaults to no timeout.
+/// * `connect_timeout` - The time limit in seconds applied to each connection attempt, covering the socket connection,
+///     TLS negotiation, authentication, and any session checks. Each host is attempted separately. Defaults to no
+///     timeout.
 /// * `keepalives` - Controls the use of TCP keepalive. A value of 0 disables keepalive and nonzero integers enable it.
 ///     This option is ignored when connecting with Unix sockets. Defaults to on.
 /// * `keepalives_idle` - The number of seconds of inactivity after which a keepalive message is sent to the server.
 ///     This option is ignored when connecting with Unix sockets. Defaults to 2 hours.
+/// * `tcp_user_timeout` - The number of milliseconds transmitted data may remain unacknowledged before the connection
+///     is forcibly closed. A value of 0 uses the system default. This option is only supported on Linux, and is
+///     ignored when connecting with Unix sockets. Defaults to the system default.
 /// * `target_session_attrs` - Specifies requirements of the session. If set to `read-write`, the client will check that
 ///     the `transaction_read_write` session parameter is set to `on`. This can be used to connect to the primary server
 ///     in a database cluster as opposed to the secondary read-only mirrors. Defaults to `all`.
+/// * `load_balance_hosts` - Controls the order in which hosts are tried. If set to `random`, the hosts are tried in a
+///     random order on each connection attempt, as are the IP addresses each hostname resolves to. Defaults to
+///     `disable`.
 ///
 /// ## Examples
 ///
@@ -96,8 +114,12 @@ pub struct Config {
     config: tokio_postgres::Config,
     // this is an option since we don't want to boot up our default runtime unless we're actually going to use it.
     executor: Option<Arc<Mutex<dyn Executor + Send>>>,
+    runtime: Option<Arc<Runtime>>,
+    notification_handler: Option<(usize, NotificationHandler)>,
 }
 
+type NotificationHandler = Arc<dyn Fn(Notification) + Sync + Send>;
+
 impl fmt::Debug for Config {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt.debug_struct("Config")
@@ -118,6 +140,8 @@ impl Config {
         Config {
             config: tokio_postgres::Config::new(),
             executor: None,
+            runtime: None,
+            notification_handler: None,
         }
     }
 
@@ -152,12 +176,51 @@ impl Config {
         self
     }
 
+    /// Adds a `-c name=value` command line option, which sets a runtime parameter when the session starts.
+    ///
+    /// Spaces and backslashes in the value are escaped.
+    pub fn runtime_param(&mut self, name: &str, value: &str) -> &mut Config {
+        self.config.runtime_param(name, value);
+        self
+    }
+
     /// Sets the value of the `application_name` runtime parameter.
     pub fn application_name(&mut self, application_name: &str) -> &mut Config {
         self.config.application_name(application_name);
         self
     }
 
+    /// Sets a value for the `application_name` runtime parameter which is used only if `application_name` is not set.
+    pub fn fallback_application_name(&mut self, fallback_application_name: &str) -> &mut Config {
+        self.config
+            .fallback_application_name(fallback_application_name);
+        self
+    }
+
+    /// Sets a callback which is invoked with each notice sent by the server, such as those raised by `RAISE NOTICE` or
+    /// warnings.
+    ///
+    /// By default notices are discarded. The callback is invoked from the thread running the connection, so it should
+    /// not block.
+    pub fn notice_callback<F>(&mut self, callback: F) -> &mut Config
+    where
+        F: Fn(DbError) + Sync + Send + 'static,
+    {
+        self.config.notice_callback(callback);
+        self
+    }
+
+    /// Sets an interceptor which is invoked around each query made by clients connected with this configuration.
+    ///
+    /// See the `Interceptor` trait for details.
+    pub fn interceptor<I>(&mut self, interceptor: I) -> &mut Config
+    where
+        I: Interceptor + 'static,
+    {
+        self.config.interceptor(interceptor);
+        self
+    }
+
     /// Sets the SSL configuration.
     ///
     /// Defaults to `prefer`.
@@ -187,6 +250,15 @@ impl Config {
         self
     }
 
+    /// Adds an IP address to the configuration.
+    ///
+    /// The connection to the corresponding host is made to this address rather than to the addresses its name resolves
+    /// to, but the host's name is still used to verify the server's TLS certificate.
+    pub fn hostaddr(&mut self, hostaddr: IpAddr) -> &mut Config {
+        self.config.hostaddr(hostaddr);
+        self
+    }
+
     /// Adds a port to the configuration.
     ///
     /// Multiple ports can be specified by calling this method multiple times. There must either be no ports, in which
@@ -197,10 +269,10 @@ impl Config {
         self
     }
 
-    /// Sets the timeout applied to socket-level connection attempts.
+    /// Sets the timeout applied to each connection attempt.
     ///
-    /// Note that hostnames can resolve to multiple IP addresses, and this timeout will apply to each address of each
-    /// host separately. Defaults to no limit.
+    /// The timeout covers establishing the socket, negotiating TLS, and authenticating, and applies to each host
+    /// separately. Defaults to no limit.
     pub fn connect_timeout(&mut self, connect_timeout: Duration) -> &mut Config {
         self.config.connect_timeout(connect_timeout);
         self
@@ -222,6 +294,15 @@ impl Config {
         self
     }
 
+    /// Sets the amount of time transmitted data may remain unacknowledged before the connection is closed, with the
+    /// `TCP_USER_TIMEOUT` socket option.
+    ///
+    /// This is only supported on Linux, and is ignored for Unix domain sockets. Defaults to the system default.
+    pub fn tcp_user_timeout(&mut self, tcp_user_timeout: Duration) -> &mut Config {
+        self.config.tcp_user_timeout(tcp_user_timeout);
+        self
+    }
+
     /// Sets the requirements of the session.
     ///
     /// This can be used to connect to the primary server in a clustered database rather than one of the read-only
@@ -242,18 +323,79 @@ impl Config {
         self
     }
 
+    /// Sets the order in which hosts, and the addresses they resolve to, are tried.
+    ///
+    /// Defaults to `Disable`.
+    pub fn load_balance_hosts(&mut self, load_balance_hosts: LoadBalanceHosts) -> &mut Config {
+        self.config.load_balance_hosts(load_balance_hosts);
+        self
+    }
+
+    /// Adds a statement to be prepared into the client's statement cache as soon as a connection is established,
+    /// before the client is returned.
+    ///
+    /// If a warm-up statement fails to prepare, the connection attempt fails.
+    pub fn warm_up_statement(&mut self, query: &str) -> &mut Config {
+        self.config.warm_up_statement(query);
+        self
+    }
+
+    /// Sets a callback which is invoked with each notification received by the connection, such as those sent by
+    /// `NOTIFY` to a channel the client is subscribed to with `LISTEN`.
+    ///
+    /// Each connection spawns a thread which invokes the callback with its notifications in the order they arrive, so
+    /// the callback may block without stalling the connection. Up to `capacity` notifications are buffered while it
+    /// does so, after which the oldest are discarded. The thread exits once the connection closes.
+    ///
+    /// By default notifications are discarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    pub fn notification_handler<F>(&mut self, capacity: usize, handler: F) -> &mut Config
+    where
+        F: Fn(Notification) + Sync + Send + 'static,
+    {
+        assert!(capacity > 0, "notification buffer capacity must be nonzero");
+        self.notification_handler = Some((capacity, Arc::new(handler)));
+        self
+    }
+
     /// Sets the executor used to run the connection futures.
     ///
+    /// An existing tokio `Runtime` can be shared with the rest of the application by passing its `executor`. The
+    /// runtime must outlive the clients created with this configuration.
+    ///
+    /// The executor must run the futures on threads other than the one using the client, since the client blocks
+    /// while it waits for them. A `current_thread` runtime's executor will deadlock the client when used from the
+    /// runtime's own thread; use a threadpool `Runtime` instead.
+    ///
     /// Defaults to a postgres-specific tokio `Runtime`.
     pub fn executor<E>(&mut self, executor: E) -> &mut Config
     where
         E: Executor + 'static + Send,
     {
         self.executor = Some(Arc::new(Mutex::new(executor)));
+        self.runtime = None;
+        self
+    }
+
+    /// Sets a runtime used to run the connection futures, which is owned by the clients created with this
+    /// configuration.
+    ///
+    /// The runtime is shut down once it has been dropped by this configuration and all of those clients.
+    ///
+    /// Defaults to a postgres-specific tokio `Runtime`.
+    pub fn runtime(&mut self, runtime: Runtime) -> &mut Config {
+        self.executor = Some(Arc::new(Mutex::new(runtime.executor())));
+        self.runtime = Some(Arc::new(runtime));
         self
     }
 
     /// Opens a connection to a PostgreSQL database.
+    ///
+    /// The connection is established on the configured executor while this method blocks, so it deadlocks if the
+    /// executor can only make progress on the calling thread, like a `current_thread` runtime called from within.
     pub fn connect<T>(&self, tls: T) -> Result<Client, Error>
     where
         T: MakeTlsConnect<Socket> + 'static + Send,
@@ -261,42 +403,53 @@ impl Config {
         T::Stream: Send,
         <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
     {
-        let (client, connection) = match &self.executor {
-            Some(executor) => {
-                let (tx, rx) = mpsc::channel();
-                let config = self.config.clone();
-                let connect = async move {
-                    let r = config.connect(tls).await;
-                    let _ = tx.send(r);
-                };
-                executor.lock().unwrap().spawn(Box::pin(connect)).unwrap();
-                rx.recv().unwrap()?
-            }
-            None => {
-                let connect = self.config.connect(tls);
-                RUNTIME.block_on(connect)?
-            }
+        // the connection is established on the executor rather than with `Runtime::block_on`, which panics if called
+        // from within another runtime
+        let executor: Arc<Mutex<dyn Executor + Send>> = match &self.executor {
+            Some(executor) => executor.clone(),
+            None => Arc::new(Mutex::new(RUNTIME.executor())),
         };
 
+        let (tx, rx) = mpsc::channel();
+        let config = self.config.clone();
+        let connect = async move {
+            let r = config.connect(tls).await;
+            let _ = tx.send(r);
+        };
+        executor.lock().unwrap().spawn(Box::pin(connect)).unwrap();
+        let (client, mut connection) = rx.recv().unwrap()?;
+
+        if let Some((capacity, handler)) = &self.notification_handler {
+            let notifications = connection.notifications(*capacity, OverflowPolicy::DropOldest);
+            let handler = handler.clone();
+            thread::Builder::new()
+                .name("postgres-notifications".to_string())
+                .spawn(move || {
+                    for notification in executor::block_on_stream(notifications) {
+                        // overflow errors are only reported with `OverflowPolicy::Error`
+                        if let Ok(notification) = notification {
+                            handler(notification);
+                        }
+                    }
+                })
+                .unwrap();
+        }
+
         let connection = connection.map(|r| {
             if let Err(e) = r {
                 error!("postgres connection error: {}", e)
             }
         });
-        match &self.executor {
-            Some(executor) => {
-                executor
-                    .lock()
-                    .unwrap()
-                    .spawn(Box::pin(connection))
-                    .unwrap();
-            }
-            None => {
-                RUNTIME.spawn(connection);
-            }
-        }
+        executor
+            .lock()
+            .unwrap()
+            .spawn(Box::pin(connection))
+            .unwrap();
 
-        Ok(Client::from(client))
+        Ok(Client::new(
+            client,
+            ClientExecutor::new(executor, self.runtime.clone()),
+        ))
     }
 }
 
@@ -313,6 +466,8 @@ impl From<tokio_postgres::Config> for Config {
         Config {
             config,
             executor: None,
+            runtime: None,
+            notification_handler: None,
         }
     }
 }
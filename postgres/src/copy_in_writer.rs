@@ -0,0 +1,103 @@
+use bytes::{Bytes, BytesMut};
+use futures::channel::mpsc;
+use futures::{executor, future};
+use std::future::Future;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::Poll;
+use tokio_postgres::Error;
+
+/// The writer returned by the `copy_in_writer` method.
+///
+/// The data written is passed along to the server verbatim, and the `finish` method must be called to complete the
+/// `COPY`. If the writer is dropped without being finished, the `COPY` is aborted.
+pub struct CopyInWriter<'a> {
+    future: Pin<Box<dyn Future<Output = Result<u64, Error>> + Send>>,
+    sender: mpsc::Sender<io::Result<Bytes>>,
+    buf: BytesMut,
+    // set if the `COPY` completes before the writer is finished, which only happens if it fails
+    result: Option<Result<u64, Error>>,
+    _p: PhantomData<&'a mut ()>,
+}
+
+// no-op impl to extend borrow until drop
+impl<'a> Drop for CopyInWriter<'a> {
+    fn drop(&mut self) {}
+}
+
+impl<'a> CopyInWriter<'a> {
+    pub(crate) fn new<F>(future: F, sender: mpsc::Sender<io::Result<Bytes>>) -> CopyInWriter<'a>
+    where
+        F: Future<Output = Result<u64, Error>> + Send + 'static,
+    {
+        CopyInWriter {
+            future: Box::pin(future),
+            sender,
+            buf: BytesMut::new(),
+            result: None,
+            _p: PhantomData,
+        }
+    }
+
+    /// Completes the `COPY`, returning the number of rows created.
+    pub fn finish(mut self) -> Result<u64, Error> {
+        let _ = self.flush();
+        if let Some(result) = self.result.take() {
+            return result;
+        }
+
+        self.sender.close_channel();
+        executor::block_on(self.future.as_mut())
+    }
+
+    fn send(&mut self, data: Bytes) -> io::Result<()> {
+        if let Some(Err(e)) = &self.result {
+            return Err(io::Error::new(io::ErrorKind::Other, e.to_string()));
+        }
+
+        // the `COPY` future has to be driven while waiting for room in the channel, since it is what empties it
+        let sender = &mut self.sender;
+        let copy = &mut self.future;
+        let result = executor::block_on(future::poll_fn(|cx| {
+            if let Poll::Ready(Ok(())) = sender.poll_ready(cx) {
+                return Poll::Ready(None);
+            }
+            copy.as_mut().poll(cx).map(Some)
+        }));
+
+        match result {
+            None => {
+                let _ = self.sender.start_send(Ok(data));
+                Ok(())
+            }
+            Some(result) => {
+                let message = match &result {
+                    Ok(_) => "COPY completed early".to_string(),
+                    Err(e) => e.to_string(),
+                };
+                self.result = Some(result);
+                Err(io::Error::new(io::ErrorKind::Other, message))
+            }
+        }
+    }
+}
+
+impl<'a> Write for CopyInWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        if self.buf.len() >= 4096 {
+            self.flush()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+
+        let data = self.buf.take().freeze();
+        self.send(data)
+    }
+}
@@ -0,0 +1,55 @@
+use fallible_iterator::FallibleIterator;
+use futures::{executor, Stream, TryStreamExt};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::vec;
+use tokio_postgres::{Error, Row};
+
+/// A fallible iterator over the rows of a query, which are fetched from the server in batches.
+///
+/// Created by `Transaction::lazy_query`.
+pub struct LazyRows<'a> {
+    chunks: Pin<Box<dyn Stream<Item = Result<Vec<Row>, Error>> + Send>>,
+    rows: vec::IntoIter<Row>,
+    _p: PhantomData<&'a mut ()>,
+}
+
+// no-op impl to extend the borrow until drop
+impl<'a> Drop for LazyRows<'a> {
+    fn drop(&mut self) {}
+}
+
+impl<'a> LazyRows<'a> {
+    pub(crate) fn new<S>(chunks: S) -> LazyRows<'a>
+    where
+        S: Stream<Item = Result<Vec<Row>, Error>> + Send + 'static,
+    {
+        LazyRows {
+            chunks: Box::pin(chunks),
+            rows: vec![].into_iter(),
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<'a> FallibleIterator for LazyRows<'a> {
+    type Item = Row;
+    type Error = Error;
+
+    fn next(&mut self) -> Result<Option<Row>, Error> {
+        loop {
+            if let Some(row) = self.rows.next() {
+                return Ok(Some(row));
+            }
+
+            match executor::block_on(self.chunks.try_next())? {
+                Some(rows) => self.rows = rows.into_iter(),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.rows.len(), None)
+    }
+}
@@ -44,7 +44,9 @@
 //!
 //! A client can be constructed directly from a `tokio-postgres` client via a `From` implementation, but the `runtime`
 //! Cargo feature (enabled by default) provides a more convenient interface. By default, connections will be spawned
-//! onto a static tokio `Runtime`, but a custom `Executor` can also be used instead.
+//! onto a static tokio `Runtime`, but a custom `Executor`, such as that of a runtime shared with asynchronous parts of
+//! the application, or a `Runtime` owned by the clients can also be used instead. See `Config::executor` and
+//! `Config::runtime`.
 //!
 //! # SSL/TLS support
 //!
@@ -63,27 +65,42 @@ use tokio::runtime::{self, Runtime};
 #[cfg(feature = "runtime")]
 pub use tokio_postgres::Socket;
 pub use tokio_postgres::{
-    accepts, error, row, tls, to_sql_checked, types, Column, Portal, SimpleQueryMessage, Statement,
+    accepts, error, row, tls, to_sql_checked, types, BatchOutcome, Column, CommandTag, FromRow,
+    Interceptor, Notification, ParamScope, Portal, SimpleColumn, SimpleQueryMessage, Statement,
 };
 
+pub use crate::cancel_token::CancelToken;
 pub use crate::client::*;
 #[cfg(feature = "runtime")]
 pub use crate::config::Config;
+pub use crate::copy_in_writer::CopyInWriter;
 #[doc(no_inline)]
 pub use crate::error::Error;
+pub use crate::lazy_rows::LazyRows;
+pub use crate::pipeline::{Pipeline, PipelineResponse};
+#[cfg(feature = "runtime")]
+pub use crate::reconnecting_client::ReconnectingClient;
 #[doc(no_inline)]
 pub use crate::row::{Row, SimpleQueryRow};
+pub use crate::row_iter::RowIter;
 #[doc(no_inline)]
 pub use crate::tls::NoTls;
 pub use crate::to_statement::*;
 pub use crate::transaction::*;
 
+mod cancel_token;
 mod client;
 #[cfg(feature = "runtime")]
 pub mod config;
 mod copy_in_stream;
+mod copy_in_writer;
 mod copy_out_reader;
 mod iter;
+mod lazy_rows;
+mod pipeline;
+#[cfg(feature = "runtime")]
+mod reconnecting_client;
+mod row_iter;
 mod to_statement;
 mod transaction;
 
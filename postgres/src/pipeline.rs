@@ -0,0 +1,86 @@
+use futures::{executor, FutureExt};
+use std::future::Future;
+use std::pin::Pin;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Error, Row, Statement};
+
+/// The result of an operation queued on a `Pipeline`.
+pub enum PipelineResponse {
+    /// The rows returned by an operation queued with `Pipeline::query`.
+    Rows(Vec<Row>),
+
+    /// The number of rows modified by an operation queued with `Pipeline::execute`.
+    RowsAffected(u64),
+
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+/// A batch of statements which are sent to the server together.
+///
+/// Operations queued on the pipeline are not sent until `send` is called, which writes all of them to the server in a
+/// single round trip and returns their results in the order they were queued.
+///
+/// Because the operations share a single `Sync`, they run in the same implicit transaction unless an explicit one is
+/// active: if one of them fails, the server skips all of the operations queued after it, which fail with an error, and
+/// rolls back the changes made by the ones before it.
+pub struct Pipeline {
+    pipeline: tokio_postgres::Pipeline,
+    responses: Vec<Pin<Box<dyn Future<Output = Result<PipelineResponse, Error>> + Send>>>,
+}
+
+impl Pipeline {
+    pub(crate) fn new(pipeline: tokio_postgres::Pipeline) -> Pipeline {
+        Pipeline {
+            pipeline,
+            responses: vec![],
+        }
+    }
+
+    /// Queues a statement whose result will be its rows, as a `PipelineResponse::Rows`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    pub fn query(&mut self, statement: &Statement, params: &[&(dyn ToSql + Sync)]) {
+        let response = self
+            .pipeline
+            .query(statement, params)
+            .map(|r| r.map(PipelineResponse::Rows));
+        self.responses.push(Box::pin(response));
+    }
+
+    /// Queues a statement whose result will be the number of rows modified, as a `PipelineResponse::RowsAffected`.
+    ///
+    /// If the statement does not modify any rows (e.g. `SELECT`), 0 is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    pub fn execute(&mut self, statement: &Statement, params: &[&(dyn ToSql + Sync)]) {
+        let response = self
+            .pipeline
+            .execute(statement, params)
+            .map(|r| r.map(PipelineResponse::RowsAffected));
+        self.responses.push(Box::pin(response));
+    }
+
+    /// Returns the number of operations queued on the pipeline.
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    /// Determines if no operations are queued on the pipeline.
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+
+    /// Sends the queued operations to the server, returning the result of each one in the order they were queued.
+    ///
+    /// An error is only returned directly if the request could not be sent at all; errors from individual operations
+    /// are reported in their own results.
+    pub fn send(self) -> Result<Vec<Result<PipelineResponse, Error>>, Error> {
+        executor::block_on(self.pipeline.send())?;
+        Ok(self.responses.into_iter().map(executor::block_on).collect())
+    }
+}
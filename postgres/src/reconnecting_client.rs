@@ -0,0 +1,158 @@
+use log::debug;
+use std::cmp;
+use std::thread;
+use std::time::Duration;
+use tokio_postgres::tls::{MakeTlsConnect, TlsConnect};
+use tokio_postgres::{Error, Socket};
+
+use crate::{Client, Config};
+
+/// A wrapper around a `Client` which transparently reconnects to the database after the connection is lost.
+///
+/// The connection is established lazily by `client`, and re-established there whenever the previous one has closed,
+/// retrying failed attempts with exponential backoff. The setup statements registered with `setup_statement` are run
+/// on each new connection before it is used, so that session state such as `SET` parameters survives a reconnect.
+///
+/// Requests made through `client` which were in flight when the connection was lost are not retried, since they may
+/// or may not have been applied by the server. They fail with an error for which `Error::is_closed` returns `true`,
+/// and the next call to `client` reconnects. Operations which are safe to repeat can instead be run with
+/// `idempotent`, which retries them on a new connection.
+///
+/// Statements are tied to the connection which prepared them, so they can't be used after a reconnect.
+pub struct ReconnectingClient<T> {
+    config: Config,
+    tls: T,
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    setup: Vec<String>,
+    client: Option<Client>,
+}
+
+impl<T> ReconnectingClient<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + 'static + Send,
+    T::TlsConnect: Send,
+    T::Stream: Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    /// Creates a new client which connects with the specified configuration.
+    ///
+    /// No connection is made until the client is first used.
+    pub fn new(config: Config, tls: T) -> ReconnectingClient<T> {
+        ReconnectingClient {
+            config,
+            tls,
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            setup: vec![],
+            client: None,
+        }
+    }
+
+    /// Sets the maximum number of connection attempts made by each reconnect before its error is returned.
+    ///
+    /// Defaults to 5.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_attempts` is 0.
+    pub fn max_attempts(&mut self, max_attempts: u32) -> &mut ReconnectingClient<T> {
+        assert!(max_attempts > 0, "max_attempts must be positive");
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the delay before the first retry of a failed connection attempt, and the maximum delay it doubles up to
+    /// on each subsequent retry.
+    ///
+    /// Defaults to 100 milliseconds and 10 seconds.
+    pub fn backoff(&mut self, initial: Duration, max: Duration) -> &mut ReconnectingClient<T> {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Adds statements to be run with `Client::batch_execute` on each new connection, in the order they were added.
+    ///
+    /// If a setup statement fails, the connection is discarded and the error is returned. Statements added while a
+    /// connection is open only take effect on the next one.
+    pub fn setup_statement(&mut self, query: &str) -> &mut ReconnectingClient<T> {
+        self.setup.push(query.to_string());
+        self
+    }
+
+    /// Determines if the client currently holds an open connection.
+    pub fn is_connected(&self) -> bool {
+        self.client.as_ref().map_or(false, |c| !c.is_closed())
+    }
+
+    /// Returns the underlying client, first connecting to the database if there is no open connection.
+    pub fn client(&mut self) -> Result<&mut Client, Error> {
+        if !self.is_connected() {
+            self.client = None;
+            let client = self.connect()?;
+            self.client = Some(client);
+        }
+
+        Ok(self.client.as_mut().unwrap())
+    }
+
+    /// Runs an operation which is safe to repeat, retrying it on a new connection if the connection is lost while it
+    /// is running.
+    ///
+    /// The operation is retried at most once. Other errors are returned as-is.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use postgres::{NoTls, ReconnectingClient};
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let config = "host=localhost user=postgres".parse()?;
+    /// let mut client = ReconnectingClient::new(config, NoTls);
+    ///
+    /// let rows = client.idempotent(|client| client.query("SELECT bar FROM foo", &[]))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn idempotent<F, R>(&mut self, mut f: F) -> Result<R, Error>
+    where
+        F: FnMut(&mut Client) -> Result<R, Error>,
+    {
+        match f(self.client()?) {
+            Err(ref e) if e.is_closed() => {
+                debug!("connection lost, retrying: {}", e);
+                self.client = None;
+                f(self.client()?)
+            }
+            r => r,
+        }
+    }
+
+    fn connect(&mut self) -> Result<Client, Error> {
+        let mut backoff = self.initial_backoff;
+        let mut attempts = 1;
+        let mut client = loop {
+            match self.config.connect(self.tls.clone()) {
+                Ok(client) => break client,
+                Err(e) => {
+                    if attempts >= self.max_attempts {
+                        return Err(e);
+                    }
+                    debug!("connection attempt {} failed: {}", attempts, e);
+                    attempts += 1;
+                    thread::sleep(backoff);
+                    backoff = cmp::min(backoff * 2, self.max_backoff);
+                }
+            }
+        };
+
+        for query in &self.setup {
+            client.batch_execute(query)?;
+        }
+
+        Ok(client)
+    }
+}
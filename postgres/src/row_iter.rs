@@ -0,0 +1,44 @@
+use fallible_iterator::FallibleIterator;
+use futures::executor;
+use futures::TryStreamExt;
+use std::marker::PhantomData;
+use tokio_postgres::{Error, Row, RowStream};
+
+/// A fallible iterator over the rows returned by a query.
+///
+/// Rows are read from the connection as the iterator is advanced, so results of any size can be processed without
+/// buffering them in memory. The client cannot be used for anything else until the iterator is dropped.
+pub struct RowIter<'a> {
+    it: RowStream,
+    _p: PhantomData<&'a mut ()>,
+}
+
+// no-op impl to extend the borrow until drop
+impl<'a> Drop for RowIter<'a> {
+    fn drop(&mut self) {}
+}
+
+impl<'a> RowIter<'a> {
+    pub(crate) fn new(stream: RowStream) -> RowIter<'a> {
+        RowIter {
+            it: stream,
+            _p: PhantomData,
+        }
+    }
+
+    /// Returns the number of rows affected by the query.
+    ///
+    /// This is `None` until the iterator has returned all of its rows.
+    pub fn rows_affected(&self) -> Option<u64> {
+        self.it.rows_affected()
+    }
+}
+
+impl<'a> FallibleIterator for RowIter<'a> {
+    type Item = Row;
+    type Error = Error;
+
+    fn next(&mut self) -> Result<Option<Row>, Error> {
+        executor::block_on(self.it.try_next())
+    }
+}
@@ -1,9 +1,16 @@
-use std::io::Read;
+use fallible_iterator::FallibleIterator;
+use std::io::{Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 use tokio::runtime::Runtime;
-use tokio_postgres::types::Type;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::{ToSql, Type};
 use tokio_postgres::NoTls;
 
 use super::*;
+use crate::config::{LoadBalanceHosts, SslMode};
 
 #[test]
 fn prepare() {
@@ -35,6 +42,120 @@ fn query_unprepared() {
     assert_eq!(rows[0].get::<_, &str>(0), "hello");
 }
 
+#[test]
+fn query_iter() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    let mut it = client
+        .query_iter("SELECT generate_series(1, $1)", &[&1_000_000i32])
+        .unwrap();
+    let mut sum = 0i64;
+    while let Some(row) = it.next().unwrap() {
+        assert_eq!(it.rows_affected(), None);
+        sum += i64::from(row.get::<_, i32>(0));
+    }
+    assert_eq!(sum, 500_000_500_000);
+    assert_eq!(it.rows_affected(), Some(1_000_000));
+    drop(it);
+
+    let mut transaction = client.transaction().unwrap();
+    let first = transaction
+        .query_iter("SELECT generate_series(1, 3)", &[])
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(first.get::<_, i32>(0), 1);
+    // the rest of the rows were discarded along with the iterator
+    transaction.execute("SELECT 1", &[]).unwrap();
+}
+
+#[test]
+fn query_raw() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    let params = vec![1i32, 2];
+    let rows = client
+        .query_raw(
+            "SELECT $1::INT + $2::INT",
+            params.iter().map(|p| p as &dyn ToSql),
+        )
+        .unwrap()
+        .collect::<Vec<_>>()
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, i32>(0), 3);
+
+    client
+        .simple_query("CREATE TEMPORARY TABLE foo (id INT)")
+        .unwrap();
+    let params = vec!["1".to_string()];
+    let modified = client
+        .execute_raw(
+            "INSERT INTO foo (id) VALUES ($1::TEXT::INT)",
+            params.iter().map(|p| p as &dyn ToSql),
+        )
+        .unwrap();
+    assert_eq!(modified, 1);
+}
+
+#[test]
+fn query_as() {
+    struct Greeting {
+        text: String,
+    }
+
+    impl FromRow for Greeting {
+        fn from_row(row: &Row) -> Result<Greeting, Error> {
+            Ok(Greeting {
+                text: row.try_get("text")?,
+            })
+        }
+    }
+
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    let greetings = client
+        .query_as::<_, Greeting>("SELECT $1::TEXT AS text", &[&"hello"])
+        .unwrap();
+    assert_eq!(greetings.len(), 1);
+    assert_eq!(greetings[0].text, "hello");
+}
+
+#[test]
+fn query_scalar() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    let value = client
+        .query_scalar::<_, String>("SELECT $1::TEXT", &[&"hello"])
+        .unwrap();
+    assert_eq!(value, "hello");
+
+    let mut transaction = client.transaction().unwrap();
+    let count = transaction
+        .query_scalar::<_, i64>("SELECT count(*) FROM generate_series(1, 3)", &[])
+        .unwrap();
+    assert_eq!(count, 3);
+}
+
+#[test]
+fn execute_returning() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id SERIAL, name TEXT)")
+        .unwrap();
+
+    let (count, rows) = client
+        .execute_returning(
+            "INSERT INTO foo (name) VALUES ('alice'), ('bob') RETURNING id, name",
+            &[],
+        )
+        .unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(rows[1].get::<_, &str>("name"), "bob");
+}
+
 #[test]
 fn transaction_commit() {
     let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
@@ -76,6 +197,48 @@ fn transaction_rollback() {
     assert_eq!(rows.len(), 0);
 }
 
+#[test]
+fn with_transaction() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    client
+        .simple_query("CREATE TEMPORARY TABLE foo (id INT)")
+        .unwrap();
+
+    let inserted = client
+        .with_transaction(|transaction| {
+            transaction.execute("INSERT INTO foo (id) VALUES (1)", &[])?;
+
+            let r = transaction.with_transaction(|transaction| {
+                transaction.execute("INSERT INTO foo (id) VALUES (2)", &[])?;
+                Err::<(), _>(transaction.execute("SELECT 1/0", &[]).unwrap_err())
+            });
+            assert!(r.is_err());
+
+            transaction.execute("INSERT INTO foo (id) VALUES (3)", &[])
+        })
+        .unwrap();
+    assert_eq!(inserted, 1);
+
+    let r = client.with_transaction(|transaction| {
+        transaction.execute("INSERT INTO foo (id) VALUES (4)", &[])?;
+        transaction.execute("INSERT INTO foo (id) VALUES ('five')", &[])
+    });
+    assert!(r.is_err());
+
+    let r = panic::catch_unwind(AssertUnwindSafe(|| {
+        client.with_transaction(|transaction| -> Result<(), Error> {
+            transaction.execute("INSERT INTO foo (id) VALUES (6)", &[])?;
+            panic!("boom");
+        })
+    }));
+    assert!(r.is_err());
+
+    let rows = client.query("SELECT id FROM foo ORDER BY id", &[]).unwrap();
+    let ids = rows.iter().map(|r| r.get(0)).collect::<Vec<i32>>();
+    assert_eq!(ids, [1, 3]);
+}
+
 #[test]
 fn transaction_drop() {
     let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
@@ -147,6 +310,79 @@ fn nested_transactions() {
     assert_eq!(rows[2].get::<_, i32>(0), 4);
 }
 
+#[test]
+fn savepoint() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT PRIMARY KEY)")
+        .unwrap();
+
+    let mut transaction = client.transaction().unwrap();
+
+    transaction
+        .execute("INSERT INTO foo (id) VALUES (1)", &[])
+        .unwrap();
+
+    {
+        let mut savepoint = transaction.savepoint("before_two").unwrap();
+        savepoint
+            .execute("INSERT INTO foo (id) VALUES (2)", &[])
+            .unwrap();
+        // dropped without committing
+    }
+
+    let mut savepoint = transaction.savepoint("before_three").unwrap();
+    savepoint
+        .execute("INSERT INTO foo (id) VALUES (3)", &[])
+        .unwrap();
+    savepoint.commit().unwrap();
+
+    transaction.commit().unwrap();
+
+    let rows = client.query("SELECT id FROM foo ORDER BY id", &[]).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+    assert_eq!(rows[1].get::<_, i32>(0), 3);
+}
+
+#[test]
+fn statement_cache() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    let prepared_count = |client: &mut Client, query: &str| {
+        client
+            .query_scalar::<_, i64>(
+                "SELECT count(*) FROM pg_prepared_statements WHERE statement = $1",
+                &[&query],
+            )
+            .unwrap()
+    };
+
+    for i in 0..3i32 {
+        client.query("SELECT $1::INT4", &[&i]).unwrap();
+    }
+    assert_eq!(prepared_count(&mut client, "SELECT $1::INT4"), 0);
+
+    client.set_statement_cache_capacity(10);
+    assert_eq!(client.statement_cache_capacity(), 10);
+
+    for i in 0..3i32 {
+        client.query("SELECT $1::INT4", &[&i]).unwrap();
+    }
+    assert_eq!(prepared_count(&mut client, "SELECT $1::INT4"), 1);
+
+    let a = client.prepare_cached("SELECT $1::INT4").unwrap();
+    let mut transaction = client.transaction().unwrap();
+    let b = transaction.prepare_cached("SELECT $1::INT4").unwrap();
+    assert_eq!(a.name(), b.name());
+    transaction.commit().unwrap();
+    drop((a, b));
+
+    client.clear_statement_cache();
+    assert_eq!(prepared_count(&mut client, "SELECT $1::INT4"), 0);
+}
+
 #[test]
 fn copy_in() {
     let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
@@ -174,6 +410,38 @@ fn copy_in() {
     assert_eq!(rows[1].get::<_, &str>(1), "timothy");
 }
 
+#[test]
+fn copy_in_writer() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    client
+        .simple_query("CREATE TEMPORARY TABLE foo (id INT, name TEXT)")
+        .unwrap();
+
+    let mut writer = client.copy_in_writer("COPY foo FROM stdin", &[]).unwrap();
+    for i in 0..10_000 {
+        writeln!(writer, "{}\tname {}", i, i).unwrap();
+    }
+    assert_eq!(writer.finish().unwrap(), 10_000);
+
+    let mut writer = client.copy_in_writer("COPY foo FROM stdin", &[]).unwrap();
+    writer.write_all(b"1\tjohn\n").unwrap();
+    drop(writer);
+
+    let mut transaction = client.transaction().unwrap();
+    let mut writer = transaction
+        .copy_in_writer("COPY foo FROM stdin", &[])
+        .unwrap();
+    writer.write_all(b"bogus\n").unwrap();
+    writer.finish().unwrap_err();
+    drop(transaction);
+
+    let count = client
+        .query_scalar::<_, i64>("SELECT count(*) FROM foo", &[])
+        .unwrap();
+    assert_eq!(count, 10_000);
+}
+
 #[test]
 fn copy_out() {
     let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
@@ -224,6 +492,57 @@ fn portal() {
     assert_eq!(rows[0].get::<_, i32>(0), 3);
 }
 
+#[test]
+fn portal_chunks() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    let mut transaction = client.transaction().unwrap();
+
+    let portal = transaction
+        .bind("SELECT generate_series(1, 5)", &[])
+        .unwrap();
+
+    let sizes = transaction
+        .query_portal_chunks(&portal, 2)
+        .map(|rows| Ok(rows.len()))
+        .collect::<Vec<_>>()
+        .unwrap();
+    assert_eq!(sizes, vec![2, 2, 1]);
+}
+
+#[test]
+fn lazy_query() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    client
+        .simple_query(
+            "CREATE TEMPORARY TABLE foo (id INT);
+             INSERT INTO foo (id) SELECT generate_series(1, 5);",
+        )
+        .unwrap();
+
+    let mut transaction = client.transaction().unwrap();
+    let stmt = transaction
+        .prepare("SELECT id FROM foo WHERE id > $1 ORDER BY id")
+        .unwrap();
+
+    for &fetch_size in &[1, 2, 4, 0] {
+        let ids = transaction
+            .lazy_query(&stmt, &[&1i32], fetch_size)
+            .unwrap()
+            .map(|r| r.try_get::<_, i32>(0))
+            .collect::<Vec<_>>()
+            .unwrap();
+        assert_eq!(ids, [2, 3, 4, 5]);
+    }
+
+    let mut rows = transaction.lazy_query(&stmt, &[&0i32], 3).unwrap();
+    assert_eq!(rows.next().unwrap().unwrap().get::<_, i32>(0), 1);
+    drop(rows);
+
+    transaction.execute("SELECT 1", &[]).unwrap();
+}
+
 #[test]
 fn custom_executor() {
     let runtime = Runtime::new().unwrap();
@@ -241,3 +560,210 @@ fn custom_executor() {
     drop(runtime);
     assert!(client.is_closed());
 }
+
+#[test]
+fn owned_runtime() {
+    let mut config = "host=localhost port=5433 user=postgres"
+        .parse::<crate::Config>()
+        .unwrap();
+    config.runtime(Runtime::new().unwrap());
+
+    let mut client = config.connect(NoTls).unwrap();
+    drop(config);
+
+    let rows = client.query("SELECT $1::TEXT", &[&"hello"]).unwrap();
+    assert_eq!(rows[0].get::<_, &str>(0), "hello");
+}
+
+#[test]
+fn connect_within_runtime() {
+    let runtime = Runtime::new().unwrap();
+    runtime.block_on(async {
+        let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+        client.ping().unwrap();
+    });
+}
+
+#[test]
+fn config_builder() {
+    let notices = Arc::new(Mutex::new(vec![]));
+
+    let mut config = crate::Config::new();
+    config
+        .host("localhost")
+        .port(5433)
+        .user("postgres")
+        .application_name("sync-builder")
+        .ssl_mode(SslMode::Disable)
+        .keepalives_idle(Duration::from_secs(30))
+        .connect_timeout(Duration::from_secs(10))
+        .load_balance_hosts(LoadBalanceHosts::Random)
+        .warm_up_statement("SELECT 1");
+    let notices2 = notices.clone();
+    config
+        .notice_callback(move |notice| notices2.lock().unwrap().push(notice.message().to_string()));
+
+    let mut client = config.connect(NoTls).unwrap();
+    let name = client
+        .query_scalar::<_, String>("SELECT current_setting('application_name')", &[])
+        .unwrap();
+    assert_eq!(name, "sync-builder");
+
+    client
+        .batch_execute("DO $$BEGIN RAISE NOTICE 'hello'; END$$")
+        .unwrap();
+    client.ping().unwrap();
+    assert_eq!(*notices.lock().unwrap(), ["hello"]);
+}
+
+#[test]
+fn cancel_query() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    let cancel_token = client.cancel_token();
+    let cancel_thread = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        cancel_token.cancel_query(NoTls).unwrap();
+    });
+
+    match client.batch_execute("SELECT pg_sleep(100)") {
+        Err(e) if e.code() == Some(&SqlState::QUERY_CANCELED) => {}
+        t => panic!("unexpected return: {:?}", t),
+    }
+
+    cancel_thread.join().unwrap();
+}
+
+#[test]
+fn pipeline() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT4 PRIMARY KEY)")
+        .unwrap();
+
+    let insert = client.prepare("INSERT INTO foo (id) VALUES ($1)").unwrap();
+    let select = client.prepare("SELECT id FROM foo ORDER BY id").unwrap();
+
+    let mut pipeline = client.pipeline();
+    pipeline.execute(&insert, &[&1i32]);
+    pipeline.execute(&insert, &[&2i32]);
+    pipeline.query(&select, &[]);
+    assert_eq!(pipeline.len(), 3);
+
+    let mut responses = pipeline.send().unwrap().into_iter();
+    match responses.next().unwrap().unwrap() {
+        PipelineResponse::RowsAffected(1) => {}
+        _ => panic!("unexpected response"),
+    }
+    match responses.next().unwrap().unwrap() {
+        PipelineResponse::RowsAffected(1) => {}
+        _ => panic!("unexpected response"),
+    }
+    match responses.next().unwrap().unwrap() {
+        PipelineResponse::Rows(rows) => {
+            assert_eq!(rows.len(), 2);
+            assert_eq!(rows[0].get::<_, i32>(0), 1);
+            assert_eq!(rows[1].get::<_, i32>(0), 2);
+        }
+        _ => panic!("unexpected response"),
+    }
+    assert!(responses.next().is_none());
+
+    let mut pipeline = client.pipeline();
+    pipeline.execute(&insert, &[&3i32]);
+    pipeline.execute(&insert, &[&1i32]);
+    pipeline.query(&select, &[]);
+
+    let responses = pipeline.send().unwrap();
+    assert_eq!(responses.len(), 3);
+    assert!(responses[0].is_ok());
+    match &responses[1] {
+        Err(e) if e.code() == Some(&SqlState::UNIQUE_VIOLATION) => {}
+        _ => panic!("unexpected response"),
+    }
+    assert!(responses[2].is_err());
+
+    let rows = client.query(&select, &[]).unwrap();
+    assert_eq!(rows.len(), 2);
+}
+
+#[test]
+fn notification_handler() {
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+
+    let mut client = "host=localhost port=5433 user=postgres"
+        .parse::<Config>()
+        .unwrap()
+        .notification_handler(16, move |n| {
+            tx.lock()
+                .unwrap()
+                .send((n.channel().to_string(), n.payload().to_string()))
+                .unwrap();
+        })
+        .connect(NoTls)
+        .unwrap();
+
+    client
+        .batch_execute(
+            "LISTEN test_notification_handler; NOTIFY test_notification_handler, 'hello'",
+        )
+        .unwrap();
+
+    let (channel, payload) = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(channel, "test_notification_handler");
+    assert_eq!(payload, "hello");
+}
+
+#[test]
+fn reconnecting_client() {
+    let config = "host=localhost port=5433 user=postgres"
+        .parse::<Config>()
+        .unwrap();
+    let mut client = ReconnectingClient::new(config, NoTls);
+    client.setup_statement("SET application_name = 'reconnecting'");
+    assert!(!client.is_connected());
+
+    let pid = client.client().unwrap().backend_pid();
+    assert!(client.is_connected());
+
+    let mut other = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+    other
+        .batch_execute(&format!("SELECT pg_terminate_backend({})", pid))
+        .unwrap();
+
+    while other
+        .query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM pg_stat_activity WHERE pid = $1)",
+            &[&pid],
+        )
+        .unwrap()
+    {
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    // depending on whether the client has noticed that its connection closed, this either reconnects up front or
+    // retries after the first attempt fails
+    let name = client
+        .idempotent(|client| {
+            client.query_scalar::<_, String>("SELECT current_setting('application_name')", &[])
+        })
+        .unwrap();
+    assert_eq!(name, "reconnecting");
+    assert_ne!(client.client().unwrap().backend_pid(), pid);
+}
+
+#[test]
+fn reconnecting_client_gives_up() {
+    let config = "host=localhost port=5433 user=postgres dbname=nonexistent"
+        .parse::<Config>()
+        .unwrap();
+    let mut client = ReconnectingClient::new(config, NoTls);
+    client
+        .max_attempts(2)
+        .backoff(Duration::from_millis(10), Duration::from_millis(10));
+
+    let err = client.client().err().unwrap();
+    assert_eq!(err.code(), Some(&SqlState::INVALID_CATALOG_NAME));
+}
@@ -13,20 +13,21 @@ pub trait Prepare {
 
 impl Prepare for Client {
     fn prepare(&mut self, query: &str) -> Result<Statement, Error> {
-        self.prepare(query)
+        self.prepare_cached(query)
     }
 }
 
 impl<'a> Prepare for Transaction<'a> {
     fn prepare(&mut self, query: &str) -> Result<Statement, Error> {
-        self.prepare(query)
+        self.prepare_cached(query)
     }
 }
 
 /// A trait abstracting over prepared and unprepared statements.
 ///
 /// Many methods are generic over this bound, so that they support both a raw query string as well as a statement which
-/// was prepared previously.
+/// was prepared previously. Raw query strings are prepared through the client's statement cache, if it has been
+/// enabled with `Client::set_statement_cache_capacity`.
 ///
 /// This trait is "sealed" and cannot be implemented by anything outside this crate.
 pub trait ToStatement: sealed::Sealed {
@@ -1,23 +1,30 @@
 use fallible_iterator::FallibleIterator;
+use futures::channel::mpsc;
 use futures::executor;
 use std::io::{BufRead, Read};
-use tokio_postgres::types::{ToSql, Type};
-use tokio_postgres::{Error, Row, SimpleQueryMessage};
+use tokio_postgres::types::{FromSqlOwned, ToSql, Type};
+use tokio_postgres::{BatchOutcome, Error, FromRow, ParamScope, Row, SimpleQueryMessage};
 
+use crate::client::ClientExecutor;
 use crate::copy_in_stream::CopyInStream;
 use crate::copy_out_reader::CopyOutReader;
 use crate::iter::Iter;
-use crate::{Portal, Statement, ToStatement};
+use crate::{
+    CancelToken, CopyInWriter, LazyRows, Pipeline, Portal, RowIter, Statement, ToStatement,
+};
 
 /// A representation of a PostgreSQL database transaction.
 ///
 /// Transactions will implicitly roll back by default when dropped. Use the `commit` method to commit the changes made
 /// in the transaction. Transactions can be nested, with inner transactions implemented via safepoints.
-pub struct Transaction<'a>(tokio_postgres::Transaction<'a>);
+pub struct Transaction<'a>(tokio_postgres::Transaction<'a>, ClientExecutor);
 
 impl<'a> Transaction<'a> {
-    pub(crate) fn new(transaction: tokio_postgres::Transaction<'a>) -> Transaction<'a> {
-        Transaction(transaction)
+    pub(crate) fn new(
+        transaction: tokio_postgres::Transaction<'a>,
+        executor: ClientExecutor,
+    ) -> Transaction<'a> {
+        Transaction(transaction, executor)
     }
 
     /// Consumes the transaction, committing all changes made within it.
@@ -42,6 +49,11 @@ impl<'a> Transaction<'a> {
         executor::block_on(self.0.prepare_typed(query, types))
     }
 
+    /// Like `Client::prepare_cached`.
+    pub fn prepare_cached(&mut self, query: &str) -> Result<Statement, Error> {
+        executor::block_on(self.0.prepare_cached(query))
+    }
+
     /// Like `Client::execute`.
     pub fn execute<T>(&mut self, query: &T, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error>
     where
@@ -51,6 +63,30 @@ impl<'a> Transaction<'a> {
         executor::block_on(self.0.execute(&statement, params))
     }
 
+    /// Like `Client::execute_returning`.
+    pub fn execute_returning<T>(
+        &mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<(u64, Vec<Row>), Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        let statement = query.__statement(self)?;
+        executor::block_on(self.0.execute_returning(&statement, params))
+    }
+
+    /// Like `Client::execute_raw`.
+    pub fn execute_raw<'b, T, I>(&mut self, query: &T, params: I) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement,
+        I: IntoIterator<Item = &'b dyn ToSql>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let statement = query.__statement(self)?;
+        executor::block_on(self.0.execute_iter(&statement, params))
+    }
+
     /// Like `Client::query`.
     pub fn query<T>(&mut self, query: &T, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error>
     where
@@ -59,17 +95,66 @@ impl<'a> Transaction<'a> {
         self.query_iter(query, params)?.collect()
     }
 
+    /// Like `Client::query_as`.
+    pub fn query_as<T, R>(
+        &mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<R>, Error>
+    where
+        T: ?Sized + ToStatement,
+        R: FromRow,
+    {
+        self.query_iter(query, params)?
+            .map(|row| row.try_into())
+            .collect()
+    }
+
+    /// Like `Client::query_scalar`.
+    pub fn query_scalar<T, R>(
+        &mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<R, Error>
+    where
+        T: ?Sized + ToStatement,
+        R: FromSqlOwned,
+    {
+        let statement = query.__statement(self)?;
+        executor::block_on(self.0.query_scalar(&statement, params))
+    }
+
     /// Like `Client::query_iter`.
     pub fn query_iter<T>(
         &mut self,
         query: &T,
         params: &[&(dyn ToSql + Sync)],
-    ) -> Result<impl FallibleIterator<Item = Row, Error = Error>, Error>
+    ) -> Result<RowIter<'_>, Error>
     where
         T: ?Sized + ToStatement,
     {
         let statement = query.__statement(self)?;
-        Ok(Iter::new(self.0.query(&statement, params)))
+        Ok(RowIter::new(self.0.query(&statement, params)))
+    }
+
+    /// Like `Client::query_raw`.
+    pub fn query_raw<'b, T, I>(&mut self, query: &T, params: I) -> Result<RowIter<'_>, Error>
+    where
+        T: ?Sized + ToStatement,
+        I: IntoIterator<Item = &'b dyn ToSql>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let statement = query.__statement(self)?;
+        Ok(RowIter::new(self.0.query_iter(&statement, params)))
+    }
+
+    /// Like `Client::query_typed`.
+    pub fn query_typed(
+        &mut self,
+        query: &str,
+        params: &[(&(dyn ToSql + Sync), Type)],
+    ) -> Result<Vec<Row>, Error> {
+        Iter::new(self.0.query_typed(query, params)).collect()
     }
 
     /// Binds parameters to a statement, creating a "portal".
@@ -104,8 +189,65 @@ impl<'a> Transaction<'a> {
         &mut self,
         portal: &Portal,
         max_rows: i32,
-    ) -> Result<impl FallibleIterator<Item = Row, Error = Error>, Error> {
-        Ok(Iter::new(self.0.query_portal(&portal, max_rows)))
+    ) -> Result<RowIter<'_>, Error> {
+        Ok(RowIter::new(self.0.query_portal(&portal, max_rows)))
+    }
+
+    /// Returns a fallible iterator which executes a portal in chunks of at most `chunk_size` rows.
+    ///
+    /// Each chunk is only requested from the server when the iterator is advanced, and iteration ends when the portal
+    /// has been exhausted. If `chunk_size` is negative or 0, all rows will be returned in a single chunk.
+    pub fn query_portal_chunks(
+        &mut self,
+        portal: &Portal,
+        chunk_size: i32,
+    ) -> impl FallibleIterator<Item = Vec<Row>, Error = Error> {
+        Iter::new(self.0.query_portal_chunks(portal, chunk_size))
+    }
+
+    /// Executes a statement, returning a fallible iterator over the resulting rows which fetches them from the server
+    /// in batches of `fetch_size` rows.
+    ///
+    /// This binds the statement to a portal, and only requests another batch once the iterator has returned all of the
+    /// previous one, so large results can be processed without buffering them in memory or blocking other queries on
+    /// the transaction for longer than it takes to fetch one batch. If `fetch_size` is negative or 0, all rows are
+    /// fetched at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use postgres::{Client, NoTls};
+    /// use fallible_iterator::FallibleIterator;
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    ///
+    /// let mut transaction = client.transaction()?;
+    /// let mut rows = transaction.lazy_query("SELECT foo FROM bar", &[], 1000)?;
+    /// while let Some(row) = rows.next()? {
+    ///     let foo: i32 = row.get("foo");
+    ///     println!("foo: {}", foo);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn lazy_query<T>(
+        &mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+        fetch_size: i32,
+    ) -> Result<LazyRows<'_>, Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        let portal = self.bind(query, params)?;
+        Ok(LazyRows::new(
+            self.0.query_portal_chunks(&portal, fetch_size),
+        ))
     }
 
     /// Like `Client::copy_in`.
@@ -123,6 +265,21 @@ impl<'a> Transaction<'a> {
         executor::block_on(self.0.copy_in(&statement, params, CopyInStream(reader)))
     }
 
+    /// Like `Client::copy_in_writer`.
+    pub fn copy_in_writer<T>(
+        &mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<CopyInWriter<'_>, Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        let statement = query.__statement(self)?;
+        let (sender, receiver) = mpsc::channel(1);
+        let future = self.0.copy_in(&statement, params, receiver);
+        Ok(CopyInWriter::new(future, sender))
+    }
+
     /// Like `Client::copy_out`.
     pub fn copy_out<'b, T>(
         &'a mut self,
@@ -155,9 +312,81 @@ impl<'a> Transaction<'a> {
         executor::block_on(self.0.batch_execute(query))
     }
 
-    /// Like `Client::transaction`.
+    /// Like `Client::batch_execute_results`.
+    pub fn batch_execute_results(&mut self, query: &str) -> Result<Vec<BatchOutcome>, Error> {
+        executor::block_on(self.0.batch_execute_results(query))
+    }
+
+    /// Like `Client::set_param`.
+    pub fn set_param(&mut self, name: &str, value: &str, scope: ParamScope) -> Result<(), Error> {
+        executor::block_on(self.0.set_param(name, value, scope))
+    }
+
+    /// Like `Client::reset_param`.
+    pub fn reset_param(&mut self, name: &str) -> Result<(), Error> {
+        executor::block_on(self.0.reset_param(name))
+    }
+
+    /// Like `Client::reset_all_params`.
+    pub fn reset_all_params(&mut self) -> Result<(), Error> {
+        executor::block_on(self.0.reset_all_params())
+    }
+
+    /// Like `Client::cancel_token`.
+    pub fn cancel_token(&self) -> CancelToken {
+        CancelToken::new(self.0.cancel_token(), self.1.clone())
+    }
+
+    /// Like `Client::pipeline`.
+    pub fn pipeline(&mut self) -> Pipeline {
+        Pipeline::new(self.0.pipeline())
+    }
+
+    /// Like `Client::transaction`, but creates a nested transaction via a savepoint.
+    ///
+    /// Committing the nested transaction releases the savepoint, and rolling it back (explicitly or by dropping it)
+    /// rolls back to the savepoint, leaving the outer transaction usable.
     pub fn transaction(&mut self) -> Result<Transaction<'_>, Error> {
         let transaction = executor::block_on(self.0.transaction())?;
-        Ok(Transaction(transaction))
+        Ok(Transaction(transaction, self.1.clone()))
+    }
+
+    /// Like `Client::transaction`, but creates a nested transaction via a savepoint with the specified name.
+    ///
+    /// The name is quoted as an identifier, so it is case sensitive and can contain any characters.
+    pub fn savepoint<I>(&mut self, name: I) -> Result<Transaction<'_>, Error>
+    where
+        I: Into<String>,
+    {
+        let transaction = executor::block_on(self.0.savepoint(name))?;
+        Ok(Transaction(transaction, self.1.clone()))
+    }
+
+    /// Like `Client::with_transaction`, but runs the closure within a nested transaction.
+    pub fn with_transaction<F, T, E>(&mut self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Transaction<'_>) -> Result<T, E>,
+        E: From<Error>,
+    {
+        let transaction = self.transaction()?;
+        transaction.run(f)
+    }
+
+    // if `f` panics, the transaction is rolled back when it is dropped during unwinding
+    pub(crate) fn run<F, T, E>(mut self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Transaction<'_>) -> Result<T, E>,
+        E: From<Error>,
+    {
+        match f(&mut self) {
+            Ok(value) => {
+                self.commit()?;
+                Ok(value)
+            }
+            Err(e) => {
+                let _ = self.rollback();
+                Err(e)
+            }
+        }
     }
 }
@@ -0,0 +1,501 @@
+//! Utilities for working with the PostgreSQL binary copy format.
+
+use crate::session::escape_identifier;
+use crate::types::{FromSql, IsNull, ToSql, Type, WrongType};
+use crate::{Client, Error};
+use bytes::{Bytes, BytesMut};
+use futures::channel::mpsc;
+use futures::{ready, SinkExt, Stream, StreamExt, TryStreamExt};
+use std::any::type_name;
+use std::error;
+use std::io;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+const MAGIC: &[u8] = b"PGCOPY\n\xff\r\n\0";
+const HEADER_LEN: usize = MAGIC.len() + 4 + 4;
+
+enum Message {
+    Data(Bytes),
+    Finish,
+}
+
+/// A writer which encodes rows of values in the binary copy format.
+///
+/// The writer is created along with a `BinaryCopyInStream`, which should be passed to `Client::copy_in` for a
+/// `COPY ... FROM STDIN (FORMAT binary)` statement. The two must be driven concurrently, for example with `join!`.
+/// Dropping the writer without calling `finish` aborts the copy.
+pub struct BinaryCopyInWriter {
+    sender: mpsc::Sender<Message>,
+    types: Vec<Type>,
+    names: Option<Vec<String>>,
+    buf: BytesMut,
+}
+
+impl BinaryCopyInWriter {
+    /// Creates a new writer for rows with the specified column types.
+    ///
+    /// The types must match those of the columns being copied into, in order.
+    pub fn new(types: &[Type]) -> (BinaryCopyInWriter, BinaryCopyInStream) {
+        BinaryCopyInWriter::with_names(types.to_vec(), None)
+    }
+
+    /// Creates a new writer for rows of the specified columns of a table, looking up their types from the server.
+    ///
+    /// The columns should be listed in the same order as in the `COPY` statement. If no columns are specified, all of
+    /// the table's columns other than generated columns are used, matching a `COPY` statement without a column list.
+    /// The table and column names are included in the lookup query verbatim, so they should be quoted where necessary.
+    ///
+    /// Errors serializing a value identify the column by name.
+    pub async fn for_table(
+        client: &mut Client,
+        table: &str,
+        columns: &[&str],
+    ) -> Result<(BinaryCopyInWriter, BinaryCopyInStream), Error> {
+        let (types, names) = table_columns(client, table, columns).await?;
+        Ok(BinaryCopyInWriter::with_names(types, Some(names)))
+    }
+
+    fn with_names(
+        types: Vec<Type>,
+        names: Option<Vec<String>>,
+    ) -> (BinaryCopyInWriter, BinaryCopyInStream) {
+        let (sender, receiver) = mpsc::channel(1);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(&0i32.to_be_bytes()); // flags
+        buf.extend_from_slice(&0i32.to_be_bytes()); // header extension length
+
+        let writer = BinaryCopyInWriter {
+            sender,
+            types,
+            names,
+            buf,
+        };
+        let stream = BinaryCopyInStream {
+            receiver,
+            done: false,
+        };
+
+        (writer, stream)
+    }
+
+    /// Returns the types of the columns written by the writer.
+    pub fn types(&self) -> &[Type] {
+        &self.types
+    }
+
+    /// Writes a single row.
+    ///
+    /// The number of values and each value's type are checked against the columns, and an error is returned without
+    /// writing anything if the row does not match them, rather than the server rejecting the copy once the data
+    /// reaches it.
+    pub async fn write(&mut self, values: &[&(dyn ToSql + Sync)]) -> Result<(), Error> {
+        if values.len() != self.types.len() {
+            return Err(Error::encode(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "expected {} values but got {}",
+                    self.types.len(),
+                    values.len()
+                ),
+            )));
+        }
+
+        let mut row = vec![];
+        row.extend_from_slice(&(self.types.len() as i16).to_be_bytes());
+        for (i, (value, type_)) in values.iter().zip(&self.types).enumerate() {
+            let base = row.len();
+            row.extend_from_slice(&[0; 4]);
+            let len = match value
+                .to_sql_checked(type_, &mut row)
+                .map_err(|e| self.to_sql_error(e, i))?
+            {
+                IsNull::Yes => -1,
+                IsNull::No => {
+                    let len = row.len() - base - 4;
+                    if len > i32::max_value() as usize {
+                        return Err(self.to_sql_error("value too large to transmit".into(), i));
+                    }
+                    len as i32
+                }
+            };
+            row[base..base + 4].copy_from_slice(&len.to_be_bytes());
+        }
+
+        self.buf.extend_from_slice(&row);
+        if self.buf.len() > 4096 {
+            self.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Completes the copy, writing out any buffered rows.
+    ///
+    /// The `Client::copy_in` future resolves once the server has processed the data.
+    pub async fn finish(mut self) -> Result<(), Error> {
+        self.buf.extend_from_slice(&(-1i16).to_be_bytes());
+        self.flush().await?;
+        self.sender
+            .send(Message::Finish)
+            .await
+            .map_err(|_| Error::closed())
+    }
+
+    fn to_sql_error(&self, e: Box<dyn error::Error + Sync + Send>, idx: usize) -> Error {
+        match &self.names {
+            Some(names) => Error::to_sql_column(e, idx, &names[idx]),
+            None => Error::to_sql(e, idx),
+        }
+    }
+
+    async fn flush(&mut self) -> Result<(), Error> {
+        let data = self.buf.take().freeze();
+        self.sender
+            .send(Message::Data(data))
+            .await
+            .map_err(|_| Error::closed())
+    }
+}
+
+/// The stream of data produced by a `BinaryCopyInWriter`.
+///
+/// The stream returns an error, aborting the copy, if its writer is dropped without calling `finish`.
+pub struct BinaryCopyInStream {
+    receiver: mpsc::Receiver<Message>,
+    done: bool,
+}
+
+impl Stream for BinaryCopyInStream {
+    type Item = Result<Bytes, io::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        match ready!(self.receiver.poll_next_unpin(cx)) {
+            Some(Message::Data(data)) => Poll::Ready(Some(Ok(data))),
+            Some(Message::Finish) => {
+                self.done = true;
+                Poll::Ready(None)
+            }
+            None => {
+                self.done = true;
+                Poll::Ready(Some(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "binary copy writer dropped without finishing",
+                ))))
+            }
+        }
+    }
+}
+
+// looks up the types and names of the columns of a table which would be copied by `COPY table (columns)`
+async fn table_columns(
+    client: &mut Client,
+    table: &str,
+    columns: &[&str],
+) -> Result<(Vec<Type>, Vec<String>), Error> {
+    let columns = if columns.is_empty() {
+        table_copy_columns(client, table).await?.join(", ")
+    } else {
+        columns.join(", ")
+    };
+    // preparing the query resolves the column types without reading anything from the table
+    let statement = client
+        .prepare(&format!("SELECT {} FROM {}", columns, table))
+        .await?;
+
+    let types = statement
+        .columns()
+        .iter()
+        .map(|c| c.type_().clone())
+        .collect();
+    let names = statement
+        .columns()
+        .iter()
+        .map(|c| c.name().to_string())
+        .collect();
+    Ok((types, names))
+}
+
+// looks up the columns copied by `COPY table` without a column list, which unlike `SELECT *` skips generated columns
+async fn table_copy_columns(client: &mut Client, table: &str) -> Result<Vec<String>, Error> {
+    // `attgenerated` was added in Postgres 12, so it's read through `to_jsonb` to handle older servers as well
+    let statement = client
+        .prepare(
+            "SELECT attname FROM pg_catalog.pg_attribute a \
+             WHERE attrelid = $1::TEXT::regclass AND attnum > 0 AND NOT attisdropped \
+             AND coalesce(to_jsonb(a) ->> 'attgenerated', '') = '' \
+             ORDER BY attnum",
+        )
+        .await?;
+    let rows = client
+        .query(&statement, &[&table])
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    rows.iter()
+        .map(|row| Ok(escape_identifier(row.try_get::<_, &str>(0)?)))
+        .collect()
+}
+
+/// A stream of rows decoded from the binary copy format.
+///
+/// It wraps the stream returned by `Client::copy_out` for a `COPY ... TO STDOUT (FORMAT binary)` statement.
+pub struct BinaryCopyOutStream {
+    stream: Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>>,
+    types: Arc<Vec<Type>>,
+    names: Option<Arc<Vec<String>>>,
+    header: bool,
+    done: bool,
+}
+
+impl BinaryCopyOutStream {
+    /// Creates a stream decoding rows with the specified column types from the data in `stream`.
+    ///
+    /// The types must match those of the columns being copied out, in order.
+    pub fn new<S>(types: &[Type], stream: S) -> BinaryCopyOutStream
+    where
+        S: Stream<Item = Result<Bytes, Error>> + Send + 'static,
+    {
+        BinaryCopyOutStream::with_names(types.to_vec(), None, stream)
+    }
+
+    /// Creates a stream decoding rows of the specified columns of a table from the data in `stream`, looking up their
+    /// types from the server.
+    ///
+    /// The columns should be listed in the same order as in the `COPY` statement, as with
+    /// `BinaryCopyInWriter::for_table`. The stream returned by `Client::copy_out` doesn't start the copy until it's
+    /// first polled, so it can be created before the lookup.
+    ///
+    /// Errors deserializing a value identify the column by name.
+    pub async fn for_table<S>(
+        client: &mut Client,
+        table: &str,
+        columns: &[&str],
+        stream: S,
+    ) -> Result<BinaryCopyOutStream, Error>
+    where
+        S: Stream<Item = Result<Bytes, Error>> + Send + 'static,
+    {
+        let (types, names) = table_columns(client, table, columns).await?;
+        Ok(BinaryCopyOutStream::with_names(types, Some(names), stream))
+    }
+
+    fn with_names<S>(types: Vec<Type>, names: Option<Vec<String>>, stream: S) -> BinaryCopyOutStream
+    where
+        S: Stream<Item = Result<Bytes, Error>> + Send + 'static,
+    {
+        BinaryCopyOutStream {
+            stream: Box::pin(stream),
+            types: Arc::new(types),
+            names: names.map(Arc::new),
+            header: false,
+            done: false,
+        }
+    }
+
+    /// Returns the types of the columns decoded by the stream.
+    pub fn types(&self) -> &[Type] {
+        &self.types
+    }
+}
+
+impl Stream for BinaryCopyOutStream {
+    type Item = Result<BinaryCopyOutRow, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        let chunk = match ready!(self.stream.as_mut().poll_next(cx)) {
+            Some(Ok(chunk)) => chunk,
+            Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+            None => return Poll::Ready(Some(Err(invalid("unexpected end of copy data")))),
+        };
+
+        let mut pos = 0;
+        if !self.header {
+            if chunk.len() < HEADER_LEN || &chunk[..MAGIC.len()] != MAGIC {
+                return Poll::Ready(Some(Err(invalid("invalid binary copy header"))));
+            }
+            let flags = read_i32(&chunk[MAGIC.len()..]);
+            if flags & (1 << 16) != 0 {
+                return Poll::Ready(Some(Err(invalid("OIDs are not supported"))));
+            }
+            let extension_len = read_i32(&chunk[MAGIC.len() + 4..]);
+            if extension_len < 0 || chunk.len() < HEADER_LEN + extension_len as usize {
+                return Poll::Ready(Some(Err(invalid("invalid binary copy header"))));
+            }
+            pos = HEADER_LEN + extension_len as usize;
+            self.header = true;
+        }
+
+        let ranges = match parse_row(&chunk[pos..], self.types.len()) {
+            Ok(Some(ranges)) => ranges,
+            Ok(None) => {
+                self.done = true;
+                return Poll::Ready(None);
+            }
+            Err(e) => return Poll::Ready(Some(Err(e))),
+        };
+
+        Poll::Ready(Some(Ok(BinaryCopyOutRow {
+            buf: chunk,
+            ranges: ranges
+                .into_iter()
+                .map(|r| r.map(|r| r.start + pos..r.end + pos))
+                .collect(),
+            types: self.types.clone(),
+            names: self.names.clone(),
+        })))
+    }
+}
+
+// rows are sent one per CopyData message, so each chunk holds exactly one row or the trailer
+fn parse_row(mut buf: &[u8], columns: usize) -> Result<Option<Vec<Option<Range<usize>>>>, Error> {
+    if buf.len() < 2 {
+        return Err(invalid("unexpected end of row"));
+    }
+    let len = i16::from_be_bytes([buf[0], buf[1]]);
+    if len == -1 {
+        return Ok(None);
+    }
+    if len as usize != columns {
+        return Err(invalid("unexpected number of columns"));
+    }
+    buf = &buf[2..];
+
+    let mut pos = 2;
+    let mut ranges = Vec::with_capacity(columns);
+    for _ in 0..columns {
+        if buf.len() < 4 {
+            return Err(invalid("unexpected end of row"));
+        }
+        let len = read_i32(buf);
+        buf = &buf[4..];
+        pos += 4;
+
+        if len < 0 {
+            ranges.push(None);
+            continue;
+        }
+        let len = len as usize;
+        if buf.len() < len {
+            return Err(invalid("unexpected end of row"));
+        }
+        ranges.push(Some(pos..pos + len));
+        buf = &buf[len..];
+        pos += len;
+    }
+
+    if !buf.is_empty() {
+        return Err(invalid("unexpected data after row"));
+    }
+
+    Ok(Some(ranges))
+}
+
+fn read_i32(buf: &[u8]) -> i32 {
+    i32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]])
+}
+
+fn invalid(message: &str) -> Error {
+    Error::parse(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+/// A row of data parsed from a binary copy.
+///
+/// Values are deserialized with `FromSql` using the column types provided to the `BinaryCopyOutStream`, in the same
+/// way as the values of a `Row`.
+pub struct BinaryCopyOutRow {
+    buf: Bytes,
+    ranges: Vec<Option<Range<usize>>>,
+    types: Arc<Vec<Type>>,
+    names: Option<Arc<Vec<String>>>,
+}
+
+impl BinaryCopyOutRow {
+    /// Determines if the row contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of values in the row.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Returns the types of the columns of the row.
+    pub fn types(&self) -> &[Type] {
+        &self.types
+    }
+
+    /// Deserializes a value from the row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds or if the value cannot be converted to the specified type.
+    pub fn get<'a, T>(&'a self, idx: usize) -> T
+    where
+        T: FromSql<'a>,
+    {
+        match self.try_get(idx) {
+            Ok(ok) => ok,
+            Err(err) => panic!("error retrieving column {}: {}", idx, err),
+        }
+    }
+
+    /// Like `BinaryCopyOutRow::get`, but returns a `Result` rather than panicking.
+    pub fn try_get<'a, T>(&'a self, idx: usize) -> Result<T, Error>
+    where
+        T: FromSql<'a>,
+    {
+        let ty = match self.types.get(idx) {
+            Some(ty) => ty,
+            None => return Err(Error::column(idx.to_string())),
+        };
+
+        if !T::accepts(ty) {
+            return Err(self.from_sql_error(Box::new(WrongType::new::<T>(ty.clone())), idx));
+        }
+
+        let buf = self.ranges[idx].clone().map(|r| &self.buf[r]);
+        FromSql::from_sql_nullable(ty, buf).map_err(|e| match &self.names {
+            Some(names) => Error::from_sql_value(e, idx, &names[idx], ty, type_name::<T>()),
+            None => Error::from_sql(e, idx),
+        })
+    }
+
+    /// Returns the raw value of a `BYTEA` column as a `Bytes` which shares the row's underlying buffer.
+    ///
+    /// Unlike `BinaryCopyOutRow::try_get::<Bytes>`, this does not copy the value.
+    pub fn try_get_bytes(&self, idx: usize) -> Result<Option<Bytes>, Error> {
+        let ty = match self.types.get(idx) {
+            Some(ty) => ty,
+            None => return Err(Error::column(idx.to_string())),
+        };
+
+        if !<Bytes as FromSql>::accepts(ty) {
+            return Err(self.from_sql_error(Box::new(WrongType::new::<Bytes>(ty.clone())), idx));
+        }
+
+        Ok(self.ranges[idx]
+            .clone()
+            .map(|r| self.buf.slice(r.start, r.end)))
+    }
+
+    fn from_sql_error(&self, e: Box<dyn error::Error + Sync + Send>, idx: usize) -> Error {
+        match &self.names {
+            Some(names) => Error::from_sql_column(e, idx, &names[idx]),
+            None => Error::from_sql(e, idx),
+        }
+    }
+}
@@ -32,13 +32,17 @@ pub struct PendingBind {
     name: String,
 }
 
-pub fn encode<'a, I>(statement: &Statement, params: I) -> Result<PendingBind, Error>
+pub fn encode<'a, I>(
+    client: &InnerClient,
+    statement: &Statement,
+    params: I,
+) -> Result<PendingBind, Error>
 where
     I: IntoIterator<Item = &'a dyn ToSql>,
     I::IntoIter: ExactSizeIterator,
 {
     let name = format!("p{}", NEXT_ID.fetch_add(1, Ordering::SeqCst));
-    let mut buf = query::encode_bind(statement, params, &name)?;
+    let mut buf = query::encode_bind(client, statement, params, &name)?;
     frontend::sync(&mut buf);
 
     Ok(PendingBind { buf, name })
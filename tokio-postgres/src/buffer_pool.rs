@@ -0,0 +1,38 @@
+use parking_lot::Mutex;
+
+// enough for the requests of a busy pipeline to be in flight without allocating
+const MAX_BUFFERS: usize = 16;
+// larger buffers, such as those of bulk inserts, are freed rather than holding on to their memory
+const MAX_CAPACITY: usize = 64 * 1024;
+
+// A pool of the buffers frontend messages are serialized into, shared by a client and its connection.
+//
+// Requests take a buffer from the pool to encode their messages, and the connection returns it once the messages have
+// been written to the socket, so that steady traffic reuses the same allocations.
+pub struct BufferPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+}
+
+impl BufferPool {
+    pub fn new() -> BufferPool {
+        BufferPool {
+            buffers: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn get(&self) -> Vec<u8> {
+        self.buffers.lock().pop().unwrap_or_default()
+    }
+
+    pub fn put(&self, mut buf: Vec<u8>) {
+        if buf.capacity() == 0 || buf.capacity() > MAX_CAPACITY {
+            return;
+        }
+        buf.clear();
+
+        let mut buffers = self.buffers.lock();
+        if buffers.len() < MAX_BUFFERS {
+            buffers.push(buf);
+        }
+    }
+}
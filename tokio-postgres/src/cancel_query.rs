@@ -1,8 +1,13 @@
 use crate::client::SocketConfig;
 use crate::config::{Host, SslMode};
 use crate::tls::MakeTlsConnect;
-use crate::{cancel_query_raw, connect_socket, Error, Socket};
+use crate::{cancel_query_raw, connect_socket, CancelToken, Error, Socket};
+use futures::future::{self, Either};
+use log::debug;
+use std::future::Future;
 use std::io;
+use std::time::{Duration, Instant};
+use tokio::timer;
 
 pub(crate) async fn cancel_query<T>(
     config: Option<SocketConfig>,
@@ -37,11 +42,41 @@ where
     let socket = connect_socket::connect_socket(
         &config.host,
         config.port,
+        config.hostaddr,
         config.connect_timeout,
         config.keepalives,
         config.keepalives_idle,
+        config.tcp_user_timeout,
     )
     .await?;
 
     cancel_query_raw::cancel_query_raw(socket, ssl_mode, tls, process_id, secret_key).await
 }
+
+// Runs a request on the connection associated with `token`, cancelling it if it does not complete within `timeout`.
+pub(crate) async fn with_timeout<F, R, T>(
+    future: F,
+    timeout: Duration,
+    token: CancelToken,
+    tls: T,
+) -> Result<R, Error>
+where
+    F: Future<Output = Result<R, Error>>,
+    T: MakeTlsConnect<Socket>,
+{
+    let mut future = Box::pin(future);
+    let delay = timer::delay(Instant::now() + timeout);
+    if let Either::Left((r, _)) = future::select(future.as_mut(), delay).await {
+        return r;
+    }
+
+    match token.cancel_query(tls).await {
+        // wait for the server to abort the request so the cancellation can't hit one made after it
+        Ok(()) => {
+            let _ = future.await;
+        }
+        Err(e) => debug!("error cancelling timed out request: {}", e),
+    }
+
+    Err(Error::timeout())
+}
@@ -0,0 +1,70 @@
+#[cfg(feature = "runtime")]
+use crate::client::SocketConfig;
+use crate::config::SslMode;
+#[cfg(feature = "runtime")]
+use crate::tls::MakeTlsConnect;
+use crate::tls::TlsConnect;
+#[cfg(feature = "runtime")]
+use crate::{cancel_query, Socket};
+use crate::{cancel_query_raw, Error};
+use std::future::Future;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// The capability to request cancellation of in-progress queries on a connection.
+///
+/// Tokens are obtained from `Client::cancel_token`, and can be cloned and moved to other tasks independently of the
+/// client they were created from.
+#[derive(Clone)]
+pub struct CancelToken {
+    #[cfg(feature = "runtime")]
+    pub(crate) socket_config: Option<SocketConfig>,
+    pub(crate) ssl_mode: SslMode,
+    pub(crate) process_id: i32,
+    pub(crate) secret_key: i32,
+}
+
+impl CancelToken {
+    /// Attempts to cancel the in-progress query on the connection associated with this `CancelToken`.
+    ///
+    /// A new connection is opened to the same server as the original, using the same TLS mode. The server provides no
+    /// information about whether a cancellation attempt was successful or not. An error will only be returned if the
+    /// client was unable to connect to the database.
+    ///
+    /// Cancellation is inherently racy. There is no guarantee that the cancellation request will reach the server
+    /// before the query terminates normally, or that the connection associated with this token is still active.
+    ///
+    /// Requires the `runtime` Cargo feature (enabled by default).
+    #[cfg(feature = "runtime")]
+    pub fn cancel_query<T>(&self, tls: T) -> impl Future<Output = Result<(), Error>>
+    where
+        T: MakeTlsConnect<Socket>,
+    {
+        cancel_query::cancel_query(
+            self.socket_config.clone(),
+            self.ssl_mode,
+            tls,
+            self.process_id,
+            self.secret_key,
+        )
+    }
+
+    /// Like `cancel_query`, but uses a stream which is already connected to the server rather than opening a new
+    /// connection itself.
+    pub fn cancel_query_raw<S, T>(
+        &self,
+        stream: S,
+        tls: T,
+    ) -> impl Future<Output = Result<(), Error>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+        T: TlsConnect<S>,
+    {
+        cancel_query_raw::cancel_query_raw(
+            stream,
+            self.ssl_mode,
+            tls,
+            self.process_id,
+            self.secret_key,
+        )
+    }
+}
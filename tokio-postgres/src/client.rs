@@ -1,28 +1,40 @@
+use crate::buffer_pool::BufferPool;
 #[cfg(feature = "runtime")]
 use crate::cancel_query;
-use crate::codec::BackendMessages;
+use crate::codec::{BackendMessages, FrontendMessage};
 use crate::config::{Host, SslMode};
 use crate::connection::{Request, RequestMessages};
+use crate::interceptor::{self, Intercepted};
+use crate::parameters::Parameters;
+use crate::statement_cache::StatementCache;
 #[cfg(feature = "runtime")]
 use crate::tls::MakeTlsConnect;
 use crate::tls::TlsConnect;
-use crate::types::{Oid, ToSql, Type};
+use crate::types::{FromSqlOwned, Oid, ToSql, Type};
 #[cfg(feature = "runtime")]
 use crate::Socket;
-use crate::{cancel_query_raw, copy_in, copy_out, query, Transaction};
-use crate::{prepare, SimpleQueryMessage};
-use crate::{simple_query, Row};
-use crate::{Error, Statement};
+use crate::{
+    cancel_query_raw, copy_both, copy_in, copy_out, query, CancelToken, Pipeline, Transaction,
+    TransactionBuilder,
+};
+use crate::{prepare, session, ParamScope, SimpleQueryMessage};
+use crate::{simple_query, FromRow, Row, RowStream};
+use crate::{
+    BatchOutcome, CopyBothDuplex, CopyOutReader, CursorBuilder, Error, Interceptor,
+    ParameterChange, Statement,
+};
 use bytes::{Bytes, IntoBuf};
 use fallible_iterator::FallibleIterator;
 use futures::channel::mpsc;
-use futures::{future, Stream, TryStream};
+use futures::{future, Stream, TryStream, TryStreamExt};
 use futures::{ready, StreamExt};
 use parking_lot::Mutex;
 use postgres_protocol::message::backend::Message;
+use postgres_protocol::message::frontend;
 use std::collections::HashMap;
 use std::error;
 use std::future::Future;
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
@@ -59,10 +71,13 @@ struct State {
     typeinfo_composite: Option<Statement>,
     typeinfo_enum: Option<Statement>,
     types: HashMap<Oid, Type>,
+    statement_cache: StatementCache,
+    interceptor: Option<Arc<dyn Interceptor>>,
 }
 
 pub struct InnerClient {
     sender: mpsc::UnboundedSender<Request>,
+    buffers: Arc<BufferPool>,
     state: Mutex<State>,
 }
 
@@ -80,6 +95,12 @@ impl InnerClient {
         })
     }
 
+    // returns an empty buffer to serialize a request's messages into, which the connection recycles once it has sent
+    // them
+    pub fn buffer(&self) -> Vec<u8> {
+        self.buffers.get()
+    }
+
     pub fn typeinfo(&self) -> Option<Statement> {
         self.state.lock().typeinfo.clone()
     }
@@ -111,15 +132,46 @@ impl InnerClient {
     pub fn set_type(&self, oid: Oid, type_: &Type) {
         self.state.lock().types.insert(oid, type_.clone());
     }
+
+    pub fn cached_statement(&self, query: &str) -> Option<Statement> {
+        self.state.lock().statement_cache.get(query)
+    }
+
+    pub fn cache_statement(&self, query: &str, statement: &Statement) {
+        self.state.lock().statement_cache.insert(query, statement);
+    }
+
+    pub fn invalidate_statement(&self, statement: &Statement) {
+        self.state.lock().statement_cache.remove(statement);
+    }
+
+    pub fn interceptor(&self) -> Option<Arc<dyn Interceptor>> {
+        self.state.lock().interceptor.clone()
+    }
+
+    pub fn set_interceptor(&self, interceptor: Arc<dyn Interceptor>) {
+        self.state.lock().interceptor = Some(interceptor);
+    }
+
+    // Notifies the interceptor, if there is one, that a query is starting.
+    pub fn intercept<'a, I>(&self, query: &str, params: I) -> Option<Intercepted>
+    where
+        I: IntoIterator<Item = &'a dyn ToSql>,
+    {
+        self.interceptor()
+            .map(|i| Intercepted::new(i, query, params))
+    }
 }
 
 #[derive(Clone)]
 pub(crate) struct SocketConfig {
     pub host: Host,
     pub port: u16,
+    pub hostaddr: Option<IpAddr>,
     pub connect_timeout: Option<Duration>,
     pub keepalives: bool,
     pub keepalives_idle: Duration,
+    pub tcp_user_timeout: Option<Duration>,
 }
 
 /// An asynchronous PostgreSQL client.
@@ -128,6 +180,7 @@ pub(crate) struct SocketConfig {
 /// through this client object.
 pub struct Client {
     inner: Arc<InnerClient>,
+    parameters: Arc<Parameters>,
     #[cfg(feature = "runtime")]
     socket_config: Option<SocketConfig>,
     ssl_mode: SslMode,
@@ -138,6 +191,8 @@ pub struct Client {
 impl Client {
     pub(crate) fn new(
         sender: mpsc::UnboundedSender<Request>,
+        buffers: Arc<BufferPool>,
+        parameters: Arc<Parameters>,
         ssl_mode: SslMode,
         process_id: i32,
         secret_key: i32,
@@ -145,13 +200,17 @@ impl Client {
         Client {
             inner: Arc::new(InnerClient {
                 sender,
+                buffers,
                 state: Mutex::new(State {
                     typeinfo: None,
                     typeinfo_composite: None,
                     typeinfo_enum: None,
                     types: HashMap::new(),
+                    statement_cache: StatementCache::new(),
+                    interceptor: None,
                 }),
             }),
+            parameters,
             #[cfg(feature = "runtime")]
             socket_config: None,
             ssl_mode,
@@ -189,34 +248,166 @@ impl Client {
         prepare::prepare(self.inner(), query, parameter_types)
     }
 
+    /// Like `prepare`, but reuses a statement previously prepared for the same query if one is in the client's
+    /// statement cache.
+    ///
+    /// The cache is disabled by default, in which case this is equivalent to `prepare`. Use
+    /// `set_statement_cache_capacity` to enable it.
+    pub fn prepare_cached(
+        &mut self,
+        query: &str,
+    ) -> impl Future<Output = Result<Statement, Error>> {
+        prepare::prepare_cached(self.inner(), query)
+    }
+
+    /// Sets the maximum number of statements held in the client's statement cache, evicting the least recently used
+    /// statements if it currently holds more.
+    ///
+    /// A capacity of 0, the default, disables the cache.
+    ///
+    /// Statements are removed from the cache automatically if a schema change causes the server to reject them with a
+    /// "cached plan must not change result type" error, so a retry of the query will prepare it again.
+    pub fn set_statement_cache_capacity(&mut self, capacity: usize) {
+        self.inner
+            .state
+            .lock()
+            .statement_cache
+            .set_capacity(capacity);
+    }
+
+    /// Returns the maximum number of statements held in the client's statement cache.
+    pub fn statement_cache_capacity(&self) -> usize {
+        self.inner.state.lock().statement_cache.capacity()
+    }
+
+    /// Removes all statements from the client's statement cache.
+    pub fn clear_statement_cache(&mut self) {
+        self.inner.state.lock().statement_cache.clear();
+    }
+
+    /// Sets an interceptor which is invoked around each query made by the client, replacing any set previously.
+    ///
+    /// See the `Interceptor` trait for details.
+    pub fn set_interceptor<I>(&mut self, interceptor: I)
+    where
+        I: Interceptor + 'static,
+    {
+        self.inner.set_interceptor(Arc::new(interceptor));
+    }
+
     /// Executes a statement, returning a stream of the resulting rows.
     ///
     /// # Panics
     ///
     /// Panics if the number of parameters provided does not match the number expected.
-    pub fn query(
+    pub fn query(&mut self, statement: &Statement, params: &[&(dyn ToSql + Sync)]) -> RowStream {
+        let intercepted = self
+            .inner
+            .intercept(statement.query(), params.iter().map(|s| *s as _));
+        let buf = query::encode(&self.inner, statement, params.iter().map(|s| *s as _));
+        query::query(self.inner(), statement.clone(), buf).intercept(intercepted)
+    }
+
+    /// Like [`query`], but converts each row into a value implementing `FromRow`.
+    ///
+    /// [`query`]: #method.query
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    pub fn query_as<T>(
         &mut self,
         statement: &Statement,
         params: &[&(dyn ToSql + Sync)],
-    ) -> impl Stream<Item = Result<Row, Error>> {
-        let buf = query::encode(statement, params.iter().map(|s| *s as _));
-        query::query(self.inner(), statement.clone(), buf)
+    ) -> impl Stream<Item = Result<T, Error>>
+    where
+        T: FromRow,
+    {
+        self.query(statement, params)
+            .map(|r| r.and_then(|row| row.try_into()))
     }
 
-    /// Like [`query`], but takes an iterator of parameters rather than a slice.
+    /// Like [`query`], but returns the single value of a query which returns one row with one column, such as
+    /// `SELECT count(*) FROM foo`.
+    ///
+    /// An error is returned if the statement does not return exactly one column, in which case it is not executed, or
+    /// if the query does not return exactly one row.
     ///
     /// [`query`]: #method.query
-    pub fn query_iter<'a, I>(
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    pub async fn query_scalar<T>(
         &mut self,
         statement: &Statement,
-        params: I,
-    ) -> impl Stream<Item = Result<Row, Error>>
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<T, Error>
+    where
+        T: FromSqlOwned,
+    {
+        if statement.columns().len() != 1 {
+            return Err(Error::column_count());
+        }
+
+        let mut rows = self.query(statement, params);
+        let row = match rows.try_next().await? {
+            Some(row) => row,
+            None => return Err(Error::row_count()),
+        };
+        if rows.try_next().await?.is_some() {
+            return Err(Error::row_count());
+        }
+
+        row.try_get(0)
+    }
+
+    /// Like [`query`], but takes an iterator of parameters rather than a slice.
+    ///
+    /// [`query`]: #method.query
+    pub fn query_iter<'a, I>(&mut self, statement: &Statement, params: I) -> RowStream
     where
         I: IntoIterator<Item = &'a dyn ToSql>,
         I::IntoIter: ExactSizeIterator,
     {
-        let buf = query::encode(statement, params);
-        query::query(self.inner(), statement.clone(), buf)
+        let (buf, intercepted) = query::encode_intercepted(&self.inner, statement, params);
+        query::query(self.inner(), statement.clone(), buf).intercept(intercepted)
+    }
+
+    /// Executes a query without first preparing it, returning a stream of the resulting rows.
+    ///
+    /// The type of each parameter is specified alongside its value, so the query can be parsed, bound, and executed
+    /// in a single round trip rather than first waiting for the server to describe the statement. The query may
+    /// contain parameters (indicated by `$1`, `$2`, etc) just like a prepared statement.
+    ///
+    /// The types of the returned columns are resolved from the server's description of the results. If any of them
+    /// is not yet known to the client, the entire result set is buffered while it is looked up.
+    pub fn query_typed(
+        &mut self,
+        query: &str,
+        params: &[(&(dyn ToSql + Sync), Type)],
+    ) -> RowStream {
+        let intercepted = self
+            .inner
+            .intercept(query, params.iter().map(|(s, _)| *s as _));
+        query::query_typed(self.inner(), query, params).intercept(intercepted)
+    }
+
+    /// Executes a statement which returns rows, such as an `INSERT`, `UPDATE`, or `DELETE` with a `RETURNING` clause,
+    /// returning both the number of rows modified and the rows returned.
+    ///
+    /// The number of rows is the one reported by the server, as returned by `execute`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    pub fn execute_returning(
+        &mut self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> impl Future<Output = Result<(u64, Vec<Row>), Error>> {
+        let stream = self.query(statement, params);
+        query::execute_returning(stream)
     }
 
     /// Executes a statement, returning the number of rows modified.
@@ -231,8 +422,14 @@ impl Client {
         statement: &Statement,
         params: &[&(dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<u64, Error>> {
-        let buf = query::encode(statement, params.iter().map(|s| *s as _));
-        query::execute(self.inner(), buf)
+        let intercepted = self
+            .inner
+            .intercept(statement.query(), params.iter().map(|s| *s as _));
+        let buf = query::encode(&self.inner, statement, params.iter().map(|s| *s as _));
+        interceptor::execute(
+            intercepted,
+            query::execute(self.inner(), statement.clone(), buf),
+        )
     }
 
     /// Like [`execute`], but takes an iterator of parameters rather than a slice.
@@ -247,8 +444,125 @@ impl Client {
         I: IntoIterator<Item = &'a dyn ToSql>,
         I::IntoIter: ExactSizeIterator,
     {
-        let buf = query::encode(statement, params);
-        query::execute(self.inner(), buf)
+        let (buf, intercepted) = query::encode_intercepted(&self.inner, statement, params);
+        interceptor::execute(
+            intercepted,
+            query::execute(self.inner(), statement.clone(), buf),
+        )
+    }
+
+    /// Executes a statement once for each set of parameters, returning the number of rows modified by each execution.
+    ///
+    /// All of the executions are sent to the server in a single request rather than waiting for each to complete
+    /// before sending the next, and run in a single implicit transaction unless an explicit one is active. If any of
+    /// them fails, the error is returned and the changes made by the others are rolled back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters in any set does not match the number expected.
+    pub fn execute_many<'a, I, P>(
+        &mut self,
+        statement: &Statement,
+        params: I,
+    ) -> impl Future<Output = Result<Vec<u64>, Error>>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[&'a (dyn ToSql + Sync)]>,
+    {
+        let buf = query::encode_many(&self.inner, statement, params);
+        query::execute_many(self.inner(), statement.clone(), buf)
+    }
+
+    /// Like [`query`], but aborts the statement if it runs for longer than `timeout`.
+    ///
+    /// The server's `statement_timeout` is set for this statement only, and the previous value is restored once it
+    /// completes. The statement runs in an implicit transaction along with the commands changing the setting unless an
+    /// explicit one is active, so it can't be used for statements which aren't allowed in a transaction block, like
+    /// `VACUUM`. If the timeout elapses, the statement fails with a `QUERY_CANCELED` database error. The timeout is
+    /// rounded up to a whole number of milliseconds, and a timeout of zero disables the limit.
+    ///
+    /// [`query`]: #method.query
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    pub fn query_with_statement_timeout(
+        &mut self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+        timeout: Duration,
+    ) -> RowStream {
+        let intercepted = self
+            .inner
+            .intercept(statement.query(), params.iter().map(|s| *s as _));
+        let buf = query::encode_with_statement_timeout(
+            &self.inner,
+            statement,
+            params.iter().map(|s| *s as _),
+            timeout,
+        );
+        query::query_with_statement_timeout(self.inner(), statement.clone(), buf)
+            .intercept(intercepted)
+    }
+
+    /// Like [`execute`], but aborts the statement if it runs for longer than `timeout`.
+    ///
+    /// See [`query_with_statement_timeout`] for details.
+    ///
+    /// [`execute`]: #method.execute
+    /// [`query_with_statement_timeout`]: #method.query_with_statement_timeout
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    pub fn execute_with_statement_timeout(
+        &mut self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+        timeout: Duration,
+    ) -> impl Future<Output = Result<u64, Error>> {
+        let intercepted = self
+            .inner
+            .intercept(statement.query(), params.iter().map(|s| *s as _));
+        let buf = query::encode_with_statement_timeout(
+            &self.inner,
+            statement,
+            params.iter().map(|s| *s as _),
+            timeout,
+        );
+        interceptor::execute(
+            intercepted,
+            query::execute_with_statement_timeout(self.inner(), statement.clone(), buf),
+        )
+    }
+
+    /// Like [`query`], but collects the resulting rows and gives up if they are not all returned within `timeout`.
+    ///
+    /// When the timeout elapses, a cancellation request is sent to the server over a new connection, as with
+    /// [`cancel_query`], so the server does not keep running the query after the caller has given up on it. The
+    /// returned error's `is_timeout` method returns `true`.
+    ///
+    /// Requires the `runtime` Cargo feature (enabled by default).
+    ///
+    /// [`query`]: #method.query
+    /// [`cancel_query`]: #method.cancel_query
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    #[cfg(feature = "runtime")]
+    pub fn query_with_timeout<T>(
+        &mut self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+        timeout: Duration,
+        tls: T,
+    ) -> impl Future<Output = Result<Vec<Row>, Error>>
+    where
+        T: MakeTlsConnect<Socket>,
+    {
+        let rows = self.query(statement, params).try_collect();
+        cancel_query::with_timeout(rows, timeout, self.cancel_token(), tls)
     }
 
     /// Executes a `COPY FROM STDIN` statement, returning the number of rows created.
@@ -271,7 +585,7 @@ impl Client {
         <S::Ok as IntoBuf>::Buf: 'static + Send,
         S::Error: Into<Box<dyn error::Error + Sync + Send>>,
     {
-        let buf = query::encode(statement, params.iter().map(|s| *s as _));
+        let buf = query::encode(&self.inner, statement, params.iter().map(|s| *s as _));
         copy_in::copy_in(self.inner(), buf, stream)
     }
 
@@ -285,10 +599,33 @@ impl Client {
         statement: &Statement,
         params: &[&(dyn ToSql + Sync)],
     ) -> impl Stream<Item = Result<Bytes, Error>> {
-        let buf = query::encode(statement, params.iter().map(|s| *s as _));
+        let buf = query::encode(&self.inner, statement, params.iter().map(|s| *s as _));
         copy_out::copy_out(self.inner(), buf)
     }
 
+    /// Like `Client::copy_out`, but returns an `AsyncRead` adapter over the data.
+    ///
+    /// This allows the output to be passed directly to utilities like `tokio::io::copy`.
+    pub fn copy_out_reader(
+        &mut self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> CopyOutReader {
+        CopyOutReader::new(self.copy_out(statement, params))
+    }
+
+    /// Executes a statement which enters `COPY BOTH` mode using the simple query protocol, returning a duplex stream
+    /// over the data sent in each direction.
+    ///
+    /// The only statements which use this mode are the `START_REPLICATION` commands of replication connections. The
+    /// `replication` module provides higher level support for them.
+    pub fn copy_both_simple(
+        &mut self,
+        query: &str,
+    ) -> impl Future<Output = Result<CopyBothDuplex, Error>> {
+        copy_both::copy_both_simple(self.inner(), query)
+    }
+
     /// Executes a sequence of SQL statements using the simple query protocol, returning the resulting rows.
     ///
     /// Statements should be separated by semicolons. If an error occurs, execution of the sequence will stop at that
@@ -323,6 +660,32 @@ impl Client {
         simple_query::batch_execute(self.inner(), query)
     }
 
+    /// Like `batch_execute`, but returns the outcome of each statement in the sequence.
+    ///
+    /// The result contains one entry per statement executed, in order. If a statement fails, its error is the last
+    /// entry, so its index identifies the failed statement. An error is only returned directly if the request itself
+    /// failed, for example because the connection was closed.
+    ///
+    /// Note that unless the script itself manages transactions, the statements run in a single implicit transaction,
+    /// so the changes made by statements preceding a failure are rolled back.
+    ///
+    /// # Warning
+    ///
+    /// Prepared statements should be use for any query which contains user-specified data, as they provided the
+    /// functionality to safely embed that data in the request. Do not form statements via string concatenation and pass
+    /// them to this method!
+    pub fn batch_execute_results(
+        &mut self,
+        query: &str,
+    ) -> impl Future<Output = Result<Vec<BatchOutcome>, Error>> {
+        simple_query::batch_execute_results(self.inner(), query)
+    }
+
+    /// Creates a new pipeline of statements to be sent to the server together.
+    pub fn pipeline(&mut self) -> Pipeline {
+        Pipeline::new(self.inner())
+    }
+
     /// Begins a new database transaction.
     ///
     /// The transaction will roll back by default - use the `commit` method to commit it.
@@ -331,6 +694,58 @@ impl Client {
         Ok(Transaction::new(self))
     }
 
+    /// Sets the value of a runtime parameter.
+    ///
+    /// The parameter is set with the `set_config` function, so neither its name nor its value need to be escaped. With
+    /// `ParamScope::Local`, the change only lasts until the end of the current transaction, like `SET LOCAL`.
+    pub fn set_param(
+        &mut self,
+        name: &str,
+        value: &str,
+        scope: ParamScope,
+    ) -> impl Future<Output = Result<(), Error>> {
+        session::set_param(self.inner(), name, value, scope)
+    }
+
+    /// Resets a runtime parameter to its default value, like `RESET`.
+    ///
+    /// The parameter's name is quoted as an identifier.
+    pub fn reset_param(&mut self, name: &str) -> impl Future<Output = Result<(), Error>> {
+        session::reset_param(self.inner(), name)
+    }
+
+    /// Resets all runtime parameters to their default values, like `RESET ALL`.
+    pub fn reset_all_params(&mut self) -> impl Future<Output = Result<(), Error>> {
+        session::reset_all_params(self.inner())
+    }
+
+    /// Returns a builder for a transaction with custom settings.
+    ///
+    /// Unlike the `transaction` method, the builder can be used to control the transaction's isolation level and other
+    /// attributes.
+    pub fn build_transaction(&mut self) -> TransactionBuilder<'_> {
+        TransactionBuilder::new(self)
+    }
+
+    /// Returns a builder for a server-side cursor.
+    ///
+    /// Unless the cursor is made holdable, it must be declared within a transaction - see `Transaction::cursor`.
+    pub fn cursor(&mut self) -> CursorBuilder<'_> {
+        CursorBuilder::new(self)
+    }
+
+    /// Constructs a cancellation token that can later be used to request cancellation of a query running on the
+    /// connection associated with this client.
+    pub fn cancel_token(&self) -> CancelToken {
+        CancelToken {
+            #[cfg(feature = "runtime")]
+            socket_config: self.socket_config.clone(),
+            ssl_mode: self.ssl_mode,
+            process_id: self.process_id,
+            secret_key: self.secret_key,
+        }
+    }
+
     /// Attempts to cancel an in-progress query.
     ///
     /// The server provides no information about whether a cancellation attempt was successful or not. An error will
@@ -371,10 +786,53 @@ impl Client {
         )
     }
 
+    /// Returns the current value of a runtime parameter reported by the server.
+    ///
+    /// The server reports the values of parameters such as `server_version`, `TimeZone`, `client_encoding`, and
+    /// `standard_conforming_strings` when the connection is established, and again whenever they change. The value is
+    /// updated as the `Connection` processes those reports.
+    pub fn parameter(&self, name: &str) -> Option<String> {
+        self.parameters.get(name)
+    }
+
+    /// Returns a stream of the changes to runtime parameters reported by the server after this method is called.
+    ///
+    /// The stream ends when the connection closes.
+    pub fn parameter_changes(&self) -> impl Stream<Item = ParameterChange> {
+        self.parameters.watch()
+    }
+
+    /// Returns the process ID of the backend server process handling this connection.
+    ///
+    /// This can be used to identify the connection in `pg_stat_activity` and in the server's logs.
+    pub fn backend_pid(&self) -> i32 {
+        self.process_id
+    }
+
     /// Determines if the connection to the server has already closed.
     ///
     /// In that case, all future queries will fail.
     pub fn is_closed(&self) -> bool {
         self.inner.sender.is_closed()
     }
+
+    /// Checks that the connection is usable by performing a round trip to the server.
+    ///
+    /// This sends a lone `Sync` message, which the server answers without running a query, so it is cheap enough to
+    /// use before handing out a pooled connection. Unlike `is_closed`, it detects connections which appear open but
+    /// no longer reach a responsive server.
+    pub fn ping(&mut self) -> impl Future<Output = Result<(), Error>> {
+        let client = self.inner();
+
+        async move {
+            let mut buf = vec![];
+            frontend::sync(&mut buf);
+            let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
+
+            match responses.next().await? {
+                Message::ReadyForQuery(_) => Ok(()),
+                _ => Err(Error::unexpected_message()),
+            }
+        }
+    }
 }
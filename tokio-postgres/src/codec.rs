@@ -1,8 +1,10 @@
+use crate::buffer_pool::BufferPool;
 use bytes::{Buf, BytesMut};
 use fallible_iterator::FallibleIterator;
 use postgres_protocol::message::backend;
 use postgres_protocol::message::frontend::CopyData;
 use std::io;
+use std::sync::Arc;
 use tokio::codec::{Decoder, Encoder};
 
 pub enum FrontendMessage {
@@ -35,7 +37,15 @@ impl FallibleIterator for BackendMessages {
     }
 }
 
-pub struct PostgresCodec;
+pub struct PostgresCodec {
+    pool: Arc<BufferPool>,
+}
+
+impl PostgresCodec {
+    pub fn new(pool: Arc<BufferPool>) -> PostgresCodec {
+        PostgresCodec { pool }
+    }
+}
 
 impl Encoder for PostgresCodec {
     type Item = FrontendMessage;
@@ -43,7 +53,10 @@ impl Encoder for PostgresCodec {
 
     fn encode(&mut self, item: FrontendMessage, dst: &mut BytesMut) -> io::Result<()> {
         match item {
-            FrontendMessage::Raw(buf) => dst.extend_from_slice(&buf),
+            FrontendMessage::Raw(buf) => {
+                dst.extend_from_slice(&buf);
+                self.pool.put(buf);
+            }
             FrontendMessage::CopyData(data) => data.write(dst),
         }
 
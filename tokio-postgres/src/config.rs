@@ -2,22 +2,24 @@
 
 #[cfg(feature = "runtime")]
 use crate::connect::connect;
-use crate::connect_raw::connect_raw;
-#[cfg(feature = "runtime")]
-use crate::tls::MakeTlsConnect;
-use crate::tls::TlsConnect;
+use crate::connect_raw::{connect_raw, warm_up};
+use crate::connect_with::connect_with;
+use crate::error::DbError;
+use crate::tls::{MakeTlsConnect, TlsConnect};
 #[cfg(feature = "runtime")]
 use crate::Socket;
-use crate::{Client, Connection, Error};
+use crate::{Client, Connection, Error, Interceptor, MakeSocket};
 use std::borrow::Cow;
 #[cfg(unix)]
 use std::ffi::OsStr;
+use std::net::IpAddr;
 #[cfg(unix)]
 use std::os::unix::ffi::OsStrExt;
 #[cfg(unix)]
 use std::path::{Path, PathBuf};
 use std::str;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 use std::{error, fmt, iter, mem};
 use tokio::io::{AsyncRead, AsyncWrite};
@@ -29,6 +31,15 @@ pub enum TargetSessionAttrs {
     Any,
     /// The session must allow writes.
     ReadWrite,
+    /// The session must not allow writes.
+    ReadOnly,
+    /// The server must not be in hot standby mode.
+    Primary,
+    /// The server must be in hot standby mode.
+    Standby,
+    /// A server in hot standby mode is preferred, but any server is accepted if none of the hosts are in hot standby
+    /// mode.
+    PreferStandby,
     #[doc(hidden)]
     __NonExhaustive,
 }
@@ -59,6 +70,28 @@ pub enum ChannelBinding {
     __NonExhaustive,
 }
 
+/// Load balancing configuration.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LoadBalanceHosts {
+    /// Hosts are tried in the order they were configured, and their addresses in the order they were resolved.
+    Disable,
+    /// Hosts are tried in a random order, and so are the addresses each of them resolves to.
+    Random,
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+/// Replication mode configuration.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ReplicationMode {
+    /// Physical replication, which streams the raw write-ahead log of the whole cluster.
+    Physical,
+    /// Logical replication, which streams changes decoded by an output plugin from a single database.
+    Logical,
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum Host {
     Tcp(String),
@@ -80,29 +113,53 @@ pub(crate) enum Host {
 /// * `user` - The username to authenticate with. Required.
 /// * `password` - The password to authenticate with.
 /// * `dbname` - The name of the database to connect to. Defaults to the username.
-/// * `options` - Command line options used to configure the server.
+/// * `options` - Command line options used to configure the server, such as `-c search_path=app -c work_mem=64MB` to
+///     set runtime parameters when the session starts. Spaces and backslashes within an option must be escaped with
+///     a backslash.
 /// * `application_name` - Sets the `application_name` parameter on the server.
+/// * `fallback_application_name` - An `application_name` to use if one isn't otherwise specified. This allows a library
+///     or tool to identify itself without overriding a name chosen by the user.
 /// * `sslmode` - Controls usage of TLS. If set to `disable`, TLS will not be used. If set to `prefer`, TLS will be used
 ///     if available, but not used otherwise. If set to `require`, TLS will be forced to be used. Defaults to `prefer`.
 /// * `host` - The host to connect to. On Unix platforms, if the host starts with a `/` character it is treated as the
-///     path to the directory containing Unix domain sockets. Otherwise, it is treated as a hostname. Multiple hosts
+///     path to the directory containing Unix domain sockets, and the connection is made to the socket named
+///     `.s.PGSQL.<port>` in it. Otherwise, it is treated as a hostname. Multiple hosts
 ///     can be specified, separated by commas. Each host will be tried in turn when connecting. Required if connecting
-///     with the `connect` method.
+///     with the `connect` method, unless `hostaddr` is specified.
+/// * `hostaddr` - The IP address to connect to, bypassing the lookup of the hostname. If both `host` and `hostaddr` are
+///     specified, the connection is made to `hostaddr` while `host` is still used to verify the server's TLS
+///     certificate. Multiple addresses can be specified, separated by commas, in which case there must be the same
+///     number of addresses as hosts. Addresses can't be paired with Unix socket hosts.
 /// * `port` - The port to connect to. Multiple ports can be specified, separated by commas. The number of ports must be
 ///     either 1, in which case it will be used for all hosts, or the same as the number of hosts. Defaults to 5432 if
 ///     omitted or the empty string.
-/// * `connect_timeout` - The time limit in seconds applied to each socket-level connection attempt. Note that hostnames
-///     can resolve to multiple IP addresses, and this limit is applied to each address. Defaults to no timeout.
+/// * `connect_timeout` - The time limit in seconds applied to each connection attempt, covering the socket connection,
+///     TLS negotiation, authentication, and any session checks. Each host is attempted separately, as is each of its
+///     addresses when `load_balance_hosts` is `random`, so a host which stops responding doesn't prevent the next one
+///     from being tried. Defaults to no timeout.
 /// * `keepalives` - Controls the use of TCP keepalive. A value of 0 disables keepalive and nonzero integers enable it.
 ///     This option is ignored when connecting with Unix sockets. Defaults to on.
 /// * `keepalives_idle` - The number of seconds of inactivity after which a keepalive message is sent to the server.
 ///     This option is ignored when connecting with Unix sockets. Defaults to 2 hours.
-/// * `target_session_attrs` - Specifies requirements of the session. If set to `read-write`, the client will check that
-///     the `transaction_read_write` session parameter is set to `on`. This can be used to connect to the primary server
-///     in a database cluster as opposed to the secondary read-only mirrors. Defaults to `all`.
+/// * `tcp_user_timeout` - The number of milliseconds transmitted data may remain unacknowledged before the connection
+///     is forcibly closed. A value of 0 uses the system default. This option is only supported on Linux, and is
+///     ignored when connecting with Unix sockets. Defaults to the system default.
+/// * `target_session_attrs` - Specifies requirements of the session. Hosts which don't meet them are skipped. If set
+///     to `read-write` or `read-only`, the client will check that the `transaction_read_only` session parameter is
+///     set to `off` or `on` respectively. If set to `primary` or `standby`, the client will check whether the server is
+///     in hot standby mode. If set to `prefer-standby`, each host is first tried as with `standby`, and if none of them
+///     is a standby, they are tried again without any requirements. This can be used to connect to the primary server
+///     in a database cluster as opposed to the secondary read-only mirrors, or vice versa. Defaults to `any`.
 /// * `channel_binding` - Controls usage of channel binding in the authentication process. If set to `disable`, channel
 ///     binding will not be used. If set to `prefer`, channel binding will be used if available, but not used otherwise.
 ///     If set to `require`, the authentication process will fail if channel binding is not used. Defaults to `prefer`.
+/// * `load_balance_hosts` - Controls the order in which hosts are tried. If set to `random`, the hosts are tried in a
+///     random order on each connection attempt, as are the IP addresses each hostname resolves to. This can be used to
+///     spread connections across a set of equivalent servers. Defaults to `disable`.
+/// * `replication` - Opens a replication connection, which accepts the replication commands of the streaming
+///     replication protocol as well as simple queries. If set to `true`, `on`, `yes`, or `1`, the connection is used
+///     for physical replication. If set to `database`, it is used for logical replication from the database specified
+///     by `dbname`. Defaults to a normal connection.
 ///
 /// ## Examples
 ///
@@ -118,6 +175,10 @@ pub(crate) enum Host {
 /// host=host1,host2,host3 port=1234,,5678 user=postgres target_session_attrs=read-write
 /// ```
 ///
+/// ```not_rust
+/// host=replica1,replica2,replica3 user=postgres load_balance_hosts=random
+/// ```
+///
 /// # Url
 ///
 /// This format resembles a URL with a scheme of either `postgres://` or `postgresql://`. All components are optional,
@@ -149,14 +210,40 @@ pub struct Config {
     pub(crate) dbname: Option<String>,
     pub(crate) options: Option<String>,
     pub(crate) application_name: Option<String>,
+    pub(crate) fallback_application_name: Option<String>,
     pub(crate) ssl_mode: SslMode,
     pub(crate) host: Vec<Host>,
+    pub(crate) hostaddr: Vec<IpAddr>,
     pub(crate) port: Vec<u16>,
     pub(crate) connect_timeout: Option<Duration>,
     pub(crate) keepalives: bool,
     pub(crate) keepalives_idle: Duration,
+    pub(crate) tcp_user_timeout: Option<Duration>,
     pub(crate) target_session_attrs: TargetSessionAttrs,
     pub(crate) channel_binding: ChannelBinding,
+    pub(crate) load_balance_hosts: LoadBalanceHosts,
+    pub(crate) replication_mode: Option<ReplicationMode>,
+    pub(crate) notice_callback: Option<NoticeCallback>,
+    pub(crate) interceptor: Option<InterceptorRef>,
+    pub(crate) warm_up: Vec<String>,
+}
+
+#[derive(Clone)]
+pub(crate) struct NoticeCallback(pub(crate) Arc<dyn Fn(DbError) + Sync + Send>);
+
+impl PartialEq for NoticeCallback {
+    fn eq(&self, other: &NoticeCallback) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct InterceptorRef(pub(crate) Arc<dyn Interceptor>);
+
+impl PartialEq for InterceptorRef {
+    fn eq(&self, other: &InterceptorRef) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
 }
 
 impl Default for Config {
@@ -174,14 +261,22 @@ impl Config {
             dbname: None,
             options: None,
             application_name: None,
+            fallback_application_name: None,
             ssl_mode: SslMode::Prefer,
             host: vec![],
+            hostaddr: vec![],
             port: vec![],
             connect_timeout: None,
             keepalives: true,
             keepalives_idle: Duration::from_secs(2 * 60 * 60),
+            tcp_user_timeout: None,
             target_session_attrs: TargetSessionAttrs::Any,
             channel_binding: ChannelBinding::Prefer,
+            load_balance_hosts: LoadBalanceHosts::Disable,
+            replication_mode: None,
+            notice_callback: None,
+            interceptor: None,
+            warm_up: vec![],
         }
     }
 
@@ -211,17 +306,68 @@ impl Config {
     }
 
     /// Sets command line options used to configure the server.
+    ///
+    /// This replaces any options previously set, including those added by `runtime_param`.
     pub fn options(&mut self, options: &str) -> &mut Config {
         self.options = Some(options.to_string());
         self
     }
 
+    /// Adds a `-c name=value` command line option, which sets a runtime parameter when the session starts.
+    ///
+    /// This avoids the round trip of a `SET` after connecting. Spaces and backslashes in the value are escaped.
+    pub fn runtime_param(&mut self, name: &str, value: &str) -> &mut Config {
+        let options = self.options.get_or_insert_with(String::new);
+        if !options.is_empty() {
+            options.push(' ');
+        }
+        options.push_str("-c ");
+        escape_option(name, options);
+        options.push('=');
+        escape_option(value, options);
+        self
+    }
+
     /// Sets the value of the `application_name` runtime parameter.
     pub fn application_name(&mut self, application_name: &str) -> &mut Config {
         self.application_name = Some(application_name.to_string());
         self
     }
 
+    /// Sets a value for the `application_name` runtime parameter which is used only if `application_name` is not set.
+    ///
+    /// This lets code which builds a configuration on behalf of a user supply a default name for the connection
+    /// without overriding one the user chose.
+    pub fn fallback_application_name(&mut self, fallback_application_name: &str) -> &mut Config {
+        self.fallback_application_name = Some(fallback_application_name.to_string());
+        self
+    }
+
+    /// Sets a callback which is invoked with each notice sent by the server, such as those raised by `RAISE NOTICE` or
+    /// warnings.
+    ///
+    /// By default notices are only returned by `Connection::poll_message`, so they are discarded when the connection is
+    /// simply spawned onto an executor. When a callback is set, notices are passed to it instead. To process notices
+    /// asynchronously, the callback can forward them to a channel.
+    pub fn notice_callback<F>(&mut self, callback: F) -> &mut Config
+    where
+        F: Fn(DbError) + Sync + Send + 'static,
+    {
+        self.notice_callback = Some(NoticeCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Sets an interceptor which is invoked around each query made by clients connected with this configuration.
+    ///
+    /// See the `Interceptor` trait for details.
+    pub fn interceptor<I>(&mut self, interceptor: I) -> &mut Config
+    where
+        I: Interceptor + 'static,
+    {
+        self.interceptor = Some(InterceptorRef(Arc::new(interceptor)));
+        self
+    }
+
     /// Sets the SSL configuration.
     ///
     /// Defaults to `prefer`.
@@ -248,7 +394,8 @@ impl Config {
 
     /// Adds a Unix socket host to the configuration.
     ///
-    /// Unlike `host`, this method allows non-UTF8 paths.
+    /// The path is the directory containing the socket, which is named `.s.PGSQL.<port>` after the host's port, as
+    /// with libpq. Unlike `host`, this method allows non-UTF8 paths.
     #[cfg(unix)]
     pub fn host_path<T>(&mut self, host: T) -> &mut Config
     where
@@ -258,6 +405,17 @@ impl Config {
         self
     }
 
+    /// Adds an IP address to the configuration.
+    ///
+    /// The connection to the corresponding host is made to this address rather than to the addresses its name resolves
+    /// to, but the host's name is still used to verify the server's TLS certificate. Multiple addresses can be specified
+    /// by calling this method multiple times. If hosts are also specified, there must be the same number of addresses
+    /// as hosts; otherwise a connection is attempted to each address directly.
+    pub fn hostaddr(&mut self, hostaddr: IpAddr) -> &mut Config {
+        self.hostaddr.push(hostaddr);
+        self
+    }
+
     /// Adds a port to the configuration.
     ///
     /// Multiple ports can be specified by calling this method multiple times. There must either be no ports, in which
@@ -268,10 +426,10 @@ impl Config {
         self
     }
 
-    /// Sets the timeout applied to socket-level connection attempts.
+    /// Sets the timeout applied to each connection attempt.
     ///
-    /// Note that hostnames can resolve to multiple IP addresses, and this timeout will apply to each address of each
-    /// host separately. Defaults to no limit.
+    /// The timeout covers establishing the socket, negotiating TLS, and authenticating, and applies to each host
+    /// separately so that the next one is tried if a host stops responding. Defaults to no limit.
     pub fn connect_timeout(&mut self, connect_timeout: Duration) -> &mut Config {
         self.connect_timeout = Some(connect_timeout);
         self
@@ -293,10 +451,19 @@ impl Config {
         self
     }
 
+    /// Sets the amount of time transmitted data may remain unacknowledged before the connection is closed, with the
+    /// `TCP_USER_TIMEOUT` socket option.
+    ///
+    /// This is only supported on Linux, and is ignored for Unix domain sockets. Defaults to the system default.
+    pub fn tcp_user_timeout(&mut self, tcp_user_timeout: Duration) -> &mut Config {
+        self.tcp_user_timeout = Some(tcp_user_timeout);
+        self
+    }
+
     /// Sets the requirements of the session.
     ///
     /// This can be used to connect to the primary server in a clustered database rather than one of the read-only
-    /// secondary servers. Defaults to `Any`.
+    /// secondary servers, or vice versa. Defaults to `Any`.
     pub fn target_session_attrs(
         &mut self,
         target_session_attrs: TargetSessionAttrs,
@@ -313,6 +480,34 @@ impl Config {
         self
     }
 
+    /// Sets the order in which hosts, and the addresses they resolve to, are tried.
+    ///
+    /// Defaults to `Disable`.
+    pub fn load_balance_hosts(&mut self, load_balance_hosts: LoadBalanceHosts) -> &mut Config {
+        self.load_balance_hosts = load_balance_hosts;
+        self
+    }
+
+    /// Opens a replication connection, used for streaming replication in the specified mode.
+    ///
+    /// Only simple queries and replication commands can be run on a replication connection, so it can't be used to
+    /// prepare statements. Defaults to a normal connection.
+    pub fn replication_mode(&mut self, replication_mode: ReplicationMode) -> &mut Config {
+        self.replication_mode = Some(replication_mode);
+        self
+    }
+
+    /// Adds a statement to be prepared as soon as a connection is established, before the client is returned.
+    ///
+    /// Warm-up statements are prepared into the client's statement cache, whose capacity is raised to hold all of them
+    /// if necessary, so that later calls to `Client::prepare_cached` for the same queries don't need a round trip to
+    /// the server. This also applies to the connections opened by `ReconnectingClient` and `Pool`, each time they
+    /// connect. If a warm-up statement fails to prepare, the connection attempt fails.
+    pub fn warm_up_statement(&mut self, query: &str) -> &mut Config {
+        self.warm_up.push(query.to_string());
+        self
+    }
+
     fn param(&mut self, key: &str, value: &str) -> Result<(), Error> {
         match key {
             "user" => {
@@ -330,6 +525,9 @@ impl Config {
             "application_name" => {
                 self.application_name(&value);
             }
+            "fallback_application_name" => {
+                self.fallback_application_name(&value);
+            }
             "sslmode" => {
                 let mode = match value {
                     "disable" => SslMode::Disable,
@@ -344,6 +542,14 @@ impl Config {
                     self.host(host);
                 }
             }
+            "hostaddr" => {
+                for hostaddr in value.split(',') {
+                    let hostaddr = hostaddr
+                        .parse()
+                        .map_err(|_| Error::config_parse(Box::new(InvalidValue("hostaddr"))))?;
+                    self.hostaddr(hostaddr);
+                }
+            }
             "port" => {
                 for port in value.split(',') {
                     let port = if port.is_empty() {
@@ -377,10 +583,22 @@ impl Config {
                     self.keepalives_idle(Duration::from_secs(keepalives_idle as u64));
                 }
             }
+            "tcp_user_timeout" => {
+                let timeout = value
+                    .parse::<i64>()
+                    .map_err(|_| Error::config_parse(Box::new(InvalidValue("tcp_user_timeout"))))?;
+                if timeout > 0 {
+                    self.tcp_user_timeout(Duration::from_millis(timeout as u64));
+                }
+            }
             "target_session_attrs" => {
                 let target_session_attrs = match &*value {
                     "any" => TargetSessionAttrs::Any,
                     "read-write" => TargetSessionAttrs::ReadWrite,
+                    "read-only" => TargetSessionAttrs::ReadOnly,
+                    "primary" => TargetSessionAttrs::Primary,
+                    "standby" => TargetSessionAttrs::Standby,
+                    "prefer-standby" => TargetSessionAttrs::PreferStandby,
                     _ => {
                         return Err(Error::config_parse(Box::new(InvalidValue(
                             "target_session_attrs",
@@ -402,6 +620,27 @@ impl Config {
                 };
                 self.channel_binding(channel_binding);
             }
+            "load_balance_hosts" => {
+                let load_balance_hosts = match value {
+                    "disable" => LoadBalanceHosts::Disable,
+                    "random" => LoadBalanceHosts::Random,
+                    _ => {
+                        return Err(Error::config_parse(Box::new(InvalidValue(
+                            "load_balance_hosts",
+                        ))))
+                    }
+                };
+                self.load_balance_hosts(load_balance_hosts);
+            }
+            "replication" => {
+                let replication_mode = match value {
+                    "true" | "on" | "yes" | "1" => Some(ReplicationMode::Physical),
+                    "database" => Some(ReplicationMode::Logical),
+                    "false" | "off" | "no" | "0" => None,
+                    _ => return Err(Error::config_parse(Box::new(InvalidValue("replication")))),
+                };
+                self.replication_mode = replication_mode;
+            }
             key => {
                 return Err(Error::config_parse(Box::new(UnknownOption(
                     key.to_string(),
@@ -423,9 +662,29 @@ impl Config {
         connect(tls, self).await
     }
 
+    /// Opens a connection to a PostgreSQL database, using `make_socket` to connect to the server.
+    ///
+    /// This performs the same connection process as `connect`, but without depending on the tokio runtime, so it can
+    /// be used with another async runtime or a custom transport. The `connect_timeout`, `keepalives`,
+    /// `keepalives_idle`, and `tcp_user_timeout` settings must be applied by the `MakeSocket` implementation if they
+    /// are needed. Queries on connections opened this way can't be cancelled with `Client::cancel_query`, since the
+    /// client doesn't know how to reconnect to the server; use `Client::cancel_query_raw` instead.
+    pub async fn connect_with<M, T>(
+        &self,
+        make_socket: M,
+        tls: T,
+    ) -> Result<(Client, Connection<M::Socket, T::Stream>), Error>
+    where
+        M: MakeSocket,
+        T: MakeTlsConnect<M::Socket>,
+    {
+        connect_with(make_socket, tls, self).await
+    }
+
     /// Connects to a PostgreSQL database over an arbitrary stream.
     ///
-    /// All of the settings other than `user`, `password`, `dbname`, `options`, and `application` name are ignored.
+    /// All of the settings other than `user`, `password`, `dbname`, `options`, `application_name`, and
+    /// `fallback_application_name` are ignored.
     pub async fn connect_raw<S, T>(
         &self,
         stream: S,
@@ -435,7 +694,19 @@ impl Config {
         S: AsyncRead + AsyncWrite + Unpin,
         T: TlsConnect<S>,
     {
-        connect_raw(stream, tls, self).await
+        let (mut client, mut connection) = connect_raw(stream, tls, self).await?;
+        warm_up(&mut client, &mut connection, self).await?;
+        Ok((client, connection))
+    }
+}
+
+// the server splits options on whitespace, and unescapes backslashes
+fn escape_option(s: &str, buf: &mut String) {
+    for c in s.chars() {
+        if c == '\\' || c.is_whitespace() {
+            buf.push('\\');
+        }
+        buf.push(c);
     }
 }
 
@@ -466,14 +737,28 @@ impl fmt::Debug for Config {
             .field("dbname", &self.dbname)
             .field("options", &self.options)
             .field("application_name", &self.application_name)
+            .field("fallback_application_name", &self.fallback_application_name)
             .field("ssl_mode", &self.ssl_mode)
             .field("host", &self.host)
+            .field("hostaddr", &self.hostaddr)
             .field("port", &self.port)
             .field("connect_timeout", &self.connect_timeout)
             .field("keepalives", &self.keepalives)
             .field("keepalives_idle", &self.keepalives_idle)
+            .field("tcp_user_timeout", &self.tcp_user_timeout)
             .field("target_session_attrs", &self.target_session_attrs)
             .field("channel_binding", &self.channel_binding)
+            .field("load_balance_hosts", &self.load_balance_hosts)
+            .field("replication_mode", &self.replication_mode)
+            .field(
+                "notice_callback",
+                &self.notice_callback.as_ref().map(|_| Redaction {}),
+            )
+            .field(
+                "interceptor",
+                &self.interceptor.as_ref().map(|_| Redaction {}),
+            )
+            .field("warm_up", &self.warm_up)
             .finish()
     }
 }
@@ -1,114 +1,87 @@
 use crate::client::SocketConfig;
-use crate::config::{Host, TargetSessionAttrs};
-use crate::connect_raw::connect_raw;
-use crate::connect_socket::connect_socket;
-use crate::tls::{MakeTlsConnect, TlsConnect};
-use crate::{Client, Config, Connection, Error, SimpleQueryMessage, Socket};
-use futures::future;
-use futures::{FutureExt, Stream};
-use pin_utils::pin_mut;
-use std::io;
-use std::task::Poll;
+use crate::connect_socket::connect_tcp;
+#[cfg(unix)]
+use crate::connect_socket::connect_unix;
+use crate::connect_with::connect_hosts;
+use crate::make_socket::{MakeSocket, SocketTarget};
+use crate::tls::MakeTlsConnect;
+use crate::{Client, Config, Connection, Error, Socket};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
 
 pub async fn connect<T>(
-    mut tls: T,
+    tls: T,
     config: &Config,
 ) -> Result<(Client, Connection<Socket, T::Stream>), Error>
 where
     T: MakeTlsConnect<Socket>,
 {
-    if config.host.is_empty() {
-        return Err(Error::config("host missing".into()));
-    }
-
-    if config.port.len() > 1 && config.port.len() != config.host.len() {
-        return Err(Error::config("invalid number of ports".into()));
-    }
-
-    let mut error = None;
-    for (i, host) in config.host.iter().enumerate() {
-        let port = *config
-            .port
-            .get(i)
-            .or_else(|| config.port.get(0))
-            .unwrap_or(&5432);
-
-        let hostname = match host {
-            Host::Tcp(host) => &**host,
-            // postgres doesn't support TLS over unix sockets, so the choice here doesn't matter
-            #[cfg(unix)]
-            Host::Unix(_) => "",
-        };
-
-        let tls = tls
-            .make_tls_connect(hostname)
-            .map_err(|e| Error::tls(e.into()))?;
+    let make_socket = TokioMakeSocket {
+        keepalives: config.keepalives,
+        keepalives_idle: config.keepalives_idle,
+        tcp_user_timeout: config.tcp_user_timeout,
+    };
+    let mut connected = connect_hosts(make_socket, tls, config, config.connect_timeout).await?;
 
-        match connect_once(host, port, tls, config).await {
-            Ok((client, connection)) => return Ok((client, connection)),
-            Err(e) => error = Some(e),
-        }
-    }
+    connected.client.set_socket_config(SocketConfig {
+        host: connected.host,
+        port: connected.port,
+        // cancellation requests must be sent to the same server as the connection
+        hostaddr: connected.hostaddr,
+        connect_timeout: config.connect_timeout,
+        keepalives: config.keepalives,
+        keepalives_idle: config.keepalives_idle,
+        tcp_user_timeout: config.tcp_user_timeout,
+    });
 
-    return Err(error.unwrap());
+    Ok((connected.client, connected.connection))
 }
 
-async fn connect_once<T>(
-    host: &Host,
-    port: u16,
-    tls: T,
-    config: &Config,
-) -> Result<(Client, Connection<Socket, T::Stream>), Error>
-where
-    T: TlsConnect<Socket>,
-{
-    let socket = connect_socket(
-        host,
-        port,
-        config.connect_timeout,
-        config.keepalives,
-        config.keepalives_idle,
-    )
-    .await?;
-    let (mut client, mut connection) = connect_raw(socket, tls, config).await?;
-
-    if let TargetSessionAttrs::ReadWrite = config.target_session_attrs {
-        let rows = client.simple_query("SHOW transaction_read_only");
-        pin_mut!(rows);
-
-        loop {
-            let next = future::poll_fn(|cx| {
-                if connection.poll_unpin(cx)?.is_ready() {
-                    return Poll::Ready(Some(Err(Error::closed())));
-                }
+// Creates tokio sockets with the configured socket options.
+//
+// The connect timeout covers the entire attempt, so it isn't applied to the socket on its own.
+struct TokioMakeSocket {
+    keepalives: bool,
+    keepalives_idle: Duration,
+    tcp_user_timeout: Option<Duration>,
+}
 
-                rows.as_mut().poll_next(cx)
-            });
+impl MakeSocket for TokioMakeSocket {
+    type Socket = Socket;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Socket, Error>> + Send>>;
 
-            match next.await.transpose()? {
-                Some(SimpleQueryMessage::Row(row)) => {
-                    if row.try_get(0)? == Some("on") {
-                        return Err(Error::connect(io::Error::new(
-                            io::ErrorKind::PermissionDenied,
-                            "database does not allow writes",
-                        )));
-                    } else {
-                        break;
-                    }
-                }
-                Some(_) => {}
-                None => return Err(Error::unexpected_message()),
+    fn make_socket(&mut self, target: SocketTarget<'_>) -> Self::Future {
+        match target {
+            SocketTarget::Tcp {
+                host,
+                port,
+                hostaddr,
+            } => {
+                let host = host.to_string();
+                let keepalives = self.keepalives;
+                let keepalives_idle = self.keepalives_idle;
+                let tcp_user_timeout = self.tcp_user_timeout;
+                Box::pin(async move {
+                    connect_tcp(
+                        &host,
+                        port,
+                        hostaddr,
+                        None,
+                        keepalives,
+                        keepalives_idle,
+                        tcp_user_timeout,
+                    )
+                    .await
+                })
             }
+            #[cfg(unix)]
+            SocketTarget::Unix { path } => {
+                let path = path.to_path_buf();
+                Box::pin(async move { connect_unix(&path, None).await })
+            }
+            SocketTarget::__NonExhaustive => unreachable!(),
         }
     }
-
-    client.set_socket_config(SocketConfig {
-        host: host.clone(),
-        port,
-        connect_timeout: config.connect_timeout,
-        keepalives: config.keepalives,
-        keepalives_idle: config.keepalives_idle,
-    });
-
-    Ok((client, connection))
 }
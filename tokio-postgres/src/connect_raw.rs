@@ -1,7 +1,9 @@
+use crate::buffer_pool::BufferPool;
 use crate::codec::{BackendMessage, BackendMessages, FrontendMessage, PostgresCodec};
 use crate::config::{self, Config};
 use crate::connect_tls::connect_tls;
 use crate::maybe_tls_stream::MaybeTlsStream;
+use crate::parameters::{ParameterSender, Parameters};
 use crate::tls::{ChannelBinding, TlsConnect};
 use crate::{Client, Connection, Error};
 use fallible_iterator::FallibleIterator;
@@ -12,9 +14,11 @@ use postgres_protocol::authentication::sasl;
 use postgres_protocol::authentication::sasl::ScramSha256;
 use postgres_protocol::message::backend::{AuthenticationSaslBody, Message};
 use postgres_protocol::message::frontend;
+use std::cmp;
 use std::collections::HashMap;
 use std::io;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use tokio::codec::Framed;
 use tokio::io::{AsyncRead, AsyncWrite};
@@ -87,8 +91,9 @@ where
 {
     let (stream, channel_binding) = connect_tls(stream, config.ssl_mode, tls).await?;
 
+    let buffers = Arc::new(BufferPool::new());
     let mut stream = StartupStream {
-        inner: Framed::new(stream, PostgresCodec),
+        inner: Framed::new(stream, PostgresCodec::new(buffers.clone())),
         buf: BackendMessages::empty(),
     };
 
@@ -97,12 +102,57 @@ where
     let (process_id, secret_key, parameters) = read_info(&mut stream).await?;
 
     let (sender, receiver) = mpsc::unbounded();
-    let client = Client::new(sender, config.ssl_mode, process_id, secret_key);
-    let connection = Connection::new(stream.inner, parameters, receiver);
+    let shared_parameters = Arc::new(Parameters::new(parameters.clone()));
+    let client = Client::new(
+        sender,
+        buffers.clone(),
+        shared_parameters.clone(),
+        config.ssl_mode,
+        process_id,
+        secret_key,
+    );
+    if let Some(interceptor) = &config.interceptor {
+        client.inner().set_interceptor(interceptor.0.clone());
+    }
+    let notice_callback = config.notice_callback.as_ref().map(|c| c.0.clone());
+    let connection = Connection::new(
+        stream.inner,
+        buffers,
+        parameters,
+        ParameterSender::new(shared_parameters),
+        receiver,
+        notice_callback,
+    );
 
     Ok((client, connection))
 }
 
+// Prepares the configured warm-up statements, driving the connection until they complete.
+//
+// This is separate from `connect_raw` so that it only runs once a host has been accepted.
+pub async fn warm_up<S, T>(
+    client: &mut Client,
+    connection: &mut Connection<S, T>,
+    config: &Config,
+) -> Result<(), Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    if config.warm_up.is_empty() {
+        return Ok(());
+    }
+
+    let capacity = cmp::max(client.statement_cache_capacity(), config.warm_up.len());
+    client.set_statement_cache_capacity(capacity);
+
+    for query in &config.warm_up {
+        connection.run_until(client.prepare_cached(query)).await??;
+    }
+
+    Ok(())
+}
+
 async fn startup<S, T>(stream: &mut StartupStream<S, T>, config: &Config) -> Result<(), Error>
 where
     S: AsyncRead + AsyncWrite + Unpin,
@@ -118,9 +168,19 @@ where
     if let Some(options) = &config.options {
         params.push(("options", &**options));
     }
-    if let Some(application_name) = &config.application_name {
+    if let Some(application_name) = config
+        .application_name
+        .as_ref()
+        .or_else(|| config.fallback_application_name.as_ref())
+    {
         params.push(("application_name", &**application_name));
     }
+    match config.replication_mode {
+        Some(config::ReplicationMode::Physical) => params.push(("replication", "true")),
+        Some(config::ReplicationMode::Logical) => params.push(("replication", "database")),
+        Some(config::ReplicationMode::__NonExhaustive) => unreachable!(),
+        None => {}
+    }
 
     let mut buf = vec![];
     frontend::startup_message(params, &mut buf).map_err(Error::encode)?;
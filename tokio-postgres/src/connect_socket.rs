@@ -1,7 +1,18 @@
 use crate::config::Host;
+#[cfg(unix)]
+use crate::make_socket::socket_path;
 use crate::{Error, Socket};
+#[cfg(target_os = "linux")]
+use std::cmp;
 use std::future::Future;
 use std::io;
+#[cfg(target_os = "linux")]
+use std::mem;
+use std::net::{IpAddr, SocketAddr};
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
+#[cfg(unix)]
+use std::path::Path;
 use std::time::Duration;
 use tokio::net::TcpStream;
 #[cfg(unix)]
@@ -11,29 +22,91 @@ use tokio::timer::Timeout;
 pub(crate) async fn connect_socket(
     host: &Host,
     port: u16,
+    hostaddr: Option<IpAddr>,
     connect_timeout: Option<Duration>,
     keepalives: bool,
     keepalives_idle: Duration,
+    tcp_user_timeout: Option<Duration>,
 ) -> Result<Socket, Error> {
     match host {
         Host::Tcp(host) => {
-            let socket =
-                connect_with_timeout(TcpStream::connect((&**host, port)), connect_timeout).await?;
-            socket.set_nodelay(true).map_err(Error::connect)?;
-            if keepalives {
-                socket
-                    .set_keepalive(Some(keepalives_idle))
-                    .map_err(Error::connect)?;
-            }
-
-            Ok(Socket::new_tcp(socket))
+            connect_tcp(
+                host,
+                port,
+                hostaddr,
+                connect_timeout,
+                keepalives,
+                keepalives_idle,
+                tcp_user_timeout,
+            )
+            .await
         }
         #[cfg(unix)]
-        Host::Unix(path) => {
-            let socket = connect_with_timeout(UnixStream::connect(path), connect_timeout).await?;
-            Ok(Socket::new_unix(socket))
+        Host::Unix(path) => connect_unix(&socket_path(path, port), connect_timeout).await,
+    }
+}
+
+pub(crate) async fn connect_tcp(
+    host: &str,
+    port: u16,
+    hostaddr: Option<IpAddr>,
+    connect_timeout: Option<Duration>,
+    keepalives: bool,
+    keepalives_idle: Duration,
+    tcp_user_timeout: Option<Duration>,
+) -> Result<Socket, Error> {
+    let socket = match hostaddr {
+        Some(addr) => {
+            let addr = SocketAddr::new(addr, port);
+            connect_with_timeout(TcpStream::connect(addr), connect_timeout).await?
         }
+        None => connect_with_timeout(TcpStream::connect((host, port)), connect_timeout).await?,
+    };
+    socket.set_nodelay(true).map_err(Error::connect)?;
+    if keepalives {
+        socket
+            .set_keepalive(Some(keepalives_idle))
+            .map_err(Error::connect)?;
+    }
+    if let Some(tcp_user_timeout) = tcp_user_timeout {
+        set_tcp_user_timeout(&socket, tcp_user_timeout).map_err(Error::connect)?;
     }
+
+    Ok(Socket::new_tcp(socket))
+}
+
+#[cfg(unix)]
+pub(crate) async fn connect_unix(
+    path: &Path,
+    connect_timeout: Option<Duration>,
+) -> Result<Socket, Error> {
+    let socket = connect_with_timeout(UnixStream::connect(path), connect_timeout).await?;
+    Ok(Socket::new_unix(socket))
+}
+
+#[cfg(target_os = "linux")]
+fn set_tcp_user_timeout(socket: &TcpStream, timeout: Duration) -> io::Result<()> {
+    let timeout = cmp::min(timeout.as_millis(), libc::c_uint::max_value() as u128) as libc::c_uint;
+    let r = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_USER_TIMEOUT,
+            &timeout as *const libc::c_uint as *const libc::c_void,
+            mem::size_of_val(&timeout) as libc::socklen_t,
+        )
+    };
+    if r == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+// the option is specific to Linux, and ignored elsewhere
+#[cfg(not(target_os = "linux"))]
+fn set_tcp_user_timeout(_: &TcpStream, _: Duration) -> io::Result<()> {
+    Ok(())
 }
 
 async fn connect_with_timeout<F, T>(connect: F, timeout: Option<Duration>) -> Result<T, Error>
@@ -0,0 +1,377 @@
+use crate::config::{Host, LoadBalanceHosts, TargetSessionAttrs};
+use crate::connect_raw::{connect_raw, warm_up};
+#[cfg(unix)]
+use crate::make_socket::socket_path;
+use crate::make_socket::{MakeSocket, SocketTarget};
+use crate::tls::{MakeTlsConnect, TlsConnect};
+use crate::{Client, Config, Connection, Error, SimpleQueryMessage};
+use futures::channel::oneshot;
+use futures::StreamExt;
+use pin_utils::pin_mut;
+use rand::seq::SliceRandom;
+use std::cmp;
+use std::error::Error as StdError;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+#[cfg(feature = "runtime")]
+use tokio::timer::Timeout;
+
+pub async fn connect_with<M, T>(
+    make_socket: M,
+    tls: T,
+    config: &Config,
+) -> Result<(Client, Connection<M::Socket, T::Stream>), Error>
+where
+    M: MakeSocket,
+    T: MakeTlsConnect<M::Socket>,
+{
+    // applying the connect timeout is left to the `MakeSocket` implementation, since it depends on the runtime
+    let connected = connect_hosts(make_socket, tls, config, None).await?;
+    Ok((connected.client, connected.connection))
+}
+
+// An established connection, along with the server it was made to.
+pub(crate) struct Connected<S, T> {
+    pub client: Client,
+    pub connection: Connection<S, T>,
+    #[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+    pub host: Host,
+    #[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+    pub port: u16,
+    #[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+    pub hostaddr: Option<IpAddr>,
+}
+
+// The connection process shared by `connect` and `connect_with`, trying each host in turn until one is accepted.
+//
+// The timeout, if any, applies to each attempt as a whole.
+pub(crate) async fn connect_hosts<M, T>(
+    mut make_socket: M,
+    mut tls: T,
+    config: &Config,
+    timeout: Option<Duration>,
+) -> Result<Connected<M::Socket, T::Stream>, Error>
+where
+    M: MakeSocket,
+    T: MakeTlsConnect<M::Socket>,
+{
+    let hosts = hosts(config)?;
+
+    let mut error = None;
+    for attrs in passes(config) {
+        for target in &hosts {
+            match connect_host(&mut make_socket, &mut tls, target, config, attrs, timeout).await {
+                Ok(connected) => return Ok(connected),
+                Err(e) => error = Some(e),
+            }
+        }
+    }
+
+    Err(error.unwrap())
+}
+
+async fn connect_host<M, T>(
+    make_socket: &mut M,
+    tls: &mut T,
+    (host, port, hostaddr): &(Host, u16, Option<IpAddr>),
+    config: &Config,
+    attrs: TargetSessionAttrs,
+    timeout: Option<Duration>,
+) -> Result<Connected<M::Socket, T::Stream>, Error>
+where
+    M: MakeSocket,
+    T: MakeTlsConnect<M::Socket>,
+{
+    let hostname = hostname(host);
+
+    let (port, hostaddr) = (*port, *hostaddr);
+
+    let addrs = match (host, hostaddr, config.load_balance_hosts) {
+        (_, Some(hostaddr), _) => vec![Some(hostaddr)],
+        (Host::Tcp(host), None, LoadBalanceHosts::Random) => {
+            let mut addrs = resolve(host, port).await?;
+            addrs.shuffle(&mut rand::thread_rng());
+            addrs.into_iter().map(Some).collect()
+        }
+        // otherwise the host is resolved while connecting, and its addresses tried in order
+        _ => vec![None],
+    };
+
+    let mut error = None;
+    for addr in addrs {
+        let tls = tls
+            .make_tls_connect(hostname)
+            .map_err(|e| Error::tls(e.into()))?;
+
+        let attempt = connect_once(make_socket, host, port, addr, tls, config, attrs);
+        match with_timeout(attempt, timeout).await {
+            Ok(connected) => return Ok(connected),
+            Err(e) => error = Some(e),
+        }
+    }
+
+    Err(error.unwrap())
+}
+
+async fn connect_once<M, T>(
+    make_socket: &mut M,
+    host: &Host,
+    port: u16,
+    hostaddr: Option<IpAddr>,
+    tls: T,
+    config: &Config,
+    attrs: TargetSessionAttrs,
+) -> Result<Connected<M::Socket, T::Stream>, Error>
+where
+    M: MakeSocket,
+    T: TlsConnect<M::Socket>,
+{
+    let socket = match host {
+        Host::Tcp(host) => {
+            make_socket
+                .make_socket(SocketTarget::Tcp {
+                    host,
+                    port,
+                    hostaddr,
+                })
+                .await
+        }
+        #[cfg(unix)]
+        Host::Unix(path) => {
+            let path = socket_path(path, port);
+            make_socket
+                .make_socket(SocketTarget::Unix { path: &path })
+                .await
+        }
+    };
+    let socket = socket.map_err(socket_error)?;
+
+    let (mut client, mut connection) = connect_raw(socket, tls, config).await?;
+    check_session_attrs(&mut client, &mut connection, attrs).await?;
+    warm_up(&mut client, &mut connection, config).await?;
+
+    Ok(Connected {
+        client,
+        connection,
+        host: host.clone(),
+        port,
+        hostaddr,
+    })
+}
+
+// Errors from the crate's own `MakeSocket` implementation are passed through as-is.
+fn socket_error<E>(e: E) -> Error
+where
+    E: Into<Box<dyn StdError + Sync + Send>>,
+{
+    match e.into().downcast::<Error>() {
+        Ok(e) => *e,
+        Err(e) => Error::connect(io::Error::new(io::ErrorKind::Other, e)),
+    }
+}
+
+#[cfg(feature = "runtime")]
+async fn with_timeout<F, T>(attempt: F, timeout: Option<Duration>) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    match timeout {
+        Some(timeout) => match Timeout::new(attempt, timeout).await {
+            Ok(r) => r,
+            Err(_) => Err(Error::connect(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "connection timed out",
+            ))),
+        },
+        None => attempt.await,
+    }
+}
+
+// timeouts depend on the runtime, so `connect_with` leaves them to the `MakeSocket` implementation
+#[cfg(not(feature = "runtime"))]
+async fn with_timeout<F, T>(attempt: F, _: Option<Duration>) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    attempt.await
+}
+
+async fn resolve(host: &str, port: u16) -> Result<Vec<IpAddr>, Error> {
+    if let Ok(addr) = host.parse() {
+        return Ok(vec![addr]);
+    }
+
+    // the standard library's resolver blocks, so it's run on a separate thread rather than stalling the executor
+    let (sender, receiver) = oneshot::channel();
+    let host = host.to_string();
+    thread::spawn(move || {
+        let addrs = (&*host, port)
+            .to_socket_addrs()
+            .map(|addrs| addrs.map(|addr| addr.ip()).collect::<Vec<_>>());
+        let _ = sender.send(addrs);
+    });
+    let addrs = match receiver.await {
+        Ok(addrs) => addrs.map_err(Error::connect)?,
+        Err(_) => {
+            return Err(Error::connect(io::Error::new(
+                io::ErrorKind::Other,
+                "address resolution failed",
+            )))
+        }
+    };
+    if addrs.is_empty() {
+        return Err(Error::connect(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "could not resolve any addresses",
+        )));
+    }
+
+    Ok(addrs)
+}
+
+// Returns the hosts to try along with their ports and addresses, in the order they should be tried.
+pub(crate) fn hosts(config: &Config) -> Result<Vec<(Host, u16, Option<IpAddr>)>, Error> {
+    if config.host.is_empty() && config.hostaddr.is_empty() {
+        return Err(Error::config("host missing".into()));
+    }
+
+    if !config.host.is_empty()
+        && !config.hostaddr.is_empty()
+        && config.host.len() != config.hostaddr.len()
+    {
+        return Err(Error::config("invalid number of hostaddrs".into()));
+    }
+
+    #[cfg(unix)]
+    {
+        let unix_hostaddr = config
+            .host
+            .iter()
+            .zip(&config.hostaddr)
+            .any(|(host, _)| match host {
+                Host::Unix(_) => true,
+                Host::Tcp(_) => false,
+            });
+        if unix_hostaddr {
+            return Err(Error::config(
+                "hostaddr cannot be used with a unix socket host".into(),
+            ));
+        }
+    }
+
+    let num_hosts = cmp::max(config.host.len(), config.hostaddr.len());
+    if config.port.len() > 1 && config.port.len() != num_hosts {
+        return Err(Error::config("invalid number of ports".into()));
+    }
+
+    let mut hosts = (0..num_hosts)
+        .map(|i| {
+            let hostaddr = config.hostaddr.get(i).cloned();
+            let host = match config.host.get(i) {
+                Some(host) => host.clone(),
+                // without a hostname, the server's certificate is verified against the address
+                None => Host::Tcp(hostaddr.unwrap().to_string()),
+            };
+            let port = *config
+                .port
+                .get(i)
+                .or_else(|| config.port.get(0))
+                .unwrap_or(&5432);
+            (host, port, hostaddr)
+        })
+        .collect::<Vec<_>>();
+    if config.load_balance_hosts == LoadBalanceHosts::Random {
+        hosts.shuffle(&mut rand::thread_rng());
+    }
+
+    Ok(hosts)
+}
+
+// prefer-standby first looks for a standby among all of the hosts, and then falls back to any of them
+pub(crate) fn passes(config: &Config) -> Vec<TargetSessionAttrs> {
+    match config.target_session_attrs {
+        TargetSessionAttrs::PreferStandby => {
+            vec![TargetSessionAttrs::Standby, TargetSessionAttrs::Any]
+        }
+        attrs => vec![attrs],
+    }
+}
+
+// The name used to verify the server's TLS certificate.
+pub(crate) fn hostname(host: &Host) -> &str {
+    match host {
+        Host::Tcp(host) => host,
+        // postgres doesn't support TLS over unix sockets, so the choice here doesn't matter
+        #[cfg(unix)]
+        Host::Unix(_) => "",
+    }
+}
+
+pub(crate) async fn check_session_attrs<S, T>(
+    client: &mut Client,
+    connection: &mut Connection<S, T>,
+    attrs: TargetSessionAttrs,
+) -> Result<(), Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    let (expected, message) = match attrs {
+        TargetSessionAttrs::ReadWrite => (false, "database does not allow writes"),
+        TargetSessionAttrs::ReadOnly => (true, "database is not read-only"),
+        TargetSessionAttrs::Primary => (false, "database is in hot standby mode"),
+        TargetSessionAttrs::Standby => (true, "database is not in hot standby mode"),
+        _ => return Ok(()),
+    };
+
+    let actual = match attrs {
+        TargetSessionAttrs::ReadWrite | TargetSessionAttrs::ReadOnly => {
+            query_value(client, connection, "SHOW transaction_read_only").await? == "on"
+        }
+        // Postgres 14 and newer report in_hot_standby when the connection is established
+        _ => match client.parameter("in_hot_standby") {
+            Some(value) => value == "on",
+            None => {
+                query_value(client, connection, "SELECT pg_catalog.pg_is_in_recovery()").await?
+                    == "t"
+            }
+        },
+    };
+
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(Error::connect(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            message,
+        )))
+    }
+}
+
+// Runs a query returning a single value, driving the connection until it completes.
+async fn query_value<S, T>(
+    client: &mut Client,
+    connection: &mut Connection<S, T>,
+    query: &str,
+) -> Result<String, Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    let rows = client.simple_query(query);
+    pin_mut!(rows);
+
+    loop {
+        match connection.run_until(rows.next()).await?.transpose()? {
+            Some(SimpleQueryMessage::Row(row)) => {
+                return Ok(row.try_get(0)?.unwrap_or("").to_string());
+            }
+            Some(_) => {}
+            None => return Err(Error::unexpected_message()),
+        }
+    }
+}
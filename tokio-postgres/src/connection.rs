@@ -1,25 +1,38 @@
+use crate::buffer_pool::BufferPool;
 use crate::codec::{BackendMessage, BackendMessages, FrontendMessage, PostgresCodec};
+use crate::copy_both::CopyBothReceiver;
 use crate::copy_in::CopyInReceiver;
 use crate::error::DbError;
 use crate::maybe_tls_stream::MaybeTlsStream;
+use crate::notifications::{NotificationSender, Notifications, OverflowPolicy};
+use crate::parameters::ParameterSender;
+use crate::write_buf::WriteBuf;
 use crate::{AsyncMessage, Error, Notification};
+use bytes::Buf;
 use fallible_iterator::FallibleIterator;
 use futures::channel::mpsc;
 use futures::stream::FusedStream;
-use futures::{ready, Sink, Stream, StreamExt};
+use futures::{future, ready, FutureExt, Stream, StreamExt};
 use log::trace;
+use pin_utils::pin_mut;
 use postgres_protocol::message::backend::Message;
 use postgres_protocol::message::frontend;
 use std::collections::{HashMap, VecDeque};
 use std::future::Future;
+use std::io;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use tokio::codec::Framed;
 use tokio::io::{AsyncRead, AsyncWrite};
 
+// once this much data is queued, requests aren't read until some of it has been written to the socket
+const BACKPRESSURE_BOUNDARY: usize = 8 * 1024;
+
 pub enum RequestMessages {
     Single(FrontendMessage),
     CopyIn(CopyInReceiver),
+    CopyBoth(CopyBothReceiver),
 }
 
 pub struct Request {
@@ -48,11 +61,15 @@ enum State {
 #[must_use = "futures do nothing unless polled"]
 pub struct Connection<S, T> {
     stream: Framed<MaybeTlsStream<S, T>, PostgresCodec>,
+    write_buf: WriteBuf,
     parameters: HashMap<String, String>,
+    shared_parameters: ParameterSender,
     receiver: mpsc::UnboundedReceiver<Request>,
     pending_request: Option<RequestMessages>,
     pending_response: Option<BackendMessage>,
     responses: VecDeque<Response>,
+    notifications: Option<NotificationSender>,
+    notice_callback: Option<Arc<dyn Fn(DbError) + Sync + Send>>,
     state: State,
 }
 
@@ -63,16 +80,23 @@ where
 {
     pub(crate) fn new(
         stream: Framed<MaybeTlsStream<S, T>, PostgresCodec>,
+        buffers: Arc<BufferPool>,
         parameters: HashMap<String, String>,
+        shared_parameters: ParameterSender,
         receiver: mpsc::UnboundedReceiver<Request>,
+        notice_callback: Option<Arc<dyn Fn(DbError) + Sync + Send>>,
     ) -> Connection<S, T> {
         Connection {
             stream,
+            write_buf: WriteBuf::new(buffers),
             parameters,
+            shared_parameters,
             receiver,
             pending_request: None,
             pending_response: None,
             responses: VecDeque::new(),
+            notifications: None,
+            notice_callback,
             state: State::Active,
         }
     }
@@ -110,6 +134,10 @@ where
             let (mut messages, request_complete) = match message {
                 BackendMessage::Async(Message::NoticeResponse(body)) => {
                     let error = DbError::parse(&mut body.fields()).map_err(Error::parse)?;
+                    if let Some(callback) = &self.notice_callback {
+                        callback(error);
+                        continue;
+                    }
                     return Ok(Some(AsyncMessage::Notice(error)));
                 }
                 BackendMessage::Async(Message::NotificationResponse(body)) => {
@@ -118,13 +146,20 @@ where
                         channel: body.channel().map_err(Error::parse)?.to_string(),
                         payload: body.message().map_err(Error::parse)?.to_string(),
                     };
+                    let notification = match &self.notifications {
+                        Some(sender) => match sender.send(notification) {
+                            Ok(()) => continue,
+                            Err(notification) => notification,
+                        },
+                        None => notification,
+                    };
                     return Ok(Some(AsyncMessage::Notification(notification)));
                 }
                 BackendMessage::Async(Message::ParameterStatus(body)) => {
-                    self.parameters.insert(
-                        body.name().map_err(Error::parse)?.to_string(),
-                        body.value().map_err(Error::parse)?.to_string(),
-                    );
+                    let name = body.name().map_err(Error::parse)?;
+                    let value = body.value().map_err(Error::parse)?;
+                    self.parameters.insert(name.to_string(), value.to_string());
+                    self.shared_parameters.set(name, value);
                     continue;
                 }
                 BackendMessage::Async(_) => unreachable!(),
@@ -198,12 +233,11 @@ where
                 return Ok(false);
             }
 
-            if let Poll::Pending = Pin::new(&mut self.stream)
-                .poll_ready(cx)
-                .map_err(Error::io)?
-            {
-                trace!("poll_write: waiting on socket");
-                return Ok(false);
+            if self.write_buf.remaining() >= BACKPRESSURE_BOUNDARY {
+                if let Poll::Pending = self.poll_write_buf(cx).map_err(Error::io)? {
+                    trace!("poll_write: waiting on socket");
+                    return Ok(false);
+                }
             }
 
             let request = match self.poll_request(cx) {
@@ -230,9 +264,7 @@ where
 
             match request {
                 RequestMessages::Single(request) => {
-                    Pin::new(&mut self.stream)
-                        .start_send(request)
-                        .map_err(Error::io)?;
+                    self.write_buf.push(request);
                     if self.state == State::Terminating {
                         trace!("poll_write: sent eof, closing");
                         self.state = State::Closing;
@@ -251,20 +283,56 @@ where
                             return Ok(true);
                         }
                     };
-                    Pin::new(&mut self.stream)
-                        .start_send(message)
-                        .map_err(Error::io)?;
+                    self.write_buf.push(message);
                     self.pending_request = Some(RequestMessages::CopyIn(receiver));
                 }
+                RequestMessages::CopyBoth(mut receiver) => {
+                    let message = match receiver.poll_next_unpin(cx) {
+                        Poll::Ready(Some(message)) => message,
+                        Poll::Ready(None) => {
+                            trace!("poll_write: finished copy_both request");
+                            continue;
+                        }
+                        Poll::Pending => {
+                            trace!("poll_write: waiting on copy_both stream");
+                            self.pending_request = Some(RequestMessages::CopyBoth(receiver));
+                            return Ok(true);
+                        }
+                    };
+                    self.write_buf.push(message);
+                    self.pending_request = Some(RequestMessages::CopyBoth(receiver));
+                }
             }
         }
     }
 
+    // writes out the queued messages, as many at a time as the socket will take with a vectored write
+    fn poll_write_buf(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let stream = self.stream.get_mut();
+        while self.write_buf.has_remaining() {
+            let n = ready!(Pin::new(&mut *stream).poll_write_buf(cx, &mut self.write_buf))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write frame to transport",
+                )));
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_flush_inner(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_buf(cx))?;
+        Pin::new(self.stream.get_mut()).poll_flush(cx)
+    }
+
+    fn poll_close_inner(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_flush_inner(cx))?;
+        Pin::new(self.stream.get_mut()).poll_shutdown(cx)
+    }
+
     fn poll_flush(&mut self, cx: &mut Context<'_>) -> Result<(), Error> {
-        match Pin::new(&mut self.stream)
-            .poll_flush(cx)
-            .map_err(Error::io)?
-        {
+        match self.poll_flush_inner(cx).map_err(Error::io)? {
             Poll::Ready(()) => trace!("poll_flush: flushed"),
             Poll::Pending => trace!("poll_flush: waiting on socket"),
         }
@@ -276,10 +344,7 @@ where
             return Poll::Pending;
         }
 
-        match Pin::new(&mut self.stream)
-            .poll_close(cx)
-            .map_err(Error::io)?
-        {
+        match self.poll_close_inner(cx).map_err(Error::io)? {
             Poll::Ready(()) => {
                 trace!("poll_shutdown: complete");
                 Poll::Ready(Ok(()))
@@ -296,6 +361,37 @@ where
         self.parameters.get(name).map(|s| &**s)
     }
 
+    /// Sets a callback which is invoked with each notice sent by the server.
+    ///
+    /// Once set, notices are passed to the callback rather than being returned by `poll_message`.
+    ///
+    /// See `Config::notice_callback` to set the callback before the connection is established.
+    pub fn set_notice_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(DbError) + Sync + Send + 'static,
+    {
+        self.notice_callback = Some(Arc::new(callback));
+    }
+
+    /// Returns a stream of the notifications received by this connection, buffering at most `capacity` of them.
+    ///
+    /// Once called, notifications are no longer returned by `poll_message` but are instead buffered until they are
+    /// consumed from the returned stream, so they can be received even when the connection is simply spawned onto an
+    /// executor. If the buffer is full when a notification arrives, `policy` determines which notification is
+    /// discarded. Dropping the stream restores the default behavior.
+    ///
+    /// Calling this method again replaces the previous stream, which ends once its buffered notifications have been
+    /// consumed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    pub fn notifications(&mut self, capacity: usize, policy: OverflowPolicy) -> Notifications {
+        let (sender, notifications) = Notifications::new(capacity, policy);
+        self.notifications = Some(sender);
+        notifications
+    }
+
     /// Polls for asynchronous messages from the server.
     ///
     /// The server can send notices as well as notifications asynchronously to the client. Applications that wish to
@@ -318,6 +414,38 @@ where
             },
         }
     }
+
+    /// Drives the connection on the current task until `future` completes, returning its output.
+    ///
+    /// This allows the connection to be used without spawning it onto an executor, for example on a single-threaded
+    /// runtime, when embedding the client in another event loop, or in deterministic tests. Futures returned by the
+    /// connection's `Client` only make progress while the connection is driven, so they should be passed to this
+    /// method rather than awaited directly. Asynchronous messages are handled as by the connection's `Future`
+    /// implementation, so notifications should be received with `notifications`.
+    ///
+    /// Returns an error if the connection fails or is closed before `future` completes, after which the connection
+    /// should not be used further.
+    pub async fn run_until<F>(&mut self, future: F) -> Result<F::Output, Error>
+    where
+        F: Future,
+    {
+        pin_mut!(future);
+
+        future::poll_fn(|cx| {
+            // the connection is polled first so that responses it reads are seen by the future on the same pass
+            let connection = self.poll_unpin(cx);
+            if let Poll::Ready(output) = future.as_mut().poll(cx) {
+                return Poll::Ready(Ok(output));
+            }
+
+            match connection {
+                Poll::Ready(Ok(())) => Poll::Ready(Err(Error::closed())),
+                Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+                Poll::Pending => Poll::Pending,
+            }
+        })
+        .await
+    }
 }
 
 impl<S, T> Future for Connection<S, T>
@@ -0,0 +1,169 @@
+use crate::client::{InnerClient, Responses};
+use crate::codec::FrontendMessage;
+use crate::connection::RequestMessages;
+use crate::Error;
+use bytes::{Buf, Bytes, IntoBuf};
+use futures::channel::mpsc;
+use futures::{ready, Sink, SinkExt, Stream, StreamExt};
+use postgres_protocol::message::backend::Message;
+use postgres_protocol::message::frontend;
+use postgres_protocol::message::frontend::CopyData;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+enum CopyBothMessage {
+    Message(FrontendMessage),
+    Done,
+}
+
+pub struct CopyBothReceiver {
+    receiver: mpsc::Receiver<CopyBothMessage>,
+    done: bool,
+}
+
+impl CopyBothReceiver {
+    fn new(receiver: mpsc::Receiver<CopyBothMessage>) -> CopyBothReceiver {
+        CopyBothReceiver {
+            receiver,
+            done: false,
+        }
+    }
+}
+
+impl Stream for CopyBothReceiver {
+    type Item = FrontendMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<FrontendMessage>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        match ready!(self.receiver.poll_next_unpin(cx)) {
+            Some(CopyBothMessage::Message(message)) => Poll::Ready(Some(message)),
+            // the server ends the query on its own once both sides have sent CopyDone, so unlike copy_in there's no
+            // Sync. It ignores the CopyDone if the query failed before entering copy mode.
+            Some(CopyBothMessage::Done) | None => {
+                self.done = true;
+                let mut buf = vec![];
+                frontend::copy_done(&mut buf);
+                Poll::Ready(Some(FrontendMessage::Raw(buf)))
+            }
+        }
+    }
+}
+
+/// A duplex stream over the data of a query in `COPY BOTH` mode, returned by `Client::copy_both_simple`.
+///
+/// The stream yields the `CopyData` messages sent by the server until it ends the copy, and the sink sends each item
+/// to the server as a `CopyData` message. Both directions pass the data along verbatim. Closing the sink ends the
+/// client's side of the copy, as does dropping the duplex.
+///
+/// The copy occupies the connection, so other requests made with the client wait until the client's side has ended.
+pub struct CopyBothDuplex {
+    responses: Responses,
+    sender: mpsc::Sender<CopyBothMessage>,
+    done: bool,
+    closed: bool,
+}
+
+impl Stream for CopyBothDuplex {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        match ready!(self.responses.poll_next(cx)?) {
+            Message::CopyData(body) => Poll::Ready(Some(Ok(body.into_bytes()))),
+            Message::CopyDone => {
+                self.done = true;
+                Poll::Ready(None)
+            }
+            _ => Poll::Ready(Some(Err(Error::unexpected_message()))),
+        }
+    }
+}
+
+impl<T> Sink<T> for CopyBothDuplex
+where
+    T: IntoBuf,
+    T::Buf: 'static + Send,
+{
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.sender.poll_ready(cx).map_err(|_| Error::closed())
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: T) -> Result<(), Error> {
+        let data: Box<dyn Buf + Send> = Box::new(item.into_buf());
+        let data = CopyData::new(data).map_err(Error::encode)?;
+        self.sender
+            .start_send(CopyBothMessage::Message(FrontendMessage::CopyData(data)))
+            .map_err(|_| Error::closed())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Pin::new(&mut self.sender)
+            .poll_flush(cx)
+            .map_err(|_| Error::closed())
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        if !self.closed {
+            ready!(self.sender.poll_ready(cx)).map_err(|_| Error::closed())?;
+            self.sender
+                .start_send(CopyBothMessage::Done)
+                .map_err(|_| Error::closed())?;
+            self.closed = true;
+        }
+
+        Pin::new(&mut self.sender)
+            .poll_flush(cx)
+            .map_err(|_| Error::closed())
+    }
+}
+
+pub fn copy_both_simple(
+    client: Arc<InnerClient>,
+    query: &str,
+) -> impl Future<Output = Result<CopyBothDuplex, Error>> {
+    let buf = encode(query);
+    copy_both(client, buf)
+}
+
+fn encode(query: &str) -> Result<Vec<u8>, Error> {
+    let mut buf = vec![];
+    frontend::query(query, &mut buf).map_err(Error::encode)?;
+    Ok(buf)
+}
+
+async fn copy_both(
+    client: Arc<InnerClient>,
+    buf: Result<Vec<u8>, Error>,
+) -> Result<CopyBothDuplex, Error> {
+    let buf = buf?;
+    let (mut sender, receiver) = mpsc::channel(1);
+    let receiver = CopyBothReceiver::new(receiver);
+    let mut responses = client.send(RequestMessages::CopyBoth(receiver))?;
+
+    sender
+        .send(CopyBothMessage::Message(FrontendMessage::Raw(buf)))
+        .await
+        .map_err(|_| Error::closed())?;
+
+    match responses.next().await? {
+        Message::CopyBothResponse(_) => {}
+        _ => return Err(Error::unexpected_message()),
+    }
+
+    Ok(CopyBothDuplex {
+        responses,
+        sender,
+        done: false,
+        closed: false,
+    })
+}
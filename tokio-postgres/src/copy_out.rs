@@ -5,9 +5,11 @@ use crate::Error;
 use bytes::Bytes;
 use futures::{ready, Stream, TryFutureExt};
 use postgres_protocol::message::backend::Message;
+use std::io;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use tokio::io::AsyncRead;
 
 pub fn copy_out(
     client: Arc<InnerClient>,
@@ -50,3 +52,44 @@ impl Stream for CopyOut {
         }
     }
 }
+
+/// An `AsyncRead` adapter over the data returned by a `COPY ... TO STDOUT` statement.
+///
+/// Errors returned by the server are reported as `io::Error`s wrapping the `Error`.
+pub struct CopyOutReader {
+    stream: Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>>,
+    cur: Bytes,
+}
+
+impl CopyOutReader {
+    pub(crate) fn new<S>(stream: S) -> CopyOutReader
+    where
+        S: Stream<Item = Result<Bytes, Error>> + Send + 'static,
+    {
+        CopyOutReader {
+            stream: Box::pin(stream),
+            cur: Bytes::new(),
+        }
+    }
+}
+
+impl AsyncRead for CopyOutReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        while self.cur.is_empty() {
+            match ready!(self.stream.as_mut().poll_next(cx)) {
+                Some(Ok(cur)) => self.cur = cur,
+                Some(Err(e)) => return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, e))),
+                None => return Poll::Ready(Ok(0)),
+            }
+        }
+
+        let len = usize::min(buf.len(), self.cur.len());
+        let chunk = self.cur.split_to(len);
+        buf[..len].copy_from_slice(&chunk);
+        Poll::Ready(Ok(len))
+    }
+}
@@ -0,0 +1,209 @@
+use futures::{ready, Stream, TryStream};
+use std::fmt;
+#[cfg(feature = "runtime")]
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+#[cfg(feature = "runtime")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "runtime")]
+use tokio::timer::{self, Delay};
+
+/// The amount of data which has passed through a `CopyProgressStream`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct CopyProgress {
+    bytes: u64,
+    chunks: u64,
+}
+
+impl CopyProgress {
+    /// Returns the number of bytes of data transferred.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Returns the number of chunks of data transferred.
+    ///
+    /// The server sends each row of a `COPY ... TO STDOUT` in a separate chunk, so for the stream returned by
+    /// `Client::copy_out` this is the number of rows. For a stream passed to `Client::copy_in`, it's only the number of
+    /// rows if the stream yields a single row per item; the writers in the `binary_copy` and `csv_copy` modules batch
+    /// rows together.
+    pub fn chunks(&self) -> u64 {
+        self.chunks
+    }
+}
+
+/// A wrapper around a stream of `COPY` data which reports the amount of data transferred, and can limit the rate at
+/// which it's transferred.
+///
+/// It can wrap either the stream passed to `Client::copy_in` or the one returned by `Client::copy_out`, passing the
+/// data through unchanged.
+///
+/// # Example
+///
+/// ```no_run
+/// use futures::TryStreamExt;
+/// use tokio_postgres::{Client, CopyProgressStream, Error};
+///
+/// # async fn f(client: &mut Client) -> Result<(), Error> {
+/// let statement = client.prepare("COPY foo TO STDOUT").await?;
+/// let stream = CopyProgressStream::new(client.copy_out(&statement, &[]))
+///     .on_progress(|progress| println!("{} bytes copied", progress.bytes()));
+/// let data = stream.try_concat().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CopyProgressStream<S>
+where
+    S: TryStream,
+{
+    stream: Pin<Box<S>>,
+    // a chunk received from the inner stream while the throttle was delaying it
+    pending: Option<S::Ok>,
+    progress: CopyProgress,
+    callback: Option<Box<dyn FnMut(CopyProgress) + Send>>,
+    #[cfg(feature = "runtime")]
+    throttle: Option<Throttle>,
+}
+
+#[cfg(feature = "runtime")]
+struct Throttle {
+    bytes_per_sec: u64,
+    start: Option<Instant>,
+    delay: Option<Delay>,
+}
+
+// the inner stream is pinned on the heap and the pending chunk is never pinned, so the wrapper can always be moved
+impl<S> Unpin for CopyProgressStream<S> where S: TryStream {}
+
+impl<S> fmt::Debug for CopyProgressStream<S>
+where
+    S: TryStream,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("CopyProgressStream")
+            .field("progress", &self.progress)
+            .finish()
+    }
+}
+
+impl<S> CopyProgressStream<S>
+where
+    S: TryStream,
+    S::Ok: AsRef<[u8]>,
+{
+    /// Wraps a stream of `COPY` data.
+    pub fn new(stream: S) -> CopyProgressStream<S> {
+        CopyProgressStream {
+            stream: Box::pin(stream),
+            pending: None,
+            progress: CopyProgress::default(),
+            callback: None,
+            #[cfg(feature = "runtime")]
+            throttle: None,
+        }
+    }
+
+    /// Sets a callback invoked with the total progress after each chunk of data passes through the stream.
+    ///
+    /// The callback is invoked from whichever task polls the stream, so it should not block.
+    pub fn on_progress<F>(mut self, callback: F) -> CopyProgressStream<S>
+    where
+        F: FnMut(CopyProgress) + Send + 'static,
+    {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Limits the average rate at which data passes through the stream to the specified number of bytes per second.
+    ///
+    /// The rate is measured from when the first chunk is received, and chunks aren't split up, so a single large chunk
+    /// can briefly exceed it. The end of the stream isn't delayed.
+    ///
+    /// Requires the `runtime` Cargo feature (enabled by default).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_sec` is 0.
+    #[cfg(feature = "runtime")]
+    pub fn throttle(mut self, bytes_per_sec: u64) -> CopyProgressStream<S> {
+        assert!(bytes_per_sec > 0, "bytes_per_sec must be positive");
+        self.throttle = Some(Throttle {
+            bytes_per_sec,
+            start: None,
+            delay: None,
+        });
+        self
+    }
+
+    /// Returns the amount of data which has passed through the stream so far.
+    pub fn progress(&self) -> CopyProgress {
+        self.progress
+    }
+
+    #[cfg(feature = "runtime")]
+    fn poll_throttle(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if let Some(throttle) = &mut self.throttle {
+            if let Some(delay) = &mut throttle.delay {
+                ready!(Pin::new(delay).poll(cx));
+                throttle.delay = None;
+            }
+        }
+
+        Poll::Ready(())
+    }
+
+    #[cfg(not(feature = "runtime"))]
+    fn poll_throttle(&mut self, _: &mut Context<'_>) -> Poll<()> {
+        Poll::Ready(())
+    }
+
+    // delays the next chunk until the bytes transferred so far fit within the rate limit
+    #[cfg(feature = "runtime")]
+    fn schedule_throttle(&mut self) {
+        if let Some(throttle) = &mut self.throttle {
+            let now = Instant::now();
+            let start = *throttle.start.get_or_insert(now);
+            let nanos = u128::from(self.progress.bytes) * 1_000_000_000
+                / u128::from(throttle.bytes_per_sec);
+            let deadline = start + Duration::from_nanos(nanos as u64);
+            if deadline > now {
+                throttle.delay = Some(timer::delay(deadline));
+            }
+        }
+    }
+
+    #[cfg(not(feature = "runtime"))]
+    fn schedule_throttle(&mut self) {}
+}
+
+impl<S> Stream for CopyProgressStream<S>
+where
+    S: TryStream,
+    S::Ok: AsRef<[u8]>,
+{
+    type Item = Result<S::Ok, S::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // the inner stream is polled before the throttle so that its end and errors are passed on without a delay
+        if self.pending.is_none() {
+            match ready!(self.stream.as_mut().try_poll_next(cx)) {
+                Some(Ok(chunk)) => self.pending = Some(chunk),
+                r => return Poll::Ready(r),
+            }
+        }
+
+        ready!(self.poll_throttle(cx));
+        let chunk = self.pending.take().unwrap();
+
+        self.progress.bytes += chunk.as_ref().len() as u64;
+        self.progress.chunks += 1;
+        let progress = self.progress;
+        if let Some(callback) = &mut self.callback {
+            callback(progress);
+        }
+        self.schedule_throttle();
+
+        Poll::Ready(Some(Ok(chunk)))
+    }
+}
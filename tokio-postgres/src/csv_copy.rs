@@ -0,0 +1,355 @@
+//! Utilities for working with the PostgreSQL CSV copy format.
+//!
+//! The helpers in this module use the default options of the format: fields are separated by `,` and quoted with
+//! `"`, and NULL is written as an unquoted empty field.
+
+use crate::types::FromSqlText;
+use crate::Error;
+use bytes::{Bytes, BytesMut};
+use futures::channel::mpsc;
+use futures::{ready, SinkExt, Stream, StreamExt};
+use std::fmt::{self, Write};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+enum Message {
+    Data(Bytes),
+    Finish,
+}
+
+/// A writer which encodes rows of values in the CSV copy format.
+///
+/// The writer is created along with a `CsvCopyInStream`, which should be passed to `Client::copy_in` for a
+/// `COPY ... FROM STDIN (FORMAT csv)` statement. The two must be driven concurrently, for example with `join!`.
+/// Dropping the writer without calling `finish` aborts the copy.
+///
+/// Values are written with their `Display` implementations, which must produce the text representation Postgres
+/// expects for the column's type.
+pub struct CsvCopyInWriter {
+    sender: mpsc::Sender<Message>,
+    buf: BytesMut,
+    field: String,
+}
+
+impl CsvCopyInWriter {
+    /// Creates a new writer.
+    pub fn new() -> (CsvCopyInWriter, CsvCopyInStream) {
+        let (sender, receiver) = mpsc::channel(1);
+
+        let writer = CsvCopyInWriter {
+            sender,
+            buf: BytesMut::new(),
+            field: String::new(),
+        };
+        let stream = CsvCopyInStream {
+            receiver,
+            done: false,
+        };
+
+        (writer, stream)
+    }
+
+    /// Writes a single row.
+    ///
+    /// `None` values are written as NULL. Other values are quoted when necessary, so an empty string is distinct from
+    /// NULL.
+    pub async fn write(&mut self, values: &[Option<&dyn fmt::Display>]) -> Result<(), Error> {
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                self.buf.extend_from_slice(b",");
+            }
+
+            if let Some(value) = value {
+                self.field.clear();
+                write!(self.field, "{}", value).map_err(|e| Error::to_sql(Box::new(e), i))?;
+                encode_field(&self.field, &mut self.buf);
+            }
+        }
+        self.buf.extend_from_slice(b"\n");
+
+        if self.buf.len() > 4096 {
+            self.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Completes the copy, writing out any buffered rows.
+    ///
+    /// The `Client::copy_in` future resolves once the server has processed the data.
+    pub async fn finish(mut self) -> Result<(), Error> {
+        if !self.buf.is_empty() {
+            self.flush().await?;
+        }
+        self.sender
+            .send(Message::Finish)
+            .await
+            .map_err(|_| Error::closed())
+    }
+
+    async fn flush(&mut self) -> Result<(), Error> {
+        let data = self.buf.take().freeze();
+        self.sender
+            .send(Message::Data(data))
+            .await
+            .map_err(|_| Error::closed())
+    }
+}
+
+fn encode_field(field: &str, buf: &mut BytesMut) {
+    // empty strings are quoted to distinguish them from NULL, and a lone `\.` would be read as the end-of-data marker
+    let quote = field.is_empty()
+        || field == "\\."
+        || field
+            .bytes()
+            .any(|b| b == b',' || b == b'"' || b == b'\n' || b == b'\r');
+
+    if !quote {
+        buf.extend_from_slice(field.as_bytes());
+        return;
+    }
+
+    buf.extend_from_slice(b"\"");
+    buf.extend_from_slice(field.replace('"', "\"\"").as_bytes());
+    buf.extend_from_slice(b"\"");
+}
+
+/// The stream of data produced by a `CsvCopyInWriter`.
+///
+/// The stream returns an error, aborting the copy, if its writer is dropped without calling `finish`.
+pub struct CsvCopyInStream {
+    receiver: mpsc::Receiver<Message>,
+    done: bool,
+}
+
+impl Stream for CsvCopyInStream {
+    type Item = Result<Bytes, io::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        match ready!(self.receiver.poll_next_unpin(cx)) {
+            Some(Message::Data(data)) => Poll::Ready(Some(Ok(data))),
+            Some(Message::Finish) => {
+                self.done = true;
+                Poll::Ready(None)
+            }
+            None => {
+                self.done = true;
+                Poll::Ready(Some(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "csv copy writer dropped without finishing",
+                ))))
+            }
+        }
+    }
+}
+
+/// A stream of rows decoded from the CSV copy format.
+///
+/// It wraps the stream returned by `Client::copy_out` for a `COPY ... TO STDOUT (FORMAT csv)` statement. Rows may be
+/// split across or combined within the chunks of the underlying stream.
+pub struct CsvCopyOutStream {
+    stream: Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>>,
+    buf: BytesMut,
+    done: bool,
+}
+
+impl CsvCopyOutStream {
+    /// Creates a stream decoding rows from the data in `stream`.
+    pub fn new<S>(stream: S) -> CsvCopyOutStream
+    where
+        S: Stream<Item = Result<Bytes, Error>> + Send + 'static,
+    {
+        CsvCopyOutStream {
+            stream: Box::pin(stream),
+            buf: BytesMut::new(),
+            done: false,
+        }
+    }
+}
+
+impl Stream for CsvCopyOutStream {
+    type Item = Result<CsvCopyOutRow, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.done {
+                return Poll::Ready(None);
+            }
+
+            match parse_row(&self.buf, false) {
+                Ok(Some((row, len))) => {
+                    self.buf.split_to(len);
+                    return Poll::Ready(Some(Ok(row)));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+
+            match ready!(self.stream.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => self.buf.extend_from_slice(&chunk),
+                Some(Err(e)) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+                None => {
+                    self.done = true;
+                    if self.buf.is_empty() {
+                        return Poll::Ready(None);
+                    }
+                    // the last row may not be terminated by a newline
+                    let r = match parse_row(&self.buf, true) {
+                        Ok(Some((row, _))) => Ok(row),
+                        Ok(None) => Err(invalid("unterminated quoted field")),
+                        Err(e) => Err(e),
+                    };
+                    return Poll::Ready(Some(r));
+                }
+            }
+        }
+    }
+}
+
+// returns the parsed row along with the number of bytes it occupied, or None if the buffer does not contain a
+// complete row
+fn parse_row(buf: &[u8], eof: bool) -> Result<Option<(CsvCopyOutRow, usize)>, Error> {
+    let mut fields = vec![];
+    let mut field = vec![];
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut pos = 0;
+
+    loop {
+        let b = match buf.get(pos) {
+            Some(&b) => b,
+            None if eof && !in_quotes => break,
+            None => return Ok(None),
+        };
+        pos += 1;
+
+        if in_quotes {
+            if b != b'"' {
+                field.push(b);
+            } else if buf.get(pos) == Some(&b'"') {
+                field.push(b'"');
+                pos += 1;
+            } else if buf.get(pos).is_none() && !eof {
+                // the next byte decides if this is an escaped quote
+                return Ok(None);
+            } else {
+                in_quotes = false;
+            }
+            continue;
+        }
+
+        match b {
+            b'"' => {
+                in_quotes = true;
+                quoted = true;
+            }
+            b',' => fields.push(take_field(&mut field, &mut quoted)?),
+            b'\n' => break,
+            b'\r' => {
+                if buf.get(pos) == Some(&b'\n') {
+                    pos += 1;
+                } else if buf.get(pos).is_none() && !eof {
+                    return Ok(None);
+                }
+                break;
+            }
+            b => field.push(b),
+        }
+    }
+    fields.push(take_field(&mut field, &mut quoted)?);
+
+    Ok(Some((CsvCopyOutRow { fields }, pos)))
+}
+
+fn take_field(field: &mut Vec<u8>, quoted: &mut bool) -> Result<Option<String>, Error> {
+    let value = if field.is_empty() && !*quoted {
+        None
+    } else {
+        let value = String::from_utf8(field.split_off(0))
+            .map_err(|_| invalid("invalid UTF-8 in CSV field"))?;
+        Some(value)
+    };
+    *quoted = false;
+    Ok(value)
+}
+
+fn invalid(message: &str) -> Error {
+    Error::parse(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+/// A row of data parsed from a CSV copy.
+#[derive(Debug)]
+pub struct CsvCopyOutRow {
+    fields: Vec<Option<String>>,
+}
+
+impl CsvCopyOutRow {
+    /// Determines if the row contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of values in the row.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns a value from the row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        match self.try_get(idx) {
+            Ok(ok) => ok,
+            Err(err) => panic!("error retrieving column {}: {}", idx, err),
+        }
+    }
+
+    /// Like `CsvCopyOutRow::get`, but returns a `Result` rather than panicking.
+    pub fn try_get(&self, idx: usize) -> Result<Option<&str>, Error> {
+        match self.fields.get(idx) {
+            Some(value) => Ok(value.as_ref().map(|s| &**s)),
+            None => Err(Error::column(idx.to_string())),
+        }
+    }
+
+    /// Parses a value from the row with its `FromSqlText` implementation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds or if the value cannot be parsed as the specified type.
+    pub fn get_as<T>(&self, idx: usize) -> Option<T>
+    where
+        T: FromSqlText,
+    {
+        match self.try_get_as(idx) {
+            Ok(ok) => ok,
+            Err(err) => panic!("error retrieving column {}: {}", idx, err),
+        }
+    }
+
+    /// Like `CsvCopyOutRow::get_as`, but returns a `Result` rather than panicking.
+    pub fn try_get_as<T>(&self, idx: usize) -> Result<Option<T>, Error>
+    where
+        T: FromSqlText,
+    {
+        match self.try_get(idx)? {
+            Some(value) => T::from_sql_text(value)
+                .map(Some)
+                .map_err(|e| Error::from_sql(e, idx)),
+            None => Ok(None),
+        }
+    }
+}
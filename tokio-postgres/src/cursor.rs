@@ -0,0 +1,189 @@
+use crate::codec::FrontendMessage;
+use crate::connection::RequestMessages;
+use crate::types::ToSql;
+use crate::{Client, Error, FromRow, Row};
+use futures::TryStreamExt;
+use postgres_protocol::message::frontend;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// The rows to retrieve with `Cursor::fetch_direction`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FetchDirection {
+    /// The next `n` rows.
+    Forward(u64),
+
+    /// All remaining rows.
+    ForwardAll,
+
+    /// The previous `n` rows, in reverse order.
+    ///
+    /// Requires a scrollable cursor.
+    Backward(u64),
+
+    /// All preceding rows, in reverse order.
+    ///
+    /// Requires a scrollable cursor.
+    BackwardAll,
+
+    /// The row at the specified position, counting from 1. Negative positions count back from the end of the result.
+    ///
+    /// Requires a scrollable cursor unless the row is ahead of the current position.
+    Absolute(i64),
+
+    /// The row at the specified offset from the current position.
+    ///
+    /// Requires a scrollable cursor unless the offset is positive.
+    Relative(i64),
+
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+/// A builder for server-side cursors.
+pub struct CursorBuilder<'a> {
+    client: &'a mut Client,
+    scroll: Option<bool>,
+    hold: bool,
+}
+
+impl<'a> CursorBuilder<'a> {
+    pub(crate) fn new(client: &'a mut Client) -> CursorBuilder<'a> {
+        CursorBuilder {
+            client,
+            scroll: None,
+            hold: false,
+        }
+    }
+
+    /// Sets whether the cursor can move backwards, as well as forwards, through its rows.
+    ///
+    /// If unset, the server decides based on the query plan.
+    pub fn scroll(mut self, scroll: bool) -> Self {
+        self.scroll = Some(scroll);
+        self
+    }
+
+    /// Sets whether the cursor remains usable after the transaction that created it commits.
+    ///
+    /// The rows of a holdable cursor are materialized when the transaction commits. Holdable cursors can also be
+    /// declared outside of a transaction. Defaults to `false`.
+    pub fn hold(mut self, hold: bool) -> Self {
+        self.hold = hold;
+        self
+    }
+
+    /// Declares a cursor over the rows returned by a query.
+    ///
+    /// The query may contain parameters, which are bound to `params`.
+    pub async fn declare(
+        self,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Cursor<'a>, Error> {
+        let name = format!("c{}", NEXT_ID.fetch_add(1, Ordering::SeqCst));
+
+        let mut declare = format!("DECLARE {}", name);
+        match self.scroll {
+            Some(true) => declare.push_str(" SCROLL"),
+            Some(false) => declare.push_str(" NO SCROLL"),
+            None => {}
+        }
+        declare.push_str(" CURSOR");
+        if self.hold {
+            declare.push_str(" WITH HOLD");
+        }
+        declare.push_str(" FOR ");
+        declare.push_str(query);
+
+        let statement = self.client.prepare(&declare).await?;
+        self.client.execute(&statement, params).await?;
+
+        Ok(Cursor {
+            client: self.client,
+            name,
+            done: false,
+        })
+    }
+}
+
+/// A server-side cursor, which retrieves the rows of a query incrementally.
+///
+/// The cursor will be closed when dropped. Cursors which are not holdable are also closed when their transaction ends.
+pub struct Cursor<'a> {
+    client: &'a mut Client,
+    name: String,
+    done: bool,
+}
+
+impl<'a> Drop for Cursor<'a> {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+
+        let mut buf = vec![];
+        frontend::query(&format!("CLOSE {}", self.name), &mut buf).unwrap();
+        let _ = self
+            .client
+            .inner()
+            .send(RequestMessages::Single(FrontendMessage::Raw(buf)));
+    }
+}
+
+impl<'a> Cursor<'a> {
+    /// Returns the name of the cursor.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Retrieves the next `n` rows.
+    ///
+    /// Fewer rows are returned once the cursor approaches the end of its result, and none once it reaches it.
+    pub async fn fetch(&mut self, n: u64) -> Result<Vec<Row>, Error> {
+        self.fetch_direction(FetchDirection::Forward(n)).await
+    }
+
+    /// Like `fetch`, but converts each row into a value implementing `FromRow`.
+    pub async fn fetch_as<T>(&mut self, n: u64) -> Result<Vec<T>, Error>
+    where
+        T: FromRow,
+    {
+        self.fetch(n)
+            .await?
+            .iter()
+            .map(|row| row.try_into())
+            .collect()
+    }
+
+    /// Retrieves all remaining rows.
+    pub async fn fetch_all(&mut self) -> Result<Vec<Row>, Error> {
+        self.fetch_direction(FetchDirection::ForwardAll).await
+    }
+
+    /// Retrieves rows relative to the cursor's current position.
+    pub async fn fetch_direction(&mut self, direction: FetchDirection) -> Result<Vec<Row>, Error> {
+        let direction = match direction {
+            FetchDirection::Forward(n) => format!("FORWARD {}", n),
+            FetchDirection::ForwardAll => "FORWARD ALL".to_string(),
+            FetchDirection::Backward(n) => format!("BACKWARD {}", n),
+            FetchDirection::BackwardAll => "BACKWARD ALL".to_string(),
+            FetchDirection::Absolute(n) => format!("ABSOLUTE {}", n),
+            FetchDirection::Relative(n) => format!("RELATIVE {}", n),
+            FetchDirection::__NonExhaustive => unreachable!(),
+        };
+        let query = format!("FETCH {} FROM {}", direction, self.name);
+
+        self.client.query_typed(&query, &[]).try_collect().await
+    }
+
+    /// Consumes the cursor, closing it.
+    ///
+    /// This is equivalent to `Cursor`'s `Drop` implementation, but provides any error encountered to the caller.
+    pub async fn close(mut self) -> Result<(), Error> {
+        self.done = true;
+        let query = format!("CLOSE {}", self.name);
+        self.client.batch_execute(&query).await
+    }
+}
@@ -1,5 +1,6 @@
 //! Errors.
 
+use crate::types::Type;
 use fallible_iterator::FallibleIterator;
 use postgres_protocol::message::backend::{ErrorFields, ErrorResponseBody};
 use std::error::{self, Error as _Error};
@@ -334,9 +335,11 @@ enum Kind {
     Io,
     UnexpectedMessage,
     Tls,
-    ToSql(usize),
-    FromSql(usize),
-    Column,
+    ToSql(usize, Option<String>),
+    FromSql(usize, Option<String>),
+    Column(String),
+    RowCount,
+    ColumnCount,
     CopyInStream,
     Closed,
     Db,
@@ -345,8 +348,14 @@ enum Kind {
     Authentication,
     ConfigParse,
     Config,
+    PipelineAborted,
+    NotificationOverflow,
     #[cfg(feature = "runtime")]
     Connect,
+    #[cfg(feature = "runtime")]
+    PoolTimeout,
+    #[cfg(feature = "runtime")]
+    Timeout,
 }
 
 struct ErrorInner {
@@ -372,9 +381,17 @@ impl fmt::Display for Error {
             Kind::Io => fmt.write_str("error communicating with the server")?,
             Kind::UnexpectedMessage => fmt.write_str("unexpected message from server")?,
             Kind::Tls => fmt.write_str("error performing TLS handshake")?,
-            Kind::ToSql(idx) => write!(fmt, "error serializing parameter {}", idx)?,
-            Kind::FromSql(idx) => write!(fmt, "error deserializing column {}", idx)?,
-            Kind::Column => fmt.write_str("invalid column")?,
+            Kind::ToSql(idx, None) => write!(fmt, "error serializing parameter {}", idx)?,
+            Kind::ToSql(idx, Some(ref context)) => {
+                write!(fmt, "error serializing column {} {}", idx, context)?
+            }
+            Kind::FromSql(idx, None) => write!(fmt, "error deserializing column {}", idx)?,
+            Kind::FromSql(idx, Some(ref context)) => {
+                write!(fmt, "error deserializing column {} {}", idx, context)?
+            }
+            Kind::Column(ref column) => write!(fmt, "invalid column `{}`", column)?,
+            Kind::RowCount => fmt.write_str("query returned an unexpected number of rows")?,
+            Kind::ColumnCount => fmt.write_str("query returned an unexpected number of columns")?,
             Kind::CopyInStream => fmt.write_str("error from a copy_in stream")?,
             Kind::Closed => fmt.write_str("connection closed")?,
             Kind::Db => fmt.write_str("db error")?,
@@ -383,8 +400,20 @@ impl fmt::Display for Error {
             Kind::Authentication => fmt.write_str("authentication error")?,
             Kind::ConfigParse => fmt.write_str("invalid connection string")?,
             Kind::Config => fmt.write_str("invalid configuration")?,
+            Kind::PipelineAborted => {
+                fmt.write_str("pipeline aborted before the operation was executed")?
+            }
+            Kind::NotificationOverflow => {
+                fmt.write_str("notifications were discarded because the buffer was full")?
+            }
             #[cfg(feature = "runtime")]
             Kind::Connect => fmt.write_str("error connecting to server")?,
+            #[cfg(feature = "runtime")]
+            Kind::PoolTimeout => {
+                fmt.write_str("timed out waiting for a connection from the pool")?
+            }
+            #[cfg(feature = "runtime")]
+            Kind::Timeout => fmt.write_str("query timed out")?,
         };
         if let Some(ref cause) = self.0.cause {
             write!(fmt, ": {}", cause)?;
@@ -415,6 +444,26 @@ impl Error {
             .map(DbError::code)
     }
 
+    /// Determines if the error was caused by the connection to the server closing or failing.
+    ///
+    /// Any requests which were in flight on the connection at the time may or may not have been processed by the
+    /// server.
+    pub fn is_closed(&self) -> bool {
+        match self.0.kind {
+            Kind::Closed | Kind::Io => true,
+            _ => false,
+        }
+    }
+
+    /// Determines if the error was caused by a query exceeding its client-side timeout.
+    pub fn is_timeout(&self) -> bool {
+        match self.0.kind {
+            #[cfg(feature = "runtime")]
+            Kind::Timeout => true,
+            _ => false,
+        }
+    }
+
     fn new(kind: Kind, cause: Option<Box<dyn error::Error + Sync + Send>>) -> Error {
         Error(Box::new(ErrorInner { kind, cause }))
     }
@@ -445,15 +494,56 @@ impl Error {
 
     #[allow(clippy::wrong_self_convention)]
     pub(crate) fn to_sql(e: Box<dyn error::Error + Sync + Send>, idx: usize) -> Error {
-        Error::new(Kind::ToSql(idx), Some(e))
+        Error::new(Kind::ToSql(idx, None), Some(e))
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    pub(crate) fn to_sql_column(
+        e: Box<dyn error::Error + Sync + Send>,
+        idx: usize,
+        name: &str,
+    ) -> Error {
+        let context = format!("`{}`", name);
+        Error::new(Kind::ToSql(idx, Some(context)), Some(e))
     }
 
     pub(crate) fn from_sql(e: Box<dyn error::Error + Sync + Send>, idx: usize) -> Error {
-        Error::new(Kind::FromSql(idx), Some(e))
+        Error::new(Kind::FromSql(idx, None), Some(e))
+    }
+
+    pub(crate) fn from_sql_column(
+        e: Box<dyn error::Error + Sync + Send>,
+        idx: usize,
+        name: &str,
+    ) -> Error {
+        let context = format!("`{}`", name);
+        Error::new(Kind::FromSql(idx, Some(context)), Some(e))
+    }
+
+    pub(crate) fn from_sql_value(
+        e: Box<dyn error::Error + Sync + Send>,
+        idx: usize,
+        name: &str,
+        ty: &Type,
+        rust: &str,
+    ) -> Error {
+        let context = format!(
+            "`{}` of Postgres type `{}` as Rust type `{}`",
+            name, ty, rust
+        );
+        Error::new(Kind::FromSql(idx, Some(context)), Some(e))
+    }
+
+    pub(crate) fn column(column: String) -> Error {
+        Error::new(Kind::Column(column), None)
+    }
+
+    pub(crate) fn row_count() -> Error {
+        Error::new(Kind::RowCount, None)
     }
 
-    pub(crate) fn column() -> Error {
-        Error::new(Kind::Column, None)
+    pub(crate) fn column_count() -> Error {
+        Error::new(Kind::ColumnCount, None)
     }
 
     pub(crate) fn copy_in_stream<E>(e: E) -> Error
@@ -483,8 +573,26 @@ impl Error {
         Error::new(Kind::Config, Some(e))
     }
 
+    pub(crate) fn pipeline_aborted() -> Error {
+        Error::new(Kind::PipelineAborted, None)
+    }
+
+    pub(crate) fn notification_overflow() -> Error {
+        Error::new(Kind::NotificationOverflow, None)
+    }
+
     #[cfg(feature = "runtime")]
     pub(crate) fn connect(e: io::Error) -> Error {
         Error::new(Kind::Connect, Some(Box::new(e)))
     }
+
+    #[cfg(feature = "runtime")]
+    pub(crate) fn pool_timeout() -> Error {
+        Error::new(Kind::PoolTimeout, None)
+    }
+
+    #[cfg(feature = "runtime")]
+    pub(crate) fn timeout() -> Error {
+        Error::new(Kind::Timeout, None)
+    }
 }
@@ -0,0 +1,101 @@
+use crate::types::ToSql;
+use crate::Error;
+use std::future::Future;
+use std::sync::Arc;
+
+/// A hook which observes and adjusts the queries made by a client.
+///
+/// Interceptors are registered with `Config::interceptor` or `Client::set_interceptor`, and allow cross-cutting
+/// concerns like metrics, auditing, or tagging queries with comments to be handled in one place rather than at every
+/// call site.
+///
+/// `before_query` and `after_query` are invoked around each query made through the `query`, `query_iter`,
+/// `query_typed`, `execute`, and `execute_iter` methods of `Client` and `Transaction`, and the methods built on top of
+/// them. Calls are made from whichever task drives the query, so implementations should not block.
+pub trait Interceptor: Sync + Send {
+    /// Rewrites the SQL of a statement before it is sent to the server to be prepared, or before a query made with
+    /// `query_typed` is sent.
+    ///
+    /// This can be used to attach a comment identifying the application or request, for example. Returning `None`
+    /// leaves the SQL unchanged. The original SQL is still the one passed to the other methods of the interceptor and
+    /// used as the key of the statement cache.
+    ///
+    /// The default implementation returns `None`.
+    fn rewrite_query(&self, query: &str) -> Option<String> {
+        let _ = query;
+        None
+    }
+
+    /// Invoked when a query is issued, with the SQL of its statement and the `Debug` representations of its
+    /// parameters.
+    ///
+    /// The default implementation does nothing.
+    fn before_query(&self, query: &str, params: &[String]) {
+        let _ = (query, params);
+    }
+
+    /// Invoked when a query completes, with the number of rows it returned or modified, or the error it failed with.
+    ///
+    /// This is not invoked if the query is abandoned before it completes, for example by dropping its `RowStream`
+    /// early.
+    ///
+    /// The default implementation does nothing.
+    fn after_query(&self, query: &str, params: &[String], result: Result<u64, &Error>) {
+        let _ = (query, params, result);
+    }
+}
+
+// A query which has been reported to an interceptor, and whose completion will be.
+pub struct Intercepted {
+    interceptor: Arc<dyn Interceptor>,
+    query: String,
+    params: Vec<String>,
+    rows: u64,
+}
+
+impl Intercepted {
+    pub fn new<'a, I>(interceptor: Arc<dyn Interceptor>, query: &str, params: I) -> Intercepted
+    where
+        I: IntoIterator<Item = &'a dyn ToSql>,
+    {
+        let params = params
+            .into_iter()
+            .map(|p| format!("{:?}", p))
+            .collect::<Vec<_>>();
+        interceptor.before_query(query, &params);
+
+        Intercepted {
+            interceptor,
+            query: query.to_string(),
+            params,
+            rows: 0,
+        }
+    }
+
+    pub fn rows(&mut self, rows: u64) {
+        self.rows += rows;
+    }
+
+    pub fn finish(&self, error: Option<&Error>) {
+        let result = match error {
+            Some(e) => Err(e),
+            None => Ok(self.rows),
+        };
+        self.interceptor
+            .after_query(&self.query, &self.params, result);
+    }
+}
+
+pub async fn execute<F>(intercepted: Option<Intercepted>, future: F) -> Result<u64, Error>
+where
+    F: Future<Output = Result<u64, Error>>,
+{
+    let r = future.await;
+    if let Some(mut intercepted) = intercepted {
+        if let Ok(rows) = r {
+            intercepted.rows(rows);
+        }
+        intercepted.finish(r.as_ref().err());
+    }
+    r
+}
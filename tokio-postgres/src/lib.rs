@@ -96,7 +96,21 @@
 //!
 //! The client works with arbitrary `AsyncRead + AsyncWrite` streams. Convenience APIs are provided to handle the
 //! connection process, but these are gated by the `runtime` Cargo feature, which is enabled by default. If disabled,
-//! all dependence on the tokio runtime is removed.
+//! all dependence on the tokio runtime is removed. `Config::connect_with` performs the same connection process with
+//! sockets created by a `MakeSocket` implementation, so the client can be used with other async runtimes or custom
+//! transports. Streams must implement tokio's `AsyncRead` and `AsyncWrite` traits, which don't depend on its runtime;
+//! streams implementing the `futures::io` traits instead, like the sockets of async-std and smol, can be wrapped in
+//! `Compat`.
+//!
+//! The `Connection` is normally spawned onto an executor, but it can instead be driven on the same task as the client
+//! with `Connection::run_until`, for environments without a background executor.
+//!
+//! # Tracing
+//!
+//! If the `with-tracing` Cargo feature is enabled, each query is recorded in a `tracing` span at the debug level. The
+//! span identifies the query by the name of its prepared statement, and an event is emitted in it when the query
+//! finishes with the number of rows returned or modified, the duration in microseconds, and the SQLSTATE code of the
+//! error if it failed. The SQL of each statement is recorded along with its name when it is prepared.
 //!
 //! # SSL/TLS support
 //!
@@ -107,25 +121,49 @@
 #![doc(html_root_url = "https://docs.rs/tokio-postgres/0.4.0-rc.3")]
 #![warn(rust_2018_idioms, clippy::all, missing_docs)]
 
+pub use crate::cancel_token::CancelToken;
 pub use crate::client::Client;
 pub use crate::config::Config;
 pub use crate::connection::Connection;
+pub use crate::copy_both::CopyBothDuplex;
+pub use crate::copy_out::CopyOutReader;
+pub use crate::copy_progress::{CopyProgress, CopyProgressStream};
+pub use crate::cursor::{Cursor, CursorBuilder, FetchDirection};
 use crate::error::DbError;
 pub use crate::error::Error;
+pub use crate::interceptor::Interceptor;
+pub use crate::make_socket::{Compat, MakeSocket, SocketTarget};
+pub use crate::notifications::{Notifications, OverflowPolicy};
+pub use crate::parameters::ParameterChange;
+pub use crate::pipeline::Pipeline;
+#[cfg(feature = "runtime")]
+pub use crate::pool::{Pool, PoolBuilder, PooledClient};
 pub use crate::portal::Portal;
-pub use crate::row::{Row, SimpleQueryRow};
+pub use crate::query::RowStream;
+#[cfg(feature = "runtime")]
+pub use crate::reconnect::ReconnectingClient;
+pub use crate::row::{FromRow, Row, SimpleQueryRow};
+pub use crate::session::ParamScope;
+pub use crate::simple_query::{BatchOutcome, CommandTag, SimpleColumn};
 #[cfg(feature = "runtime")]
 pub use crate::socket::Socket;
 #[cfg(feature = "runtime")]
 use crate::tls::MakeTlsConnect;
 pub use crate::tls::NoTls;
 pub use crate::transaction::Transaction;
+pub use crate::transaction_builder::{IsolationLevel, TransactionBuilder};
+#[cfg(feature = "derive")]
+pub use postgres_derive::FromRow;
 pub use statement::{Column, Statement};
+use std::sync::Arc;
 
+pub mod binary_copy;
 mod bind;
+mod buffer_pool;
 #[cfg(feature = "runtime")]
 mod cancel_query;
 mod cancel_query_raw;
+mod cancel_token;
 mod client;
 mod codec;
 pub mod config;
@@ -135,22 +173,43 @@ mod connect_raw;
 #[cfg(feature = "runtime")]
 mod connect_socket;
 mod connect_tls;
+mod connect_with;
 mod connection;
+mod copy_both;
 mod copy_in;
 mod copy_out;
+mod copy_progress;
+pub mod csv_copy;
+mod cursor;
 pub mod error;
+mod interceptor;
+mod make_socket;
 mod maybe_tls_stream;
+mod notifications;
+mod parameters;
+mod pipeline;
+#[cfg(feature = "runtime")]
+mod pool;
 mod portal;
 mod prepare;
 mod query;
+#[cfg(feature = "runtime")]
+mod reconnect;
+pub mod replication;
 pub mod row;
+mod session;
 mod simple_query;
 #[cfg(feature = "runtime")]
 mod socket;
 mod statement;
+mod statement_cache;
+pub mod text_copy;
 pub mod tls;
+mod trace;
 mod transaction;
+mod transaction_builder;
 pub mod types;
+mod write_buf;
 
 /// A convenience function which parses a connection string and connects to the database.
 ///
@@ -213,6 +272,10 @@ pub enum AsyncMessage {
 
 /// Message returned by the `SimpleQuery` stream.
 pub enum SimpleQueryMessage {
+    /// A description of the rows returned by a statement in the query.
+    ///
+    /// This precedes the rows of each statement which returns data, even if no rows are returned.
+    RowDescription(Arc<[SimpleColumn]>),
     /// A row of data.
     Row(SimpleQueryRow),
     /// A statement in the query has completed.
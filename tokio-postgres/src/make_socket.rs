@@ -0,0 +1,125 @@
+use std::error::Error;
+use std::future::Future;
+use std::io;
+use std::net::IpAddr;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// A server endpoint which a `MakeSocket` implementation is asked to connect to.
+#[derive(Debug, Copy, Clone)]
+pub enum SocketTarget<'a> {
+    /// A TCP socket.
+    Tcp {
+        /// The configured hostname.
+        host: &'a str,
+        /// The port.
+        port: u16,
+        /// The address which should be connected to rather than the addresses `host` resolves to, if any.
+        ///
+        /// This is the address configured with `hostaddr`, or with `load_balance_hosts=random` one of the addresses
+        /// `host` resolved to.
+        hostaddr: Option<IpAddr>,
+    },
+    /// A Unix domain socket.
+    #[cfg(unix)]
+    Unix {
+        /// The path of the socket, which is the file named `.s.PGSQL.<port>` in the configured directory.
+        path: &'a Path,
+    },
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+/// A constructor of sockets.
+///
+/// This is used by `Config::connect_with` to establish connections without depending on the tokio runtime, so that
+/// the client can be used with another async runtime or a custom transport. The connection process of
+/// `Config::connect` is otherwise shared, including the handling of multiple hosts, `hostaddr`, `load_balance_hosts`,
+/// and `target_session_attrs`. Applying the `connect_timeout`, `keepalives`, `keepalives_idle`, and `tcp_user_timeout`
+/// options is left to the implementation, since doing so depends on the runtime.
+///
+/// Sockets must implement tokio's `AsyncRead` and `AsyncWrite` traits, which don't depend on its runtime. Sockets
+/// implementing the `futures::io` traits instead, like those of async-std and smol, can be wrapped in `Compat`.
+pub trait MakeSocket {
+    /// The socket type.
+    type Socket: AsyncRead + AsyncWrite + Unpin;
+    /// The error returned if the connection fails.
+    type Error: Into<Box<dyn Error + Sync + Send>>;
+    /// The future returned by `make_socket`.
+    type Future: Future<Output = Result<Self::Socket, Self::Error>>;
+
+    /// Returns a future connecting to the target.
+    fn make_socket(&mut self, target: SocketTarget<'_>) -> Self::Future;
+}
+
+/// An adapter implementing tokio's `AsyncRead` and `AsyncWrite` traits for a stream implementing those of `futures::io`.
+///
+/// This allows the sockets of runtimes built on the `futures::io` traits, like async-std and smol, to be returned by a
+/// `MakeSocket` implementation or passed to `Config::connect_raw`.
+#[derive(Debug)]
+pub struct Compat<S>(S);
+
+impl<S> Compat<S> {
+    /// Wraps a stream.
+    pub fn new(stream: S) -> Compat<S> {
+        Compat(stream)
+    }
+
+    /// Returns a shared reference to the inner stream.
+    pub fn get_ref(&self) -> &S {
+        &self.0
+    }
+
+    /// Returns a mutable reference to the inner stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.0
+    }
+
+    /// Consumes the adapter, returning the inner stream.
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+impl<S> AsyncRead for Compat<S>
+where
+    S: futures::io::AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        futures::io::AsyncRead::poll_read(Pin::new(&mut self.0), cx, buf)
+    }
+}
+
+impl<S> AsyncWrite for Compat<S>
+where
+    S: futures::io::AsyncWrite + Unpin,
+{
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        futures::io::AsyncWrite::poll_write(Pin::new(&mut self.0), cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        futures::io::AsyncWrite::poll_flush(Pin::new(&mut self.0), cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        futures::io::AsyncWrite::poll_close(Pin::new(&mut self.0), cx)
+    }
+}
+
+// The path of the socket for `port` in a Unix socket directory.
+#[cfg(unix)]
+pub(crate) fn socket_path(dir: &Path, port: u16) -> PathBuf {
+    dir.join(format!(".s.PGSQL.{}", port))
+}
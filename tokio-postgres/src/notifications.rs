@@ -0,0 +1,149 @@
+use crate::{Error, Notification};
+use futures::Stream;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+/// The behavior of a `Notifications` buffer when a notification arrives while it is full.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discard the oldest buffered notification to make room for the new one.
+    DropOldest,
+    /// Discard the new notification.
+    DropNewest,
+    /// Discard the new notification and all later ones, and report an error from the `Notifications` stream once the
+    /// notifications buffered before it have been consumed, after which the stream ends.
+    Error,
+}
+
+struct Shared {
+    buf: VecDeque<Notification>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    overflowed: bool,
+    overflow_reported: bool,
+    waker: Option<Waker>,
+    closed: bool,
+    receiver_dropped: bool,
+}
+
+pub(crate) struct NotificationSender(Arc<Mutex<Shared>>);
+
+impl Drop for NotificationSender {
+    fn drop(&mut self) {
+        let mut shared = self.0.lock();
+        shared.closed = true;
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl NotificationSender {
+    /// Buffers a notification, returning it back if the receiver has been dropped.
+    pub(crate) fn send(&self, notification: Notification) -> Result<(), Notification> {
+        let mut shared = self.0.lock();
+        if shared.receiver_dropped {
+            return Err(notification);
+        }
+
+        // notifications after an overflow would otherwise be yielded before its error
+        if shared.overflowed {
+            return Ok(());
+        }
+
+        if shared.buf.len() >= shared.capacity {
+            match shared.policy {
+                OverflowPolicy::DropOldest => {
+                    shared.buf.pop_front();
+                }
+                OverflowPolicy::DropNewest => return Ok(()),
+                OverflowPolicy::Error => {
+                    shared.overflowed = true;
+                    return Ok(());
+                }
+            }
+        }
+
+        shared.buf.push_back(notification);
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+        Ok(())
+    }
+}
+
+/// A bounded buffer of the asynchronous notifications received by a connection.
+///
+/// Notifications are buffered as the `Connection` they were obtained from is polled, including when it is simply
+/// spawned onto an executor, and yielded by this stream's `Stream` implementation. The stream ends once the connection
+/// has closed and all buffered notifications have been consumed.
+///
+/// Returned by `Connection::notifications`.
+pub struct Notifications(Arc<Mutex<Shared>>);
+
+impl Drop for Notifications {
+    fn drop(&mut self) {
+        self.0.lock().receiver_dropped = true;
+    }
+}
+
+impl Notifications {
+    pub(crate) fn new(
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> (NotificationSender, Notifications) {
+        assert!(capacity > 0, "notification buffer capacity must be nonzero");
+
+        let shared = Arc::new(Mutex::new(Shared {
+            buf: VecDeque::with_capacity(capacity),
+            capacity,
+            policy,
+            overflowed: false,
+            overflow_reported: false,
+            waker: None,
+            closed: false,
+            receiver_dropped: false,
+        }));
+        (NotificationSender(shared.clone()), Notifications(shared))
+    }
+
+    /// Returns the number of notifications currently buffered.
+    pub fn len(&self) -> usize {
+        self.0.lock().buf.len()
+    }
+
+    /// Determines if no notifications are currently buffered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Stream for Notifications {
+    type Item = Result<Notification, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut shared = self.0.lock();
+
+        if let Some(notification) = shared.buf.pop_front() {
+            return Poll::Ready(Some(Ok(notification)));
+        }
+
+        if shared.overflowed {
+            if shared.overflow_reported {
+                return Poll::Ready(None);
+            }
+            shared.overflow_reported = true;
+            return Poll::Ready(Some(Err(Error::notification_overflow())));
+        }
+
+        if shared.closed {
+            return Poll::Ready(None);
+        }
+
+        shared.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
@@ -0,0 +1,89 @@
+use futures::channel::mpsc;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A change in the value of a runtime parameter reported by the server.
+#[derive(Debug, Clone)]
+pub struct ParameterChange {
+    name: String,
+    value: String,
+}
+
+impl ParameterChange {
+    /// Returns the name of the parameter.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the new value of the parameter.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+struct Inner {
+    values: HashMap<String, String>,
+    watchers: Vec<mpsc::UnboundedSender<ParameterChange>>,
+    closed: bool,
+}
+
+/// The runtime parameters reported by the server, shared between a `Connection` and its `Client`.
+pub struct Parameters(Mutex<Inner>);
+
+impl Parameters {
+    pub fn new(values: HashMap<String, String>) -> Parameters {
+        Parameters(Mutex::new(Inner {
+            values,
+            watchers: vec![],
+            closed: false,
+        }))
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.0.lock().values.get(name).cloned()
+    }
+
+    fn set(&self, name: &str, value: &str) {
+        let mut inner = self.0.lock();
+        inner.values.insert(name.to_string(), value.to_string());
+
+        let change = ParameterChange {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+        inner
+            .watchers
+            .retain(|watcher| watcher.unbounded_send(change.clone()).is_ok());
+    }
+
+    pub fn watch(&self) -> mpsc::UnboundedReceiver<ParameterChange> {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut inner = self.0.lock();
+        if !inner.closed {
+            inner.watchers.push(sender);
+        }
+        receiver
+    }
+}
+
+/// The `Connection`'s handle to the shared parameters, which ends the watchers' streams when dropped.
+pub struct ParameterSender(Arc<Parameters>);
+
+impl Drop for ParameterSender {
+    fn drop(&mut self) {
+        let mut inner = (self.0).0.lock();
+        inner.closed = true;
+        inner.watchers.clear();
+    }
+}
+
+impl ParameterSender {
+    pub fn new(parameters: Arc<Parameters>) -> ParameterSender {
+        ParameterSender(parameters)
+    }
+
+    pub fn set(&self, name: &str, value: &str) {
+        self.0.set(name, value)
+    }
+}
@@ -0,0 +1,200 @@
+use crate::client::{InnerClient, Responses};
+use crate::codec::FrontendMessage;
+use crate::connection::RequestMessages;
+use crate::types::ToSql;
+use crate::{query, Error, Row, Statement};
+use futures::channel::oneshot;
+use futures::FutureExt;
+use postgres_protocol::message::backend::Message;
+use postgres_protocol::message::frontend;
+use std::future::Future;
+use std::sync::Arc;
+
+enum Op {
+    Query {
+        statement: Statement,
+        sender: oneshot::Sender<Result<Vec<Row>, Error>>,
+    },
+    Execute {
+        sender: oneshot::Sender<Result<u64, Error>>,
+    },
+}
+
+impl Op {
+    fn fail(self, e: Error) {
+        match self {
+            Op::Query { sender, .. } => {
+                let _ = sender.send(Err(e));
+            }
+            Op::Execute { sender } => {
+                let _ = sender.send(Err(e));
+            }
+        }
+    }
+}
+
+/// A batch of statements which are sent to the server together.
+///
+/// Operations queued on the pipeline are not sent until `send` is called, at which point they are written to the
+/// server in a single message followed by one `Sync`. Each queued operation returns a future which resolves once
+/// `send` has processed its results.
+///
+/// Because the operations share a single `Sync`, they run in the same implicit transaction unless an explicit one is
+/// active: if one of them fails, the server skips all of the operations queued after it, which fail with an error, and
+/// rolls back the changes made by the ones before it.
+pub struct Pipeline {
+    client: Arc<InnerClient>,
+    buf: Vec<u8>,
+    ops: Vec<Op>,
+}
+
+impl Pipeline {
+    pub(crate) fn new(client: Arc<InnerClient>) -> Pipeline {
+        Pipeline {
+            client,
+            buf: vec![],
+            ops: vec![],
+        }
+    }
+
+    /// Queues a statement, returning a future which resolves to the resulting rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    pub fn query(
+        &mut self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> impl Future<Output = Result<Vec<Row>, Error>> {
+        let (sender, receiver) = oneshot::channel();
+
+        match self.encode(statement, params) {
+            Ok(()) => self.ops.push(Op::Query {
+                statement: statement.clone(),
+                sender,
+            }),
+            Err(e) => {
+                let _ = sender.send(Err(e));
+            }
+        }
+
+        receiver.map(|r| r.unwrap_or_else(|_| Err(Error::pipeline_aborted())))
+    }
+
+    /// Queues a statement, returning a future which resolves to the number of rows modified.
+    ///
+    /// If the statement does not modify any rows (e.g. `SELECT`), 0 is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of parameters provided does not match the number expected.
+    pub fn execute(
+        &mut self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> impl Future<Output = Result<u64, Error>> {
+        let (sender, receiver) = oneshot::channel();
+
+        match self.encode(statement, params) {
+            Ok(()) => self.ops.push(Op::Execute { sender }),
+            Err(e) => {
+                let _ = sender.send(Err(e));
+            }
+        }
+
+        receiver.map(|r| r.unwrap_or_else(|_| Err(Error::pipeline_aborted())))
+    }
+
+    /// Returns the number of operations queued on the pipeline.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Determines if no operations are queued on the pipeline.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Sends the queued operations to the server and processes their results.
+    ///
+    /// The futures returned when the operations were queued resolve once this future completes. An error is only
+    /// returned here if the request could not be sent at all; errors from individual operations are reported through
+    /// their own futures.
+    pub fn send(self) -> impl Future<Output = Result<(), Error>> {
+        let Pipeline {
+            client,
+            mut buf,
+            ops,
+        } = self;
+
+        async move {
+            if ops.is_empty() {
+                return Ok(());
+            }
+
+            frontend::sync(&mut buf);
+            let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
+
+            let mut ops = ops.into_iter();
+            while let Some(op) = ops.next() {
+                let ok = match op {
+                    Op::Query { statement, sender } => {
+                        let r = read_rows(&mut responses, statement).await;
+                        let ok = r.is_ok();
+                        let _ = sender.send(r);
+                        ok
+                    }
+                    Op::Execute { sender } => {
+                        let r = query::read_rows_affected(&mut responses).await;
+                        let ok = r.is_ok();
+                        let _ = sender.send(r);
+                        ok
+                    }
+                };
+
+                // the server discards everything up to the Sync after an error
+                if !ok {
+                    for op in ops {
+                        op.fail(Error::pipeline_aborted());
+                    }
+                    break;
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    fn encode(
+        &mut self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<(), Error> {
+        // the messages are written straight into the pipeline's buffer, and removed again if encoding fails
+        let len = self.buf.len();
+        let r =
+            query::encode_bind_into(statement, params.iter().map(|s| *s as _), "", &mut self.buf)
+                .and_then(|()| frontend::execute("", 0, &mut self.buf).map_err(Error::encode));
+        if r.is_err() {
+            self.buf.truncate(len);
+        }
+        r
+    }
+}
+
+async fn read_rows(responses: &mut Responses, statement: Statement) -> Result<Vec<Row>, Error> {
+    match responses.next().await? {
+        Message::BindComplete => {}
+        _ => return Err(Error::unexpected_message()),
+    }
+
+    let mut rows = vec![];
+    loop {
+        match responses.next().await? {
+            Message::DataRow(body) => rows.push(Row::new(statement.clone(), body)?),
+            Message::CommandComplete(_) | Message::EmptyQueryResponse => return Ok(rows),
+            _ => return Err(Error::unexpected_message()),
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use crate::tls::MakeTlsConnect;
+use crate::{Client, Config, Error, Socket};
+use futures::channel::oneshot;
+use futures::FutureExt;
+use log::debug;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::timer::Timeout;
+
+/// A builder for `Pool`s.
+#[derive(Debug, Clone)]
+pub struct PoolBuilder {
+    max_size: usize,
+    acquire_timeout: Option<Duration>,
+}
+
+impl Default for PoolBuilder {
+    fn default() -> PoolBuilder {
+        PoolBuilder::new()
+    }
+}
+
+impl PoolBuilder {
+    /// Creates a new builder with the default settings.
+    pub fn new() -> PoolBuilder {
+        PoolBuilder {
+            max_size: 10,
+            acquire_timeout: None,
+        }
+    }
+
+    /// Sets the maximum number of connections managed by the pool.
+    ///
+    /// Defaults to 10.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_size` is 0.
+    pub fn max_size(&mut self, max_size: usize) -> &mut PoolBuilder {
+        assert!(max_size > 0, "max_size must be positive");
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets the maximum amount of time `Pool::get` will wait for a connection, including the time spent opening a
+    /// new one.
+    ///
+    /// Defaults to no limit.
+    pub fn acquire_timeout(&mut self, acquire_timeout: Duration) -> &mut PoolBuilder {
+        self.acquire_timeout = Some(acquire_timeout);
+        self
+    }
+
+    /// Creates a pool which opens connections with the specified configuration.
+    ///
+    /// Connections are opened lazily as they are needed.
+    pub fn build<T>(&self, config: Config, tls: T) -> Pool<T>
+    where
+        T: MakeTlsConnect<Socket> + Clone,
+        T::Stream: Send + 'static,
+    {
+        Pool(Arc::new(PoolInner {
+            config,
+            tls,
+            acquire_timeout: self.acquire_timeout,
+            shared: Arc::new(Shared {
+                max_size: self.max_size,
+                state: Mutex::new(State {
+                    idle: vec![],
+                    size: 0,
+                    waiters: VecDeque::new(),
+                }),
+            }),
+        }))
+    }
+}
+
+struct State {
+    idle: Vec<Client>,
+    // the number of connections which are open or being opened, whether idle or checked out
+    size: usize,
+    waiters: VecDeque<oneshot::Sender<Slot>>,
+}
+
+struct Shared {
+    max_size: usize,
+    state: Mutex<State>,
+}
+
+// Returns a connection to the pool, or frees up its spot if it is `None` or has closed. Waiters are served in the
+// order they arrived.
+fn release(shared: &Arc<Shared>, mut client: Option<Client>) {
+    if client.as_ref().map_or(false, Client::is_closed) {
+        client = None;
+    }
+
+    loop {
+        let sender = {
+            let mut state = shared.state.lock();
+            loop {
+                match state.waiters.pop_front() {
+                    Some(sender) if sender.is_canceled() => {}
+                    Some(sender) => break sender,
+                    None => {
+                        match client {
+                            Some(client) => state.idle.push(client),
+                            None => state.size -= 1,
+                        }
+                        return;
+                    }
+                }
+            }
+        };
+
+        // the lock must not be held here, since a slot rejected by the waiter returns itself when dropped
+        let slot = Slot {
+            shared: Some(shared.clone()),
+            client: client.take(),
+        };
+        match sender.send(slot) {
+            Ok(()) => return,
+            Err(mut slot) => {
+                client = slot.client.take();
+                slot.shared = None;
+            }
+        }
+    }
+}
+
+// A claim on one of the pool's connections. If `client` is `None`, the holder is responsible for opening it. The claim
+// is released when dropped, so it is not leaked if the future acquiring a connection is cancelled.
+struct Slot {
+    shared: Option<Arc<Shared>>,
+    client: Option<Client>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if let Some(shared) = self.shared.take() {
+            release(&shared, self.client.take());
+        }
+    }
+}
+
+struct PoolInner<T> {
+    config: Config,
+    tls: T,
+    acquire_timeout: Option<Duration>,
+    shared: Arc<Shared>,
+}
+
+/// A pool of connections to a database.
+///
+/// Requests for a connection are served in the order they arrive when all of the pool's connections are in use.
+/// Connections which have closed are discarded when they are returned to the pool.
+///
+/// The pool is cheaply cloneable, and all clones share the same connections.
+///
+/// Requires the `runtime` Cargo feature (enabled by default).
+pub struct Pool<T>(Arc<PoolInner<T>>);
+
+impl<T> Clone for Pool<T> {
+    fn clone(&self) -> Pool<T> {
+        Pool(self.0.clone())
+    }
+}
+
+impl<T> Pool<T>
+where
+    T: MakeTlsConnect<Socket> + Clone,
+    T::Stream: Send + 'static,
+{
+    /// Creates a pool with the default settings.
+    ///
+    /// Use `PoolBuilder` to configure the pool.
+    pub fn new(config: Config, tls: T) -> Pool<T> {
+        PoolBuilder::new().build(config, tls)
+    }
+
+    /// Retrieves a connection from the pool, opening a new one if none are idle and the pool is not full.
+    ///
+    /// If the pool is full, waits until a connection is returned to it.
+    pub async fn get(&self) -> Result<PooledClient, Error> {
+        match self.0.acquire_timeout {
+            Some(timeout) => match Timeout::new(self.get_inner(), timeout).await {
+                Ok(r) => r,
+                Err(_) => Err(Error::pool_timeout()),
+            },
+            None => self.get_inner().await,
+        }
+    }
+
+    async fn get_inner(&self) -> Result<PooledClient, Error> {
+        let shared = &self.0.shared;
+
+        let receiver = {
+            let mut state = shared.state.lock();
+
+            while let Some(client) = state.idle.pop() {
+                if !client.is_closed() {
+                    return Ok(PooledClient {
+                        client: Some(client),
+                        shared: shared.clone(),
+                    });
+                }
+                state.size -= 1;
+            }
+
+            if state.size < shared.max_size {
+                state.size += 1;
+                None
+            } else {
+                let (sender, receiver) = oneshot::channel();
+                state.waiters.push_back(sender);
+                Some(receiver)
+            }
+        };
+
+        let mut slot = match receiver {
+            Some(receiver) => receiver.await.map_err(|_| Error::closed())?,
+            None => Slot {
+                shared: Some(shared.clone()),
+                client: None,
+            },
+        };
+
+        let client = match slot.client.take().filter(|c| !c.is_closed()) {
+            Some(client) => client,
+            None => {
+                // if this fails, dropping the slot passes it on to the next waiter
+                let (client, connection) = self.0.config.connect(self.0.tls.clone()).await?;
+                let connection = connection.map(|r| {
+                    if let Err(e) = r {
+                        debug!("connection error: {}", e);
+                    }
+                });
+                tokio::spawn(connection);
+                client
+            }
+        };
+        slot.shared = None;
+
+        Ok(PooledClient {
+            client: Some(client),
+            shared: shared.clone(),
+        })
+    }
+
+    /// Returns the number of connections currently managed by the pool, including those which are checked out.
+    pub fn size(&self) -> usize {
+        self.0.shared.state.lock().size
+    }
+
+    /// Returns the number of idle connections in the pool.
+    pub fn idle(&self) -> usize {
+        self.0.shared.state.lock().idle.len()
+    }
+}
+
+/// A connection checked out of a `Pool`.
+///
+/// It dereferences to a `Client`, and is returned to the pool when dropped.
+pub struct PooledClient {
+    client: Option<Client>,
+    shared: Arc<Shared>,
+}
+
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        release(&self.shared, self.client.take());
+    }
+}
+
+impl Deref for PooledClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.client.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledClient {
+    fn deref_mut(&mut self) -> &mut Client {
+        self.client.as_mut().unwrap()
+    }
+}
@@ -3,6 +3,7 @@ use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
 use crate::error::SqlState;
 use crate::query;
+use crate::trace;
 use crate::types::{Field, Kind, Oid, ToSql, Type};
 use crate::{Column, Error, Statement};
 use fallible_iterator::FallibleIterator;
@@ -63,7 +64,15 @@ pub fn prepare(
     types: &[Type],
 ) -> impl Future<Output = Result<Statement, Error>> + 'static {
     let name = format!("s{}", NEXT_ID.fetch_add(1, Ordering::SeqCst));
-    let buf = encode(&name, query, types);
+    trace::prepare(&name, query);
+    let rewritten = client.interceptor().and_then(|i| i.rewrite_query(query));
+    let buf = encode(
+        &client,
+        &name,
+        rewritten.as_ref().map_or(query, |q| &**q),
+        types,
+    );
+    let query = query.to_string();
 
     async move {
         let buf = buf?;
@@ -97,12 +106,35 @@ pub fn prepare(
             let mut it = row_description.fields();
             while let Some(field) = it.next().map_err(Error::parse)? {
                 let type_ = get_type(&client, field.type_oid()).await?;
-                let column = Column::new(field.name().to_string(), type_);
+                let column = Column::new(
+                    field.name().to_string(),
+                    type_,
+                    non_zero(field.table_oid()),
+                    non_zero(field.column_id()),
+                );
                 columns.push(column);
             }
         }
 
-        Ok(Statement::new(&client, name, parameters, columns))
+        Ok(Statement::new(&client, name, query, parameters, columns))
+    }
+}
+
+pub fn prepare_cached(
+    client: Arc<InnerClient>,
+    query: &str,
+) -> impl Future<Output = Result<Statement, Error>> + 'static {
+    let query = query.to_string();
+
+    async move {
+        if let Some(statement) = client.cached_statement(&query) {
+            return Ok(statement);
+        }
+
+        let statement = prepare(client.clone(), &query, &[]).await?;
+        client.cache_statement(&query, &statement);
+
+        Ok(statement)
     }
 }
 
@@ -114,8 +146,8 @@ fn prepare_rec(
     Box::pin(prepare(client, query, types))
 }
 
-fn encode(name: &str, query: &str, types: &[Type]) -> Result<Vec<u8>, Error> {
-    let mut buf = vec![];
+fn encode(client: &InnerClient, name: &str, query: &str, types: &[Type]) -> Result<Vec<u8>, Error> {
+    let mut buf = client.buffer();
     frontend::parse(name, query, types.iter().map(Type::oid), &mut buf).map_err(Error::encode)?;
     frontend::describe(b'S', &name, &mut buf).map_err(Error::encode)?;
     frontend::sync(&mut buf);
@@ -123,18 +155,30 @@ fn encode(name: &str, query: &str, types: &[Type]) -> Result<Vec<u8>, Error> {
     Ok(buf)
 }
 
-async fn get_type(client: &Arc<InnerClient>, oid: Oid) -> Result<Type, Error> {
-    if let Some(type_) = Type::from_oid(oid) {
-        return Ok(type_);
+// the server sends 0 for columns which are not plain references to a table column
+pub fn non_zero<T>(v: T) -> Option<T>
+where
+    T: Default + PartialEq,
+{
+    if v == T::default() {
+        None
+    } else {
+        Some(v)
     }
+}
+
+pub fn cached_type(client: &InnerClient, oid: Oid) -> Option<Type> {
+    Type::from_oid(oid).or_else(|| client.type_(oid))
+}
 
-    if let Some(type_) = client.type_(oid) {
+pub async fn get_type(client: &Arc<InnerClient>, oid: Oid) -> Result<Type, Error> {
+    if let Some(type_) = cached_type(client, oid) {
         return Ok(type_);
     }
 
     let stmt = typeinfo_statement(client).await?;
 
-    let buf = query::encode(&stmt, (&[&oid as &dyn ToSql]).iter().cloned());
+    let buf = query::encode(client, &stmt, (&[&oid as &dyn ToSql]).iter().cloned());
     let rows = query::query(client.clone(), stmt, buf);
     pin_mut!(rows);
 
@@ -205,7 +249,7 @@ async fn typeinfo_statement(client: &Arc<InnerClient>) -> Result<Statement, Erro
 async fn get_enum_variants(client: &Arc<InnerClient>, oid: Oid) -> Result<Vec<String>, Error> {
     let stmt = typeinfo_enum_statement(client).await?;
 
-    let buf = query::encode(&stmt, (&[&oid as &dyn ToSql]).iter().cloned());
+    let buf = query::encode(client, &stmt, (&[&oid as &dyn ToSql]).iter().cloned());
     query::query(client.clone(), stmt, buf)
         .and_then(|row| future::ready(row.try_get(0)))
         .try_collect()
@@ -232,7 +276,7 @@ async fn typeinfo_enum_statement(client: &Arc<InnerClient>) -> Result<Statement,
 async fn get_composite_fields(client: &Arc<InnerClient>, oid: Oid) -> Result<Vec<Field>, Error> {
     let stmt = typeinfo_composite_statement(client).await?;
 
-    let buf = query::encode(&stmt, (&[&oid as &dyn ToSql]).iter().cloned());
+    let buf = query::encode(client, &stmt, (&[&oid as &dyn ToSql]).iter().cloned());
     let rows = query::query(client.clone(), stmt, buf)
         .try_collect::<Vec<_>>()
         .await?;
@@ -1,33 +1,148 @@
 use crate::client::{InnerClient, Responses};
 use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
-use crate::types::{IsNull, ToSql};
-use crate::{Error, Portal, Row, Statement};
-use futures::{ready, Stream, TryFutureExt};
-use postgres_protocol::message::backend::Message;
+use crate::interceptor::Intercepted;
+use crate::prepare;
+use crate::statement_cache;
+use crate::trace::QuerySpan;
+use crate::types::{IsNull, ToSql, Type};
+use crate::{Column, Error, Portal, Row, Statement};
+use fallible_iterator::FallibleIterator;
+use futures::{ready, stream, Stream, TryStreamExt};
+use postgres_protocol::message::backend::{CommandCompleteBody, Message};
 use postgres_protocol::message::frontend;
+use std::collections::VecDeque;
+use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 pub fn query(
     client: Arc<InnerClient>,
     statement: Statement,
     buf: Result<Vec<u8>, Error>,
-) -> impl Stream<Item = Result<Row, Error>> {
-    start(client, buf)
-        .map_ok(|responses| Query {
-            statement,
+) -> RowStream {
+    let span = QuerySpan::statement(statement.name());
+    RowStream::new(
+        async move {
+            let responses = start(client, statement.clone(), buf).await?;
+            Ok(Query {
+                statement,
+                responses,
+                buffered: VecDeque::new(),
+                rows_affected: None,
+            })
+        },
+        span,
+    )
+}
+
+pub fn query_with_statement_timeout(
+    client: Arc<InnerClient>,
+    statement: Statement,
+    buf: Result<Vec<u8>, Error>,
+) -> RowStream {
+    let span = QuerySpan::statement(statement.name());
+    RowStream::new(
+        async move {
+            let responses = start_with_statement_timeout(client, statement.clone(), buf).await?;
+            Ok(Query {
+                statement,
+                responses,
+                buffered: VecDeque::new(),
+                rows_affected: None,
+            })
+        },
+        span,
+    )
+}
+
+pub fn query_typed(
+    client: Arc<InnerClient>,
+    query: &str,
+    params: &[(&(dyn ToSql + Sync), Type)],
+) -> RowStream {
+    let span = QuerySpan::unprepared(query);
+    let rewritten = client.interceptor().and_then(|i| i.rewrite_query(query));
+    let buf = encode_typed(&client, rewritten.as_ref().map_or(query, |q| &**q), params);
+    let params = params.iter().map(|(_, ty)| ty.clone()).collect::<Vec<_>>();
+    let query = query.to_string();
+
+    let start = async move {
+        let buf = buf?;
+        let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
+
+        match responses.next().await? {
+            Message::ParseComplete => {}
+            _ => return Err(Error::unexpected_message()),
+        }
+
+        match responses.next().await? {
+            Message::BindComplete => {}
+            _ => return Err(Error::unexpected_message()),
+        }
+
+        let row_description = match responses.next().await? {
+            Message::RowDescription(body) => Some(body),
+            Message::NoData => None,
+            _ => return Err(Error::unexpected_message()),
+        };
+
+        let mut buffered = VecDeque::new();
+        let mut columns = vec![];
+        if let Some(row_description) = row_description {
+            let fields = row_description
+                .fields()
+                .map(|f| {
+                    Ok((
+                        f.name().to_string(),
+                        f.type_oid(),
+                        prepare::non_zero(f.table_oid()),
+                        prepare::non_zero(f.column_id()),
+                    ))
+                })
+                .collect::<Vec<_>>()
+                .map_err(Error::parse)?;
+
+            // Looking up unknown types requires another request, which the server won't see until the rest of this
+            // one's responses have been read, so they need to be buffered first.
+            if fields
+                .iter()
+                .any(|(_, oid, _, _)| prepare::cached_type(&client, *oid).is_none())
+            {
+                loop {
+                    let message = responses.next().await?;
+                    let done = match message {
+                        Message::DataRow(_) => false,
+                        _ => true,
+                    };
+                    buffered.push_back(message);
+                    if done {
+                        break;
+                    }
+                }
+            }
+
+            for (name, oid, table_oid, column_id) in fields {
+                let type_ = prepare::get_type(&client, oid).await?;
+                columns.push(Column::new(name, type_, table_oid, column_id));
+            }
+        }
+
+        Ok(Query {
+            statement: Statement::unnamed(query, params, columns),
             responses,
+            buffered,
+            rows_affected: None,
         })
-        .try_flatten_stream()
+    };
+
+    RowStream::new(start, span)
 }
 
-pub fn query_portal(
-    client: Arc<InnerClient>,
-    portal: Portal,
-    max_rows: i32,
-) -> impl Stream<Item = Result<Row, Error>> {
+pub fn query_portal(client: Arc<InnerClient>, portal: Portal, max_rows: i32) -> RowStream {
+    let span = QuerySpan::statement(portal.statement().name());
     let start = async move {
         let mut buf = vec![];
         frontend::execute(portal.name(), max_rows, &mut buf).map_err(Error::encode)?;
@@ -38,60 +153,352 @@ pub fn query_portal(
         Ok(Query {
             statement: portal.statement().clone(),
             responses,
+            buffered: VecDeque::new(),
+            rows_affected: None,
         })
     };
 
-    start.try_flatten_stream()
+    RowStream::new(start, span)
 }
 
-pub async fn execute(client: Arc<InnerClient>, buf: Result<Vec<u8>, Error>) -> Result<u64, Error> {
-    let mut responses = start(client, buf).await?;
+pub fn query_portal_chunks(
+    client: Arc<InnerClient>,
+    portal: Portal,
+    chunk_size: i32,
+) -> impl Stream<Item = Result<Vec<Row>, Error>> {
+    stream::unfold(Some((client, portal)), move |state| async move {
+        let (client, portal) = match state {
+            Some(state) => state,
+            None => return None,
+        };
 
+        match query_portal_chunk(&client, &portal, chunk_size).await {
+            Ok((ref rows, true)) if rows.is_empty() => None,
+            Ok((rows, true)) => Some((Ok(rows), None)),
+            Ok((rows, false)) => Some((Ok(rows), Some((client, portal)))),
+            Err(e) => Some((Err(e), None)),
+        }
+    })
+}
+
+// returns the rows and whether the portal has been exhausted
+async fn query_portal_chunk(
+    client: &InnerClient,
+    portal: &Portal,
+    max_rows: i32,
+) -> Result<(Vec<Row>, bool), Error> {
+    let mut buf = vec![];
+    frontend::execute(portal.name(), max_rows, &mut buf).map_err(Error::encode)?;
+    frontend::sync(&mut buf);
+
+    let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
+
+    let mut rows = vec![];
+    loop {
+        match responses.next().await? {
+            Message::DataRow(body) => rows.push(Row::new(portal.statement().clone(), body)?),
+            Message::PortalSuspended => return Ok((rows, false)),
+            Message::CommandComplete(_) | Message::EmptyQueryResponse => return Ok((rows, true)),
+            _ => return Err(Error::unexpected_message()),
+        }
+    }
+}
+
+pub async fn execute(
+    client: Arc<InnerClient>,
+    statement: Statement,
+    buf: Result<Vec<u8>, Error>,
+) -> Result<u64, Error> {
+    let mut span = QuerySpan::statement(statement.name());
+    let r = match start(client, statement, buf).await {
+        Ok(mut responses) => read_command_complete(&mut responses).await,
+        Err(e) => Err(e),
+    };
+    if let Ok(rows) = r {
+        span.rows(rows);
+    }
+    span.finish(r.as_ref().err());
+    r
+}
+
+pub async fn execute_returning(mut stream: RowStream) -> Result<(u64, Vec<Row>), Error> {
+    let mut rows = vec![];
+    while let Some(row) = stream.try_next().await? {
+        rows.push(row);
+    }
+    Ok((stream.rows_affected().unwrap_or(0), rows))
+}
+
+pub async fn execute_with_statement_timeout(
+    client: Arc<InnerClient>,
+    statement: Statement,
+    buf: Result<Vec<u8>, Error>,
+) -> Result<u64, Error> {
+    let mut span = QuerySpan::statement(statement.name());
+    let r = match start_with_statement_timeout(client, statement, buf).await {
+        Ok(mut responses) => read_command_complete(&mut responses).await,
+        Err(e) => Err(e),
+    };
+    if let Ok(rows) = r {
+        span.rows(rows);
+    }
+    span.finish(r.as_ref().err());
+    r
+}
+
+pub async fn execute_many(
+    client: Arc<InnerClient>,
+    statement: Statement,
+    buf: Result<(Vec<u8>, usize), Error>,
+) -> Result<Vec<u64>, Error> {
+    let (buf, count) = buf?;
+    if count == 0 {
+        return Ok(vec![]);
+    }
+
+    let mut span = QuerySpan::statement(statement.name());
+    let r = execute_many_inner(client, statement, buf, count).await;
+    if let Ok(rows) = &r {
+        span.rows(rows.iter().sum());
+    }
+    span.finish(r.as_ref().err());
+    r
+}
+
+async fn execute_many_inner(
+    client: Arc<InnerClient>,
+    statement: Statement,
+    buf: Vec<u8>,
+    count: usize,
+) -> Result<Vec<u64>, Error> {
+    let mut responses = start(client, statement, Ok(buf)).await?;
+
+    let mut rows = Vec::with_capacity(count);
+    rows.push(read_command_complete(&mut responses).await?);
+    for _ in 1..count {
+        rows.push(read_rows_affected(&mut responses).await?);
+    }
+
+    Ok(rows)
+}
+
+pub async fn read_rows_affected(responses: &mut Responses) -> Result<u64, Error> {
+    match responses.next().await? {
+        Message::BindComplete => {}
+        _ => return Err(Error::unexpected_message()),
+    }
+
+    read_command_complete(responses).await
+}
+
+async fn read_command_complete(responses: &mut Responses) -> Result<u64, Error> {
     loop {
         match responses.next().await? {
             Message::DataRow(_) => {}
-            Message::CommandComplete(body) => {
-                let rows = body
-                    .tag()
-                    .map_err(Error::parse)?
-                    .rsplit(' ')
-                    .next()
-                    .unwrap()
-                    .parse()
-                    .unwrap_or(0);
-                return Ok(rows);
-            }
+            Message::CommandComplete(body) => return rows_affected(&body),
             Message::EmptyQueryResponse => return Ok(0),
             _ => return Err(Error::unexpected_message()),
         }
     }
 }
 
-async fn start(client: Arc<InnerClient>, buf: Result<Vec<u8>, Error>) -> Result<Responses, Error> {
+fn rows_affected(body: &CommandCompleteBody) -> Result<u64, Error> {
+    let rows = body
+        .tag()
+        .map_err(Error::parse)?
+        .rsplit(' ')
+        .next()
+        .unwrap()
+        .parse()
+        .unwrap_or(0);
+    Ok(rows)
+}
+
+async fn start(
+    client: Arc<InnerClient>,
+    statement: Statement,
+    buf: Result<Vec<u8>, Error>,
+) -> Result<Responses, Error> {
     let buf = buf?;
     let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
+    read_bind_complete(&client, &statement, &mut responses).await?;
+
+    Ok(responses)
+}
+
+async fn start_with_statement_timeout(
+    client: Arc<InnerClient>,
+    statement: Statement,
+    buf: Result<Vec<u8>, Error>,
+) -> Result<Responses, Error> {
+    let buf = buf?;
+    let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
+    // the statements saving the current timeout and setting the new one
+    for _ in 0..2 {
+        read_set_config(&mut responses).await?;
+    }
+    read_bind_complete(&client, &statement, &mut responses).await?;
+
+    Ok(responses)
+}
+
+async fn read_bind_complete(
+    client: &InnerClient,
+    statement: &Statement,
+    responses: &mut Responses,
+) -> Result<(), Error> {
+    match responses.next().await {
+        Ok(Message::BindComplete) => Ok(()),
+        Ok(_) => Err(Error::unexpected_message()),
+        Err(e) => {
+            if statement_cache::is_invalidated(&e) {
+                client.invalidate_statement(statement);
+            }
+            Err(e)
+        }
+    }
+}
+
+async fn read_set_config(responses: &mut Responses) -> Result<(), Error> {
+    match responses.next().await? {
+        Message::ParseComplete => {}
+        _ => return Err(Error::unexpected_message()),
+    }
 
     match responses.next().await? {
         Message::BindComplete => {}
         _ => return Err(Error::unexpected_message()),
     }
 
-    Ok(responses)
+    read_command_complete(responses).await?;
+    Ok(())
+}
+
+pub fn encode<'a, I>(
+    client: &InnerClient,
+    statement: &Statement,
+    params: I,
+) -> Result<Vec<u8>, Error>
+where
+    I: IntoIterator<Item = &'a dyn ToSql>,
+    I::IntoIter: ExactSizeIterator,
+{
+    let mut buf = encode_bind(client, statement, params, "")?;
+    frontend::execute("", 0, &mut buf).map_err(Error::encode)?;
+    frontend::sync(&mut buf);
+
+    Ok(buf)
 }
 
-pub fn encode<'a, I>(statement: &Statement, params: I) -> Result<Vec<u8>, Error>
+// The timeout is set with `set_config(..., true)`, which like `SET LOCAL` lasts until the end of the current
+// transaction. The statements all run in the same implicit transaction unless an explicit one is active, so the
+// previous value is stashed in a custom setting and restored after the statement to avoid leaking the timeout into the
+// rest of an explicit transaction. If the statement fails, the transaction is aborted and its settings are rolled back
+// anyway.
+const SAVE_STATEMENT_TIMEOUT: &str =
+    "SELECT set_config('tokio_postgres.statement_timeout', current_setting('statement_timeout'), true)";
+const SET_STATEMENT_TIMEOUT: &str = "SELECT set_config('statement_timeout', $1, true)";
+const RESTORE_STATEMENT_TIMEOUT: &str =
+    "SELECT set_config('statement_timeout', current_setting('tokio_postgres.statement_timeout'), true)";
+
+pub fn encode_with_statement_timeout<'a, I>(
+    client: &InnerClient,
+    statement: &Statement,
+    params: I,
+    timeout: Duration,
+) -> Result<Vec<u8>, Error>
 where
     I: IntoIterator<Item = &'a dyn ToSql>,
     I::IntoIter: ExactSizeIterator,
 {
-    let mut buf = encode_bind(statement, params, "")?;
+    // round up, since a sub-millisecond timeout truncated to 0 would disable the limit instead
+    let millis = (timeout.as_nanos() + 999_999) / 1_000_000;
+    let timeout = format!("{}ms", millis);
+
+    let mut buf = client.buffer();
+    encode_set_config(SAVE_STATEMENT_TIMEOUT, &[], &mut buf)?;
+    encode_set_config(SET_STATEMENT_TIMEOUT, &[&timeout], &mut buf)?;
+    encode_bind_into(statement, params, "", &mut buf)?;
     frontend::execute("", 0, &mut buf).map_err(Error::encode)?;
+    encode_set_config(RESTORE_STATEMENT_TIMEOUT, &[], &mut buf)?;
+    frontend::sync(&mut buf);
+
+    Ok(buf)
+}
+
+fn encode_set_config(query: &str, params: &[&str], buf: &mut Vec<u8>) -> Result<(), Error> {
+    let ty = Type::TEXT;
+    frontend::parse("", query, params.iter().map(|_| ty.oid()), buf).map_err(Error::encode)?;
+    encode_bind_raw("", params.iter().map(|p| (p as &dyn ToSql, &ty)), "", buf)?;
+    frontend::execute("", 0, buf).map_err(Error::encode)
+}
+
+// Like `encode`, but also reports the query to the client's interceptor if there is one. The parameters are collected
+// first in that case since they're needed twice.
+pub fn encode_intercepted<'a, I>(
+    client: &InnerClient,
+    statement: &Statement,
+    params: I,
+) -> (Result<Vec<u8>, Error>, Option<Intercepted>)
+where
+    I: IntoIterator<Item = &'a dyn ToSql>,
+    I::IntoIter: ExactSizeIterator,
+{
+    match client.interceptor() {
+        Some(interceptor) => {
+            let params = params.into_iter().collect::<Vec<_>>();
+            let intercepted =
+                Intercepted::new(interceptor, statement.query(), params.iter().cloned());
+            (encode(client, statement, params), Some(intercepted))
+        }
+        None => (encode(client, statement, params), None),
+    }
+}
+
+pub fn encode_many<'a, I, P>(
+    client: &InnerClient,
+    statement: &Statement,
+    params: I,
+) -> Result<(Vec<u8>, usize), Error>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<[&'a (dyn ToSql + Sync)]>,
+{
+    let mut buf = client.buffer();
+    let mut count = 0;
+    for params in params {
+        let params = params.as_ref();
+        encode_bind_into(statement, params.iter().map(|s| *s as _), "", &mut buf)?;
+        frontend::execute("", 0, &mut buf).map_err(Error::encode)?;
+        count += 1;
+    }
     frontend::sync(&mut buf);
 
+    Ok((buf, count))
+}
+
+pub fn encode_bind<'a, I>(
+    client: &InnerClient,
+    statement: &Statement,
+    params: I,
+    portal: &str,
+) -> Result<Vec<u8>, Error>
+where
+    I: IntoIterator<Item = &'a dyn ToSql>,
+    I::IntoIter: ExactSizeIterator,
+{
+    let mut buf = client.buffer();
+    encode_bind_into(statement, params, portal, &mut buf)?;
     Ok(buf)
 }
 
-pub fn encode_bind<'a, I>(statement: &Statement, params: I, portal: &str) -> Result<Vec<u8>, Error>
+// Like `encode_bind`, but appends the message to an existing buffer.
+pub fn encode_bind_into<'a, I>(
+    statement: &Statement,
+    params: I,
+    portal: &str,
+    buf: &mut Vec<u8>,
+) -> Result<(), Error>
 where
     I: IntoIterator<Item = &'a dyn ToSql>,
     I::IntoIter: ExactSizeIterator,
@@ -105,14 +512,50 @@ where
         params.len()
     );
 
-    let mut buf = vec![];
+    encode_bind_raw(
+        statement.name(),
+        params.zip(statement.params()),
+        portal,
+        buf,
+    )
+}
 
+fn encode_typed(
+    client: &InnerClient,
+    query: &str,
+    params: &[(&(dyn ToSql + Sync), Type)],
+) -> Result<Vec<u8>, Error> {
+    let mut buf = client.buffer();
+    frontend::parse("", query, params.iter().map(|(_, ty)| ty.oid()), &mut buf)
+        .map_err(Error::encode)?;
+    encode_bind_raw(
+        "",
+        params.iter().map(|(param, ty)| (*param as &dyn ToSql, ty)),
+        "",
+        &mut buf,
+    )?;
+    frontend::describe(b'P', "", &mut buf).map_err(Error::encode)?;
+    frontend::execute("", 0, &mut buf).map_err(Error::encode)?;
+    frontend::sync(&mut buf);
+
+    Ok(buf)
+}
+
+fn encode_bind_raw<'a, I>(
+    statement: &str,
+    params: I,
+    portal: &str,
+    buf: &mut Vec<u8>,
+) -> Result<(), Error>
+where
+    I: IntoIterator<Item = (&'a dyn ToSql, &'a Type)>,
+{
     let mut error_idx = 0;
     let r = frontend::bind(
         portal,
-        statement.name(),
+        statement,
         Some(1),
-        params.zip(statement.params()).enumerate(),
+        params.into_iter().enumerate(),
         |(idx, (param, ty)), buf| match param.to_sql_checked(ty, buf) {
             Ok(IsNull::No) => Ok(postgres_protocol::IsNull::No),
             Ok(IsNull::Yes) => Ok(postgres_protocol::IsNull::Yes),
@@ -122,33 +565,137 @@ where
             }
         },
         Some(1),
-        &mut buf,
+        buf,
     );
     match r {
-        Ok(()) => Ok(buf),
-        Err(frontend::BindError::Conversion(e)) => return Err(Error::to_sql(e, error_idx)),
-        Err(frontend::BindError::Serialization(e)) => return Err(Error::encode(e)),
+        Ok(()) => Ok(()),
+        Err(frontend::BindError::Conversion(e)) => Err(Error::to_sql(e, error_idx)),
+        Err(frontend::BindError::Serialization(e)) => Err(Error::encode(e)),
     }
 }
 
 struct Query {
     statement: Statement,
     responses: Responses,
+    buffered: VecDeque<Message>,
+    rows_affected: Option<u64>,
 }
 
 impl Stream for Query {
     type Item = Result<Row, Error>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        match ready!(self.responses.poll_next(cx)?) {
+        let message = match self.buffered.pop_front() {
+            Some(message) => message,
+            None => ready!(self.responses.poll_next(cx)?),
+        };
+
+        match message {
             Message::DataRow(body) => {
                 Poll::Ready(Some(Ok(Row::new(self.statement.clone(), body)?)))
             }
-            Message::EmptyQueryResponse
-            | Message::CommandComplete(_)
-            | Message::PortalSuspended => Poll::Ready(None),
+            Message::CommandComplete(body) => {
+                self.rows_affected = Some(rows_affected(&body)?);
+                Poll::Ready(None)
+            }
+            Message::EmptyQueryResponse | Message::PortalSuspended => Poll::Ready(None),
             Message::ErrorResponse(body) => Poll::Ready(Some(Err(Error::db(body)))),
             _ => Poll::Ready(Some(Err(Error::unexpected_message()))),
         }
     }
 }
+
+enum State {
+    Starting(Pin<Box<dyn Future<Output = Result<Query, Error>> + Send>>),
+    Streaming(Query),
+    Done,
+}
+
+/// A stream of rows returned by a query.
+///
+/// Rows are read from the connection as the stream is polled. If the stream is not polled, the connection stops
+/// reading from the socket once a small amount of data is buffered, which in turn pauses the server via TCP flow
+/// control, so a slow consumer does not cause the entire result set to be held in memory. Note that this also blocks
+/// responses to any other requests made on the same connection until the stream is polled again or dropped.
+#[must_use = "streams do nothing unless polled"]
+pub struct RowStream {
+    state: State,
+    span: QuerySpan,
+    intercepted: Option<Intercepted>,
+    rows_affected: Option<u64>,
+}
+
+impl RowStream {
+    fn new<F>(start: F, span: QuerySpan) -> RowStream
+    where
+        F: Future<Output = Result<Query, Error>> + Send + 'static,
+    {
+        RowStream {
+            state: State::Starting(Box::pin(start)),
+            span,
+            intercepted: None,
+            rows_affected: None,
+        }
+    }
+
+    pub(crate) fn intercept(mut self, intercepted: Option<Intercepted>) -> RowStream {
+        self.intercepted = intercepted;
+        self
+    }
+
+    /// Returns the number of rows affected by the query, as reported by the server once the stream has been
+    /// exhausted.
+    ///
+    /// This is `None` until the stream has returned all of its rows, and if the query was a portal execution which
+    /// stopped before the end of its results.
+    pub fn rows_affected(&self) -> Option<u64> {
+        self.rows_affected
+    }
+
+    fn row(&mut self) {
+        self.span.rows(1);
+        if let Some(intercepted) = &mut self.intercepted {
+            intercepted.rows(1);
+        }
+    }
+
+    fn finish(&mut self, error: Option<&Error>) {
+        self.state = State::Done;
+        self.span.finish(error);
+        if let Some(intercepted) = &self.intercepted {
+            intercepted.finish(error);
+        }
+    }
+}
+
+impl Stream for RowStream {
+    type Item = Result<Row, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match self.state {
+                State::Starting(ref mut start) => match ready!(start.as_mut().poll(cx)) {
+                    Ok(query) => self.state = State::Streaming(query),
+                    Err(e) => {
+                        self.finish(Some(&e));
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                },
+                State::Streaming(ref mut query) => {
+                    let r = ready!(Pin::new(&mut *query).poll_next(cx));
+                    let rows_affected = query.rows_affected;
+                    match r {
+                        Some(Ok(_)) => self.row(),
+                        Some(Err(ref e)) => self.finish(Some(e)),
+                        None => {
+                            self.rows_affected = rows_affected;
+                            self.finish(None);
+                        }
+                    }
+                    return Poll::Ready(r);
+                }
+                State::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::tls::MakeTlsConnect;
+use crate::{Client, Config, Error, Socket, Statement};
+use futures::FutureExt;
+use log::debug;
+use std::cmp;
+use std::time::{Duration, Instant};
+use tokio::timer;
+
+/// A wrapper around a `Client` which transparently reconnects to the database after the connection is lost.
+///
+/// The connection is established lazily by `client`, and re-established there whenever the previous one has closed,
+/// retrying failed attempts with exponential backoff. Each connection's background task is spawned onto the tokio
+/// runtime.
+///
+/// Requests which were in flight when the connection was lost are not retried, since they may or may not have been
+/// applied by the server. They fail with an error for which `Error::is_closed` returns `true`, and the next call to
+/// `client` reconnects.
+///
+/// Statements are tied to the connection which prepared them, so they can't be used after a reconnect. Statements
+/// prepared through `ReconnectingClient::prepare_cached` are re-prepared on each new connection so that calling it
+/// again is cheap.
+///
+/// Requires the `runtime` Cargo feature (enabled by default).
+pub struct ReconnectingClient<T> {
+    config: Config,
+    tls: T,
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    statement_cache_capacity: usize,
+    prepared: Vec<String>,
+    client: Option<Client>,
+}
+
+impl<T> ReconnectingClient<T>
+where
+    T: MakeTlsConnect<Socket> + Clone,
+    T::Stream: Send + 'static,
+{
+    /// Creates a new client which connects with the specified configuration.
+    ///
+    /// No connection is made until the client is first used.
+    pub fn new(config: Config, tls: T) -> ReconnectingClient<T> {
+        ReconnectingClient {
+            config,
+            tls,
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            statement_cache_capacity: 100,
+            prepared: vec![],
+            client: None,
+        }
+    }
+
+    /// Sets the maximum number of connection attempts made by each reconnect before its error is returned.
+    ///
+    /// Defaults to 5.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_attempts` is 0.
+    pub fn max_attempts(&mut self, max_attempts: u32) -> &mut ReconnectingClient<T> {
+        assert!(max_attempts > 0, "max_attempts must be positive");
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the delay before the first retry of a failed connection attempt, and the maximum delay it doubles up to
+    /// on each subsequent retry.
+    ///
+    /// Defaults to 100 milliseconds and 10 seconds.
+    pub fn backoff(&mut self, initial: Duration, max: Duration) -> &mut ReconnectingClient<T> {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Sets the capacity of each connection's statement cache, which also bounds the number of statements
+    /// re-prepared after a reconnect.
+    ///
+    /// Defaults to 100.
+    pub fn statement_cache_capacity(&mut self, capacity: usize) -> &mut ReconnectingClient<T> {
+        self.statement_cache_capacity = capacity;
+        self.prepared.truncate(capacity);
+        if let Some(client) = &mut self.client {
+            client.set_statement_cache_capacity(capacity);
+        }
+        self
+    }
+
+    /// Determines if the client currently holds an open connection.
+    pub fn is_connected(&self) -> bool {
+        self.client.as_ref().map_or(false, |c| !c.is_closed())
+    }
+
+    /// Returns the underlying client, first connecting to the database if there is no open connection.
+    pub async fn client(&mut self) -> Result<&mut Client, Error> {
+        if !self.is_connected() {
+            self.client = None;
+            let client = self.connect().await?;
+            self.client = Some(client);
+        }
+
+        Ok(self.client.as_mut().unwrap())
+    }
+
+    /// Like `Client::prepare_cached`, but the statement is also re-prepared on each new connection.
+    ///
+    /// The returned statement can only be used until the connection is lost, so this should be called again to
+    /// retrieve the statement for each use rather than holding on to it.
+    pub async fn prepare_cached(&mut self, query: &str) -> Result<Statement, Error> {
+        let statement = self.client().await?.prepare_cached(query).await?;
+
+        if let Some(pos) = self.prepared.iter().position(|q| q == query) {
+            let query = self.prepared.remove(pos);
+            self.prepared.push(query);
+        } else if self.statement_cache_capacity > 0 {
+            if self.prepared.len() == self.statement_cache_capacity {
+                self.prepared.remove(0);
+            }
+            self.prepared.push(query.to_string());
+        }
+
+        Ok(statement)
+    }
+
+    async fn connect(&mut self) -> Result<Client, Error> {
+        let mut backoff = self.initial_backoff;
+        let mut attempts = 1;
+        let mut client = loop {
+            match self.config.connect(self.tls.clone()).await {
+                Ok((client, connection)) => {
+                    let connection = connection.map(|r| {
+                        if let Err(e) = r {
+                            debug!("connection error: {}", e);
+                        }
+                    });
+                    tokio::spawn(connection);
+                    break client;
+                }
+                Err(e) => {
+                    if attempts >= self.max_attempts {
+                        return Err(e);
+                    }
+                    debug!("connection attempt {} failed: {}", attempts, e);
+                    attempts += 1;
+                    timer::delay(Instant::now() + backoff).await;
+                    backoff = cmp::min(backoff * 2, self.max_backoff);
+                }
+            }
+        };
+
+        client.set_statement_cache_capacity(self.statement_cache_capacity);
+        let mut i = 0;
+        while i < self.prepared.len() {
+            match client.prepare_cached(&self.prepared[i]).await {
+                Ok(_) => i += 1,
+                Err(e) if e.is_closed() => return Err(e),
+                // the statement may no longer be valid, for example if a table it references was dropped
+                Err(e) => {
+                    debug!("error re-preparing statement: {}", e);
+                    self.prepared.remove(i);
+                }
+            }
+        }
+
+        Ok(client)
+    }
+}
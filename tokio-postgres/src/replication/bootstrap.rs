@@ -0,0 +1,97 @@
+use crate::replication::{LogicalReplicationStream, ReplicationClient, ReplicationSlot};
+use crate::{Error, Transaction};
+use bytes::Bytes;
+use futures::Stream;
+
+/// The initial copy of a new logical replication slot's tables, created by `CreateSlotBuilder::bootstrap`.
+///
+/// The bootstrap's transaction sees the database as of the slot's consistent point, so the data copied with it
+/// combined with the changes streamed from the slot afterwards account for every change exactly once, even if the
+/// tables are being written to concurrently.
+///
+/// # Example
+///
+/// ```no_run
+/// use futures::TryStreamExt;
+/// use tokio_postgres::replication::ReplicationClient;
+/// use tokio_postgres::{Client, Error};
+///
+/// # async fn f(replication: &mut ReplicationClient, client: &mut Client) -> Result<(), Error> {
+/// let mut bootstrap = replication.create_slot("my_slot").bootstrap(client).await?;
+///
+/// let mut rows = bootstrap.copy_table("public.my_table").await?;
+/// while let Some(data) = rows.try_next().await? {
+///     // load the existing rows
+/// }
+///
+/// let mut stream = bootstrap.start_replication(&["my_publication"]).await?;
+/// while let Some(event) = stream.try_next().await? {
+///     // apply the changes made since
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Bootstrap<'a> {
+    replication: &'a mut ReplicationClient,
+    transaction: Transaction<'a>,
+    slot: ReplicationSlot,
+}
+
+impl<'a> Bootstrap<'a> {
+    pub(crate) fn new(
+        replication: &'a mut ReplicationClient,
+        transaction: Transaction<'a>,
+        slot: ReplicationSlot,
+    ) -> Bootstrap<'a> {
+        Bootstrap {
+            replication,
+            transaction,
+            slot,
+        }
+    }
+
+    /// Returns the slot created for the bootstrap.
+    pub fn slot(&self) -> &ReplicationSlot {
+        &self.slot
+    }
+
+    /// Returns the read only transaction which sees the database as of the slot's consistent point.
+    ///
+    /// It can be used to run other queries at the snapshot, such as reading the definitions of the tables.
+    pub fn transaction(&mut self) -> &mut Transaction<'a> {
+        &mut self.transaction
+    }
+
+    /// Copies the contents of a table as of the slot's consistent point, in the text format of `COPY TO STDOUT`.
+    ///
+    /// The table name is included in the query verbatim, so it should be quoted where necessary.
+    pub async fn copy_table(
+        &mut self,
+        table: &str,
+    ) -> Result<impl Stream<Item = Result<Bytes, Error>>, Error> {
+        let statement = self
+            .transaction
+            .prepare(&format!("COPY {} TO STDOUT", table))
+            .await?;
+        Ok(self.transaction.copy_out(&statement, &[]))
+    }
+
+    /// Ends the bootstrap's transaction, and starts streaming the changes made after the slot's consistent point to
+    /// the tables in the specified publications.
+    ///
+    /// Like `ReplicationClient::start_logical_replication`.
+    pub async fn start_replication(
+        self,
+        publications: &[&str],
+    ) -> Result<LogicalReplicationStream, Error> {
+        self.transaction.commit().await?;
+
+        let start_lsn = self
+            .slot
+            .consistent_point()
+            .ok_or_else(Error::unexpected_message)?;
+        self.replication
+            .start_logical_replication(self.slot.slot_name(), start_lsn, publications)
+            .await
+    }
+}
@@ -0,0 +1,572 @@
+use crate::replication::from_pg_timestamp;
+use crate::replication::stream::ReplicationStream;
+use crate::replication::TestDecodingParser;
+#[cfg(feature = "with-serde_json-1")]
+use crate::replication::Wal2JsonParser;
+use crate::types::{Oid, PgLsn};
+use crate::{CopyBothDuplex, Error};
+use bytes::Bytes;
+use futures::{future, ready, Stream};
+use postgres_protocol::message::backend::{
+    self, LogicalReplicationMessage, Tuple, TupleData, MESSAGE_TRANSACTIONAL, TRUNCATE_CASCADE,
+    TRUNCATE_RESTART_IDENTITY,
+};
+use std::collections::HashMap;
+use std::io;
+use std::pin::Pin;
+use std::str;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+#[cfg(feature = "runtime")]
+use std::time::Duration;
+use std::time::SystemTime;
+
+/// A change decoded from a logical replication stream.
+///
+/// Fields which are not reported by every output plugin are optional.
+#[derive(Debug, Clone)]
+pub enum ChangeEvent {
+    /// The start of a transaction.
+    ///
+    /// The changes made by the transaction follow, and then its `Commit`.
+    Begin {
+        /// The ID of the transaction.
+        xid: Option<u32>,
+        /// The position of the transaction's commit record in the write-ahead log.
+        final_lsn: Option<PgLsn>,
+        /// The time at which the transaction committed.
+        commit_time: Option<SystemTime>,
+    },
+
+    /// The end of a transaction.
+    Commit {
+        /// The ID of the transaction.
+        xid: Option<u32>,
+        /// The position of the transaction's commit record in the write-ahead log.
+        commit_lsn: Option<PgLsn>,
+        /// The position just past the transaction's commit record, up to which the changes have been streamed.
+        end_lsn: Option<PgLsn>,
+        /// The time at which the transaction committed.
+        commit_time: Option<SystemTime>,
+    },
+
+    /// A row was inserted.
+    Insert {
+        /// The relation the row was inserted into.
+        relation: Arc<Relation>,
+        /// The values of the new row, in the order of the relation's columns.
+        new: Vec<TupleValue>,
+    },
+
+    /// A row was updated.
+    Update {
+        /// The relation containing the row.
+        relation: Arc<Relation>,
+        /// The old values of the row, if they were logged.
+        ///
+        /// Depending on the relation's replica identity, this is either all of the old values or only those of its key
+        /// columns, with the others `Null`. The old values are only logged if the replica identity is `Full`, or if
+        /// the update changed the key.
+        old: Option<Vec<TupleValue>>,
+        /// The values of the updated row, in the order of the relation's columns.
+        new: Vec<TupleValue>,
+    },
+
+    /// A row was deleted.
+    Delete {
+        /// The relation the row was deleted from.
+        relation: Arc<Relation>,
+        /// The old values of the row.
+        ///
+        /// Depending on the relation's replica identity, this is either all of the old values or only those of its key
+        /// columns, with the others `Null`.
+        old: Vec<TupleValue>,
+    },
+
+    /// Relations were truncated.
+    Truncate {
+        /// The truncated relations.
+        relations: Vec<Arc<Relation>>,
+        /// Whether the `TRUNCATE` was run with `CASCADE`.
+        cascade: bool,
+        /// Whether the `TRUNCATE` was run with `RESTART IDENTITY`.
+        restart_identity: bool,
+    },
+
+    /// A message written with `pg_logical_emit_message`.
+    ///
+    /// Messages are only reported by `pgoutput`, and only if the stream was started with
+    /// `ReplicationClient::start_logical_replication_with_messages`.
+    Message {
+        /// Whether the message was written as part of a transaction.
+        ///
+        /// Transactional messages are streamed between the `Begin` and `Commit` of their transaction, and only if it
+        /// commits. Other messages are streamed immediately, even if the transaction which wrote them aborts.
+        transactional: bool,
+        /// The position of the message in the write-ahead log.
+        lsn: PgLsn,
+        /// The prefix the message was written with, which identifies its purpose.
+        prefix: String,
+        /// The content of the message.
+        content: Bytes,
+    },
+
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+/// The replica identity setting of a relation, which determines the old values logged for its updates and deletes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReplicaIdentity {
+    /// The values of the primary key columns, if it has a primary key.
+    Default,
+    /// No old values.
+    Nothing,
+    /// The values of all columns.
+    Full,
+    /// The values of the columns of a specific index.
+    Index,
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+/// A relation whose changes are included in a logical replication stream.
+#[derive(Debug, Clone)]
+pub struct Relation {
+    pub(crate) oid: Option<Oid>,
+    pub(crate) namespace: String,
+    pub(crate) name: String,
+    pub(crate) replica_identity: Option<ReplicaIdentity>,
+    pub(crate) columns: Vec<RelationColumn>,
+}
+
+impl Relation {
+    /// Returns the OID of the relation, if the output plugin reports it.
+    pub fn oid(&self) -> Option<Oid> {
+        self.oid
+    }
+
+    /// Returns the name of the schema containing the relation.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Returns the name of the relation.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the replica identity setting of the relation, if the output plugin reports it.
+    pub fn replica_identity(&self) -> Option<ReplicaIdentity> {
+        self.replica_identity
+    }
+
+    /// Returns the columns of the relation.
+    pub fn columns(&self) -> &[RelationColumn] {
+        &self.columns
+    }
+
+    /// Returns the index of the column with the specified name, if there is one.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c.name == name)
+    }
+}
+
+/// A column of a `Relation`.
+#[derive(Debug, Clone)]
+pub struct RelationColumn {
+    pub(crate) name: String,
+    pub(crate) type_oid: Option<Oid>,
+    pub(crate) type_name: Option<String>,
+    pub(crate) type_modifier: Option<i32>,
+    pub(crate) key: bool,
+}
+
+impl RelationColumn {
+    /// Returns the name of the column.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the OID of the column's type, if the output plugin reports it.
+    pub fn type_oid(&self) -> Option<Oid> {
+        self.type_oid
+    }
+
+    /// Returns the name of the column's type, if the output plugin reports it.
+    pub fn type_name(&self) -> Option<&str> {
+        self.type_name.as_ref().map(|s| &**s)
+    }
+
+    /// Returns the type modifier of the column, if the output plugin reports it.
+    pub fn type_modifier(&self) -> Option<i32> {
+        self.type_modifier
+    }
+
+    /// Determines if the column is part of the relation's replica identity key.
+    pub fn is_key(&self) -> bool {
+        self.key
+    }
+}
+
+// the relations of the plugins which describe them in each change rather than in separate messages, which are shared
+// between events as long as their columns don't change
+#[derive(Debug, Default)]
+pub(crate) struct RelationCache {
+    relations: HashMap<(String, String), Arc<Relation>>,
+}
+
+impl RelationCache {
+    pub(crate) fn get(
+        &mut self,
+        namespace: String,
+        name: String,
+        columns: Vec<RelationColumn>,
+    ) -> Arc<Relation> {
+        let key = (namespace, name);
+        if let Some(relation) = self.relations.get(&key) {
+            let matches = relation.columns.len() == columns.len()
+                && relation.columns.iter().zip(&columns).all(|(a, b)| {
+                    a.name == b.name
+                        && a.type_oid == b.type_oid
+                        && a.type_name == b.type_name
+                        && a.type_modifier == b.type_modifier
+                        && a.key == b.key
+                });
+            if matches {
+                return relation.clone();
+            }
+        }
+
+        let relation = Arc::new(Relation {
+            oid: None,
+            namespace: key.0.clone(),
+            name: key.1.clone(),
+            replica_identity: None,
+            columns,
+        });
+        self.relations.insert(key, relation.clone());
+        relation
+    }
+
+    // returns the last relation seen with the name, or one without columns if there hasn't been one
+    pub(crate) fn get_existing(&self, namespace: String, name: String) -> Arc<Relation> {
+        let key = (namespace, name);
+        match self.relations.get(&key) {
+            Some(relation) => relation.clone(),
+            None => Arc::new(Relation {
+                oid: None,
+                namespace: key.0,
+                name: key.1,
+                replica_identity: None,
+                columns: vec![],
+            }),
+        }
+    }
+}
+
+/// The value of a column in a changed row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TupleValue {
+    /// A `NULL` value.
+    Null,
+    /// A TOASTed value which was not changed by an update, and so was not logged.
+    UnchangedToast,
+    /// A value in the text format.
+    Text(String),
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+impl TupleValue {
+    /// Returns the text of the value, if it has one.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            TupleValue::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// A stream of the changes decoded from a logical replication slot.
+///
+/// The changes are decoded from the output of the `pgoutput` plugin, as started by
+/// `ReplicationClient::start_logical_replication`, that of the `test_decoding` plugin, as started by
+/// `ReplicationClient::start_test_decoding_replication`, or that of the `wal2json` plugin, as started by
+/// `ReplicationClient::start_wal2json_replication`. Fields of the events which the plugin doesn't report are
+/// filled in from the stream where possible, as with the `end_lsn` of a `Commit`.
+///
+/// The stream ends if the server ends replication, and dropping it stops replication.
+///
+/// The stream reports its progress to the server in standby status updates, which are sent when the server requests
+/// one, when `send_status_update` is called, and, with the `runtime` feature, periodically while the stream is being
+/// polled. The server disconnects consumers which don't report in for longer than its `wal_sender_timeout`.
+///
+/// The slot only advances past the changes which are reported as flushed, so the consumer should call
+/// `set_flushed_lsn` with a transaction's `end_lsn` once its changes have been durably applied. Until then, they will
+/// be streamed again the next time replication is started from the slot.
+pub struct LogicalReplicationStream {
+    stream: ReplicationStream,
+    decoder: Decoder,
+}
+
+impl LogicalReplicationStream {
+    pub(crate) fn new(
+        duplex: CopyBothDuplex,
+        start_lsn: PgLsn,
+        decoder: Decoder,
+    ) -> LogicalReplicationStream {
+        LogicalReplicationStream {
+            stream: ReplicationStream::new(duplex, start_lsn),
+            decoder,
+        }
+    }
+
+    /// Returns the position just past the end of the data received so far, or the start position if none has been.
+    pub fn received_lsn(&self) -> PgLsn {
+        self.stream.received_lsn()
+    }
+
+    /// Returns the position reported to the server as flushed.
+    pub fn flushed_lsn(&self) -> PgLsn {
+        self.stream.flushed_lsn()
+    }
+
+    /// Sets the position up to which the changes have been durably applied by the consumer.
+    ///
+    /// The slot's `confirmed_flush_lsn` advances to it with the next status update.
+    pub fn set_flushed_lsn(&mut self, lsn: PgLsn) {
+        self.stream.set_flushed_lsn(lsn);
+    }
+
+    /// Returns the position reported to the server as applied.
+    pub fn applied_lsn(&self) -> PgLsn {
+        self.stream.applied_lsn()
+    }
+
+    /// Sets the position up to which the changes have been applied by the consumer, whether or not durably.
+    pub fn set_applied_lsn(&mut self, lsn: PgLsn) {
+        self.stream.set_applied_lsn(lsn);
+    }
+
+    /// Sets the interval at which status updates are sent while the stream is polled.
+    ///
+    /// It should be shorter than the server's `wal_sender_timeout`. If `None`, updates are only sent when the server
+    /// requests them or `send_status_update` is called.
+    ///
+    /// Defaults to 10 seconds.
+    ///
+    /// Requires the `runtime` Cargo feature (enabled by default).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    #[cfg(feature = "runtime")]
+    pub fn status_interval(&mut self, interval: Option<Duration>) -> &mut LogicalReplicationStream {
+        self.stream.set_status_interval(interval);
+        self
+    }
+
+    /// Sends a status update to the server immediately.
+    ///
+    /// If `reply` is set, the server is asked to respond with a keepalive message.
+    pub async fn send_status_update(&mut self, reply: bool) -> Result<(), Error> {
+        self.stream.queue_status_update(reply);
+        let stream = &mut self.stream;
+        future::poll_fn(|cx| stream.poll_send(cx)).await
+    }
+}
+
+// the decoders of the supported output plugins
+pub(crate) enum Decoder {
+    PgOutput(PgOutputDecoder),
+    TestDecoding(TestDecodingParser),
+    #[cfg(feature = "with-serde_json-1")]
+    Wal2Json(Wal2JsonParser),
+}
+
+impl Decoder {
+    pub(crate) fn pgoutput() -> Decoder {
+        Decoder::PgOutput(PgOutputDecoder {
+            relations: HashMap::new(),
+        })
+    }
+
+    pub(crate) fn test_decoding() -> Decoder {
+        Decoder::TestDecoding(TestDecodingParser::new())
+    }
+
+    #[cfg(feature = "with-serde_json-1")]
+    pub(crate) fn wal2json() -> Decoder {
+        Decoder::Wal2Json(Wal2JsonParser::new())
+    }
+
+    fn decode(&mut self, data: &Bytes) -> Result<Option<ChangeEvent>, Error> {
+        match self {
+            Decoder::PgOutput(decoder) => decoder.decode(data),
+            Decoder::TestDecoding(parser) => parser.parse(text(data)?),
+            #[cfg(feature = "with-serde_json-1")]
+            Decoder::Wal2Json(parser) => parser.parse(text(data)?),
+        }
+    }
+}
+
+pub(crate) struct PgOutputDecoder {
+    relations: HashMap<Oid, Arc<Relation>>,
+}
+
+impl PgOutputDecoder {
+    fn relation(&self, oid: Oid) -> Result<Arc<Relation>, Error> {
+        match self.relations.get(&oid) {
+            Some(relation) => Ok(relation.clone()),
+            None => Err(Error::parse(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("change to unknown relation {}", oid),
+            ))),
+        }
+    }
+
+    fn decode(&mut self, data: &Bytes) -> Result<Option<ChangeEvent>, Error> {
+        let event = match LogicalReplicationMessage::parse(data).map_err(Error::parse)? {
+            LogicalReplicationMessage::Begin(body) => ChangeEvent::Begin {
+                xid: Some(body.xid()),
+                final_lsn: Some(PgLsn(body.final_lsn())),
+                commit_time: Some(from_pg_timestamp(body.timestamp())),
+            },
+            LogicalReplicationMessage::Commit(body) => ChangeEvent::Commit {
+                xid: None,
+                commit_lsn: Some(PgLsn(body.commit_lsn())),
+                end_lsn: Some(PgLsn(body.end_lsn())),
+                commit_time: Some(from_pg_timestamp(body.timestamp())),
+            },
+            LogicalReplicationMessage::Relation(body) => {
+                let replica_identity = match body.replica_identity() {
+                    backend::ReplicaIdentity::Default => ReplicaIdentity::Default,
+                    backend::ReplicaIdentity::Nothing => ReplicaIdentity::Nothing,
+                    backend::ReplicaIdentity::Full => ReplicaIdentity::Full,
+                    backend::ReplicaIdentity::Index => ReplicaIdentity::Index,
+                };
+                let columns = body
+                    .columns()
+                    .iter()
+                    .map(|c| {
+                        Ok(RelationColumn {
+                            name: c.name().map_err(Error::parse)?.to_string(),
+                            type_oid: Some(c.type_id()),
+                            type_name: None,
+                            type_modifier: Some(c.type_modifier()),
+                            key: c.flags() & 1 != 0,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let relation = Relation {
+                    oid: Some(body.rel_id()),
+                    namespace: body.namespace().map_err(Error::parse)?.to_string(),
+                    name: body.name().map_err(Error::parse)?.to_string(),
+                    replica_identity: Some(replica_identity),
+                    columns,
+                };
+                self.relations.insert(body.rel_id(), Arc::new(relation));
+                return Ok(None);
+            }
+            LogicalReplicationMessage::Insert(body) => ChangeEvent::Insert {
+                relation: self.relation(body.rel_id())?,
+                new: tuple_values(body.tuple())?,
+            },
+            LogicalReplicationMessage::Update(body) => {
+                let old = match body.old_tuple().or_else(|| body.key_tuple()) {
+                    Some(tuple) => Some(tuple_values(tuple)?),
+                    None => None,
+                };
+                ChangeEvent::Update {
+                    relation: self.relation(body.rel_id())?,
+                    old,
+                    new: tuple_values(body.new_tuple())?,
+                }
+            }
+            LogicalReplicationMessage::Delete(body) => {
+                let old = match body.old_tuple().or_else(|| body.key_tuple()) {
+                    Some(tuple) => tuple_values(tuple)?,
+                    None => return Err(Error::unexpected_message()),
+                };
+                ChangeEvent::Delete {
+                    relation: self.relation(body.rel_id())?,
+                    old,
+                }
+            }
+            LogicalReplicationMessage::Truncate(body) => ChangeEvent::Truncate {
+                relations: body
+                    .rel_ids()
+                    .iter()
+                    .map(|oid| self.relation(*oid))
+                    .collect::<Result<_, _>>()?,
+                cascade: body.options() & TRUNCATE_CASCADE != 0,
+                restart_identity: body.options() & TRUNCATE_RESTART_IDENTITY != 0,
+            },
+            LogicalReplicationMessage::Message(body) => ChangeEvent::Message {
+                transactional: body.flags() & MESSAGE_TRANSACTIONAL != 0,
+                lsn: PgLsn(body.message_lsn()),
+                prefix: body.prefix().map_err(Error::parse)?.to_string(),
+                content: body.content().clone(),
+            },
+            // origins and types are informational, since columns are reported by type OID and values as text
+            LogicalReplicationMessage::Origin(_)
+            | LogicalReplicationMessage::Type(_)
+            | LogicalReplicationMessage::__ForExtensibility => return Ok(None),
+        };
+
+        Ok(Some(event))
+    }
+}
+
+impl Stream for LogicalReplicationStream {
+    type Item = Result<ChangeEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let body = match ready!(Pin::new(&mut self.stream).poll_next(cx)?) {
+                Some(body) => body,
+                None => return Poll::Ready(None),
+            };
+
+            let mut event = match self.decoder.decode(body.data())? {
+                Some(event) => event,
+                None => continue,
+            };
+
+            // a commit's data is sent at the position just past its record, which is where the stream should resume
+            // from once the transaction has been applied
+            if let ChangeEvent::Commit { end_lsn, .. } = &mut event {
+                if end_lsn.is_none() {
+                    *end_lsn = Some(PgLsn(body.wal_start()));
+                }
+            }
+
+            return Poll::Ready(Some(Ok(event)));
+        }
+    }
+}
+
+fn text(data: &[u8]) -> Result<&str, Error> {
+    str::from_utf8(data).map_err(|e| Error::parse(io::Error::new(io::ErrorKind::InvalidData, e)))
+}
+
+fn tuple_values(tuple: &Tuple) -> Result<Vec<TupleValue>, Error> {
+    tuple
+        .tuple_data()
+        .iter()
+        .map(|data| match data {
+            TupleData::Null => Ok(TupleValue::Null),
+            TupleData::UnchangedToast => Ok(TupleValue::UnchangedToast),
+            TupleData::Text(buf) => {
+                let s = str::from_utf8(buf)
+                    .map_err(|e| Error::parse(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+                Ok(TupleValue::Text(s.to_string()))
+            }
+            TupleData::Binary(_) => Err(Error::parse(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected binary tuple data",
+            ))),
+        })
+        .collect()
+}
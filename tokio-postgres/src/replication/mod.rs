@@ -0,0 +1,399 @@
+//! Streaming replication.
+//!
+//! Replication commands can only be run on a replication connection, which is opened by setting
+//! `Config::replication_mode`, or the `replication` connection string parameter. Logical replication connections also
+//! accept simple queries, but not prepared statements.
+//!
+//! Logical replication streams the changes made to tables as `ChangeEvent`s, decoded from the output of the
+//! `pgoutput`, `test_decoding`, or (with the `with-serde_json-1` feature) `wal2json` plugins, so that the same handling
+//! code works with each of them. Physical replication streams the raw write-ahead log of the whole cluster, as used by
+//! standby servers and backup tools.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::{FutureExt, TryStreamExt};
+//! use tokio_postgres::replication::{ChangeEvent, ReplicationClient};
+//! use tokio_postgres::types::PgLsn;
+//! use tokio_postgres::{Error, NoTls};
+//!
+//! # #[cfg(feature = "runtime")]
+//! # async fn f() -> Result<(), Error> {
+//! let (client, connection) =
+//!     tokio_postgres::connect("host=localhost user=postgres replication=database", NoTls).await?;
+//! tokio::spawn(connection.map(|_| ()));
+//!
+//! let mut client = ReplicationClient::new(client);
+//! client.create_logical_slot("my_slot", "pgoutput", false).await?;
+//!
+//! let mut stream = client
+//!     .start_logical_replication("my_slot", PgLsn(0), &["my_publication"])
+//!     .await?;
+//! while let Some(event) = stream.try_next().await? {
+//!     match event {
+//!         ChangeEvent::Insert { relation, new, .. } => {
+//!             println!("inserted into {}: {:?}", relation.name(), new);
+//!         }
+//!         // let the slot advance past the transaction
+//!         ChangeEvent::Commit {
+//!             end_lsn: Some(end_lsn),
+//!             ..
+//!         } => stream.set_flushed_lsn(end_lsn),
+//!         _ => {}
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::replication::logical::Decoder;
+use crate::session::escape_identifier;
+use crate::types::{PgLsn, NSEC_PER_USEC, TIME_SEC_CONVERSION, USEC_PER_SEC};
+use crate::{Client, Error, SimpleQueryMessage, SimpleQueryRow};
+use futures::TryStreamExt;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub use crate::replication::bootstrap::Bootstrap;
+pub use crate::replication::logical::{
+    ChangeEvent, LogicalReplicationStream, Relation, RelationColumn, ReplicaIdentity, TupleValue,
+};
+pub use crate::replication::physical::{PhysicalReplicationStream, XLogData};
+pub use crate::replication::slot::{
+    CreateSlotBuilder, ReplicationSlot, ReplicationSlotInfo, SnapshotAction,
+};
+pub use crate::replication::test_decoding::TestDecodingParser;
+#[cfg(feature = "with-serde_json-1")]
+pub use crate::replication::wal2json::Wal2JsonParser;
+
+mod bootstrap;
+mod logical;
+mod physical;
+mod slot;
+mod stream;
+mod test_decoding;
+#[cfg(feature = "with-serde_json-1")]
+mod wal2json;
+
+/// A client for a replication connection.
+pub struct ReplicationClient {
+    client: Client,
+}
+
+impl ReplicationClient {
+    /// Creates a new replication client from a client connected with a `replication_mode`.
+    pub fn new(client: Client) -> ReplicationClient {
+        ReplicationClient { client }
+    }
+
+    /// Returns a shared reference to the underlying client.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Returns a mutable reference to the underlying client, which can be used to run simple queries.
+    pub fn client_mut(&mut self) -> &mut Client {
+        &mut self.client
+    }
+
+    /// Consumes the replication client, returning the underlying client.
+    pub fn into_inner(self) -> Client {
+        self.client
+    }
+
+    /// Returns information about the server which is needed to start physical replication.
+    pub async fn identify_system(&mut self) -> Result<IdentifySystem, Error> {
+        let row = self.simple_row("IDENTIFY_SYSTEM").await?;
+        IdentifySystem::parse(&row)
+    }
+
+    /// Returns a builder used to create a replication slot with the specified name.
+    pub fn create_slot(&mut self, slot_name: &str) -> CreateSlotBuilder<'_> {
+        CreateSlotBuilder::new(self, slot_name)
+    }
+
+    /// Creates a logical replication slot which decodes changes with the specified output plugin.
+    ///
+    /// A temporary slot is dropped when the connection closes, and can only be used by the connection that created it.
+    /// This is equivalent to `create_slot(slot_name).temporary(temporary).logical(output_plugin)`.
+    pub async fn create_logical_slot(
+        &mut self,
+        slot_name: &str,
+        output_plugin: &str,
+        temporary: bool,
+    ) -> Result<ReplicationSlot, Error> {
+        self.create_slot(slot_name)
+            .temporary(temporary)
+            .logical(output_plugin)
+            .await
+    }
+
+    /// Drops a replication slot.
+    ///
+    /// If the slot is in use by another connection, an error is returned unless `wait` is set, in which case the
+    /// command waits until the slot is released.
+    pub async fn drop_slot(&mut self, slot_name: &str, wait: bool) -> Result<(), Error> {
+        let mut command = format!("DROP_REPLICATION_SLOT {}", escape_identifier(slot_name));
+        if wait {
+            command.push_str(" WAIT");
+        }
+
+        self.client.batch_execute(&command).await
+    }
+
+    /// Returns information about a physical replication slot, or `None` if it doesn't exist.
+    ///
+    /// Requires Postgres 15 or newer.
+    pub async fn read_slot(
+        &mut self,
+        slot_name: &str,
+    ) -> Result<Option<ReplicationSlotInfo>, Error> {
+        let command = format!("READ_REPLICATION_SLOT {}", escape_identifier(slot_name));
+        let row = self.simple_row(&command).await?;
+        ReplicationSlotInfo::parse(&row)
+    }
+
+    /// Starts streaming the changes decoded by the `pgoutput` plugin from a logical replication slot.
+    ///
+    /// The stream begins at `start_lsn`, or at the slot's confirmed position if that is later, and includes the
+    /// changes to the tables in the specified publications. Streaming occupies the connection, so other requests made
+    /// with the client wait until the stream is dropped.
+    pub async fn start_logical_replication(
+        &mut self,
+        slot_name: &str,
+        start_lsn: PgLsn,
+        publications: &[&str],
+    ) -> Result<LogicalReplicationStream, Error> {
+        self.start_pgoutput_replication(slot_name, start_lsn, publications, false)
+            .await
+    }
+
+    /// Like `ReplicationClient::start_logical_replication`, but also streams the messages written with
+    /// `pg_logical_emit_message` as `ChangeEvent::Message`s.
+    ///
+    /// Requires Postgres 14 or newer.
+    pub async fn start_logical_replication_with_messages(
+        &mut self,
+        slot_name: &str,
+        start_lsn: PgLsn,
+        publications: &[&str],
+    ) -> Result<LogicalReplicationStream, Error> {
+        self.start_pgoutput_replication(slot_name, start_lsn, publications, true)
+            .await
+    }
+
+    async fn start_pgoutput_replication(
+        &mut self,
+        slot_name: &str,
+        start_lsn: PgLsn,
+        publications: &[&str],
+        messages: bool,
+    ) -> Result<LogicalReplicationStream, Error> {
+        let publications = publications
+            .iter()
+            .map(|p| escape_identifier(p))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut command = format!(
+            "START_REPLICATION SLOT {} LOGICAL {} (\"proto_version\" '1', \"publication_names\" {}",
+            escape_identifier(slot_name),
+            start_lsn,
+            quote_literal(&publications),
+        );
+        // the option is rejected by servers which don't support it, so it's only sent when needed
+        if messages {
+            command.push_str(", \"messages\" 'true'");
+        }
+        command.push(')');
+
+        let duplex = self.client.copy_both_simple(&command).await?;
+        Ok(LogicalReplicationStream::new(
+            duplex,
+            start_lsn,
+            Decoder::pgoutput(),
+        ))
+    }
+
+    /// Starts streaming the changes decoded by the `test_decoding` plugin from a logical replication slot.
+    ///
+    /// The stream begins at `start_lsn`, or at the slot's confirmed position if that is later, and includes the
+    /// changes to all tables. Its output is parsed as described by `TestDecodingParser`. Streaming occupies the
+    /// connection, so other requests made with the client wait until the stream is dropped.
+    pub async fn start_test_decoding_replication(
+        &mut self,
+        slot_name: &str,
+        start_lsn: PgLsn,
+    ) -> Result<LogicalReplicationStream, Error> {
+        let command = format!(
+            "START_REPLICATION SLOT {} LOGICAL {}",
+            escape_identifier(slot_name),
+            start_lsn,
+        );
+
+        let duplex = self.client.copy_both_simple(&command).await?;
+        Ok(LogicalReplicationStream::new(
+            duplex,
+            start_lsn,
+            Decoder::test_decoding(),
+        ))
+    }
+
+    /// Starts streaming the changes decoded by the `wal2json` plugin from a logical replication slot.
+    ///
+    /// The stream begins at `start_lsn`, or at the slot's confirmed position if that is later, and includes the
+    /// changes to all tables. The plugin's `format-version` 2 is used with numbers written as strings, and its output
+    /// is parsed as described by `Wal2JsonParser`. Streaming occupies the connection, so other requests made with the
+    /// client wait until the stream is dropped.
+    ///
+    /// Requires the `with-serde_json-1` Cargo feature.
+    #[cfg(feature = "with-serde_json-1")]
+    pub async fn start_wal2json_replication(
+        &mut self,
+        slot_name: &str,
+        start_lsn: PgLsn,
+    ) -> Result<LogicalReplicationStream, Error> {
+        let command = format!(
+            "START_REPLICATION SLOT {} LOGICAL {} (\"format-version\" '2', \"include-xids\" '1', \
+             \"include-lsn\" '1', \"include-type-oids\" '1', \"numeric-data-types-as-string\" '1')",
+            escape_identifier(slot_name),
+            start_lsn,
+        );
+
+        let duplex = self.client.copy_both_simple(&command).await?;
+        Ok(LogicalReplicationStream::new(
+            duplex,
+            start_lsn,
+            Decoder::wal2json(),
+        ))
+    }
+
+    /// Starts streaming the write-ahead log from the specified position.
+    ///
+    /// If a physical replication slot is specified, the server retains the log the slot hasn't confirmed yet. The
+    /// timeline defaults to the server's current one, as returned by `identify_system`. Streaming occupies the
+    /// connection, so other requests made with the client wait until the stream is dropped.
+    pub async fn start_physical_replication(
+        &mut self,
+        slot_name: Option<&str>,
+        start_lsn: PgLsn,
+        timeline: Option<u32>,
+    ) -> Result<PhysicalReplicationStream, Error> {
+        let mut command = "START_REPLICATION".to_string();
+        if let Some(slot_name) = slot_name {
+            command.push_str(" SLOT ");
+            command.push_str(&escape_identifier(slot_name));
+        }
+        command.push_str(&format!(" PHYSICAL {}", start_lsn));
+        if let Some(timeline) = timeline {
+            command.push_str(&format!(" TIMELINE {}", timeline));
+        }
+
+        let duplex = self.client.copy_both_simple(&command).await?;
+        Ok(PhysicalReplicationStream::new(duplex, start_lsn))
+    }
+
+    // runs a replication command which returns a single row
+    async fn simple_row(&mut self, command: &str) -> Result<SimpleQueryRow, Error> {
+        let messages = self
+            .client
+            .simple_query(command)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        messages
+            .into_iter()
+            .filter_map(|m| match m {
+                SimpleQueryMessage::Row(row) => Some(row),
+                _ => None,
+            })
+            .next()
+            .ok_or_else(Error::unexpected_message)
+    }
+}
+
+/// Information about the server, as returned by `ReplicationClient::identify_system`.
+#[derive(Debug, Clone)]
+pub struct IdentifySystem {
+    system_id: String,
+    timeline: u32,
+    xlog_pos: PgLsn,
+    dbname: Option<String>,
+}
+
+impl IdentifySystem {
+    fn parse(row: &SimpleQueryRow) -> Result<IdentifySystem, Error> {
+        let system_id = row.try_get(0)?.ok_or_else(Error::unexpected_message)?;
+        let timeline = row
+            .try_get(1)?
+            .ok_or_else(Error::unexpected_message)?
+            .parse::<u32>()
+            .map_err(|e| Error::parse(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+
+        Ok(IdentifySystem {
+            system_id: system_id.to_string(),
+            timeline,
+            xlog_pos: parse_lsn(row.try_get(2)?)?,
+            dbname: row.try_get(3)?.map(str::to_string),
+        })
+    }
+
+    /// Returns the unique identifier of the database cluster.
+    pub fn system_id(&self) -> &str {
+        &self.system_id
+    }
+
+    /// Returns the server's current timeline.
+    pub fn timeline(&self) -> u32 {
+        self.timeline
+    }
+
+    /// Returns the server's current position in the write-ahead log.
+    pub fn xlog_pos(&self) -> PgLsn {
+        self.xlog_pos
+    }
+
+    /// Returns the database the connection is for, if it is a logical replication connection.
+    pub fn dbname(&self) -> Option<&str> {
+        self.dbname.as_ref().map(|s| &**s)
+    }
+}
+
+fn parse_lsn(s: Option<&str>) -> Result<PgLsn, Error> {
+    s.ok_or_else(Error::unexpected_message)?
+        .parse()
+        .map_err(|e| Error::parse(io::Error::new(io::ErrorKind::InvalidData, e)))
+}
+
+fn quote_literal(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+// converts a timestamp in microseconds since 2000-01-01, as used in the replication protocol
+fn from_pg_timestamp(time: i64) -> SystemTime {
+    let epoch = UNIX_EPOCH + Duration::from_secs(TIME_SEC_CONVERSION);
+
+    let offset = time.wrapping_abs() as u64;
+    let offset = Duration::new(
+        offset / USEC_PER_SEC,
+        ((offset % USEC_PER_SEC) * NSEC_PER_USEC) as u32,
+    );
+
+    if time < 0 {
+        epoch - offset
+    } else {
+        epoch + offset
+    }
+}
+
+fn to_pg_timestamp(time: SystemTime) -> i64 {
+    let epoch = UNIX_EPOCH + Duration::from_secs(TIME_SEC_CONVERSION);
+
+    let to_usec =
+        |d: Duration| d.as_secs() * USEC_PER_SEC + u64::from(d.subsec_nanos()) / NSEC_PER_USEC;
+
+    match time.duration_since(epoch) {
+        Ok(duration) => to_usec(duration) as i64,
+        Err(e) => -(to_usec(e.duration()) as i64),
+    }
+}
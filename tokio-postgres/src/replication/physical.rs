@@ -0,0 +1,151 @@
+use crate::replication::from_pg_timestamp;
+use crate::replication::stream::ReplicationStream;
+use crate::types::PgLsn;
+use crate::{CopyBothDuplex, Error};
+use bytes::Bytes;
+use futures::{future, ready, Stream};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+#[cfg(feature = "runtime")]
+use std::time::Duration;
+use std::time::SystemTime;
+
+/// A chunk of the write-ahead log streamed by physical replication.
+#[derive(Debug, Clone)]
+pub struct XLogData {
+    wal_start: PgLsn,
+    wal_end: PgLsn,
+    timestamp: SystemTime,
+    data: Bytes,
+}
+
+impl XLogData {
+    /// Returns the position in the write-ahead log of the start of the data.
+    pub fn wal_start(&self) -> PgLsn {
+        self.wal_start
+    }
+
+    /// Returns the position of the end of the write-ahead log on the server when the data was sent.
+    pub fn wal_end(&self) -> PgLsn {
+        self.wal_end
+    }
+
+    /// Returns the position just past the end of the data, where the next chunk starts.
+    pub fn next_lsn(&self) -> PgLsn {
+        PgLsn(self.wal_start.0 + self.data.len() as u64)
+    }
+
+    /// Returns the time at which the server sent the data.
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// Returns the raw write-ahead log data.
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// Consumes the chunk, returning its data.
+    pub fn into_data(self) -> Bytes {
+        self.data
+    }
+}
+
+/// A stream of the write-ahead log sent by physical replication.
+///
+/// The data is streamed in order without gaps, starting at the requested position. The stream ends if the server
+/// reaches the end of the requested timeline, and dropping it stops replication.
+///
+/// The stream reports its progress to the server in standby status updates, which are sent when the server requests
+/// one, when `send_status_update` is called, and, with the `runtime` feature, periodically while the stream is being
+/// polled. The server disconnects standbys which don't report in for longer than its `wal_sender_timeout`. The
+/// positions reported as flushed and applied are the start position until they are advanced with `set_flushed_lsn`
+/// and `set_applied_lsn`.
+pub struct PhysicalReplicationStream {
+    stream: ReplicationStream,
+}
+
+impl PhysicalReplicationStream {
+    pub(crate) fn new(duplex: CopyBothDuplex, start_lsn: PgLsn) -> PhysicalReplicationStream {
+        PhysicalReplicationStream {
+            stream: ReplicationStream::new(duplex, start_lsn),
+        }
+    }
+
+    /// Returns the position just past the end of the data received so far, or the start position if none has been.
+    pub fn received_lsn(&self) -> PgLsn {
+        self.stream.received_lsn()
+    }
+
+    /// Returns the position reported to the server as flushed to durable storage.
+    pub fn flushed_lsn(&self) -> PgLsn {
+        self.stream.flushed_lsn()
+    }
+
+    /// Sets the position up to which the received data has been flushed to durable storage.
+    ///
+    /// If the stream uses a replication slot, the server retains the write-ahead log after this position for it.
+    pub fn set_flushed_lsn(&mut self, lsn: PgLsn) {
+        self.stream.set_flushed_lsn(lsn);
+    }
+
+    /// Returns the position reported to the server as applied.
+    pub fn applied_lsn(&self) -> PgLsn {
+        self.stream.applied_lsn()
+    }
+
+    /// Sets the position up to which the received data has been applied.
+    ///
+    /// The server uses it to wait for synchronous standbys with `synchronous_commit = remote_apply`.
+    pub fn set_applied_lsn(&mut self, lsn: PgLsn) {
+        self.stream.set_applied_lsn(lsn);
+    }
+
+    /// Sets the interval at which status updates are sent while the stream is polled.
+    ///
+    /// It should be shorter than the server's `wal_sender_timeout`. If `None`, updates are only sent when the server
+    /// requests them or `send_status_update` is called.
+    ///
+    /// Defaults to 10 seconds.
+    ///
+    /// Requires the `runtime` Cargo feature (enabled by default).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    #[cfg(feature = "runtime")]
+    pub fn status_interval(
+        &mut self,
+        interval: Option<Duration>,
+    ) -> &mut PhysicalReplicationStream {
+        self.stream.set_status_interval(interval);
+        self
+    }
+
+    /// Sends a status update to the server immediately.
+    ///
+    /// If `reply` is set, the server is asked to respond with a keepalive message.
+    pub async fn send_status_update(&mut self, reply: bool) -> Result<(), Error> {
+        self.stream.queue_status_update(reply);
+        let stream = &mut self.stream;
+        future::poll_fn(|cx| stream.poll_send(cx)).await
+    }
+}
+
+impl Stream for PhysicalReplicationStream {
+    type Item = Result<XLogData, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let body = match ready!(Pin::new(&mut self.stream).poll_next(cx)?) {
+            Some(body) => body,
+            None => return Poll::Ready(None),
+        };
+
+        Poll::Ready(Some(Ok(XLogData {
+            wal_start: PgLsn(body.wal_start()),
+            wal_end: PgLsn(body.wal_end()),
+            timestamp: from_pg_timestamp(body.timestamp()),
+            data: body.into_data(),
+        })))
+    }
+}
@@ -0,0 +1,258 @@
+use crate::replication::{parse_lsn, quote_literal, Bootstrap, ReplicationClient};
+use crate::session::escape_identifier;
+use crate::types::PgLsn;
+use crate::{Client, Error, IsolationLevel, SimpleQueryRow};
+use std::io;
+
+/// The handling of the snapshot created along with a logical replication slot.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SnapshotAction {
+    /// Export the snapshot, so that other sessions can import it with `SET TRANSACTION SNAPSHOT` to see the database as
+    /// of the slot's consistent point. The snapshot remains valid until the next command is run on the replication
+    /// connection, or it closes.
+    Export,
+    /// Don't export the snapshot.
+    NoExport,
+    /// Use the snapshot for the current transaction on the replication connection. The transaction must be a
+    /// `REPEATABLE READ` transaction which hasn't run any queries yet.
+    Use,
+    #[doc(hidden)]
+    __NonExhaustive,
+}
+
+/// A builder for replication slots.
+pub struct CreateSlotBuilder<'a> {
+    client: &'a mut ReplicationClient,
+    slot_name: String,
+    temporary: bool,
+    snapshot: Option<SnapshotAction>,
+    two_phase: bool,
+    reserve_wal: bool,
+}
+
+impl<'a> CreateSlotBuilder<'a> {
+    pub(crate) fn new(client: &'a mut ReplicationClient, slot_name: &str) -> CreateSlotBuilder<'a> {
+        CreateSlotBuilder {
+            client,
+            slot_name: slot_name.to_string(),
+            temporary: false,
+            snapshot: None,
+            two_phase: false,
+            reserve_wal: false,
+        }
+    }
+
+    /// Sets whether the slot is dropped when the connection closes, and can only be used by the connection that
+    /// created it.
+    ///
+    /// Defaults to `false`.
+    pub fn temporary(mut self, temporary: bool) -> Self {
+        self.temporary = temporary;
+        self
+    }
+
+    /// Sets the handling of the snapshot created along with a logical slot.
+    ///
+    /// Ignored for physical slots. If unset, the server's default of `Export` is used.
+    pub fn snapshot(mut self, snapshot: SnapshotAction) -> Self {
+        self.snapshot = Some(snapshot);
+        self
+    }
+
+    /// Sets whether a logical slot decodes prepared transactions when they are prepared rather than when they commit.
+    ///
+    /// Ignored for physical slots. Requires Postgres 14 or newer, and an output plugin which supports it. Defaults to
+    /// `false`.
+    pub fn two_phase(mut self, two_phase: bool) -> Self {
+        self.two_phase = two_phase;
+        self
+    }
+
+    /// Sets whether a physical slot reserves the write-ahead log immediately, rather than when a client first streams
+    /// from it.
+    ///
+    /// Ignored for logical slots. Defaults to `false`.
+    pub fn reserve_wal(mut self, reserve_wal: bool) -> Self {
+        self.reserve_wal = reserve_wal;
+        self
+    }
+
+    /// Creates a logical replication slot which decodes changes with the specified output plugin.
+    ///
+    /// Logical slots can only be created on a logical replication connection.
+    pub async fn logical(self, output_plugin: &str) -> Result<ReplicationSlot, Error> {
+        let command = self.logical_command(output_plugin);
+        let row = self.client.simple_row(&command).await?;
+        ReplicationSlot::parse(&row)
+    }
+
+    /// Creates a logical replication slot which decodes changes with the `pgoutput` plugin, and starts a transaction
+    /// on `client` which sees the database as of the slot's consistent point.
+    ///
+    /// The existing contents of tables can then be copied with the returned `Bootstrap`, after which it streams the
+    /// changes made since, so that every change is seen exactly once. The snapshot is always exported, regardless of
+    /// the `snapshot` setting. `client` must be connected to the same database as the replication connection.
+    pub async fn bootstrap(mut self, client: &'a mut Client) -> Result<Bootstrap<'a>, Error> {
+        self.snapshot = Some(SnapshotAction::Export);
+        let command = self.logical_command("pgoutput");
+        let row = self.client.simple_row(&command).await?;
+        let slot = ReplicationSlot::parse(&row)?;
+
+        // the snapshot is only importable until the next command is run on the replication connection, so it has to
+        // be imported before this returns
+        let snapshot_name = slot.snapshot_name().ok_or_else(Error::unexpected_message)?;
+        let mut transaction = client
+            .build_transaction()
+            .isolation_level(IsolationLevel::RepeatableRead)
+            .read_only(true)
+            .start()
+            .await?;
+        transaction
+            .batch_execute(&format!(
+                "SET TRANSACTION SNAPSHOT {}",
+                quote_literal(snapshot_name)
+            ))
+            .await?;
+
+        Ok(Bootstrap::new(self.client, transaction, slot))
+    }
+
+    /// Creates a physical replication slot.
+    pub async fn physical(self) -> Result<ReplicationSlot, Error> {
+        let mut command = self.command();
+        command.push_str(" PHYSICAL");
+        if self.reserve_wal {
+            command.push_str(" RESERVE_WAL");
+        }
+
+        let row = self.client.simple_row(&command).await?;
+        ReplicationSlot::parse(&row)
+    }
+
+    fn logical_command(&self, output_plugin: &str) -> String {
+        let mut command = self.command();
+        command.push_str(" LOGICAL ");
+        command.push_str(&escape_identifier(output_plugin));
+        match self.snapshot {
+            Some(SnapshotAction::Export) => command.push_str(" EXPORT_SNAPSHOT"),
+            Some(SnapshotAction::NoExport) => command.push_str(" NOEXPORT_SNAPSHOT"),
+            Some(SnapshotAction::Use) => command.push_str(" USE_SNAPSHOT"),
+            Some(SnapshotAction::__NonExhaustive) => unreachable!(),
+            None => {}
+        }
+        if self.two_phase {
+            command.push_str(" TWO_PHASE");
+        }
+        command
+    }
+
+    fn command(&self) -> String {
+        let mut command = format!(
+            "CREATE_REPLICATION_SLOT {}",
+            escape_identifier(&self.slot_name)
+        );
+        if self.temporary {
+            command.push_str(" TEMPORARY");
+        }
+        command
+    }
+}
+
+/// A replication slot created by `ReplicationClient::create_slot`.
+#[derive(Debug, Clone)]
+pub struct ReplicationSlot {
+    slot_name: String,
+    consistent_point: Option<PgLsn>,
+    snapshot_name: Option<String>,
+    output_plugin: Option<String>,
+}
+
+impl ReplicationSlot {
+    fn parse(row: &SimpleQueryRow) -> Result<ReplicationSlot, Error> {
+        let slot_name = row.try_get(0)?.ok_or_else(Error::unexpected_message)?;
+        let consistent_point = match row.try_get(1)? {
+            Some(lsn) => Some(parse_lsn(Some(lsn))?),
+            None => None,
+        };
+
+        Ok(ReplicationSlot {
+            slot_name: slot_name.to_string(),
+            consistent_point,
+            snapshot_name: row.try_get(2)?.map(str::to_string),
+            output_plugin: row.try_get(3)?.map(str::to_string),
+        })
+    }
+
+    /// Returns the name of the slot.
+    pub fn slot_name(&self) -> &str {
+        &self.slot_name
+    }
+
+    /// Returns the position in the write-ahead log at which a logical slot became consistent.
+    ///
+    /// This is the earliest position streaming from the slot can start at. Newer servers don't report it for physical
+    /// slots.
+    pub fn consistent_point(&self) -> Option<PgLsn> {
+        self.consistent_point
+    }
+
+    /// Returns the name of the snapshot exported by the command, if any.
+    pub fn snapshot_name(&self) -> Option<&str> {
+        self.snapshot_name.as_ref().map(|s| &**s)
+    }
+
+    /// Returns the name of the slot's output plugin, if it is a logical slot.
+    pub fn output_plugin(&self) -> Option<&str> {
+        self.output_plugin.as_ref().map(|s| &**s)
+    }
+}
+
+/// Information about an existing replication slot, as returned by `ReplicationClient::read_slot`.
+#[derive(Debug, Clone)]
+pub struct ReplicationSlotInfo {
+    slot_type: String,
+    restart_lsn: Option<PgLsn>,
+    restart_timeline: Option<u32>,
+}
+
+impl ReplicationSlotInfo {
+    fn parse(row: &SimpleQueryRow) -> Result<Option<ReplicationSlotInfo>, Error> {
+        let slot_type = match row.try_get(0)? {
+            Some(slot_type) => slot_type.to_string(),
+            None => return Ok(None),
+        };
+        let restart_lsn = match row.try_get(1)? {
+            Some(lsn) => Some(parse_lsn(Some(lsn))?),
+            None => None,
+        };
+        let restart_timeline = match row.try_get(2)? {
+            Some(timeline) => Some(
+                timeline
+                    .parse()
+                    .map_err(|e| Error::parse(io::Error::new(io::ErrorKind::InvalidData, e)))?,
+            ),
+            None => None,
+        };
+
+        Ok(Some(ReplicationSlotInfo {
+            slot_type,
+            restart_lsn,
+            restart_timeline,
+        }))
+    }
+
+    /// Returns the type of the slot, e.g. `physical`.
+    pub fn slot_type(&self) -> &str {
+        &self.slot_type
+    }
+
+    /// Returns the oldest position in the write-ahead log the slot retains, if it has reserved the log.
+    pub fn restart_lsn(&self) -> Option<PgLsn> {
+        self.restart_lsn
+    }
+
+    /// Returns the timeline of the restart position, if it has one.
+    pub fn restart_timeline(&self) -> Option<u32> {
+        self.restart_timeline
+    }
+}
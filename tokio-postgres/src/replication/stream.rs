@@ -0,0 +1,190 @@
+use crate::replication::to_pg_timestamp;
+use crate::types::PgLsn;
+use crate::{CopyBothDuplex, Error};
+use bytes::Bytes;
+use futures::{ready, Sink, Stream};
+use log::trace;
+use postgres_protocol::message::backend::{ReplicationMessage, XLogDataBody};
+use postgres_protocol::message::frontend;
+use std::cmp;
+#[cfg(feature = "runtime")]
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::SystemTime;
+#[cfg(feature = "runtime")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "runtime")]
+use tokio::timer::{self, Delay};
+
+/// The shared implementation of the replication streams, which yields their `XLogData` messages and reports the
+/// consumer's progress back to the server.
+///
+/// Standby status updates are sent when the server requests one in a keepalive, when explicitly requested, and, with
+/// the `runtime` feature, every status interval while the stream is polled.
+pub(crate) struct ReplicationStream {
+    duplex: CopyBothDuplex,
+    received_lsn: PgLsn,
+    flushed_lsn: PgLsn,
+    applied_lsn: PgLsn,
+    pending_update: Option<Vec<u8>>,
+    #[cfg(feature = "runtime")]
+    status_interval: Option<Duration>,
+    #[cfg(feature = "runtime")]
+    timer: Option<Delay>,
+}
+
+impl ReplicationStream {
+    pub(crate) fn new(duplex: CopyBothDuplex, start_lsn: PgLsn) -> ReplicationStream {
+        ReplicationStream {
+            duplex,
+            received_lsn: start_lsn,
+            flushed_lsn: start_lsn,
+            applied_lsn: start_lsn,
+            pending_update: None,
+            #[cfg(feature = "runtime")]
+            status_interval: Some(Duration::from_secs(10)),
+            #[cfg(feature = "runtime")]
+            timer: None,
+        }
+    }
+
+    pub(crate) fn received_lsn(&self) -> PgLsn {
+        self.received_lsn
+    }
+
+    pub(crate) fn flushed_lsn(&self) -> PgLsn {
+        self.flushed_lsn
+    }
+
+    pub(crate) fn applied_lsn(&self) -> PgLsn {
+        self.applied_lsn
+    }
+
+    pub(crate) fn set_flushed_lsn(&mut self, lsn: PgLsn) {
+        self.flushed_lsn = lsn;
+    }
+
+    pub(crate) fn set_applied_lsn(&mut self, lsn: PgLsn) {
+        self.applied_lsn = lsn;
+    }
+
+    #[cfg(feature = "runtime")]
+    pub(crate) fn set_status_interval(&mut self, interval: Option<Duration>) {
+        assert!(
+            interval != Some(Duration::from_secs(0)),
+            "status interval must be nonzero"
+        );
+        self.status_interval = interval;
+        self.timer = None;
+    }
+
+    pub(crate) fn queue_status_update(&mut self, reply: bool) {
+        let mut buf = vec![];
+        // the write position can't be behind the others, even if the consumer reports positions it received earlier
+        let written = cmp::max(
+            self.received_lsn,
+            cmp::max(self.flushed_lsn, self.applied_lsn),
+        );
+        frontend::standby_status_update(
+            written.0,
+            self.flushed_lsn.0,
+            self.applied_lsn.0,
+            to_pg_timestamp(SystemTime::now()),
+            reply,
+            &mut buf,
+        );
+        self.pending_update = Some(buf);
+
+        #[cfg(feature = "runtime")]
+        {
+            if let Some(interval) = self.status_interval {
+                let deadline = Instant::now() + interval;
+                match &mut self.timer {
+                    Some(timer) => timer.reset(deadline),
+                    None => self.timer = Some(timer::delay(deadline)),
+                }
+            }
+        }
+    }
+
+    /// Sends the pending status update, if there is one, and flushes it to the connection.
+    pub(crate) fn poll_send(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        if let Some(update) = self.pending_update.take() {
+            match Sink::<Vec<u8>>::poll_ready(Pin::new(&mut self.duplex), cx)? {
+                Poll::Ready(()) => {
+                    trace!("sending standby status update");
+                    Pin::new(&mut self.duplex).start_send(update)?;
+                }
+                Poll::Pending => {
+                    self.pending_update = Some(update);
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        Sink::<Vec<u8>>::poll_flush(Pin::new(&mut self.duplex), cx)
+    }
+
+    #[cfg(feature = "runtime")]
+    fn poll_timer(&mut self, cx: &mut Context<'_>) {
+        let interval = match self.status_interval {
+            Some(interval) => interval,
+            None => return,
+        };
+
+        if self.timer.is_none() {
+            self.timer = Some(timer::delay(Instant::now() + interval));
+        }
+
+        if let Some(timer) = &mut self.timer {
+            if Pin::new(timer).poll(cx).is_pending() {
+                return;
+            }
+        }
+        trace!("status interval elapsed");
+        self.queue_status_update(false);
+
+        // queueing an update resets the timer, which is polled once with its new deadline to register for a wakeup.
+        // At most one update is queued per poll, even if the deadline has already passed again.
+        if let Some(timer) = &mut self.timer {
+            let _ = Pin::new(timer).poll(cx);
+        }
+    }
+
+    #[cfg(not(feature = "runtime"))]
+    fn poll_timer(&mut self, _: &mut Context<'_>) {}
+}
+
+impl Stream for ReplicationStream {
+    type Item = Result<XLogDataBody<Bytes>, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            self.poll_timer(cx);
+            if let Poll::Ready(Err(e)) = self.poll_send(cx) {
+                return Poll::Ready(Some(Err(e)));
+            }
+
+            let data = match ready!(Pin::new(&mut self.duplex).poll_next(cx)?) {
+                Some(data) => data,
+                None => return Poll::Ready(None),
+            };
+
+            match ReplicationMessage::parse(&data).map_err(Error::parse)? {
+                ReplicationMessage::XLogData(body) => {
+                    let end = body.wal_start() + body.data().len() as u64;
+                    self.received_lsn = cmp::max(self.received_lsn, PgLsn(end));
+                    return Poll::Ready(Some(Ok(body)));
+                }
+                ReplicationMessage::PrimaryKeepAlive(body) => {
+                    if body.reply() == 1 {
+                        trace!("keepalive requested a reply");
+                        self.queue_status_update(false);
+                    }
+                }
+                ReplicationMessage::__ForExtensibility => {}
+            }
+        }
+    }
+}
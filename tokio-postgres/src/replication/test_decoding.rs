@@ -0,0 +1,314 @@
+use crate::replication::logical::RelationCache;
+use crate::replication::{ChangeEvent, Relation, RelationColumn, TupleValue};
+use crate::Error;
+use std::io;
+use std::sync::Arc;
+
+/// A parser for the text output of the `test_decoding` plugin.
+///
+/// The plugin's output can be read with `ReplicationClient::start_test_decoding_replication`, which parses it
+/// automatically, or through the SQL interface with the rows returned by `pg_logical_slot_get_changes`, whose `data`
+/// column can be passed to `parse`.
+///
+/// `test_decoding` only reports the names and types of the columns included in each change, so the relations of the
+/// events have no OID or replica identity, and none of their columns are marked as keys. The old values of a `Delete`
+/// only include the columns of the replica identity, and so do the columns of its relation. The `Begin` and `Commit`
+/// events don't include positions or times.
+#[derive(Debug, Default)]
+pub struct TestDecodingParser {
+    relations: RelationCache,
+}
+
+impl TestDecodingParser {
+    /// Creates a new parser.
+    pub fn new() -> TestDecodingParser {
+        TestDecodingParser::default()
+    }
+
+    /// Parses a line of output.
+    ///
+    /// Returns `None` for output which doesn't correspond to a `ChangeEvent`, such as messages written with
+    /// `pg_logical_emit_message`.
+    pub fn parse(&mut self, line: &str) -> Result<Option<ChangeEvent>, Error> {
+        let mut parser = Parser { s: line };
+
+        if parser.eat("BEGIN") {
+            return Ok(Some(ChangeEvent::Begin {
+                xid: parser.xid()?,
+                final_lsn: None,
+                commit_time: None,
+            }));
+        }
+
+        if parser.eat("COMMIT") {
+            return Ok(Some(ChangeEvent::Commit {
+                xid: parser.xid()?,
+                commit_lsn: None,
+                end_lsn: None,
+                commit_time: None,
+            }));
+        }
+
+        if parser.eat("message:") {
+            return Ok(None);
+        }
+
+        parser.expect("table ")?;
+        let mut names = vec![parser.qualified_name()?];
+        while parser.eat(", ") {
+            names.push(parser.qualified_name()?);
+        }
+        parser.expect(": ")?;
+
+        let event = if parser.eat("INSERT:") {
+            let (namespace, name) = single(names)?;
+            let new = parser.tuple()?;
+            let relation = self.relation(namespace, name, &new);
+            ChangeEvent::Insert {
+                relation,
+                new: values(new),
+            }
+        } else if parser.eat("UPDATE:") {
+            let (namespace, name) = single(names)?;
+            let old = if parser.eat(" old-key:") {
+                let old = parser.tuple()?;
+                parser.expect(" new-tuple:")?;
+                Some(old)
+            } else {
+                None
+            };
+            let new = parser.tuple()?;
+            let relation = self.relation(namespace, name, &new);
+
+            // line the old values up with the new ones, like the old key of the other plugins
+            let old = old.map(|mut old| {
+                new.iter()
+                    .map(
+                        |column| match old.iter().position(|c| c.name == column.name) {
+                            Some(i) => old.swap_remove(i).value,
+                            None => TupleValue::Null,
+                        },
+                    )
+                    .collect::<Vec<_>>()
+            });
+
+            ChangeEvent::Update {
+                relation,
+                old,
+                new: values(new),
+            }
+        } else if parser.eat("DELETE:") {
+            let (namespace, name) = single(names)?;
+            let old = parser.tuple()?;
+            let relation = self.relation(namespace, name, &old);
+            ChangeEvent::Delete {
+                relation,
+                old: values(old),
+            }
+        } else if parser.eat("TRUNCATE:") {
+            let mut cascade = false;
+            let mut restart_identity = false;
+            if !parser.eat(" (no-flags)") {
+                while !parser.is_empty() {
+                    if parser.eat(" restart_seqs") {
+                        restart_identity = true;
+                    } else {
+                        parser.expect(" cascade")?;
+                        cascade = true;
+                    }
+                }
+            }
+
+            let relations = names
+                .into_iter()
+                .map(|(namespace, name)| self.relations.get_existing(namespace, name))
+                .collect();
+
+            ChangeEvent::Truncate {
+                relations,
+                cascade,
+                restart_identity,
+            }
+        } else {
+            return Err(invalid(format!("unknown change in `{}`", line)));
+        };
+
+        if !parser.is_empty() {
+            return Err(invalid(format!("unexpected trailing data in `{}`", line)));
+        }
+
+        Ok(Some(event))
+    }
+
+    fn relation(&mut self, namespace: String, name: String, columns: &[Column]) -> Arc<Relation> {
+        let columns = columns
+            .iter()
+            .map(|c| RelationColumn {
+                name: c.name.clone(),
+                type_oid: None,
+                type_name: Some(c.type_name.clone()),
+                type_modifier: None,
+                key: false,
+            })
+            .collect();
+        self.relations.get(namespace, name, columns)
+    }
+}
+
+struct Column {
+    name: String,
+    type_name: String,
+    value: TupleValue,
+}
+
+fn values(columns: Vec<Column>) -> Vec<TupleValue> {
+    columns.into_iter().map(|c| c.value).collect()
+}
+
+fn single(mut names: Vec<(String, String)>) -> Result<(String, String), Error> {
+    if names.len() != 1 {
+        return Err(invalid("expected a single relation".to_string()));
+    }
+    Ok(names.remove(0))
+}
+
+fn invalid(message: String) -> Error {
+    Error::parse(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+struct Parser<'a> {
+    s: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn is_empty(&self) -> bool {
+        self.s.is_empty()
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.s.starts_with(prefix) {
+            self.s = &self.s[prefix.len()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, prefix: &str) -> Result<(), Error> {
+        if self.eat(prefix) {
+            Ok(())
+        } else {
+            Err(invalid(format!("expected `{}` at `{}`", prefix, self.s)))
+        }
+    }
+
+    // consumes up to the next space, or the end of the input
+    fn token(&mut self) -> &'a str {
+        let end = self.s.find(' ').unwrap_or(self.s.len());
+        let token = &self.s[..end];
+        self.s = &self.s[end..];
+        token
+    }
+
+    // the xid is omitted if the `include-xids` option is off, and followed by the commit time if `include-timestamp`
+    // is on
+    fn xid(&mut self) -> Result<Option<u32>, Error> {
+        if !self.eat(" ") {
+            return Ok(None);
+        }
+
+        let token = self.token();
+        let xid = token
+            .parse()
+            .map_err(|_| invalid(format!("invalid xid `{}`", token)))?;
+        self.s = "";
+        Ok(Some(xid))
+    }
+
+    fn qualified_name(&mut self) -> Result<(String, String), Error> {
+        let namespace = self.identifier()?;
+        self.expect(".")?;
+        let name = self.identifier()?;
+        Ok((namespace, name))
+    }
+
+    fn identifier(&mut self) -> Result<String, Error> {
+        if self.eat("\"") {
+            return self.quoted('"');
+        }
+
+        let end = self
+            .s
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+            .unwrap_or(self.s.len());
+        if end == 0 {
+            return Err(invalid(format!("expected an identifier at `{}`", self.s)));
+        }
+        let identifier = self.s[..end].to_string();
+        self.s = &self.s[end..];
+        Ok(identifier)
+    }
+
+    // parses the rest of a string quoted with `quote`, in which the quote character is escaped by doubling it
+    fn quoted(&mut self, quote: char) -> Result<String, Error> {
+        let s = self.s;
+        let mut value = String::new();
+        let mut it = s.char_indices();
+        while let Some((i, c)) = it.next() {
+            if c != quote {
+                value.push(c);
+                continue;
+            }
+
+            if s[i + 1..].starts_with(quote) {
+                value.push(quote);
+                it.next();
+            } else {
+                self.s = &s[i + 1..];
+                return Ok(value);
+            }
+        }
+
+        Err(invalid("unterminated quoted string".to_string()))
+    }
+
+    // parses a sequence of ` name[type]:value` columns, stopping at the end of the input or the start of an update's
+    // new tuple
+    fn tuple(&mut self) -> Result<Vec<Column>, Error> {
+        if self.eat(" (no-tuple-data)") {
+            return Ok(vec![]);
+        }
+
+        let mut columns = vec![];
+        while !self.is_empty() && !self.s.starts_with(" new-tuple:") {
+            self.expect(" ")?;
+            let name = self.identifier()?;
+            self.expect("[")?;
+            // type names can contain brackets, as in `integer[]`
+            let end = match self.s.find("]:") {
+                Some(end) => end,
+                None => return Err(invalid(format!("expected a type at `{}`", self.s))),
+            };
+            let type_name = self.s[..end].to_string();
+            self.s = &self.s[end + 2..];
+
+            let value = if self.eat("'") {
+                TupleValue::Text(self.quoted('\'')?)
+            } else {
+                match self.token() {
+                    "null" => TupleValue::Null,
+                    "unchanged-toast-datum" => TupleValue::UnchangedToast,
+                    token => TupleValue::Text(token.to_string()),
+                }
+            };
+
+            columns.push(Column {
+                name,
+                type_name,
+                value,
+            });
+        }
+
+        Ok(columns)
+    }
+}
@@ -0,0 +1,243 @@
+use crate::replication::logical::RelationCache;
+use crate::replication::{ChangeEvent, Relation, RelationColumn, TupleValue};
+use crate::types::PgLsn;
+use crate::Error;
+use serde_json_1::{Map, Value};
+use std::io;
+use std::sync::Arc;
+
+/// A parser for the output of the `wal2json` plugin in its `format-version` 2, which writes a JSON object per change.
+///
+/// The plugin's output can be read with `ReplicationClient::start_wal2json_replication`, which parses it
+/// automatically, or through the SQL interface with the rows returned by `pg_logical_slot_get_changes` with the
+/// `format-version` option set to `2`, whose `data` column can be passed to `parse`.
+///
+/// Values are converted to the text format used by the other plugins. Strings are passed through and booleans become
+/// `t` or `f`, but numbers and `json` values are reformatted by `serde_json`, which can differ from the server's own
+/// formatting. Integers are kept exactly, but other numbers are parsed as 64-bit floats, so `numeric` values can be
+/// rounded to about 17 significant digits. The plugin's `numeric-data-types-as-string` option writes them as strings instead, which are
+/// passed through exactly; `ReplicationClient::start_wal2json_replication` enables it.
+///
+/// The type OIDs of columns are only reported with the `include-type-oids` option, and the key columns are only
+/// marked with the `include-pk` option. Like with `test_decoding`, the old values of a `Delete` only include the
+/// columns of the replica identity, and so do the columns of its relation. Each truncated relation is reported in a
+/// separate `Truncate`, without its options.
+///
+/// Requires the `with-serde_json-1` Cargo feature.
+#[derive(Debug, Default)]
+pub struct Wal2JsonParser {
+    relations: RelationCache,
+}
+
+impl Wal2JsonParser {
+    /// Creates a new parser.
+    pub fn new() -> Wal2JsonParser {
+        Wal2JsonParser::default()
+    }
+
+    /// Parses a JSON object written by the plugin.
+    ///
+    /// Returns `None` for output which doesn't correspond to a `ChangeEvent`, such as messages written with
+    /// `pg_logical_emit_message`.
+    pub fn parse(&mut self, json: &str) -> Result<Option<ChangeEvent>, Error> {
+        let object = match serde_json_1::from_str::<Value>(json) {
+            Ok(Value::Object(object)) => object,
+            Ok(_) => return Err(invalid("expected a JSON object".to_string())),
+            Err(e) => return Err(Error::parse(io::Error::new(io::ErrorKind::InvalidData, e))),
+        };
+
+        let event = match string(&object, "action")? {
+            "B" => ChangeEvent::Begin {
+                xid: xid(&object)?,
+                final_lsn: None,
+                commit_time: None,
+            },
+            "C" => ChangeEvent::Commit {
+                xid: xid(&object)?,
+                commit_lsn: lsn(&object, "lsn")?,
+                end_lsn: lsn(&object, "nextlsn")?,
+                commit_time: None,
+            },
+            "I" => {
+                let new = columns(&object, "columns")?;
+                ChangeEvent::Insert {
+                    relation: self.relation(&object, &new)?,
+                    new: values(new),
+                }
+            }
+            "U" => {
+                let new = columns(&object, "columns")?;
+                let old = match object.get("identity") {
+                    Some(_) => {
+                        let mut old = columns(&object, "identity")?;
+                        // line the old values up with the new ones, like the old key of the other plugins
+                        let old = new
+                            .iter()
+                            .map(
+                                |column| match old.iter().position(|c| c.name == column.name) {
+                                    Some(i) => old.swap_remove(i).value,
+                                    None => TupleValue::Null,
+                                },
+                            )
+                            .collect::<Vec<_>>();
+                        Some(old)
+                    }
+                    None => None,
+                };
+                ChangeEvent::Update {
+                    relation: self.relation(&object, &new)?,
+                    old,
+                    new: values(new),
+                }
+            }
+            "D" => {
+                // there's no identity if the relation's replica identity is `NOTHING`
+                let old = match object.get("identity") {
+                    Some(_) => columns(&object, "identity")?,
+                    None => vec![],
+                };
+                ChangeEvent::Delete {
+                    relation: self.relation(&object, &old)?,
+                    old: values(old),
+                }
+            }
+            "T" => {
+                let namespace = string(&object, "schema")?.to_string();
+                let name = string(&object, "table")?.to_string();
+                ChangeEvent::Truncate {
+                    relations: vec![self.relations.get_existing(namespace, name)],
+                    cascade: false,
+                    restart_identity: false,
+                }
+            }
+            "M" => return Ok(None),
+            action => return Err(invalid(format!("unknown action `{}`", action))),
+        };
+
+        Ok(Some(event))
+    }
+
+    fn relation(
+        &mut self,
+        object: &Map<String, Value>,
+        columns: &[Column],
+    ) -> Result<Arc<Relation>, Error> {
+        let namespace = string(object, "schema")?.to_string();
+        let name = string(object, "table")?.to_string();
+
+        let mut keys = vec![];
+        if let Some(pk) = object.get("pk") {
+            let pk = match pk {
+                Value::Array(pk) => pk,
+                _ => return Err(invalid("expected `pk` to be an array".to_string())),
+            };
+            for key in pk {
+                match key {
+                    Value::Object(key) => keys.push(string(key, "name")?),
+                    _ => return Err(invalid("expected `pk` to contain objects".to_string())),
+                }
+            }
+        }
+
+        let columns = columns
+            .iter()
+            .map(|c| RelationColumn {
+                name: c.name.clone(),
+                type_oid: c.type_oid,
+                type_name: c.type_name.clone(),
+                type_modifier: None,
+                key: keys.contains(&&*c.name),
+            })
+            .collect();
+
+        Ok(self.relations.get(namespace, name, columns))
+    }
+}
+
+struct Column {
+    name: String,
+    type_name: Option<String>,
+    type_oid: Option<u32>,
+    value: TupleValue,
+}
+
+fn values(columns: Vec<Column>) -> Vec<TupleValue> {
+    columns.into_iter().map(|c| c.value).collect()
+}
+
+fn invalid(message: String) -> Error {
+    Error::parse(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+fn string<'a>(object: &'a Map<String, Value>, field: &str) -> Result<&'a str, Error> {
+    match object.get(field) {
+        Some(Value::String(s)) => Ok(s),
+        _ => Err(invalid(format!("expected `{}` to be a string", field))),
+    }
+}
+
+fn xid(object: &Map<String, Value>) -> Result<Option<u32>, Error> {
+    match object.get("xid") {
+        Some(xid) => match xid.as_u64() {
+            Some(xid) if xid <= u64::from(u32::max_value()) => Ok(Some(xid as u32)),
+            _ => Err(invalid(format!("invalid xid `{}`", xid))),
+        },
+        None => Ok(None),
+    }
+}
+
+fn lsn(object: &Map<String, Value>, field: &str) -> Result<Option<PgLsn>, Error> {
+    if object.get(field).is_none() {
+        return Ok(None);
+    }
+
+    let lsn = string(object, field)?;
+    match lsn.parse() {
+        Ok(lsn) => Ok(Some(lsn)),
+        Err(_) => Err(invalid(format!("invalid LSN `{}`", lsn))),
+    }
+}
+
+fn columns(object: &Map<String, Value>, field: &str) -> Result<Vec<Column>, Error> {
+    let columns = match object.get(field) {
+        Some(Value::Array(columns)) => columns,
+        _ => return Err(invalid(format!("expected `{}` to be an array", field))),
+    };
+
+    columns
+        .iter()
+        .map(|column| {
+            let column = match column {
+                Value::Object(column) => column,
+                _ => return Err(invalid(format!("expected `{}` to contain objects", field))),
+            };
+
+            let type_oid = match column.get("typeoid") {
+                Some(oid) => match oid.as_u64() {
+                    Some(oid) if oid <= u64::from(u32::max_value()) => Some(oid as u32),
+                    _ => return Err(invalid(format!("invalid type OID `{}`", oid))),
+                },
+                None => None,
+            };
+
+            let value = match column.get("value") {
+                None | Some(Value::Null) => TupleValue::Null,
+                Some(Value::Bool(true)) => TupleValue::Text("t".to_string()),
+                Some(Value::Bool(false)) => TupleValue::Text("f".to_string()),
+                Some(Value::String(s)) => TupleValue::Text(s.clone()),
+                // numbers are written unquoted, as are json values
+                Some(value) => TupleValue::Text(value.to_string()),
+            };
+
+            Ok(Column {
+                name: string(column, "name")?.to_string(),
+                type_name: match column.get("type") {
+                    Some(_) => Some(string(column, "type")?.to_string()),
+                    None => None,
+                },
+                type_oid,
+                value,
+            })
+        })
+        .collect()
+}
@@ -2,10 +2,13 @@
 
 use crate::row::sealed::{AsName, Sealed};
 use crate::statement::Column;
-use crate::types::{FromSql, Type, WrongType};
-use crate::{Error, Statement};
+use crate::types::{FromSql, FromSqlText, Type, Value, WrongType};
+use crate::{Error, SimpleColumn, Statement};
+use bytes::Bytes;
 use fallible_iterator::FallibleIterator;
 use postgres_protocol::message::backend::DataRowBody;
+use std::any::type_name;
+use std::collections::HashMap;
 use std::fmt;
 use std::ops::Range;
 use std::str;
@@ -25,16 +28,16 @@ impl AsName for Column {
     }
 }
 
-impl AsName for String {
+impl AsName for SimpleColumn {
     fn as_name(&self) -> &str {
-        self
+        self.name()
     }
 }
 
 /// A trait implemented by types that can index into columns of a row.
 ///
 /// This cannot be implemented outside of this crate.
-pub trait RowIndex: Sealed {
+pub trait RowIndex: Sealed + fmt::Display {
     #[doc(hidden)]
     fn __idx<T>(&self, columns: &[T]) -> Option<usize>
     where
@@ -93,16 +96,150 @@ where
     }
 }
 
+/// An index which selects a column by name when the name is shared by multiple columns, as is common with joins.
+///
+/// `Nth("id", 1)` refers to the second column named `id`. Names are matched in the same way as with a `str` index.
+#[derive(Debug, Copy, Clone)]
+pub struct Nth<'a>(pub &'a str, pub usize);
+
+impl<'a> fmt::Display for Nth<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{} (occurrence {})", self.0, self.1)
+    }
+}
+
+impl<'a> Sealed for Nth<'a> {}
+
+impl<'a> RowIndex for Nth<'a> {
+    fn __idx<T>(&self, columns: &[T]) -> Option<usize>
+    where
+        T: AsName,
+    {
+        let exact = columns.iter().any(|d| d.as_name() == self.0);
+        columns
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| {
+                if exact {
+                    d.as_name() == self.0
+                } else {
+                    d.as_name().eq_ignore_ascii_case(self.0)
+                }
+            })
+            .nth(self.1)
+            .map(|(idx, _)| idx)
+    }
+}
+
+// unlike a str index, this doesn't prefer an exact match over an earlier case-insensitive one
+struct CaseInsensitive<'a>(&'a str);
+
+impl<'a> fmt::Display for CaseInsensitive<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(self.0)
+    }
+}
+
+impl<'a> Sealed for CaseInsensitive<'a> {}
+
+impl<'a> RowIndex for CaseInsensitive<'a> {
+    fn __idx<T>(&self, columns: &[T]) -> Option<usize>
+    where
+        T: AsName,
+    {
+        columns
+            .iter()
+            .position(|d| d.as_name().eq_ignore_ascii_case(self.0))
+    }
+}
+
+/// A trait for types which can be constructed from a `Row`.
+///
+/// If the `derive` Cargo feature is enabled, this trait can be derived for structs with named fields. Each field is
+/// looked up by its name, which can be overridden with `#[postgres(name = "...")]`. Fields annotated with
+/// `#[postgres(flatten)]` are themselves constructed from the entire row via `FromRow`.
+///
+/// ```rust
+/// # #[cfg(feature = "derive")]
+/// use tokio_postgres::FromRow;
+///
+/// # #[cfg(feature = "derive")]
+/// #[derive(FromRow)]
+/// struct User {
+///     id: i32,
+///     #[postgres(name = "user_name")]
+///     name: String,
+/// }
+/// ```
+///
+/// `#[postgres(transparent)]` only applies to `FromSql` and `ToSql` newtypes, and is rejected on fields:
+///
+/// ```compile_fail
+/// use tokio_postgres::FromRow;
+///
+/// #[derive(FromRow)]
+/// struct User {
+///     #[postgres(transparent)]
+///     id: i32,
+/// }
+/// ```
+pub trait FromRow: Sized {
+    /// Constructs a value from a row.
+    fn from_row(row: &Row) -> Result<Self, Error>;
+}
+
+// The location of a value in its `DataRow` message, packed into 8 bytes rather than the 24 of an
+// `Option<Range<usize>>` since a row holds one for each of its columns. Messages are at most `i32::max_value()` bytes
+// long, so the offset fits in a `u32`, and like in the message a negative length marks a NULL.
+#[derive(Copy, Clone)]
+struct FieldRange {
+    offset: u32,
+    len: i32,
+}
+
+impl FieldRange {
+    fn new(range: Option<Range<usize>>) -> FieldRange {
+        match range {
+            Some(range) => FieldRange {
+                offset: range.start as u32,
+                len: (range.end - range.start) as i32,
+            },
+            None => FieldRange { offset: 0, len: -1 },
+        }
+    }
+
+    fn get(self) -> Option<Range<usize>> {
+        if self.len < 0 {
+            None
+        } else {
+            let start = self.offset as usize;
+            Some(start..start + self.len as usize)
+        }
+    }
+}
+
+fn field_ranges(body: &DataRowBody) -> Result<Vec<FieldRange>, Error> {
+    body.ranges()
+        .map(|range| Ok(FieldRange::new(range)))
+        .collect()
+        .map_err(Error::parse)
+}
+
 /// A row of data returned from the database by a query.
+///
+/// The row holds on to the message it was received in, which shares the connection's receive buffer, so borrowed
+/// values like `&str` and `&[u8]` point directly into that buffer rather than being copied. Owned values are copied
+/// out of it, including a `Bytes` retrieved with `Row::get`; `Row::try_get_bytes` returns a reference counted slice
+/// of the buffer instead. Each row does allocate an index of where its values are in the message.
 pub struct Row {
     statement: Statement,
     body: DataRowBody,
-    ranges: Vec<Option<Range<usize>>>,
+    ranges: Vec<FieldRange>,
 }
 
 impl Row {
     pub(crate) fn new(statement: Statement, body: DataRowBody) -> Result<Row, Error> {
-        let ranges = body.ranges().collect().map_err(Error::parse)?;
+        let ranges = field_ranges(&body)?;
         Ok(Row {
             statement,
             body,
@@ -152,6 +289,85 @@ impl Row {
         self.get_inner(&idx)
     }
 
+    /// Like `Row::try_get`, but looks up the column by its name ignoring ASCII case.
+    ///
+    /// The first column whose name matches is used, even if a later one matches exactly.
+    pub fn try_get_ci<'a, T>(&'a self, name: &str) -> Result<T, Error>
+    where
+        T: FromSql<'a>,
+    {
+        self.get_inner(&CaseInsensitive(name))
+    }
+
+    /// Returns the raw value of a `BYTEA` column as a `Bytes` which shares the row's underlying buffer.
+    ///
+    /// Unlike `Row::try_get::<_, Bytes>`, this does not copy the value.
+    pub fn try_get_bytes<I>(&self, idx: I) -> Result<Option<Bytes>, Error>
+    where
+        I: RowIndex,
+    {
+        let idx = match idx.__idx(self.columns()) {
+            Some(idx) => idx,
+            None => return Err(Error::column(idx.to_string())),
+        };
+
+        let column = &self.columns()[idx];
+        let ty = column.type_();
+        if !<Bytes as FromSql>::accepts(ty) {
+            return Err(Error::from_sql_column(
+                Box::new(WrongType::new::<Bytes>(ty.clone())),
+                idx,
+                column.name(),
+            ));
+        }
+
+        Ok(self.ranges[idx]
+            .get()
+            .map(|r| self.body.buffer_bytes().slice(r.start, r.end)))
+    }
+
+    /// Converts the row into a JSON object mapping column names to their values.
+    ///
+    /// Values are converted based on their column types: booleans, integers and floating point values map to JSON
+    /// booleans and numbers (non-finite floats map to `null`), `JSON` and `JSONB` values are embedded directly, and
+    /// textual types, `NUMERIC`, `UUID`, `INET` and `BYTEA` (in hex format) map to strings. Arrays of these types map
+    /// to JSON arrays. An error is returned for columns with any other type; cast them to `TEXT` in the query instead.
+    ///
+    /// If multiple columns have the same name, the last one wins.
+    ///
+    /// Requires the `with-serde_json-1` Cargo feature.
+    #[cfg(feature = "with-serde_json-1")]
+    pub fn to_json(&self) -> Result<serde_json_1::Value, Error> {
+        let mut map = serde_json_1::Map::new();
+        for (idx, column) in self.columns().iter().enumerate() {
+            let value = self.try_get::<_, crate::types::JsonColumn>(idx)?;
+            map.insert(column.name().to_string(), value.0);
+        }
+        Ok(serde_json_1::Value::Object(map))
+    }
+
+    /// Converts the row into a map of column names to their values.
+    ///
+    /// The values are converted as described in the documentation of `Value`, so this works for rows of any query.
+    ///
+    /// If multiple columns have the same name, the last one wins.
+    pub fn to_map(&self) -> Result<HashMap<String, Value>, Error> {
+        let mut map = HashMap::with_capacity(self.len());
+        for (idx, column) in self.columns().iter().enumerate() {
+            let value = self.try_get::<_, Value>(idx)?;
+            map.insert(column.name().to_string(), value);
+        }
+        Ok(map)
+    }
+
+    /// Converts the row into a value implementing `FromRow`.
+    pub fn try_into<T>(&self) -> Result<T, Error>
+    where
+        T: FromRow,
+    {
+        T::from_row(self)
+    }
+
     fn get_inner<'a, I, T>(&'a self, idx: &I) -> Result<T, Error>
     where
         I: RowIndex,
@@ -159,33 +375,39 @@ impl Row {
     {
         let idx = match idx.__idx(self.columns()) {
             Some(idx) => idx,
-            None => return Err(Error::column()),
+            None => return Err(Error::column(idx.to_string())),
         };
 
-        let ty = self.columns()[idx].type_();
+        let column = &self.columns()[idx];
+        let ty = column.type_();
         if !T::accepts(ty) {
-            return Err(Error::from_sql(
+            return Err(Error::from_sql_column(
                 Box::new(WrongType::new::<T>(ty.clone())),
                 idx,
+                column.name(),
             ));
         }
 
-        let buf = self.ranges[idx].clone().map(|r| &self.body.buffer()[r]);
-        FromSql::from_sql_nullable(ty, buf).map_err(|e| Error::from_sql(e, idx))
+        let buf = self.ranges[idx].get().map(|r| &self.body.buffer()[r]);
+        FromSql::from_sql_nullable(ty, buf)
+            .map_err(|e| Error::from_sql_value(e, idx, column.name(), ty, type_name::<T>()))
     }
 }
 
 /// A row of data returned from the database by a simple query.
 pub struct SimpleQueryRow {
-    columns: Arc<[String]>,
+    columns: Arc<[SimpleColumn]>,
     body: DataRowBody,
-    ranges: Vec<Option<Range<usize>>>,
+    ranges: Vec<FieldRange>,
 }
 
 impl SimpleQueryRow {
     #[allow(clippy::new_ret_no_self)]
-    pub(crate) fn new(columns: Arc<[String]>, body: DataRowBody) -> Result<SimpleQueryRow, Error> {
-        let ranges = body.ranges().collect().map_err(Error::parse)?;
+    pub(crate) fn new(
+        columns: Arc<[SimpleColumn]>,
+        body: DataRowBody,
+    ) -> Result<SimpleQueryRow, Error> {
+        let ranges = field_ranges(&body)?;
         Ok(SimpleQueryRow {
             columns,
             body,
@@ -193,6 +415,11 @@ impl SimpleQueryRow {
         })
     }
 
+    /// Returns information about the columns of data in the row.
+    pub fn columns(&self) -> &[SimpleColumn] {
+        &self.columns
+    }
+
     /// Determines if the row contains no values.
     pub fn is_empty(&self) -> bool {
         self.len() == 0
@@ -228,16 +455,75 @@ impl SimpleQueryRow {
         self.get_inner(&idx)
     }
 
+    /// Parses a value from the row with its `FromSqlText` implementation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds or if the value cannot be parsed as the specified type.
+    pub fn get_as<I, T>(&self, idx: I) -> Option<T>
+    where
+        I: RowIndex + fmt::Display,
+        T: FromSqlText,
+    {
+        match self.get_as_inner(&idx) {
+            Ok(ok) => ok,
+            Err(err) => panic!("error retrieving column {}: {}", idx, err),
+        }
+    }
+
+    /// Like `SimpleQueryRow::get_as`, but returns a `Result` rather than panicking.
+    pub fn try_get_as<I, T>(&self, idx: I) -> Result<Option<T>, Error>
+    where
+        I: RowIndex,
+        T: FromSqlText,
+    {
+        self.get_as_inner(&idx)
+    }
+
+    fn get_as_inner<I, T>(&self, idx: &I) -> Result<Option<T>, Error>
+    where
+        I: RowIndex,
+        T: FromSqlText,
+    {
+        let value = match self.get_inner(idx)? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        // get_inner has already validated the index
+        let idx = idx.__idx(&self.columns).unwrap();
+        T::from_sql_text(value)
+            .map(Some)
+            .map_err(|e| Error::from_sql_column(e, idx, self.columns[idx].name()))
+    }
+
+    /// Returns the raw bytes of a value from the row, without checking that they are valid UTF-8.
+    ///
+    /// This can be used to read values from a `SQL_ASCII` database, which the server returns without converting them
+    /// to UTF-8.
+    pub fn try_get_bytes<I>(&self, idx: I) -> Result<Option<&[u8]>, Error>
+    where
+        I: RowIndex,
+    {
+        let idx = match idx.__idx(&self.columns) {
+            Some(idx) => idx,
+            None => return Err(Error::column(idx.to_string())),
+        };
+
+        Ok(self.ranges[idx].get().map(|r| &self.body.buffer()[r]))
+    }
+
     fn get_inner<I>(&self, idx: &I) -> Result<Option<&str>, Error>
     where
         I: RowIndex,
     {
         let idx = match idx.__idx(&self.columns) {
             Some(idx) => idx,
-            None => return Err(Error::column()),
+            None => return Err(Error::column(idx.to_string())),
         };
 
-        let buf = self.ranges[idx].clone().map(|r| &self.body.buffer()[r]);
-        FromSql::from_sql_nullable(&Type::TEXT, buf).map_err(|e| Error::from_sql(e, idx))
+        let buf = self.ranges[idx].get().map(|r| &self.body.buffer()[r]);
+        FromSql::from_sql_nullable(&Type::TEXT, buf)
+            .map_err(|e| Error::from_sql_column(e, idx, self.columns[idx].name()))
     }
 }
@@ -0,0 +1,55 @@
+use crate::client::InnerClient;
+use crate::types::Type;
+use crate::{query, simple_query, Error};
+use futures::{future, TryStreamExt};
+use std::future::Future;
+use std::sync::Arc;
+
+/// The scope of a runtime parameter change made by `Client::set_param`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParamScope {
+    /// The change lasts for the rest of the session, unless the current transaction is rolled back.
+    ///
+    /// Equivalent to `SET`.
+    Session,
+    /// The change lasts until the end of the current transaction.
+    ///
+    /// Equivalent to `SET LOCAL`. It has no effect outside of a transaction.
+    Local,
+}
+
+pub fn set_param(
+    client: Arc<InnerClient>,
+    name: &str,
+    value: &str,
+    scope: ParamScope,
+) -> impl Future<Output = Result<(), Error>> {
+    let is_local = scope == ParamScope::Local;
+    let rows = query::query_typed(
+        client,
+        "SELECT set_config($1, $2, $3)",
+        &[
+            (&name, Type::TEXT),
+            (&value, Type::TEXT),
+            (&is_local, Type::BOOL),
+        ],
+    );
+
+    rows.try_for_each(|_| future::ok(()))
+}
+
+pub fn reset_param(
+    client: Arc<InnerClient>,
+    name: &str,
+) -> impl Future<Output = Result<(), Error>> {
+    let query = format!("RESET {}", escape_identifier(name));
+    simple_query::batch_execute(client, &query)
+}
+
+pub fn reset_all_params(client: Arc<InnerClient>) -> impl Future<Output = Result<(), Error>> {
+    simple_query::batch_execute(client, "RESET ALL")
+}
+
+pub fn escape_identifier(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
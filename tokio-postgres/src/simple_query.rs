@@ -1,7 +1,9 @@
 use crate::client::{InnerClient, Responses};
 use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
-use crate::{Error, SimpleQueryMessage, SimpleQueryRow};
+use crate::prepare;
+use crate::types::{Oid, Type};
+use crate::{BatchOutcome, CommandTag, Error, SimpleQueryMessage, SimpleQueryRow};
 use fallible_iterator::FallibleIterator;
 use futures::{ready, Stream, TryFutureExt};
 use postgres_protocol::message::backend::Message;
@@ -11,17 +13,46 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
+/// Information about a column of a result returned by a simple query.
+#[derive(Debug)]
+pub struct SimpleColumn {
+    name: String,
+    type_oid: Oid,
+    type_: Option<Type>,
+}
+
+impl SimpleColumn {
+    /// Returns the name of the column.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the OID of the column's declared type.
+    pub fn type_oid(&self) -> Oid {
+        self.type_oid
+    }
+
+    /// Returns the declared type of the column, if it is known.
+    ///
+    /// The simple query protocol does not allow types to be looked up while a query is in progress, so this is only
+    /// `Some` for built-in types and types which the client has already loaded for a prepared statement.
+    pub fn type_(&self) -> Option<&Type> {
+        self.type_.as_ref()
+    }
+}
+
 pub fn simple_query(
     client: Arc<InnerClient>,
     query: &str,
 ) -> impl Stream<Item = Result<SimpleQueryMessage, Error>> {
-    let buf = encode(query);
+    let buf = encode(&client, query);
 
     let start = async move {
         let buf = buf?;
         let responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
 
         Ok(SimpleQuery {
+            client,
             responses,
             columns: None,
         })
@@ -34,7 +65,7 @@ pub fn batch_execute(
     client: Arc<InnerClient>,
     query: &str,
 ) -> impl Future<Output = Result<(), Error>> {
-    let buf = encode(query);
+    let buf = encode(&client, query);
 
     async move {
         let buf = buf?;
@@ -53,15 +84,47 @@ pub fn batch_execute(
     }
 }
 
-fn encode(query: &str) -> Result<Vec<u8>, Error> {
-    let mut buf = vec![];
+pub fn batch_execute_results(
+    client: Arc<InnerClient>,
+    query: &str,
+) -> impl Future<Output = Result<Vec<BatchOutcome>, Error>> {
+    let buf = encode(&client, query);
+
+    async move {
+        let buf = buf?;
+        let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
+
+        let mut outcomes = vec![];
+        loop {
+            match responses.next().await {
+                Ok(Message::ReadyForQuery(_)) => return Ok(outcomes),
+                Ok(Message::CommandComplete(body)) => {
+                    let tag = body.tag().map_err(Error::parse)?;
+                    outcomes.push(BatchOutcome::Complete(CommandTag::new(tag)));
+                }
+                Ok(Message::EmptyQueryResponse) => {
+                    outcomes.push(BatchOutcome::Complete(CommandTag::new("")));
+                }
+                Ok(Message::RowDescription(_)) | Ok(Message::DataRow(_)) => {}
+                Ok(_) => return Err(Error::unexpected_message()),
+                // the server sends ReadyForQuery after the error, so keep reading until then
+                Err(e) if e.code().is_some() => outcomes.push(BatchOutcome::Failed(e)),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn encode(client: &InnerClient, query: &str) -> Result<Vec<u8>, Error> {
+    let mut buf = client.buffer();
     frontend::query(query, &mut buf).map_err(Error::encode)?;
     Ok(buf)
 }
 
 struct SimpleQuery {
+    client: Arc<InnerClient>,
     responses: Responses,
-    columns: Option<Arc<[String]>>,
+    columns: Option<Arc<[SimpleColumn]>>,
 }
 
 impl Stream for SimpleQuery {
@@ -71,27 +134,28 @@ impl Stream for SimpleQuery {
         loop {
             match ready!(self.responses.poll_next(cx)?) {
                 Message::CommandComplete(body) => {
-                    let rows = body
-                        .tag()
-                        .map_err(Error::parse)?
-                        .rsplit(' ')
-                        .next()
-                        .unwrap()
-                        .parse()
-                        .unwrap_or(0);
+                    let rows = rows_affected(body.tag().map_err(Error::parse)?);
                     return Poll::Ready(Some(Ok(SimpleQueryMessage::CommandComplete(rows))));
                 }
                 Message::EmptyQueryResponse => {
                     return Poll::Ready(Some(Ok(SimpleQueryMessage::CommandComplete(0))));
                 }
                 Message::RowDescription(body) => {
-                    let columns = body
+                    let client = &self.client;
+                    let columns: Arc<[SimpleColumn]> = body
                         .fields()
-                        .map(|f| Ok(f.name().to_string()))
+                        .map(|f| {
+                            Ok(SimpleColumn {
+                                name: f.name().to_string(),
+                                type_oid: f.type_oid(),
+                                type_: prepare::cached_type(client, f.type_oid()),
+                            })
+                        })
                         .collect::<Vec<_>>()
                         .map_err(Error::parse)?
                         .into();
-                    self.columns = Some(columns);
+                    self.columns = Some(columns.clone());
+                    return Poll::Ready(Some(Ok(SimpleQueryMessage::RowDescription(columns))));
                 }
                 Message::DataRow(body) => {
                     let row = match &self.columns {
@@ -106,3 +170,58 @@ impl Stream for SimpleQuery {
         }
     }
 }
+
+fn rows_affected(tag: &str) -> u64 {
+    tag.rsplit(' ').next().unwrap().parse().unwrap_or(0)
+}
+
+/// The command tag sent by the server when a statement completes.
+#[derive(Debug, Clone)]
+pub struct CommandTag {
+    tag: String,
+    rows: u64,
+}
+
+impl CommandTag {
+    fn new(tag: &str) -> CommandTag {
+        CommandTag {
+            tag: tag.to_string(),
+            rows: rows_affected(tag),
+        }
+    }
+
+    /// Returns the full command tag, e.g. `INSERT 0 2`.
+    ///
+    /// The tag is empty if the statement was empty.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Returns the command name, e.g. `INSERT`.
+    pub fn command(&self) -> &str {
+        self.tag
+            .split(|c: char| c.is_ascii_digit())
+            .next()
+            .unwrap()
+            .trim_end()
+    }
+
+    /// Returns the number of rows modified or selected by the statement.
+    ///
+    /// This is 0 for statements which do not report a row count.
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+}
+
+/// The outcome of a single statement executed by `Client::batch_execute_results`.
+#[derive(Debug)]
+pub enum BatchOutcome {
+    /// The statement completed successfully.
+    Complete(CommandTag),
+    /// The statement failed.
+    ///
+    /// The statements following it in the script were not executed. If the server reported the location of the error,
+    /// its position is relative to the start of the entire script rather than the failed statement.
+    Failed(Error),
+}
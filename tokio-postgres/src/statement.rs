@@ -1,19 +1,29 @@
 use crate::client::InnerClient;
 use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
-use crate::types::Type;
+use crate::types::{Oid, Type};
+use crate::Error;
+use postgres_protocol::message::backend::Message;
 use postgres_protocol::message::frontend;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Weak};
 
 struct StatementInner {
     client: Weak<InnerClient>,
     name: String,
+    query: String,
     params: Vec<Type>,
     columns: Vec<Column>,
+    closed: AtomicBool,
 }
 
 impl Drop for StatementInner {
     fn drop(&mut self) {
+        if *self.closed.get_mut() {
+            return;
+        }
+
         if let Some(client) = self.client.upgrade() {
             let mut buf = vec![];
             frontend::close(b'S', &self.name, &mut buf).expect("statement name not valid");
@@ -25,7 +35,8 @@ impl Drop for StatementInner {
 
 /// A prepared statement.
 ///
-/// Prepared statements can only be used with the connection that created them.
+/// Prepared statements can only be used with the connection that created them. They are closed on the server when the
+/// last clone of them is dropped, or explicitly with the `close` method.
 #[derive(Clone)]
 pub struct Statement(Arc<StatementInner>);
 
@@ -33,14 +44,29 @@ impl Statement {
     pub(crate) fn new(
         inner: &Arc<InnerClient>,
         name: String,
+        query: String,
         params: Vec<Type>,
         columns: Vec<Column>,
     ) -> Statement {
         Statement(Arc::new(StatementInner {
             client: Arc::downgrade(inner),
             name,
+            query,
+            params,
+            columns,
+            closed: AtomicBool::new(false),
+        }))
+    }
+
+    // the unnamed statement is replaced by the next one parsed, so it doesn't need to be closed
+    pub(crate) fn unnamed(query: String, params: Vec<Type>, columns: Vec<Column>) -> Statement {
+        Statement(Arc::new(StatementInner {
+            client: Weak::new(),
+            name: String::new(),
+            query,
             params,
             columns,
+            closed: AtomicBool::new(false),
         }))
     }
 
@@ -48,6 +74,10 @@ impl Statement {
         &self.0.name
     }
 
+    pub(crate) fn query(&self) -> &str {
+        &self.0.query
+    }
+
     /// Returns the expected types of the statement's parameters.
     pub fn params(&self) -> &[Type] {
         &self.0.params
@@ -57,6 +87,35 @@ impl Statement {
     pub fn columns(&self) -> &[Column] {
         &self.0.columns
     }
+
+    /// Closes the statement, releasing the resources held for it by the server.
+    ///
+    /// Unlike the close performed when the statement is dropped, this waits for the server to confirm that the
+    /// statement was closed and reports any error encountered. The statement is also removed from the client's
+    /// statement cache. Neither it nor any of its clones can be used afterwards.
+    pub fn close(&self) -> impl Future<Output = Result<(), Error>> {
+        let inner = self.0.clone();
+
+        async move {
+            // the unnamed statement is never closed
+            if inner.name.is_empty() || inner.closed.swap(true, Ordering::SeqCst) {
+                return Ok(());
+            }
+
+            let client = inner.client.upgrade().ok_or_else(Error::closed)?;
+            client.invalidate_statement(&Statement(inner.clone()));
+
+            let mut buf = vec![];
+            frontend::close(b'S', &inner.name, &mut buf).map_err(Error::encode)?;
+            frontend::sync(&mut buf);
+            let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
+
+            match responses.next().await? {
+                Message::CloseComplete => Ok(()),
+                _ => Err(Error::unexpected_message()),
+            }
+        }
+    }
 }
 
 /// Information about a column of a query.
@@ -64,11 +123,23 @@ impl Statement {
 pub struct Column {
     name: String,
     type_: Type,
+    table_oid: Option<Oid>,
+    column_id: Option<i16>,
 }
 
 impl Column {
-    pub(crate) fn new(name: String, type_: Type) -> Column {
-        Column { name, type_ }
+    pub(crate) fn new(
+        name: String,
+        type_: Type,
+        table_oid: Option<Oid>,
+        column_id: Option<i16>,
+    ) -> Column {
+        Column {
+            name,
+            type_,
+            table_oid,
+            column_id,
+        }
     }
 
     /// Returns the name of the column.
@@ -80,4 +151,14 @@ impl Column {
     pub fn type_(&self) -> &Type {
         &self.type_
     }
+
+    /// Returns the OID of the table the column was taken from, if it is a plain reference to a table column.
+    pub fn table_oid(&self) -> Option<Oid> {
+        self.table_oid
+    }
+
+    /// Returns the attribute number of the column within its table, if it is a plain reference to a table column.
+    pub fn column_id(&self) -> Option<i16> {
+        self.column_id
+    }
 }
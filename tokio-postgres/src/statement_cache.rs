@@ -0,0 +1,96 @@
+use crate::error::{DbError, SqlState};
+use crate::{Error, Statement};
+use std::collections::HashMap;
+use std::error;
+
+struct Entry {
+    statement: Statement,
+    last_used: u64,
+}
+
+/// A least-recently-used cache of prepared statements, keyed by query text.
+pub struct StatementCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, Entry>,
+}
+
+impl StatementCache {
+    pub fn new() -> StatementCache {
+        StatementCache {
+            capacity: 0,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > capacity {
+            self.evict();
+        }
+    }
+
+    pub fn get(&mut self, query: &str) -> Option<Statement> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(query).map(|entry| {
+            entry.last_used = tick;
+            entry.statement.clone()
+        })
+    }
+
+    pub fn insert(&mut self, query: &str, statement: &Statement) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if !self.entries.contains_key(query) && self.entries.len() >= self.capacity {
+            self.evict();
+        }
+
+        self.tick += 1;
+        self.entries.insert(
+            query.to_string(),
+            Entry {
+                statement: statement.clone(),
+                last_used: self.tick,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, statement: &Statement) {
+        self.entries
+            .retain(|_, entry| entry.statement.name() != statement.name());
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn evict(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(query, _)| query.clone());
+
+        if let Some(oldest) = oldest {
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+/// Determines if an error indicates that a cached statement was invalidated by a schema change.
+pub fn is_invalidated(e: &Error) -> bool {
+    error::Error::source(e)
+        .and_then(|e| e.downcast_ref::<DbError>())
+        .map_or(false, |e| {
+            *e.code() == SqlState::FEATURE_NOT_SUPPORTED
+                && e.message() == "cached plan must not change result type"
+        })
+}
@@ -0,0 +1,164 @@
+//! Utilities for working with the PostgreSQL text copy format.
+//!
+//! The helpers in this module use the default options of the format: fields are separated by a tab, rows are
+//! terminated by a newline, and NULL is written as `\N`. Fields are handled as bytes, since octal and hex escapes can
+//! produce values which aren't valid UTF-8.
+
+use crate::Error;
+use std::io;
+
+/// Appends a field to `buf`, escaping it for the text copy format.
+///
+/// `None` is written as `\N`. Backslashes and the control characters with named escapes are escaped with a
+/// backslash, matching the output of `COPY ... TO`; all other bytes are written verbatim.
+pub fn encode_field(value: Option<&[u8]>, buf: &mut Vec<u8>) {
+    let value = match value {
+        Some(value) => value,
+        None => {
+            buf.extend_from_slice(b"\\N");
+            return;
+        }
+    };
+
+    for &b in value {
+        let escape = match b {
+            b'\\' => b'\\',
+            b'\x08' => b'b',
+            b'\x0c' => b'f',
+            b'\n' => b'n',
+            b'\r' => b'r',
+            b'\t' => b't',
+            b'\x0b' => b'v',
+            b => {
+                buf.push(b);
+                continue;
+            }
+        };
+        buf.push(b'\\');
+        buf.push(escape);
+    }
+}
+
+/// Appends a row to `buf`, escaping each of its fields and terminating it with a newline.
+pub fn encode_row(values: &[Option<&[u8]>], buf: &mut Vec<u8>) {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            buf.push(b'\t');
+        }
+        encode_field(*value, buf);
+    }
+    buf.push(b'\n');
+}
+
+/// Decodes a single escaped field of the text copy format.
+///
+/// A field consisting of only `\N` is decoded as `None`. In addition to the escapes written by `encode_field`, octal
+/// escapes of one to three digits (`\NNN`) and hex escapes of one or two digits (`\xNN`) are decoded, and a backslash
+/// followed by any other character is decoded as that character, as the server does.
+pub fn decode_field(field: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+    if field == b"\\N" {
+        return Ok(None);
+    }
+
+    let mut value = Vec::with_capacity(field.len());
+    let mut pos = 0;
+    while pos < field.len() {
+        let b = field[pos];
+        pos += 1;
+        if b != b'\\' {
+            value.push(b);
+            continue;
+        }
+
+        let b = match field.get(pos) {
+            Some(&b) => b,
+            None => return Err(invalid("unterminated escape sequence")),
+        };
+        pos += 1;
+
+        let b = match b {
+            b'b' => b'\x08',
+            b'f' => b'\x0c',
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'v' => b'\x0b',
+            b'0'..=b'7' => {
+                let mut n = u32::from(b - b'0');
+                for _ in 0..2 {
+                    match field.get(pos).cloned() {
+                        Some(d @ b'0'..=b'7') => {
+                            n = n * 8 + u32::from(d - b'0');
+                            pos += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                // like the server, values above \377 wrap around
+                n as u8
+            }
+            b'x' => match field.get(pos).and_then(|&d| hex(d)) {
+                Some(mut n) => {
+                    pos += 1;
+                    if let Some(d) = field.get(pos).and_then(|&d| hex(d)) {
+                        n = n * 16 + d;
+                        pos += 1;
+                    }
+                    n
+                }
+                // a `\x` not followed by a hex digit is just an `x`
+                None => b'x',
+            },
+            b => b,
+        };
+        value.push(b);
+    }
+
+    Ok(Some(value))
+}
+
+/// Decodes a row of the text copy format into its fields.
+///
+/// The row may be terminated by a newline, optionally preceded by a carriage return, which isn't included in the
+/// last field. Tabs which are escaped with a backslash are part of their field rather than separating fields.
+pub fn decode_row(line: &[u8]) -> Result<Vec<Option<Vec<u8>>>, Error> {
+    let line = if line.ends_with(b"\r\n") {
+        &line[..line.len() - 2]
+    } else if line.ends_with(b"\n") {
+        &line[..line.len() - 1]
+    } else {
+        line
+    };
+
+    let mut fields = vec![];
+    let mut start = 0;
+    let mut pos = 0;
+    while pos < line.len() {
+        match line[pos] {
+            b'\\' => pos += 2,
+            b'\t' => {
+                fields.push(decode_field(&line[start..pos])?);
+                pos += 1;
+                start = pos;
+            }
+            _ => pos += 1,
+        }
+    }
+    // a trailing backslash pushes `pos` past the end, and is rejected by `decode_field`
+    fields.push(decode_field(&line[start..])?);
+
+    Ok(fields)
+}
+
+fn hex(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::parse(io::Error::new(io::ErrorKind::InvalidData, message))
+}
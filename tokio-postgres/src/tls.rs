@@ -33,9 +33,6 @@ impl ChannelBinding {
 }
 
 /// A constructor of `TlsConnect`ors.
-///
-/// Requires the `runtime` Cargo feature (enabled by default).
-#[cfg(feature = "runtime")]
 pub trait MakeTlsConnect<S> {
     /// The stream type created by the `TlsConnect` implementation.
     type Stream: AsyncRead + AsyncWrite + Unpin;
@@ -71,9 +68,9 @@ pub trait TlsConnect<S> {
 /// A `MakeTlsConnect` and `TlsConnect` implementation which simply returns an error.
 ///
 /// This can be used when `sslmode` is `none` or `prefer`.
+#[derive(Debug, Copy, Clone)]
 pub struct NoTls;
 
-#[cfg(feature = "runtime")]
 impl<S> MakeTlsConnect<S> for NoTls {
     type Stream = NoTlsStream;
     type TlsConnect = NoTls;
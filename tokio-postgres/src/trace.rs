@@ -0,0 +1,81 @@
+// Query instrumentation, which compiles down to nothing unless the `with-tracing` feature is enabled.
+
+use crate::Error;
+#[cfg(feature = "with-tracing")]
+use std::time::Instant;
+#[cfg(feature = "with-tracing")]
+use tracing::{debug, debug_span, Span};
+
+// Records that the statement named `name` is being prepared from `query`, so the statement names recorded in query
+// spans can be matched to their SQL.
+#[cfg(feature = "with-tracing")]
+pub fn prepare(name: &str, query: &str) {
+    debug!(statement = name, query, "preparing statement");
+}
+
+#[cfg(not(feature = "with-tracing"))]
+pub fn prepare(_: &str, _: &str) {}
+
+// A span covering the execution of a single query, which records the number of rows it returned or modified, how
+// long it took, and the SQLSTATE of its error, if any, when it finishes.
+#[cfg(feature = "with-tracing")]
+pub struct QuerySpan {
+    span: Span,
+    start: Instant,
+    rows: u64,
+}
+
+#[cfg(feature = "with-tracing")]
+impl QuerySpan {
+    // A query of a prepared statement.
+    pub fn statement(name: &str) -> QuerySpan {
+        QuerySpan::new(debug_span!("query", statement = name))
+    }
+
+    // A query executed without first being prepared.
+    pub fn unprepared(query: &str) -> QuerySpan {
+        QuerySpan::new(debug_span!("query", query))
+    }
+
+    fn new(span: Span) -> QuerySpan {
+        QuerySpan {
+            span,
+            start: Instant::now(),
+            rows: 0,
+        }
+    }
+
+    pub fn rows(&mut self, rows: u64) {
+        self.rows += rows;
+    }
+
+    pub fn finish(&self, error: Option<&Error>) {
+        let rows = self.rows;
+        let duration_us = self.start.elapsed().as_micros() as u64;
+        match error {
+            None => debug!(parent: &self.span, rows, duration_us, "query completed"),
+            Some(e) => {
+                let code = e.code().map_or("", |c| c.code());
+                debug!(parent: &self.span, rows, duration_us, code, error = %e, "query failed");
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "with-tracing"))]
+pub struct QuerySpan;
+
+#[cfg(not(feature = "with-tracing"))]
+impl QuerySpan {
+    pub fn statement(_: &str) -> QuerySpan {
+        QuerySpan
+    }
+
+    pub fn unprepared(_: &str) -> QuerySpan {
+        QuerySpan
+    }
+
+    pub fn rows(&mut self, _: u64) {}
+
+    pub fn finish(&self, _: Option<&Error>) {}
+}
@@ -1,29 +1,40 @@
 use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
+use crate::session::escape_identifier;
 #[cfg(feature = "runtime")]
 use crate::tls::MakeTlsConnect;
 use crate::tls::TlsConnect;
-use crate::types::{ToSql, Type};
+use crate::types::{FromSqlOwned, ToSql, Type};
 #[cfg(feature = "runtime")]
 use crate::Socket;
-use crate::{bind, query, Client, Error, Portal, Row, SimpleQueryMessage, Statement};
+use crate::{
+    bind, interceptor, query, BatchOutcome, CancelToken, Client, CopyOutReader, CursorBuilder,
+    Error, FromRow, ParamScope, Pipeline, Portal, Row, RowStream, SimpleQueryMessage, Statement,
+};
 use bytes::{Bytes, IntoBuf};
 use futures::{Stream, TryStream};
 use postgres_protocol::message::frontend;
 use std::error;
 use std::future::Future;
+use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWrite};
 
 /// A representation of a PostgreSQL database transaction.
 ///
 /// Transactions will implicitly roll back when dropped. Use the `commit` method to commit the changes made in the
-/// transaction. Transactions can be nested, with inner transactions implemented via safepoints.
+/// transaction. Transactions can be nested, with inner transactions implemented via savepoints.
 pub struct Transaction<'a> {
     client: &'a mut Client,
-    depth: u32,
+    savepoint: Option<Savepoint>,
     done: bool,
 }
 
+/// A representation of a PostgreSQL database savepoint.
+struct Savepoint {
+    name: String,
+    depth: u32,
+}
+
 impl<'a> Drop for Transaction<'a> {
     fn drop(&mut self) {
         if self.done {
@@ -31,10 +42,9 @@ impl<'a> Drop for Transaction<'a> {
         }
 
         let mut buf = vec![];
-        let query = if self.depth == 0 {
-            "ROLLBACK".to_string()
-        } else {
-            format!("ROLLBACK TO sp{}", self.depth)
+        let query = match &self.savepoint {
+            Some(sp) => format!("ROLLBACK TO {}", escape_identifier(&sp.name)),
+            None => "ROLLBACK".to_string(),
         };
         frontend::query(&query, &mut buf).unwrap();
         let _ = self
@@ -48,18 +58,25 @@ impl<'a> Transaction<'a> {
     pub(crate) fn new(client: &'a mut Client) -> Transaction<'a> {
         Transaction {
             client,
-            depth: 0,
+            savepoint: None,
             done: false,
         }
     }
 
+    /// Returns a builder for a server-side cursor, which steps through the rows of a query without loading them into
+    /// memory all at once.
+    ///
+    /// The cursor is closed when the transaction ends unless it is made holdable.
+    pub fn cursor(&mut self) -> CursorBuilder<'_> {
+        CursorBuilder::new(self.client)
+    }
+
     /// Consumes the transaction, committing all changes made within it.
     pub async fn commit(mut self) -> Result<(), Error> {
         self.done = true;
-        let query = if self.depth == 0 {
-            "COMMIT".to_string()
-        } else {
-            format!("RELEASE sp{}", self.depth)
+        let query = match &self.savepoint {
+            Some(sp) => format!("RELEASE {}", escape_identifier(&sp.name)),
+            None => "COMMIT".to_string(),
         };
         self.client.batch_execute(&query).await
     }
@@ -69,10 +86,9 @@ impl<'a> Transaction<'a> {
     /// This is equivalent to `Transaction`'s `Drop` implementation, but provides any error encountered to the caller.
     pub async fn rollback(mut self) -> Result<(), Error> {
         self.done = true;
-        let query = if self.depth == 0 {
-            "ROLLBACK".to_string()
-        } else {
-            format!("ROLLBACK TO sp{}", self.depth)
+        let query = match &self.savepoint {
+            Some(sp) => format!("ROLLBACK TO {}", escape_identifier(&sp.name)),
+            None => "ROLLBACK".to_string(),
         };
         self.client.batch_execute(&query).await
     }
@@ -91,28 +107,62 @@ impl<'a> Transaction<'a> {
         self.client.prepare_typed(query, parameter_types)
     }
 
+    /// Like `Client::prepare_cached`.
+    pub fn prepare_cached(
+        &mut self,
+        query: &str,
+    ) -> impl Future<Output = Result<Statement, Error>> {
+        self.client.prepare_cached(query)
+    }
+
     /// Like `Client::query`.
-    pub fn query(
+    pub fn query(&mut self, statement: &Statement, params: &[&(dyn ToSql + Sync)]) -> RowStream {
+        self.client.query(statement, params)
+    }
+
+    /// Like `Client::query_as`.
+    pub fn query_as<T>(
         &mut self,
         statement: &Statement,
         params: &[&(dyn ToSql + Sync)],
-    ) -> impl Stream<Item = Result<Row, Error>> {
-        self.client.query(statement, params)
+    ) -> impl Stream<Item = Result<T, Error>>
+    where
+        T: FromRow,
+    {
+        self.client.query_as(statement, params)
     }
 
-    /// Like `Client::query_iter`.
-    pub fn query_iter<'b, I>(
+    /// Like `Client::query_scalar`.
+    pub async fn query_scalar<T>(
         &mut self,
         statement: &Statement,
-        params: I,
-    ) -> impl Stream<Item = Result<Row, Error>> + 'static
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<T, Error>
+    where
+        T: FromSqlOwned,
+    {
+        self.client.query_scalar(statement, params).await
+    }
+
+    /// Like `Client::query_iter`.
+    pub fn query_iter<'b, I>(&mut self, statement: &Statement, params: I) -> RowStream
     where
         I: IntoIterator<Item = &'b dyn ToSql>,
         I::IntoIter: ExactSizeIterator,
     {
         // https://github.com/rust-lang/rust/issues/63032
-        let buf = query::encode(statement, params);
-        query::query(self.client.inner(), statement.clone(), buf)
+        let inner = self.client.inner();
+        let (buf, intercepted) = query::encode_intercepted(&inner, statement, params);
+        query::query(inner, statement.clone(), buf).intercept(intercepted)
+    }
+
+    /// Like `Client::query_typed`.
+    pub fn query_typed(
+        &mut self,
+        query: &str,
+        params: &[(&(dyn ToSql + Sync), Type)],
+    ) -> RowStream {
+        self.client.query_typed(query, params)
     }
 
     /// Like `Client::execute`.
@@ -124,6 +174,15 @@ impl<'a> Transaction<'a> {
         self.client.execute(statement, params)
     }
 
+    /// Like `Client::execute_returning`.
+    pub fn execute_returning(
+        &mut self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> impl Future<Output = Result<(u64, Vec<Row>), Error>> {
+        self.client.execute_returning(statement, params)
+    }
+
     /// Like `Client::execute_iter`.
     pub fn execute_iter<'b, I>(
         &mut self,
@@ -135,8 +194,63 @@ impl<'a> Transaction<'a> {
         I::IntoIter: ExactSizeIterator,
     {
         // https://github.com/rust-lang/rust/issues/63032
-        let buf = query::encode(statement, params);
-        query::execute(self.client.inner(), buf)
+        let inner = self.client.inner();
+        let (buf, intercepted) = query::encode_intercepted(&inner, statement, params);
+        interceptor::execute(intercepted, query::execute(inner, statement.clone(), buf))
+    }
+
+    /// Like `Client::execute_many`.
+    pub fn execute_many<'b, I, P>(
+        &mut self,
+        statement: &Statement,
+        params: I,
+    ) -> impl Future<Output = Result<Vec<u64>, Error>>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[&'b (dyn ToSql + Sync)]>,
+    {
+        // https://github.com/rust-lang/rust/issues/63032
+        let inner = self.client.inner();
+        let buf = query::encode_many(&inner, statement, params);
+        query::execute_many(inner, statement.clone(), buf)
+    }
+
+    /// Like `Client::query_with_statement_timeout`.
+    pub fn query_with_statement_timeout(
+        &mut self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+        timeout: Duration,
+    ) -> RowStream {
+        self.client
+            .query_with_statement_timeout(statement, params, timeout)
+    }
+
+    /// Like `Client::execute_with_statement_timeout`.
+    pub fn execute_with_statement_timeout(
+        &mut self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+        timeout: Duration,
+    ) -> impl Future<Output = Result<u64, Error>> {
+        self.client
+            .execute_with_statement_timeout(statement, params, timeout)
+    }
+
+    /// Like `Client::query_with_timeout`.
+    #[cfg(feature = "runtime")]
+    pub fn query_with_timeout<T>(
+        &mut self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+        timeout: Duration,
+        tls: T,
+    ) -> impl Future<Output = Result<Vec<Row>, Error>>
+    where
+        T: MakeTlsConnect<Socket>,
+    {
+        self.client
+            .query_with_timeout(statement, params, timeout, tls)
     }
 
     /// Binds a statement to a set of parameters, creating a `Portal` which can be incrementally queried.
@@ -153,8 +267,9 @@ impl<'a> Transaction<'a> {
         params: &[&(dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Portal, Error>> {
         // https://github.com/rust-lang/rust/issues/63032
-        let buf = bind::encode(statement, params.iter().map(|s| *s as _));
-        bind::bind(self.client.inner(), statement.clone(), buf)
+        let inner = self.client.inner();
+        let buf = bind::encode(&inner, statement, params.iter().map(|s| *s as _));
+        bind::bind(inner, statement.clone(), buf)
     }
 
     /// Like [`bind`], but takes an iterator of parameters rather than a slice.
@@ -169,20 +284,30 @@ impl<'a> Transaction<'a> {
         I: IntoIterator<Item = &'b dyn ToSql>,
         I::IntoIter: ExactSizeIterator,
     {
-        let buf = bind::encode(statement, params);
-        bind::bind(self.client.inner(), statement.clone(), buf)
+        let inner = self.client.inner();
+        let buf = bind::encode(&inner, statement, params);
+        bind::bind(inner, statement.clone(), buf)
     }
 
     /// Continues execution of a portal, returning a stream of the resulting rows.
     ///
     /// Unlike `query`, portals can be incrementally evaluated by limiting the number of rows returned in each call to
     /// `query_portal`. If the requested number is negative or 0, all rows will be returned.
-    pub fn query_portal(
+    pub fn query_portal(&mut self, portal: &Portal, max_rows: i32) -> RowStream {
+        query::query_portal(self.client.inner(), portal.clone(), max_rows)
+    }
+
+    /// Returns a stream which executes a portal in chunks of at most `chunk_size` rows.
+    ///
+    /// The next chunk is only requested from the server once the stream is polled after the previous chunk has been
+    /// returned, and the stream ends when the portal has been exhausted. If `chunk_size` is negative or 0, all rows
+    /// will be returned in a single chunk.
+    pub fn query_portal_chunks(
         &mut self,
         portal: &Portal,
-        max_rows: i32,
-    ) -> impl Stream<Item = Result<Row, Error>> {
-        query::query_portal(self.client.inner(), portal.clone(), max_rows)
+        chunk_size: i32,
+    ) -> impl Stream<Item = Result<Vec<Row>, Error>> {
+        query::query_portal_chunks(self.client.inner(), portal.clone(), chunk_size)
     }
 
     /// Like `Client::copy_in`.
@@ -210,6 +335,15 @@ impl<'a> Transaction<'a> {
         self.client.copy_out(statement, params)
     }
 
+    /// Like `Client::copy_out_reader`.
+    pub fn copy_out_reader(
+        &mut self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> CopyOutReader {
+        self.client.copy_out_reader(statement, params)
+    }
+
     /// Like `Client::simple_query`.
     pub fn simple_query(
         &mut self,
@@ -218,11 +352,49 @@ impl<'a> Transaction<'a> {
         self.client.simple_query(query)
     }
 
+    /// Like `Client::pipeline`.
+    pub fn pipeline(&mut self) -> Pipeline {
+        self.client.pipeline()
+    }
+
     /// Like `Client::batch_execute`.
     pub fn batch_execute(&mut self, query: &str) -> impl Future<Output = Result<(), Error>> {
         self.client.batch_execute(query)
     }
 
+    /// Like `Client::batch_execute_results`.
+    pub fn batch_execute_results(
+        &mut self,
+        query: &str,
+    ) -> impl Future<Output = Result<Vec<BatchOutcome>, Error>> {
+        self.client.batch_execute_results(query)
+    }
+
+    /// Like `Client::set_param`.
+    pub fn set_param(
+        &mut self,
+        name: &str,
+        value: &str,
+        scope: ParamScope,
+    ) -> impl Future<Output = Result<(), Error>> {
+        self.client.set_param(name, value, scope)
+    }
+
+    /// Like `Client::reset_param`.
+    pub fn reset_param(&mut self, name: &str) -> impl Future<Output = Result<(), Error>> {
+        self.client.reset_param(name)
+    }
+
+    /// Like `Client::reset_all_params`.
+    pub fn reset_all_params(&mut self) -> impl Future<Output = Result<(), Error>> {
+        self.client.reset_all_params()
+    }
+
+    /// Like `Client::cancel_token`.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.client.cancel_token()
+    }
+
     /// Like `Client::cancel_query`.
     #[cfg(feature = "runtime")]
     pub fn cancel_query<T>(&mut self, tls: T) -> impl Future<Output = Result<(), Error>>
@@ -245,15 +417,38 @@ impl<'a> Transaction<'a> {
         self.client.cancel_query_raw(stream, tls)
     }
 
-    /// Like `Client::transaction`.
+    /// Like `Client::transaction`, but creates a nested transaction via a savepoint.
+    ///
+    /// Committing the nested transaction releases the savepoint, and rolling it back (explicitly or by dropping it)
+    /// rolls back to the savepoint, leaving the outer transaction usable.
     pub async fn transaction(&mut self) -> Result<Transaction<'_>, Error> {
-        let depth = self.depth + 1;
-        let query = format!("SAVEPOINT sp{}", depth);
+        let depth = self.savepoint.as_ref().map_or(0, |sp| sp.depth) + 1;
+        let name = format!("sp{}", depth);
+        self.savepoint_inner(name, depth).await
+    }
+
+    /// Like `Client::transaction`, but creates a nested transaction via a savepoint with the specified name.
+    ///
+    /// The name is quoted as an identifier, so it is case sensitive and can contain any characters.
+    pub async fn savepoint<I>(&mut self, name: I) -> Result<Transaction<'_>, Error>
+    where
+        I: Into<String>,
+    {
+        let depth = self.savepoint.as_ref().map_or(0, |sp| sp.depth) + 1;
+        self.savepoint_inner(name.into(), depth).await
+    }
+
+    async fn savepoint_inner(
+        &mut self,
+        name: String,
+        depth: u32,
+    ) -> Result<Transaction<'_>, Error> {
+        let query = format!("SAVEPOINT {}", escape_identifier(&name));
         self.batch_execute(&query).await?;
 
         Ok(Transaction {
             client: self.client,
-            depth,
+            savepoint: Some(Savepoint { name, depth }),
             done: false,
         })
     }
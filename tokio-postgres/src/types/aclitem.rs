@@ -0,0 +1,172 @@
+use std::error::Error;
+use std::str;
+
+use crate::types::{FromSql, FromSqlText, Type};
+
+/// A privilege which can appear in an `ACLITEM`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Privilege {
+    /// `SELECT` (`r`).
+    Select,
+    /// `INSERT` (`a`).
+    Insert,
+    /// `UPDATE` (`w`).
+    Update,
+    /// `DELETE` (`d`).
+    Delete,
+    /// `TRUNCATE` (`D`).
+    Truncate,
+    /// `REFERENCES` (`x`).
+    References,
+    /// `TRIGGER` (`t`).
+    Trigger,
+    /// `EXECUTE` (`X`).
+    Execute,
+    /// `USAGE` (`U`).
+    Usage,
+    /// `CREATE` (`C`).
+    Create,
+    /// `TEMPORARY` (`T`).
+    Temporary,
+    /// `CONNECT` (`c`).
+    Connect,
+    /// `SET` (`s`).
+    Set,
+    /// `ALTER SYSTEM` (`A`).
+    AlterSystem,
+    /// `MAINTAIN` (`m`).
+    Maintain,
+}
+
+impl Privilege {
+    fn from_char(c: char) -> Option<Privilege> {
+        let privilege = match c {
+            'r' => Privilege::Select,
+            'a' => Privilege::Insert,
+            'w' => Privilege::Update,
+            'd' => Privilege::Delete,
+            'D' => Privilege::Truncate,
+            'x' => Privilege::References,
+            't' => Privilege::Trigger,
+            'X' => Privilege::Execute,
+            'U' => Privilege::Usage,
+            'C' => Privilege::Create,
+            'T' => Privilege::Temporary,
+            'c' => Privilege::Connect,
+            's' => Privilege::Set,
+            'A' => Privilege::AlterSystem,
+            'm' => Privilege::Maintain,
+            _ => return None,
+        };
+        Some(privilege)
+    }
+}
+
+/// A privilege granted by an `ACLITEM`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AclPrivilege {
+    /// The privilege.
+    pub privilege: Privilege,
+    /// Whether the grantee may grant the privilege to others.
+    pub grant_option: bool,
+}
+
+/// A parsed `ACLITEM` value, as found in catalog columns like `pg_class.relacl`.
+///
+/// Postgres does not support the binary format for `ACLITEM`, so values must be cast to text in the query
+/// (e.g. `SELECT relacl::TEXT[] FROM pg_class`) when read with `Row::get`, or read from a simple query with
+/// `SimpleQueryRow::get_as`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AclItem {
+    /// The role the privileges are granted to, or `None` for `PUBLIC`.
+    pub grantee: Option<String>,
+    /// The role which granted the privileges.
+    pub grantor: String,
+    /// The granted privileges.
+    pub privileges: Vec<AclPrivilege>,
+}
+
+impl AclItem {
+    /// Determines if the item grants the specified privilege.
+    pub fn has_privilege(&self, privilege: Privilege) -> bool {
+        self.privileges.iter().any(|p| p.privilege == privilege)
+    }
+}
+
+impl FromSqlText for AclItem {
+    fn from_sql_text(s: &str) -> Result<AclItem, Box<dyn Error + Sync + Send>> {
+        let (grantee, s) = parse_role(s)?;
+        let s = expect(s, '=')?;
+
+        let end = s.find('/').ok_or("invalid aclitem: missing grantor")?;
+        let mut privileges = vec![];
+        let mut chars = s[..end].chars().peekable();
+        while let Some(c) = chars.next() {
+            let privilege = Privilege::from_char(c)
+                .ok_or_else(|| format!("invalid aclitem privilege `{}`", c))?;
+            let grant_option = chars.peek() == Some(&'*');
+            if grant_option {
+                chars.next();
+            }
+            privileges.push(AclPrivilege {
+                privilege,
+                grant_option,
+            });
+        }
+
+        let (grantor, s) = parse_role(&s[end + 1..])?;
+        if !s.is_empty() {
+            return Err("invalid aclitem: trailing data".into());
+        }
+
+        Ok(AclItem {
+            grantee: if grantee.is_empty() {
+                None
+            } else {
+                Some(grantee)
+            },
+            grantor,
+            privileges,
+        })
+    }
+}
+
+impl<'a> FromSql<'a> for AclItem {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<AclItem, Box<dyn Error + Sync + Send>> {
+        AclItem::from_sql_text(str::from_utf8(raw)?)
+    }
+
+    accepts!(TEXT, VARCHAR);
+}
+
+fn expect(s: &str, c: char) -> Result<&str, Box<dyn Error + Sync + Send>> {
+    if s.starts_with(c) {
+        Ok(&s[c.len_utf8()..])
+    } else {
+        Err(format!("invalid aclitem: expected `{}`", c).into())
+    }
+}
+
+// role names are double quoted if they contain special characters, with embedded quotes doubled
+fn parse_role(s: &str) -> Result<(String, &str), Box<dyn Error + Sync + Send>> {
+    if !s.starts_with('"') {
+        let end = s.find(|c| c == '=' || c == '/').unwrap_or_else(|| s.len());
+        return Ok((s[..end].to_string(), &s[end..]));
+    }
+
+    let mut name = String::new();
+    let mut chars = s[1..].char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == '"' {
+            match chars.peek() {
+                Some(&(_, '"')) => {
+                    chars.next();
+                }
+                _ => return Ok((name, &s[i + 2..])),
+            }
+        }
+        name.push(c);
+    }
+
+    Err("invalid aclitem: unterminated quoted role".into())
+}
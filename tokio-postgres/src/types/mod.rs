@@ -1,14 +1,17 @@
 //! Types.
 
+use bytes::Bytes;
 use fallible_iterator::FallibleIterator;
 use postgres_protocol;
-use postgres_protocol::types::{self, ArrayDimension};
+use postgres_protocol::types::{self, ArrayDimension, NumericSign};
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::error::Error;
 use std::fmt;
 use std::hash::BuildHasher;
 use std::net::IpAddr;
+use std::str;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
@@ -17,12 +20,26 @@ use crate::types::type_gen::{Inner, Other};
 #[doc(inline)]
 pub use postgres_protocol::Oid;
 
-pub use crate::types::special::{Date, Timestamp};
+pub use crate::types::aclitem::{AclItem, AclPrivilege, Privilege};
+pub use crate::types::pg_lsn::{ParsePgLsnError, PgLsn};
+pub use crate::types::reg::{
+    Regclass, Regconfig, Regdictionary, Regnamespace, Regoper, Regoperator, Regproc, Regprocedure,
+    Regrole, Regtype,
+};
+pub use crate::types::snapshot::PgSnapshot;
+pub use crate::types::special::{Date, Lossy, Numeric, Timestamp, Trimmed};
+pub use crate::types::timetz::TimeTz;
+pub use crate::types::value::Value;
+
+#[cfg(feature = "pgvector")]
+pub use crate::types::pgvector::{SparseVector, Vector};
+#[cfg(feature = "derive")]
+pub use postgres_derive::{FromSql, ToSql};
 
 // Number of seconds from 1970-01-01 to 2000-01-01
-const TIME_SEC_CONVERSION: u64 = 946_684_800;
-const USEC_PER_SEC: u64 = 1_000_000;
-const NSEC_PER_USEC: u64 = 1_000;
+pub(crate) const TIME_SEC_CONVERSION: u64 = 946_684_800;
+pub(crate) const USEC_PER_SEC: u64 = 1_000_000;
+pub(crate) const NSEC_PER_USEC: u64 = 1_000;
 
 /// Generates a simple implementation of `ToSql::accepts` which accepts the
 /// types passed to it.
@@ -73,6 +90,41 @@ where
     v.to_sql(ty, out)
 }
 
+// WARNING: this function is not considered part of this crate's public API.
+// It is subject to change at any time.
+#[doc(hidden)]
+pub fn __composite_values(buf: &[u8]) -> Result<Vec<Option<&[u8]>>, Box<dyn Error + Sync + Send>> {
+    types::record_from_sql(buf)?
+        .fields()
+        .map(|(_, v)| Ok(v))
+        .collect()
+}
+
+// WARNING: this function is not considered part of this crate's public API.
+// It is subject to change at any time.
+#[doc(hidden)]
+pub fn __unknown_enum_label(
+    ty: &Type,
+    label: &str,
+    known: &[&str],
+) -> Box<dyn Error + Sync + Send> {
+    let mut msg = format!("unknown label `{}` for enum type `{}`", label, ty);
+    if let Kind::Enum(ref labels) = *ty.kind() {
+        let missing = labels
+            .iter()
+            .filter(|l| !known.contains(&l.as_str()))
+            .map(|l| format!("`{}`", l))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            msg.push_str(&format!(
+                " (labels not handled by the Rust type: {})",
+                missing.join(", ")
+            ));
+        }
+    }
+    msg.into()
+}
+
 #[cfg(feature = "with-bit-vec-0_5")]
 mod bit_vec_05;
 #[cfg(feature = "with-chrono-0_4")]
@@ -81,16 +133,26 @@ mod chrono_04;
 mod eui48_04;
 #[cfg(feature = "with-geo-types-0_4")]
 mod geo_types_04;
+#[cfg(feature = "pgvector")]
+mod pgvector;
 #[cfg(feature = "with-serde_json-1")]
 mod serde_json_1;
 #[cfg(feature = "with-uuid-0_7")]
 mod uuid_07;
 
+mod aclitem;
+mod pg_lsn;
+mod reg;
+mod snapshot;
 mod special;
+mod timetz;
 mod type_gen;
+mod value;
 
 #[cfg(feature = "with-serde_json-1")]
 pub use crate::types::serde_json_1::Json;
+#[cfg(feature = "with-serde_json-1")]
+pub(crate) use crate::types::serde_json_1::JsonColumn;
 use std::any::type_name;
 
 /// A Postgres type.
@@ -145,6 +207,18 @@ impl Type {
     pub fn name(&self) -> &str {
         self.0.name()
     }
+
+    /// Determines if this type has the specified name.
+    ///
+    /// The name may be qualified by a schema (e.g. `myschema.semver`), in which case the type's schema must match as
+    /// well. This is useful when implementing `accepts` for types defined by extensions, whose OIDs differ between
+    /// databases.
+    pub fn matches_name(&self, name: &str) -> bool {
+        match name.find('.') {
+            Some(idx) => self.schema() == &name[..idx] && self.name() == &name[idx + 1..],
+            None => self.name() == name,
+        }
+    }
 }
 
 /// Represents the kind of a Postgres type.
@@ -246,15 +320,22 @@ impl WrongType {
 /// | `i8`                              | "char"                                        |
 /// | `i16`                             | SMALLINT, SMALLSERIAL                         |
 /// | `i32`                             | INT, SERIAL                                   |
-/// | `u32`                             | OID                                           |
+/// | `u16`                             | INT                                           |
+/// | `u32`                             | OID, BIGINT                                   |
+/// | `u64`                             | NUMERIC                                       |
 /// | `i64`                             | BIGINT, BIGSERIAL                             |
+/// | `i128`/`u128`                     | NUMERIC                                       |
 /// | `f32`                             | REAL                                          |
 /// | `f64`                             | DOUBLE PRECISION                              |
-/// | `&str`/`String`                   | VARCHAR, CHAR(n), TEXT, CITEXT, NAME, UNKNOWN |
-/// | `&[u8]`/`Vec<u8>`                 | BYTEA                                         |
+/// | `&str`/`String`/`Cow<str>`        | VARCHAR, CHAR(n), TEXT, CITEXT, NAME, UNKNOWN |
+/// | `&[u8]`/`Vec<u8>`/`Bytes`         | BYTEA                                         |
 /// | `HashMap<String, Option<String>>` | HSTORE                                        |
 /// | `SystemTime`                      | TIMESTAMP, TIMESTAMP WITH TIME ZONE           |
+/// | `TimeTz`                          | TIME WITH TIME ZONE                           |
+/// | `PgSnapshot`                      | PG_SNAPSHOT, TXID_SNAPSHOT                    |
 /// | `IpAddr`                          | INET                                          |
+/// | `Regclass`, `Regtype`, etc.       | REGCLASS, REGTYPE, etc.                       |
+/// | `AclItem`                         | ACLITEM (cast to TEXT)                        |
 ///
 /// In addition, some implementations are provided for types in third party
 /// crates. These are disabled by default; to opt into one of these
@@ -279,6 +360,10 @@ impl WrongType {
 /// | `bit_vec::BitVec`               | BIT, VARBIT                         |
 /// | `eui48::MacAddress`             | MACADDR                             |
 ///
+/// The `pgvector` feature enables the `Vector` and `SparseVector` types,
+/// corresponding to the `vector` and `sparsevec` types of the pgvector
+/// extension.
+///
 /// # Nullability
 ///
 /// In addition to the types listed above, `FromSql` is implemented for
@@ -288,7 +373,80 @@ impl WrongType {
 /// # Arrays
 ///
 /// `FromSql` is implemented for `Vec<T>` where `T` implements `FromSql`, and
-/// corresponds to one-dimensional Postgres arrays.
+/// corresponds to one-dimensional Postgres arrays. This includes the
+/// `int2vector` and `oidvector` catalog types, which can be read as `Vec<i16>`
+/// and `Vec<Oid>` respectively.
+///
+/// # Records
+///
+/// `FromSql` is implemented for tuples of up to 8 elements which each
+/// implement `FromSql`. A tuple corresponds to an anonymous `RECORD` value
+/// (e.g. the result of `SELECT (1, 'a')`) or a composite type with the same
+/// number of fields. The types of the fields of an anonymous record are
+/// determined by their OIDs, so only built-in types are supported there.
+///
+/// # Deriving
+///
+/// If the `derive` Cargo feature is enabled, `FromSql` can be derived for
+/// C-like enums corresponding to Postgres enum types. The Rust and Postgres
+/// type and variant names default to the identifiers, and can be overridden
+/// with `#[postgres(name = "...")]`. A type name can be qualified by a schema
+/// (e.g. `#[postgres(name = "myschema.mood")]`):
+///
+/// ```rust
+/// # #[cfg(feature = "derive")]
+/// use tokio_postgres::types::{FromSql, ToSql};
+///
+/// # #[cfg(feature = "derive")]
+/// #[derive(Debug, ToSql, FromSql)]
+/// #[postgres(name = "mood")]
+/// enum Mood {
+///     #[postgres(name = "sad")]
+///     Sad,
+///     #[postgres(name = "ok")]
+///     Ok,
+///     #[postgres(name = "happy")]
+///     Happy,
+/// }
+/// ```
+///
+/// Deserializing a label which has no corresponding variant produces an error
+/// listing the server's labels which are not handled by the Rust type.
+///
+/// Structs with named fields correspond to Postgres composite types. Fields
+/// are matched by name, which can be overridden with
+/// `#[postgres(name = "...")]`, and may themselves be composites or arrays of
+/// composites:
+///
+/// ```rust
+/// # #[cfg(feature = "derive")]
+/// use tokio_postgres::types::{FromSql, ToSql};
+///
+/// # #[cfg(feature = "derive")]
+/// #[derive(Debug, ToSql, FromSql)]
+/// #[postgres(name = "inventory_item")]
+/// struct InventoryItem {
+///     name: String,
+///     supplier_id: i32,
+///     #[postgres(name = "price")]
+///     price_cents: Option<i64>,
+/// }
+/// ```
+///
+/// Single-field structs annotated with `#[postgres(transparent)]` delegate to
+/// the wrapped type. If the value's type is a domain, its name must match the
+/// struct's name (or the `name` override) and the domain's base type must be
+/// accepted by the wrapped type:
+///
+/// ```rust
+/// # #[cfg(feature = "derive")]
+/// use tokio_postgres::types::{FromSql, ToSql};
+///
+/// # #[cfg(feature = "derive")]
+/// #[derive(Debug, ToSql, FromSql)]
+/// #[postgres(name = "email", transparent)]
+/// struct Email(String);
+/// ```
 pub trait FromSql<'a>: Sized {
     /// Creates a new value of this type from a buffer of data of the specified
     /// Postgres `Type` in its binary format.
@@ -388,6 +546,17 @@ impl<'a> FromSql<'a> for &'a [u8] {
     accepts!(BYTEA);
 }
 
+/// Copies the value, since `FromSql` is only given a borrowed slice of the row.
+///
+/// `Row::try_get_bytes` returns a `Bytes` which shares the row's buffer instead.
+impl<'a> FromSql<'a> for Bytes {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Bytes, Box<dyn Error + Sync + Send>> {
+        Ok(Bytes::from(types::bytea_from_sql(raw)))
+    }
+
+    accepts!(BYTEA);
+}
+
 impl<'a> FromSql<'a> for String {
     fn from_sql(_: &Type, raw: &'a [u8]) -> Result<String, Box<dyn Error + Sync + Send>> {
         types::text_from_sql(raw).map(ToString::to_string)
@@ -412,6 +581,16 @@ impl<'a> FromSql<'a> for &'a str {
     }
 }
 
+impl<'a> FromSql<'a> for Cow<'a, str> {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Cow<'a, str>, Box<dyn Error + Sync + Send>> {
+        <&str as FromSql>::from_sql(ty, raw).map(Cow::Borrowed)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <&str as FromSql>::accepts(ty)
+    }
+}
+
 macro_rules! simple_from {
     ($t:ty, $f:ident, $($expected:ident),+) => {
         impl<'a> FromSql<'a> for $t {
@@ -428,7 +607,6 @@ simple_from!(bool, bool_from_sql, BOOL);
 simple_from!(i8, char_from_sql, CHAR);
 simple_from!(i16, int2_from_sql, INT2);
 simple_from!(i32, int4_from_sql, INT4);
-simple_from!(u32, oid_from_sql, OID);
 simple_from!(i64, int8_from_sql, INT8);
 simple_from!(f32, float4_from_sql, FLOAT4);
 simple_from!(f64, float8_from_sql, FLOAT8);
@@ -457,19 +635,21 @@ impl<'a> FromSql<'a> for SystemTime {
         let epoch = UNIX_EPOCH + Duration::from_secs(TIME_SEC_CONVERSION);
 
         let negative = time < 0;
-        let time = time.abs() as u64;
+        let time = time.wrapping_abs() as u64;
 
         let secs = time / USEC_PER_SEC;
         let nsec = (time % USEC_PER_SEC) * NSEC_PER_USEC;
         let offset = Duration::new(secs, nsec as u32);
 
         let time = if negative {
-            epoch - offset
+            epoch.checked_sub(offset)
         } else {
-            epoch + offset
+            epoch.checked_add(offset)
         };
 
-        Ok(time)
+        // infinite timestamps are represented as i64::MIN/MAX, which may not fit in a SystemTime on all platforms.
+        // Timestamp<T> can be used to handle them explicitly.
+        time.ok_or_else(|| "timestamp out of range for SystemTime".into())
     }
 
     accepts!(TIMESTAMP, TIMESTAMPTZ);
@@ -484,6 +664,325 @@ impl<'a> FromSql<'a> for IpAddr {
     accepts!(INET);
 }
 
+fn numeric_to_i128(raw: &[u8]) -> Result<i128, Box<dyn Error + Sync + Send>> {
+    let numeric = types::numeric_from_sql(raw)?;
+    let negative = match numeric.sign() {
+        NumericSign::Positive => false,
+        NumericSign::Negative => true,
+        _ => return Err("NUMERIC value is not finite".into()),
+    };
+
+    let mut value = 0i128;
+    let mut digits = numeric.digits();
+    for _ in 0..=numeric.weight() {
+        let digit = i128::from(digits.next()?.unwrap_or(0));
+        value = value
+            .checked_mul(10_000)
+            .and_then(|v| {
+                if negative {
+                    v.checked_sub(digit)
+                } else {
+                    v.checked_add(digit)
+                }
+            })
+            .ok_or("NUMERIC value out of range")?;
+    }
+
+    if digits.any(|d| Ok(d != 0))? {
+        return Err("NUMERIC value is not an integer".into());
+    }
+
+    Ok(value)
+}
+
+impl<'a> FromSql<'a> for i128 {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<i128, Box<dyn Error + Sync + Send>> {
+        numeric_to_i128(raw)
+    }
+
+    accepts!(NUMERIC);
+}
+
+impl<'a> FromSql<'a> for u128 {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<u128, Box<dyn Error + Sync + Send>> {
+        let numeric = types::numeric_from_sql(raw)?;
+        match numeric.sign() {
+            NumericSign::Positive => {}
+            NumericSign::Negative => return Err("NUMERIC value out of range".into()),
+            _ => return Err("NUMERIC value is not finite".into()),
+        }
+
+        let mut value = 0u128;
+        let mut digits = numeric.digits();
+        for _ in 0..=numeric.weight() {
+            let digit = u128::from(digits.next()?.unwrap_or(0) as u16);
+            value = value
+                .checked_mul(10_000)
+                .and_then(|v| v.checked_add(digit))
+                .ok_or("NUMERIC value out of range")?;
+        }
+
+        if digits.any(|d| Ok(d != 0))? {
+            return Err("NUMERIC value is not an integer".into());
+        }
+
+        Ok(value)
+    }
+
+    accepts!(NUMERIC);
+}
+
+impl<'a> FromSql<'a> for u16 {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<u16, Box<dyn Error + Sync + Send>> {
+        let v = types::int4_from_sql(raw)?;
+        Ok(u16::try_from(v).map_err(|_| "value out of range for u16")?)
+    }
+
+    accepts!(INT4);
+}
+
+impl<'a> FromSql<'a> for u32 {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<u32, Box<dyn Error + Sync + Send>> {
+        match *ty {
+            Type::OID => types::oid_from_sql(raw),
+            _ => {
+                let v = types::int8_from_sql(raw)?;
+                Ok(u32::try_from(v).map_err(|_| "value out of range for u32")?)
+            }
+        }
+    }
+
+    accepts!(OID, INT8);
+}
+
+impl<'a> FromSql<'a> for u64 {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<u64, Box<dyn Error + Sync + Send>> {
+        let v = <u128 as FromSql>::from_sql(ty, raw)?;
+        Ok(u64::try_from(v).map_err(|_| "value out of range for u64")?)
+    }
+
+    accepts!(NUMERIC);
+}
+
+// Renders a NUMERIC value in the same format as Postgres's text output.
+#[cfg_attr(not(feature = "with-serde_json-1"), allow(dead_code))]
+pub(crate) fn numeric_to_string(raw: &[u8]) -> Result<String, Box<dyn Error + Sync + Send>> {
+    let numeric = types::numeric_from_sql(raw)?;
+    let mut s = String::new();
+    match numeric.sign() {
+        NumericSign::Positive => {}
+        NumericSign::Negative => s.push('-'),
+        NumericSign::NaN => return Ok("NaN".to_string()),
+        NumericSign::PositiveInfinity => return Ok("Infinity".to_string()),
+        NumericSign::NegativeInfinity => return Ok("-Infinity".to_string()),
+    }
+
+    let digits = numeric.digits().collect::<Vec<_>>()?;
+    let digit = |i: i32| {
+        if i < 0 {
+            0
+        } else {
+            digits.get(i as usize).cloned().unwrap_or(0)
+        }
+    };
+
+    let weight = i32::from(numeric.weight());
+    if weight < 0 {
+        s.push('0');
+    } else {
+        s.push_str(&digit(0).to_string());
+        for i in 1..=weight {
+            s.push_str(&format!("{:04}", digit(i)));
+        }
+    }
+
+    let scale = usize::from(numeric.scale());
+    if scale > 0 {
+        let mut frac = String::new();
+        let mut i = weight + 1;
+        while frac.len() < scale {
+            frac.push_str(&format!("{:04}", digit(i)));
+            i += 1;
+        }
+        frac.truncate(scale);
+        s.push('.');
+        s.push_str(&frac);
+    }
+
+    Ok(s)
+}
+
+fn record_field<'a, T>(
+    ty: &Type,
+    idx: usize,
+    field: Option<(Oid, Option<&'a [u8]>)>,
+) -> Result<T, Box<dyn Error + Sync + Send>>
+where
+    T: FromSql<'a>,
+{
+    let (oid, raw) = match field {
+        Some(field) => field,
+        None => return Err("record contains too few fields".into()),
+    };
+
+    let field_type = match *ty.kind() {
+        Kind::Composite(ref fields) => fields[idx].type_().clone(),
+        _ => match Type::from_oid(oid) {
+            Some(field_type) => field_type,
+            None => {
+                return Err(format!("record field {} has an unknown type OID {}", idx, oid).into())
+            }
+        },
+    };
+
+    if !T::accepts(&field_type) {
+        return Err(Box::new(WrongType::new::<T>(field_type)));
+    }
+
+    T::from_sql_nullable(&field_type, raw)
+}
+
+macro_rules! tuple_from {
+    ($n:expr; $($t:ident $idx:tt),+) => {
+        impl<'a, $($t),+> FromSql<'a> for ($($t,)+)
+        where
+            $($t: FromSql<'a>,)+
+        {
+            fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<($($t,)+), Box<dyn Error + Sync + Send>> {
+                let record = types::record_from_sql(raw)?;
+                if record.len() != $n {
+                    let msg = format!(
+                        "expected a record with {} fields but got {}",
+                        $n,
+                        record.len(),
+                    );
+                    return Err(msg.into());
+                }
+
+                let mut fields = record.fields();
+                Ok(($(record_field::<$t>(ty, $idx, fields.next()?)?,)+))
+            }
+
+            fn accepts(ty: &Type) -> bool {
+                match *ty.kind() {
+                    Kind::Composite(ref fields) => {
+                        fields.len() == $n && $(<$t as FromSql>::accepts(fields[$idx].type_()))&&+
+                    }
+                    _ => *ty == Type::RECORD,
+                }
+            }
+        }
+    }
+}
+
+tuple_from!(1; A 0);
+tuple_from!(2; A 0, B 1);
+tuple_from!(3; A 0, B 1, C 2);
+tuple_from!(4; A 0, B 1, C 2, D 3);
+tuple_from!(5; A 0, B 1, C 2, D 3, E 4);
+tuple_from!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+tuple_from!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+tuple_from!(8; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+/// A trait for types that can be parsed from the text representation of a Postgres value.
+///
+/// Values are normally transferred in Postgres's binary format and converted with `FromSql`, but some sources only
+/// provide values in the text format. Most notably, the rows returned by `Client::simple_query` contain text values,
+/// which can be parsed with `SimpleQueryRow::get_as` and `SimpleQueryRow::try_get_as`.
+///
+/// # Types
+///
+/// | Rust type                         | Postgres type(s)                              |
+/// |-----------------------------------|-----------------------------------------------|
+/// | `bool`                            | BOOL                                          |
+/// | `i16`                             | SMALLINT, SMALLSERIAL                         |
+/// | `i32`                             | INT, SERIAL                                   |
+/// | `u32`                             | OID                                           |
+/// | `i64`                             | BIGINT, BIGSERIAL                             |
+/// | `i128`/`u128`                     | NUMERIC                                       |
+/// | `f32`                             | REAL                                          |
+/// | `f64`                             | DOUBLE PRECISION                              |
+/// | `String`                          | VARCHAR, CHAR(n), TEXT, CITEXT, NAME, UNKNOWN |
+/// | `Vec<u8>`                         | BYTEA                                         |
+/// | `IpAddr`                          | INET                                          |
+pub trait FromSqlText: Sized {
+    /// Parses a value from its text representation.
+    fn from_sql_text(s: &str) -> Result<Self, Box<dyn Error + Sync + Send>>;
+}
+
+impl FromSqlText for bool {
+    fn from_sql_text(s: &str) -> Result<bool, Box<dyn Error + Sync + Send>> {
+        match s {
+            "t" | "true" => Ok(true),
+            "f" | "false" => Ok(false),
+            _ => Err("invalid boolean".into()),
+        }
+    }
+}
+
+macro_rules! parse_text {
+    ($($t:ty),+) => {
+        $(
+            impl FromSqlText for $t {
+                fn from_sql_text(s: &str) -> Result<$t, Box<dyn Error + Sync + Send>> {
+                    s.parse().map_err(Into::into)
+                }
+            }
+        )+
+    }
+}
+
+parse_text!(i16, i32, u32, i64, i128, u128, String);
+
+macro_rules! parse_float_text {
+    ($($t:ident),+) => {
+        $(
+            impl FromSqlText for $t {
+                fn from_sql_text(s: &str) -> Result<$t, Box<dyn Error + Sync + Send>> {
+                    match s {
+                        "NaN" => Ok(std::$t::NAN),
+                        "Infinity" => Ok(std::$t::INFINITY),
+                        "-Infinity" => Ok(std::$t::NEG_INFINITY),
+                        s => s.parse().map_err(Into::into),
+                    }
+                }
+            }
+        )+
+    }
+}
+
+parse_float_text!(f32, f64);
+
+impl FromSqlText for Vec<u8> {
+    fn from_sql_text(s: &str) -> Result<Vec<u8>, Box<dyn Error + Sync + Send>> {
+        // only the default hex output format is supported
+        if !s.starts_with("\\x") || s.len() % 2 != 0 {
+            return Err("invalid hex-format bytea".into());
+        }
+
+        s.as_bytes()[2..]
+            .chunks(2)
+            .map(|c| {
+                str::from_utf8(c)
+                    .ok()
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+                    .ok_or_else(|| "invalid hex-format bytea".into())
+            })
+            .collect()
+    }
+}
+
+impl FromSqlText for IpAddr {
+    fn from_sql_text(s: &str) -> Result<IpAddr, Box<dyn Error + Sync + Send>> {
+        // the netmask is omitted for host addresses
+        let addr = match s.find('/') {
+            Some(idx) => &s[..idx],
+            None => s,
+        };
+        addr.parse().map_err(Into::into)
+    }
+}
+
 /// An enum representing the nullability of a Postgres value.
 pub enum IsNull {
     /// The value is NULL.
@@ -505,15 +1004,21 @@ pub enum IsNull {
 /// | `i8`                              | "char"                               |
 /// | `i16`                             | SMALLINT, SMALLSERIAL                |
 /// | `i32`                             | INT, SERIAL                          |
-/// | `u32`                             | OID                                  |
+/// | `u16`                             | INT                                  |
+/// | `u32`                             | OID, BIGINT                          |
+/// | `u64`                             | NUMERIC                              |
 /// | `i64`                             | BIGINT, BIGSERIAL                    |
+/// | `i128`/`u128`                     | NUMERIC                              |
 /// | `f32`                             | REAL                                 |
 /// | `f64`                             | DOUBLE PRECISION                     |
-/// | `&str`/`String`                   | VARCHAR, CHAR(n), TEXT, CITEXT, NAME |
-/// | `&[u8]`/Vec<u8>`                  | BYTEA                                |
+/// | `&str`/`String`/`Cow<str>`        | VARCHAR, CHAR(n), TEXT, CITEXT, NAME |
+/// | `&[u8]`/`Vec<u8>`/`Bytes`         | BYTEA                                |
 /// | `HashMap<String, Option<String>>` | HSTORE                               |
 /// | `SystemTime`                      | TIMESTAMP, TIMESTAMP WITH TIME ZONE  |
+/// | `TimeTz`                          | TIME WITH TIME ZONE                  |
+/// | `PgSnapshot`                      | PG_SNAPSHOT, TXID_SNAPSHOT           |
 /// | `IpAddr`                          | INET                                 |
+/// | `Regclass`, `Regtype`, etc.       | REGCLASS, REGTYPE, etc.              |
 ///
 /// In addition, some implementations are provided for types in third party
 /// crates. These are disabled by default; to opt into one of these
@@ -538,6 +1043,10 @@ pub enum IsNull {
 /// | `bit_vec::BitVec`               | BIT, VARBIT                         |
 /// | `eui48::MacAddress`             | MACADDR                             |
 ///
+/// The `pgvector` feature enables the `Vector` and `SparseVector` types,
+/// corresponding to the `vector` and `sparsevec` types of the pgvector
+/// extension.
+///
 /// # Nullability
 ///
 /// In addition to the types listed above, `ToSql` is implemented for
@@ -548,6 +1057,23 @@ pub enum IsNull {
 ///
 /// `ToSql` is implemented for `Vec<T>` and `&[T]` where `T` implements `ToSql`,
 /// and corresponds to one-dimensional Postgres arrays with an index offset of 1.
+/// They can also be sent as the `int2vector` and `oidvector` catalog types,
+/// with an index offset of 0, but not when empty, since the server rejects an
+/// empty array for them.
+///
+/// # Records
+///
+/// `ToSql` is implemented for tuples of up to 8 elements which each implement
+/// `ToSql`, and corresponds to composite types with the same number of
+/// fields. Postgres does not support anonymous `RECORD` parameters, so a
+/// parameter's type must be a named composite type (e.g. `$1::my_type`).
+///
+/// # Deriving
+///
+/// If the `derive` Cargo feature is enabled, `ToSql` can be derived for C-like
+/// enums corresponding to Postgres enum types, for structs corresponding to
+/// composite types, and for `#[postgres(transparent)]` newtypes. See the
+/// `FromSql` documentation for details.
 pub trait ToSql: fmt::Debug {
     /// Converts the value of `self` into the binary format of the specified
     /// Postgres `Type`, appending it to `out`.
@@ -616,9 +1142,21 @@ impl<'a, T: ToSql> ToSql for &'a [T] {
             _ => panic!("expected array type"),
         };
 
+        // int2vector and oidvector values are zero-indexed, and the server can't receive empty ones, since it decodes
+        // an empty array as having no dimensions
+        let lower_bound = match *ty {
+            Type::INT2_VECTOR | Type::OID_VECTOR => {
+                if self.is_empty() {
+                    return Err(format!("empty {} values can't be sent", ty.name()).into());
+                }
+                0
+            }
+            _ => 1,
+        };
+
         let dimension = ArrayDimension {
             len: downcast(self.len())?,
-            lower_bound: 1,
+            lower_bound,
         };
 
         types::array_to_sql(
@@ -679,6 +1217,18 @@ impl ToSql for Vec<u8> {
     to_sql_checked!();
 }
 
+impl ToSql for Bytes {
+    fn to_sql(&self, ty: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        <&[u8] as ToSql>::to_sql(&&**self, ty, w)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <&[u8] as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
 impl<'a> ToSql for &'a str {
     fn to_sql(&self, _: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
         types::text_to_sql(*self, w);
@@ -742,7 +1292,6 @@ simple_to!(bool, bool_to_sql, BOOL);
 simple_to!(i8, char_to_sql, CHAR);
 simple_to!(i16, int2_to_sql, INT2);
 simple_to!(i32, int4_to_sql, INT4);
-simple_to!(u32, oid_to_sql, OID);
 simple_to!(i64, int8_to_sql, INT8);
 simple_to!(f32, float4_to_sql, FLOAT4);
 simple_to!(f64, float8_to_sql, FLOAT8);
@@ -802,6 +1351,138 @@ impl ToSql for IpAddr {
     to_sql_checked!();
 }
 
+fn u128_to_numeric(
+    sign: NumericSign,
+    mut value: u128,
+    w: &mut Vec<u8>,
+) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+    let mut digits = vec![];
+    while value != 0 {
+        digits.push((value % 10_000) as i16);
+        value /= 10_000;
+    }
+    digits.reverse();
+
+    let weight = digits.len() as i16 - 1;
+    while let Some(&0) = digits.last() {
+        digits.pop();
+    }
+    let weight = if digits.is_empty() { 0 } else { weight };
+
+    types::numeric_to_sql(sign, weight, 0, digits, w)?;
+    Ok(IsNull::No)
+}
+
+impl ToSql for i128 {
+    fn to_sql(&self, _: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        let sign = if *self < 0 {
+            NumericSign::Negative
+        } else {
+            NumericSign::Positive
+        };
+        u128_to_numeric(sign, self.wrapping_abs() as u128, w)
+    }
+
+    accepts!(NUMERIC);
+
+    to_sql_checked!();
+}
+
+impl ToSql for u128 {
+    fn to_sql(&self, _: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        u128_to_numeric(NumericSign::Positive, *self, w)
+    }
+
+    accepts!(NUMERIC);
+
+    to_sql_checked!();
+}
+
+impl ToSql for u16 {
+    fn to_sql(&self, _: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        types::int4_to_sql(i32::from(*self), w);
+        Ok(IsNull::No)
+    }
+
+    accepts!(INT4);
+
+    to_sql_checked!();
+}
+
+impl ToSql for u32 {
+    fn to_sql(&self, ty: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        match *ty {
+            Type::OID => types::oid_to_sql(*self, w),
+            _ => types::int8_to_sql(i64::from(*self), w),
+        }
+        Ok(IsNull::No)
+    }
+
+    accepts!(OID, INT8);
+
+    to_sql_checked!();
+}
+
+impl ToSql for u64 {
+    fn to_sql(&self, _: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        u128_to_numeric(NumericSign::Positive, u128::from(*self), w)
+    }
+
+    accepts!(NUMERIC);
+
+    to_sql_checked!();
+}
+
+macro_rules! tuple_to {
+    ($n:expr; $($t:ident $idx:tt),+) => {
+        impl<$($t),+> ToSql for ($($t,)+)
+        where
+            $($t: ToSql,)+
+        {
+            fn to_sql(&self, ty: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+                let fields = match *ty.kind() {
+                    Kind::Composite(ref fields) => fields,
+                    _ => panic!("expected composite type"),
+                };
+
+                let values: [&dyn ToSql; $n] = [$(&self.$idx),+];
+                types::record_to_sql(
+                    fields
+                        .iter()
+                        .zip(values.iter())
+                        .map(|(f, v)| (f.type_().oid(), (f.type_(), *v))),
+                    |(ty, v), w| match v.to_sql(ty, w)? {
+                        IsNull::No => Ok(postgres_protocol::IsNull::No),
+                        IsNull::Yes => Ok(postgres_protocol::IsNull::Yes),
+                    },
+                    w,
+                )?;
+                Ok(IsNull::No)
+            }
+
+            fn accepts(ty: &Type) -> bool {
+                match *ty.kind() {
+                    Kind::Composite(ref fields) => {
+                        fields.len() == $n && $(<$t as ToSql>::accepts(fields[$idx].type_()))&&+
+                    }
+                    _ => false,
+                }
+            }
+
+            to_sql_checked!();
+        }
+    }
+}
+
+tuple_to!(1; A 0);
+tuple_to!(2; A 0, B 1);
+tuple_to!(3; A 0, B 1, C 2);
+tuple_to!(4; A 0, B 1, C 2, D 3);
+tuple_to!(5; A 0, B 1, C 2, D 3, E 4);
+tuple_to!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+tuple_to!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+tuple_to!(8; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
 fn downcast(len: usize) -> Result<i32, Box<dyn Error + Sync + Send>> {
     if len > i32::max_value() as usize {
         Err("value too large to transmit".into())
@@ -0,0 +1,79 @@
+use postgres_protocol::types;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::types::{FromSql, IsNull, ToSql, Type};
+
+/// A position in the write-ahead log, corresponding to the `PG_LSN` type.
+///
+/// It is formatted as two hexadecimal numbers of up to 8 digits each, separated by a slash, e.g. `16/B374D848`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct PgLsn(pub u64);
+
+impl From<u64> for PgLsn {
+    fn from(lsn: u64) -> PgLsn {
+        PgLsn(lsn)
+    }
+}
+
+impl From<PgLsn> for u64 {
+    fn from(lsn: PgLsn) -> u64 {
+        lsn.0
+    }
+}
+
+impl fmt::Display for PgLsn {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{:X}/{:X}", self.0 >> 32, self.0 & 0xffff_ffff)
+    }
+}
+
+impl FromStr for PgLsn {
+    type Err = ParsePgLsnError;
+
+    fn from_str(s: &str) -> Result<PgLsn, ParsePgLsnError> {
+        let mut it = s.splitn(2, '/');
+        let (hi, lo) = match (it.next(), it.next()) {
+            (Some(hi), Some(lo)) if !hi.is_empty() && !lo.is_empty() => (hi, lo),
+            _ => return Err(ParsePgLsnError(())),
+        };
+        if hi.len() > 8 || lo.len() > 8 {
+            return Err(ParsePgLsnError(()));
+        }
+        let hi = u64::from_str_radix(hi, 16).map_err(|_| ParsePgLsnError(()))?;
+        let lo = u64::from_str_radix(lo, 16).map_err(|_| ParsePgLsnError(()))?;
+        Ok(PgLsn(hi << 32 | lo))
+    }
+}
+
+impl<'a> FromSql<'a> for PgLsn {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<PgLsn, Box<dyn Error + Sync + Send>> {
+        types::pg_lsn_from_sql(raw).map(PgLsn)
+    }
+
+    accepts!(PG_LSN);
+}
+
+impl ToSql for PgLsn {
+    fn to_sql(&self, _: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        types::pg_lsn_to_sql(self.0, w);
+        Ok(IsNull::No)
+    }
+
+    accepts!(PG_LSN);
+
+    to_sql_checked!();
+}
+
+/// An error returned when parsing an invalid `PgLsn`.
+#[derive(Debug)]
+pub struct ParsePgLsnError(());
+
+impl fmt::Display for ParsePgLsnError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("invalid LSN")
+    }
+}
+
+impl Error for ParsePgLsnError {}
@@ -0,0 +1,162 @@
+use std::convert::TryFrom;
+use std::error::Error;
+
+use crate::types::{FromSql, IsNull, ToSql, Type};
+
+/// A value of the pgvector extension's `vector` type.
+///
+/// The type is matched by name since extension types do not have fixed OIDs.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Vector(pub Vec<f32>);
+
+impl From<Vec<f32>> for Vector {
+    fn from(v: Vec<f32>) -> Vector {
+        Vector(v)
+    }
+}
+
+impl From<Vector> for Vec<f32> {
+    fn from(v: Vector) -> Vec<f32> {
+        v.0
+    }
+}
+
+impl<'a> FromSql<'a> for Vector {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Vector, Box<dyn Error + Sync + Send>> {
+        if raw.len() < 4 {
+            return Err("invalid message length".into());
+        }
+        let dim = u16::from_be_bytes([raw[0], raw[1]]) as usize;
+        let raw = &raw[4..];
+        if raw.len() != dim * 4 {
+            return Err("invalid message length".into());
+        }
+
+        Ok(Vector(raw.chunks(4).map(read_f32).collect()))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        ty.name() == "vector"
+    }
+}
+
+impl ToSql for Vector {
+    fn to_sql(&self, _: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        let dim = u16::try_from(self.0.len()).map_err(|_| "vector has too many dimensions")?;
+        w.extend_from_slice(&dim.to_be_bytes());
+        w.extend_from_slice(&[0; 2]);
+        for v in &self.0 {
+            w.extend_from_slice(&v.to_bits().to_be_bytes());
+        }
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        ty.name() == "vector"
+    }
+
+    to_sql_checked!();
+}
+
+/// A value of the pgvector extension's `sparsevec` type.
+///
+/// Only the nonzero elements are stored, as parallel lists of their zero-based indices and values.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SparseVector {
+    /// The number of dimensions of the vector.
+    pub dim: i32,
+    /// The indices of the nonzero elements, in increasing order.
+    pub indices: Vec<i32>,
+    /// The values of the nonzero elements.
+    pub values: Vec<f32>,
+}
+
+impl SparseVector {
+    /// Expands the vector into a dense representation.
+    ///
+    /// Returns `None` if an index is out of range for the vector's dimensions, or if the numbers of indices and values
+    /// differ. Values read from the database are always valid.
+    pub fn to_dense(&self) -> Option<Vector> {
+        if self.indices.len() != self.values.len() {
+            return None;
+        }
+
+        let mut v = vec![0.; self.dim.max(0) as usize];
+        for (&i, &value) in self.indices.iter().zip(&self.values) {
+            if i < 0 {
+                return None;
+            }
+            *v.get_mut(i as usize)? = value;
+        }
+        Some(Vector(v))
+    }
+}
+
+impl<'a> FromSql<'a> for SparseVector {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<SparseVector, Box<dyn Error + Sync + Send>> {
+        if raw.len() < 12 {
+            return Err("invalid message length".into());
+        }
+        let dim = read_i32(&raw[0..4]);
+        let nnz = read_i32(&raw[4..8]);
+        if nnz < 0 {
+            return Err("invalid nonzero element count".into());
+        }
+        let nnz = nnz as usize;
+        let raw = &raw[12..];
+        if raw.len() != nnz * 8 {
+            return Err("invalid message length".into());
+        }
+
+        let (indices, values) = raw.split_at(nnz * 4);
+        let indices = indices.chunks(4).map(read_i32).collect::<Vec<_>>();
+        if indices.iter().any(|&i| i < 0 || i >= dim) {
+            return Err("invalid sparsevec index".into());
+        }
+
+        Ok(SparseVector {
+            dim,
+            indices,
+            values: values.chunks(4).map(read_f32).collect(),
+        })
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        ty.name() == "sparsevec"
+    }
+}
+
+impl ToSql for SparseVector {
+    fn to_sql(&self, _: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        if self.indices.len() != self.values.len() {
+            return Err("sparsevec indices and values have different lengths".into());
+        }
+        let nnz =
+            i32::try_from(self.indices.len()).map_err(|_| "sparsevec has too many elements")?;
+
+        w.extend_from_slice(&self.dim.to_be_bytes());
+        w.extend_from_slice(&nnz.to_be_bytes());
+        w.extend_from_slice(&[0; 4]);
+        for i in &self.indices {
+            w.extend_from_slice(&i.to_be_bytes());
+        }
+        for v in &self.values {
+            w.extend_from_slice(&v.to_bits().to_be_bytes());
+        }
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        ty.name() == "sparsevec"
+    }
+
+    to_sql_checked!();
+}
+
+fn read_i32(buf: &[u8]) -> i32 {
+    i32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]])
+}
+
+fn read_f32(buf: &[u8]) -> f32 {
+    f32::from_bits(u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]))
+}
@@ -0,0 +1,94 @@
+use postgres_protocol::types;
+use std::error::Error;
+
+use crate::types::{FromSql, IsNull, Oid, ToSql, Type};
+
+macro_rules! reg_type {
+    ($(#[$attr:meta])* $name:ident, $expected:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(pub Oid);
+
+        impl From<Oid> for $name {
+            fn from(oid: Oid) -> $name {
+                $name(oid)
+            }
+        }
+
+        impl From<$name> for Oid {
+            fn from(v: $name) -> Oid {
+                v.0
+            }
+        }
+
+        impl<'a> FromSql<'a> for $name {
+            fn from_sql(_: &Type, raw: &'a [u8]) -> Result<$name, Box<dyn Error + Sync + Send>> {
+                types::oid_from_sql(raw).map($name)
+            }
+
+            accepts!($expected);
+        }
+
+        impl ToSql for $name {
+            fn to_sql(&self, _: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+                types::oid_to_sql(self.0, w);
+                Ok(IsNull::No)
+            }
+
+            accepts!($expected);
+
+            to_sql_checked!();
+        }
+    };
+}
+
+reg_type!(
+    /// The OID of a relation, corresponding to the `REGCLASS` type.
+    Regclass,
+    REGCLASS
+);
+reg_type!(
+    /// The OID of a type, corresponding to the `REGTYPE` type.
+    Regtype,
+    REGTYPE
+);
+reg_type!(
+    /// The OID of a function, corresponding to the `REGPROC` type.
+    Regproc,
+    REGPROC
+);
+reg_type!(
+    /// The OID of a function with argument types, corresponding to the `REGPROCEDURE` type.
+    Regprocedure,
+    REGPROCEDURE
+);
+reg_type!(
+    /// The OID of an operator, corresponding to the `REGOPER` type.
+    Regoper,
+    REGOPER
+);
+reg_type!(
+    /// The OID of an operator with argument types, corresponding to the `REGOPERATOR` type.
+    Regoperator,
+    REGOPERATOR
+);
+reg_type!(
+    /// The OID of a namespace, corresponding to the `REGNAMESPACE` type.
+    Regnamespace,
+    REGNAMESPACE
+);
+reg_type!(
+    /// The OID of a role, corresponding to the `REGROLE` type.
+    Regrole,
+    REGROLE
+);
+reg_type!(
+    /// The OID of a text search configuration, corresponding to the `REGCONFIG` type.
+    Regconfig,
+    REGCONFIG
+);
+reg_type!(
+    /// The OID of a text search dictionary, corresponding to the `REGDICTIONARY` type.
+    Regdictionary,
+    REGDICTIONARY
+);
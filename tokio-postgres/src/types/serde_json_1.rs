@@ -1,10 +1,13 @@
+use fallible_iterator::FallibleIterator;
+use postgres_protocol::types;
 use serde_1::{Deserialize, Serialize};
-use serde_json_1::Value;
+use serde_json_1::{Number, Value};
 use std::error::Error;
-use std::fmt::Debug;
+use std::fmt::{Debug, Write};
 use std::io::Read;
+use std::net::IpAddr;
 
-use crate::types::{FromSql, IsNull, ToSql, Type};
+use crate::types::{numeric_to_string, FromSql, IsNull, Kind, ToSql, Type};
 
 /// A wrapper type to allow arbitrary `Serialize`/`Deserialize` types to convert to Postgres JSON values.
 #[derive(Debug)]
@@ -63,3 +66,96 @@ impl ToSql for Value {
     accepts!(JSON, JSONB);
     to_sql_checked!();
 }
+
+/// A JSON representation of an arbitrary column value, used by `Row::to_json`.
+pub(crate) struct JsonColumn(pub Value);
+
+impl<'a> FromSql<'a> for JsonColumn {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<JsonColumn, Box<dyn Error + Sync + Send>> {
+        column_from_sql(ty, raw).map(JsonColumn)
+    }
+
+    fn from_sql_null(_: &Type) -> Result<JsonColumn, Box<dyn Error + Sync + Send>> {
+        Ok(JsonColumn(Value::Null))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        match *ty {
+            Type::BOOL
+            | Type::INT2
+            | Type::INT4
+            | Type::INT8
+            | Type::OID
+            | Type::FLOAT4
+            | Type::FLOAT8
+            | Type::NUMERIC
+            | Type::BYTEA
+            | Type::JSON
+            | Type::JSONB
+            | Type::UUID
+            | Type::INET => true,
+            ref ty if <&str as FromSql>::accepts(ty) => true,
+            ref ty => match *ty.kind() {
+                Kind::Array(ref member) => JsonColumn::accepts(member),
+                _ => false,
+            },
+        }
+    }
+}
+
+fn column_from_sql(ty: &Type, raw: &[u8]) -> Result<Value, Box<dyn Error + Sync + Send>> {
+    let value = match *ty {
+        Type::BOOL => Value::Bool(types::bool_from_sql(raw)?),
+        Type::INT2 => Value::from(types::int2_from_sql(raw)?),
+        Type::INT4 => Value::from(types::int4_from_sql(raw)?),
+        Type::INT8 => Value::from(types::int8_from_sql(raw)?),
+        Type::OID => Value::from(types::oid_from_sql(raw)?),
+        Type::FLOAT4 => float(f64::from(types::float4_from_sql(raw)?)),
+        Type::FLOAT8 => float(types::float8_from_sql(raw)?),
+        // NUMERIC values can't in general be represented losslessly as JSON numbers
+        Type::NUMERIC => Value::String(numeric_to_string(raw)?),
+        Type::BYTEA => {
+            let mut s = "\\x".to_string();
+            for b in types::bytea_from_sql(raw) {
+                write!(s, "{:02x}", b).unwrap();
+            }
+            Value::String(s)
+        }
+        Type::JSON | Type::JSONB => Value::from_sql(ty, raw)?,
+        Type::UUID => {
+            let b = types::uuid_from_sql(raw)?;
+            let mut s = String::new();
+            for (i, b) in b.iter().enumerate() {
+                if i == 4 || i == 6 || i == 8 || i == 10 {
+                    s.push('-');
+                }
+                write!(s, "{:02x}", b).unwrap();
+            }
+            Value::String(s)
+        }
+        Type::INET => Value::String(IpAddr::from_sql(ty, raw)?.to_string()),
+        ref ty if <&str as FromSql>::accepts(ty) => {
+            Value::String(types::text_from_sql(raw)?.to_string())
+        }
+        ref ty => match *ty.kind() {
+            Kind::Array(ref member) => {
+                let array = types::array_from_sql(raw)?;
+                let values = array
+                    .values()
+                    .map(|v| match v {
+                        Some(v) => column_from_sql(member, v),
+                        None => Ok(Value::Null),
+                    })
+                    .collect()?;
+                Value::Array(values)
+            }
+            _ => return Err(format!("unsupported type {}", ty).into()),
+        },
+    };
+
+    Ok(value)
+}
+
+fn float(v: f64) -> Value {
+    Number::from_f64(v).map_or(Value::Null, Value::Number)
+}
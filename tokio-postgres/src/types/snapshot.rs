@@ -0,0 +1,57 @@
+use fallible_iterator::FallibleIterator;
+use postgres_protocol::types;
+use std::error::Error;
+
+use crate::types::{FromSql, IsNull, ToSql, Type};
+
+/// A transaction snapshot, as returned by `pg_current_snapshot()` or `txid_current_snapshot()`.
+///
+/// Corresponds to the `PG_SNAPSHOT` type added in Postgres 13 and the older `TXID_SNAPSHOT` type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PgSnapshot {
+    /// The earliest transaction ID that is still active.
+    pub xmin: u64,
+    /// The first as-yet-unassigned transaction ID.
+    pub xmax: u64,
+    /// The transaction IDs active at the time of the snapshot.
+    pub xip_list: Vec<u64>,
+}
+
+impl PgSnapshot {
+    /// Determines if the changes made by a transaction are visible in the snapshot.
+    pub fn is_visible(&self, xid: u64) -> bool {
+        xid < self.xmin || (xid < self.xmax && !self.xip_list.contains(&xid))
+    }
+}
+
+fn accepts(ty: &Type) -> bool {
+    *ty == Type::TXID_SNAPSHOT || ty.name() == "pg_snapshot"
+}
+
+impl<'a> FromSql<'a> for PgSnapshot {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<PgSnapshot, Box<dyn Error + Sync + Send>> {
+        let snapshot = types::snapshot_from_sql(raw)?;
+        Ok(PgSnapshot {
+            xmin: snapshot.xmin(),
+            xmax: snapshot.xmax(),
+            xip_list: snapshot.xip().collect()?,
+        })
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        accepts(ty)
+    }
+}
+
+impl ToSql for PgSnapshot {
+    fn to_sql(&self, _: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        types::snapshot_to_sql(self.xmin, self.xmax, self.xip_list.iter().cloned(), w)?;
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        accepts(ty)
+    }
+
+    to_sql_checked!();
+}
@@ -1,4 +1,5 @@
-use postgres_protocol::types;
+use postgres_protocol::types::{self, NumericSign};
+use std::borrow::Cow;
 use std::error::Error;
 use std::{i32, i64};
 
@@ -98,3 +99,110 @@ impl<T: ToSql> ToSql for Timestamp<T> {
 
     to_sql_checked!();
 }
+
+/// A wrapper that can be used to represent the special `NaN` and infinite values of `Type::NUMERIC`.
+///
+/// Infinite `NUMERIC` values are supported by Postgres 14 and newer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Numeric<T> {
+    /// Represents `NaN`.
+    NaN,
+    /// Represents `Infinity`, a value that is larger than all other values.
+    PosInfinity,
+    /// Represents `-Infinity`, a value that is smaller than all other values.
+    NegInfinity,
+    /// The wrapped value.
+    Value(T),
+}
+
+impl<'a, T: FromSql<'a>> FromSql<'a> for Numeric<T> {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        match types::numeric_from_sql(raw)?.sign() {
+            NumericSign::NaN => Ok(Numeric::NaN),
+            NumericSign::PositiveInfinity => Ok(Numeric::PosInfinity),
+            NumericSign::NegativeInfinity => Ok(Numeric::NegInfinity),
+            NumericSign::Positive | NumericSign::Negative => {
+                T::from_sql(ty, raw).map(Numeric::Value)
+            }
+        }
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::NUMERIC && T::accepts(ty)
+    }
+}
+
+impl<T: ToSql> ToSql for Numeric<T> {
+    fn to_sql(&self, ty: &Type, out: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        let sign = match *self {
+            Numeric::NaN => NumericSign::NaN,
+            Numeric::PosInfinity => NumericSign::PositiveInfinity,
+            Numeric::NegInfinity => NumericSign::NegativeInfinity,
+            Numeric::Value(ref v) => return v.to_sql(ty, out),
+        };
+
+        types::numeric_to_sql(sign, 0, 0, None, out)?;
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::NUMERIC && T::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+/// A wrapper which strips the trailing padding from `CHAR(n)` values.
+///
+/// Postgres pads `CHAR(n)` (`Type::BPCHAR`) values with spaces to their declared length, so a `CHAR(5)` column
+/// containing `'123'` is returned as `"123  "`. Values of other types are passed through unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Trimmed<T>(pub T);
+
+impl<'a, T: FromSql<'a>> FromSql<'a> for Trimmed<T> {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let raw = if *ty == Type::BPCHAR {
+            let len = raw.iter().rposition(|b| *b != b' ').map_or(0, |i| i + 1);
+            &raw[..len]
+        } else {
+            raw
+        };
+
+        T::from_sql(ty, raw).map(Trimmed)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        T::accepts(ty)
+    }
+}
+
+impl<T: ToSql> ToSql for Trimmed<T> {
+    fn to_sql(&self, ty: &Type, out: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        self.0.to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        T::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+/// A wrapper which decodes text values leniently, replacing invalid UTF-8 sequences with `U+FFFD REPLACEMENT CHARACTER`.
+///
+/// The client always requests UTF-8 from the server, which converts text from the database's encoding, so values are
+/// normally valid UTF-8. The exception is a `SQL_ASCII` database, where the server stores and returns bytes as is, so
+/// legacy data in encodings such as `LATIN1` or `SJIS` would cause decoding a `String` or `&str` to fail. Values which
+/// are valid UTF-8 are borrowed without copying.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Lossy<'a>(pub Cow<'a, str>);
+
+impl<'a> FromSql<'a> for Lossy<'a> {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Lossy<'a>, Box<dyn Error + Sync + Send>> {
+        Ok(Lossy(String::from_utf8_lossy(raw)))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <&str as FromSql>::accepts(ty)
+    }
+}
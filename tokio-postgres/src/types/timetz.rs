@@ -0,0 +1,42 @@
+use postgres_protocol::types;
+use std::error::Error;
+
+use crate::types::{FromSql, IsNull, ToSql, Type};
+
+/// A `TIME WITH TIME ZONE` value.
+///
+/// Postgres stores the time of day alongside a fixed UTC offset rather than a named time zone, so the value is
+/// represented directly rather than through a date/time library type.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimeTz {
+    /// The number of microseconds since midnight.
+    pub microseconds: i64,
+    /// The offset from UTC in seconds, with positive values east of Greenwich.
+    ///
+    /// Note that this is the opposite of the sign convention Postgres uses on the wire, but matches the one used
+    /// in its text representation (e.g. `04:05:06+02`).
+    pub offset: i32,
+}
+
+impl<'a> FromSql<'a> for TimeTz {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<TimeTz, Box<dyn Error + Sync + Send>> {
+        let (microseconds, zone) = types::timetz_from_sql(raw)?;
+        Ok(TimeTz {
+            microseconds,
+            offset: -zone,
+        })
+    }
+
+    accepts!(TIMETZ);
+}
+
+impl ToSql for TimeTz {
+    fn to_sql(&self, _: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        types::timetz_to_sql(self.microseconds, -self.offset, w);
+        Ok(IsNull::No)
+    }
+
+    accepts!(TIMETZ);
+
+    to_sql_checked!();
+}
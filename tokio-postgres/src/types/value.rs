@@ -0,0 +1,164 @@
+use fallible_iterator::FallibleIterator;
+use postgres_protocol::types;
+use std::error::Error;
+use std::time::SystemTime;
+
+use crate::types::{FromSql, IsNull, Kind, ToSql, Type, WrongType};
+
+/// A dynamically typed Postgres value.
+///
+/// `Value` can be converted to and from values of any Postgres type, which makes it useful for working with queries
+/// whose result types aren't known at compile time. Types without a dedicated variant are represented by `Other`,
+/// which contains the value in Postgres's binary format.
+///
+/// Domains are handled as their base types, and enum labels are represented as `Text`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A `NULL` value.
+    Null,
+    /// A `BOOL` value.
+    Bool(bool),
+    /// A `SMALLINT` value.
+    Int2(i16),
+    /// An `INT` value.
+    Int4(i32),
+    /// A `BIGINT` value.
+    Int8(i64),
+    /// A `REAL` or `DOUBLE PRECISION` value.
+    Float(f64),
+    /// A textual value, such as `TEXT`, `VARCHAR`, or an enum label.
+    Text(String),
+    /// A `BYTEA` value.
+    Bytea(Vec<u8>),
+    /// A `TIMESTAMP` or `TIMESTAMP WITH TIME ZONE` value.
+    Timestamp(SystemTime),
+    /// A `UUID` value.
+    Uuid([u8; 16]),
+    /// A one-dimensional array value.
+    Array(Vec<Value>),
+    /// A composite or record value, as a list of field names and values.
+    ///
+    /// The fields of anonymous records are named `f1`, `f2`, etc, as they are by Postgres.
+    Composite(Vec<(String, Value)>),
+    /// A value of any other type, in its binary format.
+    Other(Vec<u8>),
+}
+
+impl<'a> FromSql<'a> for Value {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Value, Box<dyn Error + Sync + Send>> {
+        let value = match *ty {
+            Type::BOOL => Value::Bool(types::bool_from_sql(raw)?),
+            Type::INT2 => Value::Int2(types::int2_from_sql(raw)?),
+            Type::INT4 => Value::Int4(types::int4_from_sql(raw)?),
+            Type::INT8 => Value::Int8(types::int8_from_sql(raw)?),
+            Type::FLOAT4 => Value::Float(f64::from(types::float4_from_sql(raw)?)),
+            Type::FLOAT8 => Value::Float(types::float8_from_sql(raw)?),
+            Type::BYTEA => Value::Bytea(types::bytea_from_sql(raw).to_vec()),
+            Type::TIMESTAMP | Type::TIMESTAMPTZ => Value::Timestamp(SystemTime::from_sql(ty, raw)?),
+            Type::UUID => Value::Uuid(types::uuid_from_sql(raw)?),
+            ref ty if <&str as FromSql>::accepts(ty) => {
+                Value::Text(types::text_from_sql(raw)?.to_string())
+            }
+            ref ty => match *ty.kind() {
+                Kind::Enum(_) => Value::Text(types::text_from_sql(raw)?.to_string()),
+                Kind::Domain(ref base) => Value::from_sql(base, raw)?,
+                Kind::Array(_) => Value::Array(Vec::<Value>::from_sql(ty, raw)?),
+                Kind::Composite(ref fields) => {
+                    let record = types::record_from_sql(raw)?;
+                    if record.len() != fields.len() {
+                        return Err("unexpected number of composite fields".into());
+                    }
+                    let values = record
+                        .fields()
+                        .enumerate()
+                        .map(|(i, (_, raw))| {
+                            let field = &fields[i];
+                            let value = Value::from_sql_nullable(field.type_(), raw)?;
+                            Ok((field.name().to_string(), value))
+                        })
+                        .collect()?;
+                    Value::Composite(values)
+                }
+                _ if *ty == Type::RECORD => {
+                    let record = types::record_from_sql(raw)?;
+                    let values = record
+                        .fields()
+                        .enumerate()
+                        .map(|(i, (oid, raw))| {
+                            let value = match (raw, Type::from_oid(oid)) {
+                                (None, _) => Value::Null,
+                                (Some(raw), Some(ty)) => Value::from_sql(&ty, raw)?,
+                                (Some(raw), None) => Value::Other(raw.to_vec()),
+                            };
+                            Ok((format!("f{}", i + 1), value))
+                        })
+                        .collect()?;
+                    Value::Composite(values)
+                }
+                _ => Value::Other(raw.to_vec()),
+            },
+        };
+
+        Ok(value)
+    }
+
+    fn from_sql_null(_: &Type) -> Result<Value, Box<dyn Error + Sync + Send>> {
+        Ok(Value::Null)
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+impl ToSql for Value {
+    fn to_sql(&self, ty: &Type, w: &mut Vec<u8>) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        if let Kind::Domain(ref base) = *ty.kind() {
+            return self.to_sql(base, w);
+        }
+
+        match (self, ty.kind()) {
+            (Value::Null, _) => return Ok(IsNull::Yes),
+            (Value::Other(raw), _) => w.extend_from_slice(raw),
+            (Value::Bool(v), _) if *ty == Type::BOOL => types::bool_to_sql(*v, w),
+            (Value::Int2(v), _) if *ty == Type::INT2 => types::int2_to_sql(*v, w),
+            (Value::Int4(v), _) if *ty == Type::INT4 => types::int4_to_sql(*v, w),
+            (Value::Int8(v), _) if *ty == Type::INT8 => types::int8_to_sql(*v, w),
+            (Value::Float(v), _) if *ty == Type::FLOAT4 => types::float4_to_sql(*v as f32, w),
+            (Value::Float(v), _) if *ty == Type::FLOAT8 => types::float8_to_sql(*v, w),
+            (Value::Bytea(v), _) if *ty == Type::BYTEA => types::bytea_to_sql(v, w),
+            (Value::Timestamp(v), _) if <SystemTime as ToSql>::accepts(ty) => {
+                return v.to_sql(ty, w);
+            }
+            (Value::Uuid(v), _) if *ty == Type::UUID => types::uuid_to_sql(*v, w),
+            (Value::Text(v), &Kind::Enum(_)) => types::text_to_sql(v, w),
+            (Value::Text(v), _) if <&str as ToSql>::accepts(ty) => types::text_to_sql(v, w),
+            (Value::Array(v), &Kind::Array(_)) => return v.to_sql(ty, w),
+            (Value::Composite(v), &Kind::Composite(ref fields)) => {
+                if v.len() != fields.len() {
+                    return Err("unexpected number of composite fields".into());
+                }
+                types::record_to_sql(
+                    fields
+                        .iter()
+                        .zip(v)
+                        .map(|(f, (_, v))| (f.type_().oid(), (f.type_(), v))),
+                    |(ty, v), w| match v.to_sql(ty, w)? {
+                        IsNull::No => Ok(postgres_protocol::IsNull::No),
+                        IsNull::Yes => Ok(postgres_protocol::IsNull::Yes),
+                    },
+                    w,
+                )?;
+            }
+            _ => return Err(Box::new(WrongType::new::<Value>(ty.clone()))),
+        }
+
+        Ok(IsNull::No)
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
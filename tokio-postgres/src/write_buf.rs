@@ -0,0 +1,169 @@
+use crate::buffer_pool::BufferPool;
+use crate::codec::FrontendMessage;
+use bytes::buf::Chain;
+use bytes::Buf;
+use iovec::IoVec;
+use std::collections::VecDeque;
+use std::io;
+use std::sync::Arc;
+
+enum Chunk {
+    Raw(io::Cursor<Vec<u8>>),
+    CopyData(Chain<io::Cursor<[u8; 5]>, Box<dyn Buf + Send>>),
+}
+
+impl Chunk {
+    fn buf(&self) -> &dyn Buf {
+        match self {
+            Chunk::Raw(buf) => buf,
+            Chunk::CopyData(buf) => buf,
+        }
+    }
+
+    fn buf_mut(&mut self) -> &mut dyn Buf {
+        match self {
+            Chunk::Raw(buf) => buf,
+            Chunk::CopyData(buf) => buf,
+        }
+    }
+}
+
+// The frontend messages queued to be written by the connection.
+//
+// The messages are kept as they were sent by the client rather than being copied into a contiguous buffer, and are
+// exposed as a `Buf` whose `bytes_vec` spans all of them so that they can be written with a single vectored write.
+// Message buffers are returned to the pool once they've been written.
+pub struct WriteBuf {
+    pool: Arc<BufferPool>,
+    chunks: VecDeque<Chunk>,
+    remaining: usize,
+}
+
+impl WriteBuf {
+    pub fn new(pool: Arc<BufferPool>) -> WriteBuf {
+        WriteBuf {
+            pool,
+            chunks: VecDeque::new(),
+            remaining: 0,
+        }
+    }
+
+    pub fn push(&mut self, message: FrontendMessage) {
+        let chunk = match message {
+            FrontendMessage::Raw(buf) => Chunk::Raw(io::Cursor::new(buf)),
+            FrontendMessage::CopyData(data) => Chunk::CopyData(data.into_buf()),
+        };
+
+        // empty chunks would end the iovecs early
+        let len = chunk.buf().remaining();
+        if len == 0 {
+            self.release(chunk);
+            return;
+        }
+
+        self.remaining += len;
+        self.chunks.push_back(chunk);
+    }
+
+    fn release(&self, chunk: Chunk) {
+        if let Chunk::Raw(buf) = chunk {
+            self.pool.put(buf.into_inner());
+        }
+    }
+}
+
+impl Buf for WriteBuf {
+    fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self.chunks.front() {
+            Some(chunk) => chunk.buf().bytes(),
+            None => &[],
+        }
+    }
+
+    fn bytes_vec<'a>(&'a self, dst: &mut [&'a IoVec]) -> usize {
+        let mut n = 0;
+        for chunk in &self.chunks {
+            if n == dst.len() {
+                break;
+            }
+            let buf = chunk.buf();
+            let added = buf.bytes_vec(&mut dst[n..]);
+            let len = dst[n..n + added].iter().map(|v| v.len()).sum::<usize>();
+            n += added;
+            // a chunk which isn't fully covered, like a `Buf` whose `bytes_vec` only returns its first segment, has to be
+            // written before anything after it
+            if len < buf.remaining() {
+                break;
+            }
+        }
+        n
+    }
+
+    fn advance(&mut self, mut cnt: usize) {
+        assert!(cnt <= self.remaining, "cannot advance past `remaining`");
+        self.remaining -= cnt;
+
+        while cnt > 0 {
+            let len = self.chunks[0].buf().remaining();
+            if cnt < len {
+                self.chunks[0].buf_mut().advance(cnt);
+                return;
+            }
+
+            cnt -= len;
+            let chunk = self.chunks.pop_front().unwrap();
+            self.release(chunk);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytes::IntoBuf;
+    use postgres_protocol::message::frontend::CopyData;
+
+    // A two-segment buffer which, like many `Buf` implementations, only exposes its first segment in `bytes_vec`.
+    struct Segments(Chain<io::Cursor<&'static [u8]>, io::Cursor<&'static [u8]>>);
+
+    impl Buf for Segments {
+        fn remaining(&self) -> usize {
+            self.0.remaining()
+        }
+
+        fn bytes(&self) -> &[u8] {
+            self.0.bytes()
+        }
+
+        fn advance(&mut self, cnt: usize) {
+            self.0.advance(cnt)
+        }
+    }
+
+    #[test]
+    fn bytes_vec_stops_at_partial_chunk() {
+        let mut buf = WriteBuf::new(Arc::new(BufferPool::new()));
+        let data = Segments((&b"ab"[..]).into_buf().chain(&b"cd"[..]));
+        let data = CopyData::new(Box::new(data) as Box<dyn Buf + Send>).unwrap();
+        buf.push(FrontendMessage::CopyData(data));
+        buf.push(FrontendMessage::Raw(b"c\0\0\0\x04".to_vec()));
+
+        // writes everything exposed by each call to `bytes_vec`, like a vectored write would
+        let mut out = vec![];
+        while buf.has_remaining() {
+            let mut dst = [<&IoVec>::default(); 16];
+            let n = buf.bytes_vec(&mut dst);
+            let len = out.len();
+            for iovec in &dst[..n] {
+                out.extend_from_slice(iovec);
+            }
+            buf.advance(out.len() - len);
+        }
+
+        assert_eq!(out, b"d\0\0\0\x08abcdc\0\0\0\x04");
+    }
+}
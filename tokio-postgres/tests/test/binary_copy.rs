@@ -0,0 +1,232 @@
+use bytes::Bytes;
+use futures::{join, TryStreamExt};
+use tokio_postgres::binary_copy::{BinaryCopyInWriter, BinaryCopyOutStream};
+use tokio_postgres::types::Type;
+
+use crate::connect;
+
+#[tokio::test]
+async fn write_basic() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT, bar TEXT)")
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare("COPY foo (id, bar) FROM STDIN BINARY")
+        .await
+        .unwrap();
+    let (mut writer, stream) = BinaryCopyInWriter::new(&[Type::INT4, Type::TEXT]);
+    let copy = client.copy_in(&stmt, &[], stream);
+    let write = async move {
+        writer.write(&[&1i32, &"foobar"]).await?;
+        writer.write(&[&2i32, &None::<&str>]).await?;
+        writer.finish().await
+    };
+    let (rows, write) = join!(copy, write);
+    write.unwrap();
+    assert_eq!(rows.unwrap(), 2);
+
+    let stmt = client
+        .prepare("SELECT id, bar FROM foo ORDER BY id")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+    assert_eq!(rows[0].get::<_, Option<&str>>(1), Some("foobar"));
+    assert_eq!(rows[1].get::<_, i32>(0), 2);
+    assert_eq!(rows[1].get::<_, Option<&str>>(1), None);
+}
+
+#[tokio::test]
+async fn write_many_rows() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT, bar TEXT)")
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare("COPY foo (id, bar) FROM STDIN BINARY")
+        .await
+        .unwrap();
+    let (mut writer, stream) = BinaryCopyInWriter::new(&[Type::INT4, Type::TEXT]);
+    let copy = client.copy_in(&stmt, &[], stream);
+    let write = async move {
+        for i in 0..10_000i32 {
+            writer.write(&[&i, &format!("the value for {}", i)]).await?;
+        }
+        writer.finish().await
+    };
+    let (rows, write) = join!(copy, write);
+    write.unwrap();
+    assert_eq!(rows.unwrap(), 10_000);
+}
+
+#[tokio::test]
+async fn write_wrong_type() {
+    let (mut writer, _stream) = BinaryCopyInWriter::new(&[Type::INT4]);
+    writer.write(&[&"hello"]).await.unwrap_err();
+}
+
+#[tokio::test]
+async fn write_for_table() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT, bar TEXT)")
+        .await
+        .unwrap();
+
+    let (mut writer, stream) = BinaryCopyInWriter::for_table(&mut client, "foo", &["bar", "id"])
+        .await
+        .unwrap();
+    assert_eq!(writer.types(), &[Type::TEXT, Type::INT4]);
+
+    let err = writer.write(&[&"foobar", &"1"]).await.unwrap_err();
+    assert!(err.to_string().contains("column 1 `id`"), "{}", err);
+    writer.write(&[&"foobar"]).await.unwrap_err();
+
+    let stmt = client
+        .prepare("COPY foo (bar, id) FROM STDIN BINARY")
+        .await
+        .unwrap();
+    let copy = client.copy_in(&stmt, &[], stream);
+    let write = async move {
+        writer.write(&[&"foobar", &1i32]).await?;
+        writer.finish().await
+    };
+    let (rows, write) = join!(copy, write);
+    write.unwrap();
+    assert_eq!(rows.unwrap(), 1);
+
+    let (writer, _stream) = BinaryCopyInWriter::for_table(&mut client, "foo", &[])
+        .await
+        .unwrap();
+    assert_eq!(writer.types(), &[Type::INT4, Type::TEXT]);
+}
+
+#[tokio::test]
+async fn write_dropped() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT)")
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare("COPY foo (id) FROM STDIN BINARY")
+        .await
+        .unwrap();
+    let (mut writer, stream) = BinaryCopyInWriter::new(&[Type::INT4]);
+    let copy = client.copy_in(&stmt, &[], stream);
+    let write = async move {
+        writer.write(&[&1i32]).await.unwrap();
+    };
+    let (rows, ()) = join!(copy, write);
+    rows.unwrap_err();
+
+    let stmt = client.prepare("SELECT id FROM foo").await.unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert!(rows.is_empty());
+}
+
+#[tokio::test]
+async fn read_basic() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, bar TEXT);
+             INSERT INTO foo (id, bar) VALUES (1, 'foobar'), (2, NULL);",
+        )
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare("COPY foo (id, bar) TO STDOUT BINARY")
+        .await
+        .unwrap();
+    let stream = client.copy_out(&stmt, &[]);
+    let rows = BinaryCopyOutStream::new(&[Type::INT4, Type::TEXT], stream)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+
+    assert_eq!(rows[0].get::<i32>(0), 1);
+    assert_eq!(rows[0].get::<Option<&str>>(1), Some("foobar"));
+    assert_eq!(rows[1].get::<i32>(0), 2);
+    assert_eq!(rows[1].get::<Option<&str>>(1), None);
+    assert!(rows[0].try_get::<&str>(0).is_err());
+}
+
+#[tokio::test]
+async fn read_for_table() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, data BYTEA, bar TEXT);
+             INSERT INTO foo (id, data, bar) VALUES (1, '\\x0102', 'foobar'), (2, NULL, NULL);",
+        )
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare("COPY foo (id, data) TO STDOUT BINARY")
+        .await
+        .unwrap();
+    let stream = client.copy_out(&stmt, &[]);
+    let stream = BinaryCopyOutStream::for_table(&mut client, "foo", &["id", "data"], stream)
+        .await
+        .unwrap();
+    assert_eq!(stream.types(), &[Type::INT4, Type::BYTEA]);
+    let rows = stream.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(rows.len(), 2);
+
+    assert_eq!(rows[0].get::<i32>(0), 1);
+    assert_eq!(
+        rows[0].try_get_bytes(1).unwrap(),
+        Some(Bytes::from(&b"\x01\x02"[..]))
+    );
+    assert_eq!(rows[1].try_get_bytes(1).unwrap(), None);
+
+    let err = rows[0].try_get::<&str>(0).unwrap_err();
+    assert!(err.to_string().contains("`id`"), "{}", err);
+    assert!(rows[0].try_get_bytes(0).is_err());
+}
+
+#[tokio::test]
+async fn read_many_rows() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("COPY (SELECT i, 'the value for ' || i FROM generate_series(0, 9999) i) TO STDOUT BINARY")
+        .await
+        .unwrap();
+    let stream = client.copy_out(&stmt, &[]);
+    let rows = BinaryCopyOutStream::new(&[Type::INT4, Type::TEXT], stream)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 10_000);
+
+    for (i, row) in rows.iter().enumerate() {
+        assert_eq!(row.get::<i32>(0), i as i32);
+        assert_eq!(row.get::<&str>(1), format!("the value for {}", i));
+    }
+}
@@ -0,0 +1,142 @@
+use bytes::Bytes;
+use futures::{join, stream, TryStreamExt};
+use tokio_postgres::csv_copy::{CsvCopyInWriter, CsvCopyOutStream};
+use tokio_postgres::Error;
+
+use crate::connect;
+
+#[tokio::test]
+async fn write_basic() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT, bar TEXT)")
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare("COPY foo (id, bar) FROM STDIN (FORMAT csv)")
+        .await
+        .unwrap();
+    let (mut writer, stream) = CsvCopyInWriter::new();
+    let copy = client.copy_in(&stmt, &[], stream);
+    let write = async move {
+        writer.write(&[Some(&1), Some(&"foo, \"bar\"")]).await?;
+        writer.write(&[Some(&2), Some(&"line\nbreak")]).await?;
+        writer.write(&[Some(&3), Some(&"")]).await?;
+        writer.write(&[Some(&4), None]).await?;
+        writer.write(&[Some(&5), Some(&"\\.")]).await?;
+        writer.finish().await
+    };
+    let (rows, write) = join!(copy, write);
+    write.unwrap();
+    assert_eq!(rows.unwrap(), 5);
+
+    let stmt = client
+        .prepare("SELECT bar FROM foo ORDER BY id")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let values = rows
+        .iter()
+        .map(|r| r.get::<_, Option<&str>>(0))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        [
+            Some("foo, \"bar\""),
+            Some("line\nbreak"),
+            Some(""),
+            None,
+            Some("\\.")
+        ]
+    );
+}
+
+#[tokio::test]
+async fn write_dropped() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT)")
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare("COPY foo (id) FROM STDIN (FORMAT csv)")
+        .await
+        .unwrap();
+    let (mut writer, stream) = CsvCopyInWriter::new();
+    let copy = client.copy_in(&stmt, &[], stream);
+    let write = async move {
+        writer.write(&[Some(&1)]).await.unwrap();
+    };
+    let (rows, ()) = join!(copy, write);
+    assert!(rows.is_err());
+}
+
+#[tokio::test]
+async fn read_basic() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, bar TEXT);
+             INSERT INTO foo (id, bar) VALUES
+                (1, 'foo, \"bar\"'), (2, E'line\\nbreak'), (3, ''), (4, NULL);",
+        )
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare("COPY (SELECT id, bar FROM foo ORDER BY id) TO STDOUT (FORMAT csv)")
+        .await
+        .unwrap();
+    let stream = client.copy_out(&stmt, &[]);
+    let rows = CsvCopyOutStream::new(stream)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 4);
+
+    assert_eq!(rows[0].get_as::<i32>(0), Some(1));
+    assert_eq!(rows[0].get(1), Some("foo, \"bar\""));
+    assert_eq!(rows[1].get(1), Some("line\nbreak"));
+    assert_eq!(rows[2].get(1), Some(""));
+    assert_eq!(rows[3].get(1), None);
+    assert!(rows[0].try_get_as::<i32>(1).is_err());
+    assert!(rows[0].try_get(2).is_err());
+}
+
+#[tokio::test]
+async fn read_split_chunks() {
+    let chunks: Vec<Result<Bytes, Error>> = vec![
+        Ok(Bytes::from_static(b"1,\"a\"")),
+        Ok(Bytes::from_static(b"\"b\"\n2,\n3,\"")),
+        Ok(Bytes::from_static(b"\"\n4,\"c\r\nd\"")),
+    ];
+    let rows = CsvCopyOutStream::new(stream::iter(chunks))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 4);
+
+    assert_eq!(rows[0].get(1), Some("a\"b"));
+    assert_eq!(rows[1].get(1), None);
+    assert_eq!(rows[2].get(1), Some(""));
+    assert_eq!(rows[3].get_as::<i64>(0), Some(4));
+    assert_eq!(rows[3].get(1), Some("c\r\nd"));
+}
+
+#[tokio::test]
+async fn read_unterminated_quote() {
+    let chunks: Vec<Result<Bytes, Error>> = vec![Ok(Bytes::from_static(b"1,\"abc\n"))];
+    let r = CsvCopyOutStream::new(stream::iter(chunks))
+        .try_collect::<Vec<_>>()
+        .await;
+    assert!(r.is_err());
+}
@@ -3,18 +3,36 @@
 use futures::channel::mpsc;
 use futures::{future, stream, StreamExt};
 use futures::{join, try_join, FutureExt, TryStreamExt};
+use std::error;
 use std::fmt::Write;
+use std::future::Future;
+use std::io;
+use std::net::IpAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
 use tokio::net::TcpStream;
 use tokio::timer;
-use tokio_postgres::error::SqlState;
+use tokio_postgres::error::{DbError, ErrorPosition, SqlState};
+use tokio_postgres::row::Nth;
 use tokio_postgres::tls::{NoTls, NoTlsStream};
-use tokio_postgres::types::{Kind, Type};
-use tokio_postgres::{AsyncMessage, Client, Config, Connection, Error, SimpleQueryMessage};
-
+use tokio_postgres::types::{Kind, ToSql, Type};
+use tokio_postgres::{
+    AsyncMessage, BatchOutcome, Client, Config, Connection, CopyProgressStream, Error,
+    FetchDirection, FromRow, Interceptor, IsolationLevel, MakeSocket, OverflowPolicy, ParamScope,
+    Row, RowStream, SimpleQueryMessage, SocketTarget,
+};
+
+mod binary_copy;
+mod csv_copy;
 mod parse;
+mod replication;
 #[cfg(feature = "runtime")]
 mod runtime;
+mod text_copy;
 mod types;
 
 async fn connect_raw(s: &str) -> Result<(Client, Connection<TcpStream, NoTlsStream>), Error> {
@@ -30,6 +48,110 @@ async fn connect(s: &str) -> Client {
     client
 }
 
+#[tokio::test]
+async fn run_until() {
+    let (mut client, mut connection) = connect_raw("user=postgres").await.unwrap();
+
+    let stmt = connection
+        .run_until(client.prepare("SELECT $1::INT4"))
+        .await
+        .unwrap()
+        .unwrap();
+    let rows = connection
+        .run_until(client.query(&stmt, &[&1i32]).try_collect::<Vec<_>>())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+
+    drop(stmt);
+    drop(client);
+    let err = connection
+        .run_until(future::pending::<()>())
+        .await
+        .unwrap_err();
+    assert!(err.is_closed());
+}
+
+struct TestSocket;
+
+impl MakeSocket for TestSocket {
+    type Socket = TcpStream;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<TcpStream>> + Send>>;
+
+    fn make_socket(&mut self, target: SocketTarget<'_>) -> Self::Future {
+        let (host, port) = match target {
+            SocketTarget::Tcp {
+                host,
+                port,
+                hostaddr,
+            } => (hostaddr.map_or(host.to_string(), |a| a.to_string()), port),
+            _ => panic!("unexpected target"),
+        };
+        Box::pin(async move { TcpStream::connect((&*host, port)).await })
+    }
+}
+
+#[tokio::test]
+async fn connect_with() {
+    let config = "host=foobar.invalid,localhost port=5433 user=postgres"
+        .parse::<Config>()
+        .unwrap();
+    let (mut client, connection) = config.connect_with(TestSocket, NoTls).await.unwrap();
+    let connection = connection.map(|r| r.unwrap());
+    tokio::spawn(connection);
+
+    let rows = client
+        .simple_query("SELECT 1")
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    match &rows[0] {
+        SimpleQueryMessage::Row(row) => assert_eq!(row.get(0), Some("1")),
+        _ => panic!("unexpected message"),
+    }
+}
+
+// Records the unix socket paths it's asked to connect to, and fails to connect to them.
+#[cfg(unix)]
+struct UnixPathSocket(Arc<Mutex<Vec<PathBuf>>>);
+
+#[cfg(unix)]
+impl MakeSocket for UnixPathSocket {
+    type Socket = TcpStream;
+    type Error = io::Error;
+    type Future = future::Ready<io::Result<TcpStream>>;
+
+    fn make_socket(&mut self, target: SocketTarget<'_>) -> Self::Future {
+        match target {
+            SocketTarget::Unix { path } => self.0.lock().unwrap().push(path.to_path_buf()),
+            _ => panic!("unexpected target"),
+        }
+        future::err(io::Error::new(io::ErrorKind::NotFound, "no socket"))
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix_socket_path() {
+    let paths = Arc::new(Mutex::new(vec![]));
+    let config = "host=/var/run/postgresql port=5433 user=postgres"
+        .parse::<Config>()
+        .unwrap();
+    config
+        .connect_with(UnixPathSocket(paths.clone()), NoTls)
+        .await
+        .err()
+        .unwrap();
+
+    assert_eq!(
+        *paths.lock().unwrap(),
+        [PathBuf::from("/var/run/postgresql/.s.PGSQL.5433")]
+    );
+}
+
 #[tokio::test]
 async fn plain_password_missing() {
     connect_raw("user=pass_user dbname=postgres")
@@ -277,24 +399,135 @@ async fn simple_query() {
         _ => panic!("unexpected message"),
     }
     match &messages[2] {
+        SimpleQueryMessage::RowDescription(columns) => {
+            assert_eq!(columns.len(), 2);
+            assert_eq!(columns[0].name(), "id");
+            assert_eq!(columns[0].type_(), Some(&Type::INT4));
+            assert_eq!(columns[1].name(), "name");
+            assert_eq!(columns[1].type_(), Some(&Type::TEXT));
+        }
+        _ => panic!("unexpected message"),
+    }
+    match &messages[3] {
         SimpleQueryMessage::Row(row) => {
+            assert_eq!(row.columns()[0].name(), "id");
             assert_eq!(row.get(0), Some("1"));
             assert_eq!(row.get(1), Some("steven"));
         }
         _ => panic!("unexpected message"),
     }
-    match &messages[3] {
+    match &messages[4] {
         SimpleQueryMessage::Row(row) => {
             assert_eq!(row.get(0), Some("2"));
             assert_eq!(row.get(1), Some("joe"));
+            assert_eq!(row.try_get_bytes(1).unwrap(), Some(&b"joe"[..]));
         }
         _ => panic!("unexpected message"),
     }
-    match messages[4] {
+    match messages[5] {
         SimpleQueryMessage::CommandComplete(2) => {}
         _ => panic!("unexpected message"),
     }
-    assert_eq!(messages.len(), 5);
+    assert_eq!(messages.len(), 6);
+}
+
+#[tokio::test]
+async fn simple_query_no_rows() {
+    let mut client = connect("user=postgres").await;
+
+    let messages = client
+        .simple_query("SELECT 1::INT8 AS a, 'foo'::VARCHAR AS b WHERE false")
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    match &messages[0] {
+        SimpleQueryMessage::RowDescription(columns) => {
+            assert_eq!(columns[0].name(), "a");
+            assert_eq!(columns[0].type_oid(), Type::INT8.oid());
+            assert_eq!(columns[1].name(), "b");
+            assert_eq!(columns[1].type_(), Some(&Type::VARCHAR));
+        }
+        _ => panic!("unexpected message"),
+    }
+    match messages[1] {
+        SimpleQueryMessage::CommandComplete(0) => {}
+        _ => panic!("unexpected message"),
+    }
+    assert_eq!(messages.len(), 2);
+}
+
+#[tokio::test]
+async fn simple_query_parse_text() {
+    let mut client = connect("user=postgres").await;
+
+    let messages = client
+        .simple_query(
+            "SELECT 1::INT4, true, 'NaN'::FLOAT8, '\\x0102'::BYTEA, '10.0.0.1'::INET, NULL::INT8, 'foo'",
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    match &messages[1] {
+        SimpleQueryMessage::Row(row) => {
+            assert_eq!(row.get_as::<_, i32>(0), Some(1));
+            assert_eq!(row.get_as::<_, bool>(1), Some(true));
+            assert!(row.get_as::<_, f64>(2).unwrap().is_nan());
+            assert_eq!(row.get_as::<_, Vec<u8>>(3), Some(vec![1, 2]));
+            assert_eq!(
+                row.get_as::<_, IpAddr>(4),
+                Some("10.0.0.1".parse().unwrap())
+            );
+            assert_eq!(row.get_as::<_, i64>(5), None);
+            assert!(row.try_get_as::<_, i32>(6).is_err());
+        }
+        _ => panic!("unexpected message"),
+    }
+}
+
+#[tokio::test]
+async fn batch_execute_results() {
+    let mut client = connect("user=postgres").await;
+
+    let script = "CREATE TEMPORARY TABLE foo (id INT);
+                  INSERT INTO foo (id) VALUES (1), (2);
+                  SELECT bogus FROM foo;
+                  INSERT INTO foo (id) VALUES (3);";
+    let outcomes = client.batch_execute_results(script).await.unwrap();
+    assert_eq!(outcomes.len(), 3);
+
+    match &outcomes[0] {
+        BatchOutcome::Complete(tag) => {
+            assert_eq!(tag.tag(), "CREATE TABLE");
+            assert_eq!(tag.rows(), 0);
+        }
+        o => panic!("unexpected outcome: {:?}", o),
+    }
+    match &outcomes[1] {
+        BatchOutcome::Complete(tag) => {
+            assert_eq!(tag.command(), "INSERT");
+            assert_eq!(tag.rows(), 2);
+        }
+        o => panic!("unexpected outcome: {:?}", o),
+    }
+    match &outcomes[2] {
+        BatchOutcome::Failed(e) => {
+            assert_eq!(e.code(), Some(&SqlState::UNDEFINED_COLUMN));
+            let position = error::Error::source(e)
+                .and_then(|e| e.downcast_ref::<DbError>())
+                .and_then(DbError::position);
+            let expected = script.find("bogus").unwrap() as u32 + 1;
+            assert_eq!(position, Some(&ErrorPosition::Original(expected)));
+        }
+        o => panic!("unexpected outcome: {:?}", o),
+    }
+
+    let outcomes = client.batch_execute_results("").await.unwrap();
+    match &outcomes[..] {
+        [BatchOutcome::Complete(tag)] => assert_eq!(tag.tag(), ""),
+        o => panic!("unexpected outcomes: {:?}", o),
+    }
 }
 
 #[tokio::test]
@@ -313,6 +546,25 @@ async fn cancel_query_raw() {
     }
 }
 
+#[tokio::test]
+async fn cancel_token_raw() {
+    let mut client = connect("user=postgres").await;
+
+    let token = client.cancel_token();
+    let cancel = async move {
+        timer::delay(Instant::now() + Duration::from_millis(100)).await;
+        let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+        token.cancel_query_raw(socket, NoTls).await
+    };
+
+    let sleep = client.batch_execute("SELECT pg_sleep(100)");
+
+    match join!(sleep, cancel) {
+        (Err(ref e), Ok(())) if e.code() == Some(&SqlState::QUERY_CANCELED) => {}
+        t => panic!("unexpected return: {:?}", t),
+    }
+}
+
 #[tokio::test]
 async fn transaction_commit() {
     let mut client = connect("user=postgres").await;
@@ -345,6 +597,181 @@ async fn transaction_commit() {
     assert_eq!(rows[0].get::<_, &str>(0), "steven");
 }
 
+#[tokio::test]
+async fn transaction_builder() {
+    let mut client = connect("user=postgres").await;
+
+    let mut transaction = client
+        .build_transaction()
+        .isolation_level(IsolationLevel::Serializable)
+        .read_only(true)
+        .deferrable(true)
+        .start()
+        .await
+        .unwrap();
+
+    let stmt = transaction
+        .prepare(
+            "SELECT current_setting('transaction_isolation'), \
+             current_setting('transaction_read_only'), \
+             current_setting('transaction_deferrable')",
+        )
+        .await
+        .unwrap();
+    let rows = transaction
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(rows[0].get::<_, &str>(0), "serializable");
+    assert_eq!(rows[0].get::<_, &str>(1), "on");
+    assert_eq!(rows[0].get::<_, &str>(2), "on");
+
+    let err = transaction
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT)")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::READ_ONLY_SQL_TRANSACTION));
+}
+
+#[tokio::test]
+async fn cursor() {
+    let mut client = connect("user=postgres").await;
+
+    let mut transaction = client.transaction().await.unwrap();
+    let mut cursor = transaction
+        .cursor()
+        .scroll(true)
+        .declare("SELECT generate_series(1, $1)", &[&5i32])
+        .await
+        .unwrap();
+
+    let rows = cursor.fetch(2).await.unwrap();
+    let values = rows.iter().map(|r| r.get(0)).collect::<Vec<i32>>();
+    assert_eq!(values, [1, 2]);
+
+    let rows = cursor
+        .fetch_direction(FetchDirection::Backward(1))
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+
+    let rows = cursor.fetch_all().await.unwrap();
+    let values = rows.iter().map(|r| r.get(0)).collect::<Vec<i32>>();
+    assert_eq!(values, [2, 3, 4, 5]);
+
+    assert!(cursor.fetch(1).await.unwrap().is_empty());
+    cursor.close().await.unwrap();
+
+    transaction.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn cursor_hold() {
+    let mut client = connect("user=postgres").await;
+
+    let mut transaction = client.transaction().await.unwrap();
+    transaction
+        .batch_execute(
+            "
+            CREATE TEMPORARY TABLE foo (id INT) ON COMMIT DROP;
+            INSERT INTO foo (id) VALUES (1), (2), (3);
+            ",
+        )
+        .await
+        .unwrap();
+    let cursor = transaction
+        .cursor()
+        .hold(true)
+        .declare("SELECT id FROM foo ORDER BY id", &[])
+        .await
+        .unwrap();
+    let name = cursor.name().to_string();
+    // a dropped cursor is closed
+    drop(cursor);
+    let err = transaction
+        .batch_execute(&format!("FETCH 1 FROM {}", name))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::INVALID_CURSOR_NAME));
+    drop(transaction);
+
+    let mut cursor = client
+        .cursor()
+        .hold(true)
+        .declare("SELECT generate_series(1, 3)", &[])
+        .await
+        .unwrap();
+    let rows = cursor.fetch(2).await.unwrap();
+    let values = rows.iter().map(|r| r.get(0)).collect::<Vec<i32>>();
+    assert_eq!(values, [1, 2]);
+    let rows = cursor.fetch(2).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    cursor.close().await.unwrap();
+
+    let err = client
+        .cursor()
+        .declare("SELECT 1", &[])
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), Some(&SqlState::NO_ACTIVE_SQL_TRANSACTION));
+}
+
+#[tokio::test]
+async fn savepoint() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT PRIMARY KEY)")
+        .await
+        .unwrap();
+
+    let mut transaction = client.transaction().await.unwrap();
+    transaction
+        .batch_execute("INSERT INTO foo (id) VALUES (1)")
+        .await
+        .unwrap();
+
+    let mut savepoint = transaction.savepoint("before_dup").await.unwrap();
+    let err = savepoint
+        .batch_execute("INSERT INTO foo (id) VALUES (1)")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::UNIQUE_VIOLATION));
+    drop(savepoint);
+
+    let mut savepoint = transaction.savepoint("Second \"sp\"; x").await.unwrap();
+    savepoint
+        .batch_execute("INSERT INTO foo (id) VALUES (2)")
+        .await
+        .unwrap();
+    let mut nested = savepoint.transaction().await.unwrap();
+    nested
+        .batch_execute("INSERT INTO foo (id) VALUES (3)")
+        .await
+        .unwrap();
+    nested.commit().await.unwrap();
+    savepoint.commit().await.unwrap();
+    transaction.commit().await.unwrap();
+
+    let stmt = client
+        .prepare("SELECT id FROM foo ORDER BY id")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+    assert_eq!(rows[1].get::<_, i32>(0), 2);
+    assert_eq!(rows[2].get::<_, i32>(0), 3);
+}
+
 #[tokio::test]
 async fn transaction_rollback() {
     let mut client = connect("user=postgres").await;
@@ -531,61 +958,416 @@ async fn copy_out() {
 }
 
 #[tokio::test]
-async fn notifications() {
-    let (mut client, mut connection) = connect_raw("user=postgres").await.unwrap();
-
-    let (tx, rx) = mpsc::unbounded();
-    let stream = stream::poll_fn(move |cx| connection.poll_message(cx)).map_err(|e| panic!(e));
-    let connection = stream.forward(tx).map(|r| r.unwrap());
-    tokio::spawn(connection);
+async fn copy_progress() {
+    let mut client = connect("user=postgres").await;
 
     client
-        .batch_execute(
-            "LISTEN test_notifications;
-             NOTIFY test_notifications, 'hello';
-             NOTIFY test_notifications, 'world';",
-        )
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INTEGER, name TEXT)")
         .await
         .unwrap();
 
-    drop(client);
+    let reports = Arc::new(Mutex::new(vec![]));
+    let stmt = client.prepare("COPY foo FROM STDIN").await.unwrap();
+    let stream = stream::iter(
+        vec![b"1\tjim\n".to_vec(), b"2\tjoe\n".to_vec()]
+            .into_iter()
+            .map(Ok::<_, String>),
+    );
+    let stream = CopyProgressStream::new(stream).on_progress({
+        let reports = reports.clone();
+        move |progress| reports.lock().unwrap().push(progress)
+    });
+    let rows = client.copy_in(&stmt, &[], stream).await.unwrap();
+    assert_eq!(rows, 2);
 
-    let notifications = rx
-        .filter_map(|m| match m {
-            AsyncMessage::Notification(n) => future::ready(Some(n)),
-            _ => future::ready(None),
-        })
-        .collect::<Vec<_>>()
-        .await;
-    assert_eq!(notifications.len(), 2);
-    assert_eq!(notifications[0].channel(), "test_notifications");
-    assert_eq!(notifications[0].payload(), "hello");
-    assert_eq!(notifications[1].channel(), "test_notifications");
-    assert_eq!(notifications[1].payload(), "world");
+    let reports = reports.lock().unwrap().clone();
+    assert_eq!(reports.len(), 2);
+    assert_eq!((reports[0].bytes(), reports[0].chunks()), (6, 1));
+    assert_eq!((reports[1].bytes(), reports[1].chunks()), (12, 2));
+
+    let stmt = client.prepare("COPY foo TO STDOUT").await.unwrap();
+    let mut stream = CopyProgressStream::new(client.copy_out(&stmt, &[]));
+    let mut data = vec![];
+    while let Some(chunk) = stream.try_next().await.unwrap() {
+        data.extend_from_slice(&chunk);
+    }
+    assert_eq!(&data[..], b"1\tjim\n2\tjoe\n");
+    assert_eq!(stream.progress().bytes(), 12);
+    assert_eq!(stream.progress().chunks(), 2);
 }
 
 #[tokio::test]
-async fn query_portal() {
+async fn copy_progress_throttle() {
     let mut client = connect("user=postgres").await;
 
     client
-        .batch_execute(
-            "CREATE TEMPORARY TABLE foo (
-                id SERIAL,
-                name TEXT
-            );
-
-            INSERT INTO foo (name) VALUES ('alice'), ('bob'), ('charlie');",
-        )
-        .await
-        .unwrap();
-
-    let stmt = client
-        .prepare("SELECT id, name FROM foo ORDER BY id")
+        .batch_execute("CREATE TEMPORARY TABLE foo (name TEXT)")
         .await
         .unwrap();
 
-    let mut transaction = client.transaction().await.unwrap();
+    let stmt = client.prepare("COPY foo FROM STDIN").await.unwrap();
+    let line = format!("{}\n", "a".repeat(99)).into_bytes();
+    let stream = stream::iter(vec![Ok::<_, String>(line); 3]);
+    let stream = CopyProgressStream::new(stream).throttle(1000);
+
+    let start = Instant::now();
+    let rows = client.copy_in(&stmt, &[], stream).await.unwrap();
+    assert_eq!(rows, 3);
+    // the second and third chunks are delayed until 100 and 200 milliseconds after the first
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
+
+#[tokio::test]
+async fn copy_progress_throttle_end() {
+    let stream = stream::iter(vec![Ok::<_, String>(vec![0; 1000])]);
+    let stream = CopyProgressStream::new(stream).throttle(1000);
+
+    let start = Instant::now();
+    let chunks = stream.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(chunks.len(), 1);
+    // the chunk would delay another one by a second, but not the end of the stream
+    assert!(start.elapsed() < Duration::from_millis(500));
+}
+
+#[tokio::test]
+async fn copy_out_reader() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (
+            id SERIAL,
+            name TEXT
+        );
+
+        INSERT INTO foo (name) VALUES ('jim'), ('joe');",
+        )
+        .await
+        .unwrap();
+
+    let stmt = client.prepare("COPY foo TO STDOUT").await.unwrap();
+    let mut reader = client.copy_out_reader(&stmt, &[]);
+
+    let mut buf = [0; 3];
+    reader.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"1\tj");
+
+    let mut data = vec![];
+    reader.read_to_end(&mut data).await.unwrap();
+    assert_eq!(&data[..], b"im\n2\tjoe\n");
+}
+
+#[tokio::test]
+async fn notifications() {
+    let (mut client, mut connection) = connect_raw("user=postgres").await.unwrap();
+
+    let (tx, rx) = mpsc::unbounded();
+    let stream = stream::poll_fn(move |cx| connection.poll_message(cx)).map_err(|e| panic!(e));
+    let connection = stream.forward(tx).map(|r| r.unwrap());
+    tokio::spawn(connection);
+
+    client
+        .batch_execute(
+            "LISTEN test_notifications;
+             NOTIFY test_notifications, 'hello';
+             NOTIFY test_notifications, 'world';",
+        )
+        .await
+        .unwrap();
+
+    drop(client);
+
+    let notifications = rx
+        .filter_map(|m| match m {
+            AsyncMessage::Notification(n) => future::ready(Some(n)),
+            _ => future::ready(None),
+        })
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(notifications.len(), 2);
+    assert_eq!(notifications[0].channel(), "test_notifications");
+    assert_eq!(notifications[0].payload(), "hello");
+    assert_eq!(notifications[1].channel(), "test_notifications");
+    assert_eq!(notifications[1].payload(), "world");
+}
+
+async fn current_setting(client: &mut Client, name: &str) -> String {
+    let rows = client
+        .query_typed("SELECT current_setting($1)", &[(&name, Type::TEXT)])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    rows[0].get(0)
+}
+
+#[tokio::test]
+async fn application_name() {
+    let mut client = connect("user=postgres application_name=app").await;
+    assert_eq!(
+        current_setting(&mut client, "application_name").await,
+        "app"
+    );
+
+    let mut client = connect("user=postgres fallback_application_name=tool").await;
+    assert_eq!(
+        current_setting(&mut client, "application_name").await,
+        "tool"
+    );
+
+    let mut client =
+        connect("user=postgres application_name=app fallback_application_name=tool").await;
+    assert_eq!(
+        current_setting(&mut client, "application_name").await,
+        "app"
+    );
+}
+
+#[tokio::test]
+async fn options() {
+    let mut client =
+        connect("user=postgres options='-c search_path=foo -c statement_timeout=5s'").await;
+    assert_eq!(current_setting(&mut client, "search_path").await, "foo");
+    assert_eq!(
+        current_setting(&mut client, "statement_timeout").await,
+        "5s"
+    );
+
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let (mut client, connection) = Config::new()
+        .user("postgres")
+        .runtime_param("application_name", r"my \ app")
+        .connect_raw(socket, NoTls)
+        .await
+        .unwrap();
+    let connection = connection.map(|r| r.unwrap());
+    tokio::spawn(connection);
+    assert_eq!(
+        current_setting(&mut client, "application_name").await,
+        r"my \ app"
+    );
+}
+
+#[tokio::test]
+async fn set_param() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .set_param("search_path", "foo, bar", ParamScope::Session)
+        .await
+        .unwrap();
+    assert_eq!(
+        current_setting(&mut client, "search_path").await,
+        "foo, bar"
+    );
+
+    let mut transaction = client.transaction().await.unwrap();
+    transaction
+        .set_param("application_name", "local", ParamScope::Local)
+        .await
+        .unwrap();
+    transaction.rollback().await.unwrap();
+    assert_eq!(current_setting(&mut client, "application_name").await, "");
+
+    client.reset_param("search_path").await.unwrap();
+    assert_eq!(
+        current_setting(&mut client, "search_path").await,
+        "\"$user\", public"
+    );
+
+    let err = client
+        .reset_param("search_path; DROP TABLE foo")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::UNDEFINED_OBJECT));
+
+    client
+        .set_param("application_name", "session", ParamScope::Session)
+        .await
+        .unwrap();
+    client.reset_all_params().await.unwrap();
+    assert_eq!(current_setting(&mut client, "application_name").await, "");
+}
+
+#[tokio::test]
+async fn ping() {
+    let (mut client, connection) = connect_raw("user=postgres").await.unwrap();
+    tokio::spawn(connection.map(|_| ()));
+
+    client.ping().await.unwrap();
+    assert!(!client.is_closed());
+
+    let mut other = connect("user=postgres").await;
+    other
+        .batch_execute(&format!(
+            "SELECT pg_terminate_backend({})",
+            client.backend_pid()
+        ))
+        .await
+        .unwrap();
+
+    // the backend exits asynchronously
+    for _ in 0..50 {
+        if client.ping().await.is_err() {
+            return;
+        }
+        timer::delay(Instant::now() + Duration::from_millis(100)).await;
+    }
+    panic!("ping succeeded after the backend was terminated");
+}
+
+#[tokio::test]
+async fn backend_pid() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client.prepare("SELECT pg_backend_pid()").await.unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(rows[0].get::<_, i32>(0), client.backend_pid());
+}
+
+#[tokio::test]
+async fn parameters() {
+    let mut client = connect("user=postgres").await;
+
+    assert!(client.parameter("server_version").is_some());
+    assert_eq!(
+        client.parameter("TimeZone").as_ref().map(|s| &**s),
+        Some("GMT")
+    );
+    assert_eq!(client.parameter("bogus"), None);
+
+    let mut changes = Box::pin(client.parameter_changes());
+    client
+        .batch_execute("SET TimeZone = 'America/New_York'")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        client.parameter("TimeZone").as_ref().map(|s| &**s),
+        Some("America/New_York")
+    );
+    let change = changes.next().await.unwrap();
+    assert_eq!(change.name(), "TimeZone");
+    assert_eq!(change.value(), "America/New_York");
+
+    drop(client);
+    assert!(changes.next().await.is_none());
+}
+
+#[tokio::test]
+async fn notice_callback() {
+    let notices = Arc::new(Mutex::new(vec![]));
+
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config.notice_callback({
+        let notices = notices.clone();
+        move |notice| notices.lock().unwrap().push(notice)
+    });
+    let (mut client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    client
+        .batch_execute(
+            "DO $$
+             BEGIN
+                RAISE NOTICE 'hello';
+                RAISE WARNING 'world';
+             END
+             $$",
+        )
+        .await
+        .unwrap();
+
+    let notices = notices.lock().unwrap();
+    assert_eq!(notices.len(), 2);
+    assert_eq!(notices[0].severity(), "NOTICE");
+    assert_eq!(notices[0].message(), "hello");
+    assert_eq!(notices[1].severity(), "WARNING");
+    assert_eq!(notices[1].message(), "world");
+}
+
+#[tokio::test]
+async fn notification_buffer() {
+    let (mut client, mut connection) = connect_raw("user=postgres").await.unwrap();
+    let notifications = connection.notifications(2, OverflowPolicy::DropOldest);
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    client
+        .batch_execute(
+            "LISTEN test_notifications;
+             NOTIFY test_notifications, 'one';
+             NOTIFY test_notifications, 'two';
+             NOTIFY test_notifications, 'three';",
+        )
+        .await
+        .unwrap();
+
+    drop(client);
+
+    let notifications = notifications.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(notifications.len(), 2);
+    assert_eq!(notifications[0].payload(), "two");
+    assert_eq!(notifications[1].payload(), "three");
+}
+
+#[tokio::test]
+async fn notification_buffer_overflow_error() {
+    let (mut client, mut connection) = connect_raw("user=postgres").await.unwrap();
+    let mut notifications = connection.notifications(1, OverflowPolicy::Error);
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    client
+        .batch_execute(
+            "LISTEN test_notifications;
+             NOTIFY test_notifications, 'one';
+             NOTIFY test_notifications, 'two';",
+        )
+        .await
+        .unwrap();
+
+    let first = notifications.next().await.unwrap().unwrap();
+    assert_eq!(first.payload(), "one");
+
+    // there's room in the buffer again, but the overflow has to be reported first
+    client
+        .batch_execute("NOTIFY test_notifications, 'three'")
+        .await
+        .unwrap();
+
+    drop(client);
+
+    let messages = notifications.collect::<Vec<_>>().await;
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].is_err());
+}
+
+#[tokio::test]
+async fn query_portal() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (
+                id SERIAL,
+                name TEXT
+            );
+
+            INSERT INTO foo (name) VALUES ('alice'), ('bob'), ('charlie');",
+        )
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare("SELECT id, name FROM foo ORDER BY id")
+        .await
+        .unwrap();
+
+    let mut transaction = client.transaction().await.unwrap();
 
     let portal = transaction.bind(&stmt, &[]).await.unwrap();
     let f1 = transaction.query_portal(&portal, 2).try_collect::<Vec<_>>();
@@ -607,6 +1389,40 @@ async fn query_portal() {
     assert_eq!(r3.len(), 0);
 }
 
+#[tokio::test]
+async fn query_portal_chunks() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT generate_series(1, 5)")
+        .await
+        .unwrap();
+
+    let mut transaction = client.transaction().await.unwrap();
+
+    let portal = transaction.bind(&stmt, &[]).await.unwrap();
+    let chunks = transaction
+        .query_portal_chunks(&portal, 2)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let chunks = chunks
+        .iter()
+        .map(|rows| rows.iter().map(|r| r.get(0)).collect::<Vec<i32>>())
+        .collect::<Vec<_>>();
+    assert_eq!(chunks, vec![vec![1, 2], vec![3, 4], vec![5]]);
+
+    let portal = transaction.bind(&stmt, &[]).await.unwrap();
+    let chunks = transaction
+        .query_portal_chunks(&portal, 5)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].len(), 5);
+}
+
 #[tokio::test]
 async fn require_channel_binding() {
     connect_raw("user=postgres channel_binding=require")
@@ -624,3 +1440,670 @@ async fn prefer_channel_binding() {
 async fn disable_channel_binding() {
     connect("user=postgres channel_binding=disable").await;
 }
+
+#[tokio::test]
+async fn query_as() {
+    #[derive(Debug, PartialEq)]
+    struct Person {
+        id: i32,
+        name: String,
+    }
+
+    impl FromRow for Person {
+        fn from_row(row: &Row) -> Result<Person, Error> {
+            Ok(Person {
+                id: row.try_get("id")?,
+                name: row.try_get("name")?,
+            })
+        }
+    }
+
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "
+            CREATE TEMPORARY TABLE foo (id SERIAL, name TEXT);
+            INSERT INTO foo (name) VALUES ('alice'), ('bob');
+            ",
+        )
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare("SELECT id, name FROM foo ORDER BY id")
+        .await
+        .unwrap();
+    let people = client
+        .query_as::<Person>(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        people,
+        vec![
+            Person {
+                id: 1,
+                name: "alice".to_string(),
+            },
+            Person {
+                id: 2,
+                name: "bob".to_string(),
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn query_scalar() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT count(*) FROM generate_series(1, $1)")
+        .await
+        .unwrap();
+    let count = client.query_scalar::<i64>(&stmt, &[&3i32]).await.unwrap();
+    assert_eq!(count, 3);
+
+    let mut transaction = client.transaction().await.unwrap();
+    let stmt = transaction.prepare("SELECT NULL::TEXT").await.unwrap();
+    let value = transaction
+        .query_scalar::<Option<String>>(&stmt, &[])
+        .await
+        .unwrap();
+    assert_eq!(value, None);
+    drop(transaction);
+
+    let stmt = client
+        .prepare("SELECT generate_series(1, $1)")
+        .await
+        .unwrap();
+    let err = client
+        .query_scalar::<i32>(&stmt, &[&0i32])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "query returned an unexpected number of rows"
+    );
+    client
+        .query_scalar::<i32>(&stmt, &[&2i32])
+        .await
+        .unwrap_err();
+
+    let stmt = client.prepare("SELECT 1, 2").await.unwrap();
+    let err = client.query_scalar::<i32>(&stmt, &[]).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "query returned an unexpected number of columns"
+    );
+
+    // the connection is still usable after the row count error
+    let stmt = client.prepare("SELECT 1").await.unwrap();
+    assert_eq!(client.query_scalar::<i32>(&stmt, &[]).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn execute_returning() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id SERIAL, name TEXT)")
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare("INSERT INTO foo (name) VALUES ($1), ($2) RETURNING id")
+        .await
+        .unwrap();
+    let (count, rows) = client
+        .execute_returning(&stmt, &[&"alice", &"bob"])
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+    let ids = rows.iter().map(|r| r.get(0)).collect::<Vec<i32>>();
+    assert_eq!(ids, [1, 2]);
+
+    let stmt = client
+        .prepare("UPDATE foo SET name = 'carol' WHERE id = 3 RETURNING id")
+        .await
+        .unwrap();
+    let (count, rows) = client.execute_returning(&stmt, &[]).await.unwrap();
+    assert_eq!(count, 0);
+    assert!(rows.is_empty());
+
+    let stmt = client.prepare("DELETE FROM foo").await.unwrap();
+    let mut stream = client.query(&stmt, &[]);
+    assert_eq!(stream.rows_affected(), None);
+    assert!(stream.try_next().await.unwrap().is_none());
+    assert_eq!(stream.rows_affected(), Some(2));
+}
+
+#[tokio::test]
+async fn query_typed() {
+    let mut client = connect("user=postgres").await;
+
+    let rows = client
+        .query_typed(
+            "SELECT $1::TEXT || 'bar', $2 + 1",
+            &[(&"foo", Type::TEXT), (&41i32, Type::INT4)],
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, &str>(0), "foobar");
+    assert_eq!(rows[0].get::<_, i32>(1), 42);
+    assert_eq!(rows[0].columns()[1].type_(), &Type::INT4);
+}
+
+#[tokio::test]
+async fn query_typed_custom_type() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TYPE pg_temp.mood AS ENUM ('sad', 'ok', 'happy')")
+        .await
+        .unwrap();
+
+    let rows = client
+        .query_typed(
+            "SELECT 'happy'::mood FROM generate_series(1, $1)",
+            &[(&1000i32, Type::INT4)],
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(rows.len(), 1000);
+    assert_eq!(rows[0].columns()[0].type_().name(), "mood");
+}
+
+#[tokio::test]
+async fn query_typed_no_rows() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT4)")
+        .await
+        .unwrap();
+
+    let rows = client
+        .query_typed("INSERT INTO foo (id) VALUES ($1)", &[(&1i32, Type::INT4)])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert!(rows.is_empty());
+
+    let err = client
+        .query_typed("SELECT $1::TEXT", &[(&1i32, Type::TEXT)])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("error serializing parameter 0"));
+}
+
+async fn prepared_count(client: &mut Client, query: &str) -> i64 {
+    let stmt = client
+        .prepare("SELECT count(*) FROM pg_prepared_statements WHERE statement = $1")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[&query])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    rows[0].get(0)
+}
+
+#[tokio::test]
+async fn statement_cache() {
+    let mut client = connect("user=postgres").await;
+
+    let _a = client.prepare_cached("SELECT 1::INT4").await.unwrap();
+    let _b = client.prepare_cached("SELECT 1::INT4").await.unwrap();
+    assert_eq!(prepared_count(&mut client, "SELECT 1::INT4").await, 2);
+
+    client.set_statement_cache_capacity(1);
+    assert_eq!(client.statement_cache_capacity(), 1);
+
+    let _c = client.prepare_cached("SELECT 2::INT4").await.unwrap();
+    let _d = client.prepare_cached("SELECT 2::INT4").await.unwrap();
+    assert_eq!(prepared_count(&mut client, "SELECT 2::INT4").await, 1);
+
+    // evicts the first statement, which is closed once all of its handles are dropped
+    let _e = client.prepare_cached("SELECT 3::INT4").await.unwrap();
+    drop(_c);
+    drop(_d);
+    assert_eq!(prepared_count(&mut client, "SELECT 2::INT4").await, 0);
+
+    client.clear_statement_cache();
+    let _f = client.prepare_cached("SELECT 3::INT4").await.unwrap();
+    assert_eq!(prepared_count(&mut client, "SELECT 3::INT4").await, 2);
+}
+
+#[tokio::test]
+async fn warm_up_statements() {
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config
+        .warm_up_statement("SELECT 1::INT4")
+        .warm_up_statement("SELECT 2::INT4");
+    let (mut client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    assert_eq!(client.statement_cache_capacity(), 2);
+    assert_eq!(prepared_count(&mut client, "SELECT 1::INT4").await, 1);
+    assert_eq!(prepared_count(&mut client, "SELECT 2::INT4").await, 1);
+
+    let _a = client.prepare_cached("SELECT 1::INT4").await.unwrap();
+    assert_eq!(prepared_count(&mut client, "SELECT 1::INT4").await, 1);
+
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config.warm_up_statement("SELECT * FROM nonexistent_table");
+    config.connect_raw(socket, NoTls).await.err().unwrap();
+}
+
+#[tokio::test]
+async fn statement_cache_invalidation() {
+    let mut client = connect("user=postgres").await;
+    client.set_statement_cache_capacity(10);
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT4)")
+        .await
+        .unwrap();
+
+    let stmt = client.prepare_cached("SELECT * FROM foo").await.unwrap();
+    client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    client
+        .batch_execute("ALTER TABLE foo ADD COLUMN name TEXT")
+        .await
+        .unwrap();
+
+    client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap_err();
+
+    let stmt = client.prepare_cached("SELECT * FROM foo").await.unwrap();
+    assert_eq!(stmt.columns().len(), 2);
+    client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn column_lookup() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT 1::INT4 AS id, 2::INT4 AS id, 3::INT4 AS \"Id\", 4::INT4 AS \"Name\"")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let row = &rows[0];
+
+    assert_eq!(row.get::<_, i32>("id"), 1);
+    assert_eq!(row.get::<_, i32>(Nth("id", 0)), 1);
+    assert_eq!(row.get::<_, i32>(Nth("id", 1)), 2);
+    assert!(row.try_get::<_, i32>(Nth("id", 2)).is_err());
+    assert_eq!(row.get::<_, i32>(Nth("name", 0)), 4);
+
+    assert_eq!(row.get::<_, i32>("Id"), 3);
+    assert_eq!(row.try_get_ci::<i32>("Id").unwrap(), 1);
+    assert_eq!(row.try_get_ci::<i32>("NAME").unwrap(), 4);
+    assert!(row.try_get_ci::<i32>("missing").is_err());
+}
+
+#[tokio::test]
+async fn try_get_error_context() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT 1::INT4 AS foo, NULL::TEXT AS bar")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let err = rows[0].try_get::<_, i64>("foo").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "error deserializing column 0 `foo`: cannot convert between the Rust type `i64` and the Postgres type `int4`",
+    );
+
+    let err = rows[0].try_get::<_, &str>("bar").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "error deserializing column 1 `bar` of Postgres type `text` as Rust type `&str`: a Postgres value was `NULL`",
+    );
+}
+
+#[tokio::test]
+async fn statement_metadata() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT4, name TEXT)")
+        .await
+        .unwrap();
+
+    let stmt = client.prepare("SELECT 'foo'::regclass::oid").await.unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let table_oid = rows[0].get::<_, u32>(0);
+
+    let stmt = client
+        .prepare("SELECT name, id, $1::TEXT AS bar FROM foo")
+        .await
+        .unwrap();
+    assert_eq!(stmt.params(), &[Type::TEXT]);
+
+    let columns = stmt.columns();
+    assert_eq!(columns.len(), 3);
+    assert_eq!(columns[0].name(), "name");
+    assert_eq!(columns[0].type_(), &Type::TEXT);
+    assert_eq!(columns[0].table_oid(), Some(table_oid));
+    assert_eq!(columns[0].column_id(), Some(2));
+    assert_eq!(columns[1].name(), "id");
+    assert_eq!(columns[1].type_(), &Type::INT4);
+    assert_eq!(columns[1].table_oid(), Some(table_oid));
+    assert_eq!(columns[1].column_id(), Some(1));
+    assert_eq!(columns[2].name(), "bar");
+    assert_eq!(columns[2].table_oid(), None);
+    assert_eq!(columns[2].column_id(), None);
+}
+
+#[tokio::test]
+async fn statement_close() {
+    let mut client = connect("user=postgres").await;
+    client.set_statement_cache_capacity(10);
+
+    let stmt = client.prepare_cached("SELECT 1::INT4").await.unwrap();
+    let other = stmt.clone();
+    assert_eq!(prepared_count(&mut client, "SELECT 1::INT4").await, 1);
+
+    stmt.close().await.unwrap();
+    assert_eq!(prepared_count(&mut client, "SELECT 1::INT4").await, 0);
+
+    let err = client
+        .query(&other, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::INVALID_SQL_STATEMENT_NAME));
+
+    // closing again is a no-op
+    other.close().await.unwrap();
+    drop(stmt);
+    drop(other);
+
+    let stmt = client.prepare_cached("SELECT 1::INT4").await.unwrap();
+    client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn pipeline() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id SERIAL, name TEXT)")
+        .await
+        .unwrap();
+
+    let insert = client
+        .prepare("INSERT INTO foo (name) VALUES ($1), ($2)")
+        .await
+        .unwrap();
+    let select = client
+        .prepare("SELECT name FROM foo ORDER BY id")
+        .await
+        .unwrap();
+
+    let mut pipeline = client.pipeline();
+    let inserted = pipeline.execute(&insert, &[&"alice", &"bob"]);
+    let rows = pipeline.query(&select, &[]);
+    assert_eq!(pipeline.len(), 2);
+    pipeline.send().await.unwrap();
+
+    assert_eq!(inserted.await.unwrap(), 2);
+    let rows = rows.await.unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].get::<_, &str>(0), "alice");
+    assert_eq!(rows[1].get::<_, &str>(0), "bob");
+}
+
+#[tokio::test]
+async fn pipeline_error() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT4 PRIMARY KEY)")
+        .await
+        .unwrap();
+
+    let insert = client
+        .prepare("INSERT INTO foo (id) VALUES ($1)")
+        .await
+        .unwrap();
+    let select = client.prepare("SELECT id FROM foo").await.unwrap();
+
+    let mut pipeline = client.pipeline();
+    let a = pipeline.execute(&insert, &[&1i32]);
+    let b = pipeline.execute(&insert, &[&1i32]);
+    let c = pipeline.query(&select, &[]);
+    let d = pipeline.execute(&insert, &[&"not an int"]);
+    pipeline.send().await.unwrap();
+
+    assert_eq!(a.await.unwrap(), 1);
+    assert_eq!(
+        b.await.unwrap_err().code(),
+        Some(&SqlState::UNIQUE_VIOLATION)
+    );
+    c.await.unwrap_err();
+    d.await.unwrap_err();
+
+    // the whole pipeline ran in one implicit transaction
+    let rows = client
+        .query(&select, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert!(rows.is_empty());
+}
+
+#[tokio::test]
+async fn execute_many() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT4 PRIMARY KEY, name TEXT)")
+        .await
+        .unwrap();
+
+    let insert = client
+        .prepare("INSERT INTO foo (id, name) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .await
+        .unwrap();
+
+    let params: Vec<(i32, String)> = (0..100).map(|i| (i % 90, format!("name {}", i))).collect();
+    let counts = client
+        .execute_many(
+            &insert,
+            params
+                .iter()
+                .map(|(id, name)| [id as &(dyn ToSql + Sync), name as &(dyn ToSql + Sync)]),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(counts.len(), 100);
+    assert_eq!(counts.iter().sum::<u64>(), 90);
+    assert_eq!(counts[95], 0);
+
+    let stmt = client.prepare("SELECT count(*) FROM foo").await.unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i64>(0), 90);
+
+    let counts = client
+        .execute_many(&insert, Vec::<Vec<&(dyn ToSql + Sync)>>::new())
+        .await
+        .unwrap();
+    assert!(counts.is_empty());
+}
+
+#[tokio::test]
+async fn row_stream_drop() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT generate_series(1, 100000)")
+        .await
+        .unwrap();
+
+    let mut rows: RowStream = client.query(&stmt, &[]);
+    for i in 1..=10 {
+        let row = rows.try_next().await.unwrap().unwrap();
+        assert_eq!(row.get::<_, i32>(0), i);
+    }
+    drop(rows);
+
+    // the remainder of the result set is discarded rather than blocking the connection
+    let stmt = client.prepare("SELECT 1::INT4").await.unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+}
+
+#[tokio::test]
+async fn statement_timeout() {
+    let mut client = connect("user=postgres").await;
+
+    let sleep = client.prepare("SELECT pg_sleep(1)").await.unwrap();
+    let err = client
+        .execute_with_statement_timeout(&sleep, &[], Duration::from_millis(100))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::QUERY_CANCELED));
+
+    let stmt = client.prepare("SELECT 1::INT4").await.unwrap();
+    let rows = client
+        .query_with_statement_timeout(&stmt, &[], Duration::from_secs(10))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+
+    // the timeout is restored within an explicit transaction
+    let show = client.prepare("SHOW statement_timeout").await.unwrap();
+    let mut transaction = client.transaction().await.unwrap();
+    transaction
+        .execute_with_statement_timeout(&stmt, &[], Duration::from_secs(10))
+        .await
+        .unwrap();
+    let rows = transaction
+        .query(&show, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, &str>(0), "0");
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+async fn interceptor() {
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Interceptor for Recorder {
+        fn rewrite_query(&self, query: &str) -> Option<String> {
+            Some(format!("{} /* app=test */", query))
+        }
+
+        fn before_query(&self, query: &str, params: &[String]) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("before {} {:?}", query, params));
+        }
+
+        fn after_query(&self, query: &str, _: &[String], result: Result<u64, &Error>) {
+            let result = match result {
+                Ok(rows) => rows.to_string(),
+                Err(e) => e.code().unwrap().code().to_string(),
+            };
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("after {} {}", query, result));
+        }
+    }
+
+    let events = Arc::new(Mutex::new(vec![]));
+
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config.interceptor(Recorder(events.clone()));
+    let (mut client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    let stmt = client
+        .prepare("SELECT current_query(), $1::TEXT")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[&"hi"])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(
+        rows[0].get::<_, &str>(0),
+        "SELECT current_query(), $1::TEXT /* app=test */"
+    );
+
+    let stmt = client.prepare("SELECT 1 / $1::INT4").await.unwrap();
+    client.execute(&stmt, &[&0i32]).await.unwrap_err();
+
+    let events = events.lock().unwrap();
+    assert_eq!(
+        *events,
+        [
+            "before SELECT current_query(), $1::TEXT [\"\\\"hi\\\"\"]",
+            "after SELECT current_query(), $1::TEXT 1",
+            "before SELECT 1 / $1::INT4 [\"0\"]",
+            "after SELECT 1 / $1::INT4 22012",
+        ]
+    );
+}
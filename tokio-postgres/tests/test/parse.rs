@@ -1,5 +1,6 @@
+use std::net::{IpAddr, Ipv4Addr};
 use std::time::Duration;
-use tokio_postgres::config::{Config, TargetSessionAttrs};
+use tokio_postgres::config::{Config, LoadBalanceHosts, ReplicationMode, TargetSessionAttrs};
 
 fn check(s: &str, config: &Config) {
     assert_eq!(s.parse::<Config>().expect(s), *config, "`{}`", s);
@@ -34,6 +35,50 @@ fn settings() {
             .keepalives_idle(Duration::from_secs(30))
             .target_session_attrs(TargetSessionAttrs::ReadWrite),
     );
+    check(
+        "target_session_attrs=prefer-standby",
+        Config::new().target_session_attrs(TargetSessionAttrs::PreferStandby),
+    );
+    check(
+        "host=db.example.com,db2.example.com hostaddr=127.0.0.1,::1",
+        Config::new()
+            .host("db.example.com")
+            .host("db2.example.com")
+            .hostaddr(IpAddr::V4(Ipv4Addr::LOCALHOST))
+            .hostaddr("::1".parse().unwrap()),
+    );
+    check(
+        r"options='-c search_path=app -c application_name=my\\ app'",
+        Config::new()
+            .runtime_param("search_path", "app")
+            .runtime_param("application_name", "my app"),
+    );
+    check(
+        "fallback_application_name=tool",
+        Config::new().fallback_application_name("tool"),
+    );
+    check(
+        "connect_timeout=5 tcp_user_timeout=2500",
+        Config::new()
+            .connect_timeout(Duration::from_secs(5))
+            .tcp_user_timeout(Duration::from_millis(2500)),
+    );
+    check(
+        "host=host1,host2 load_balance_hosts=random",
+        Config::new()
+            .host("host1")
+            .host("host2")
+            .load_balance_hosts(LoadBalanceHosts::Random),
+    );
+    check(
+        "replication=database",
+        Config::new().replication_mode(ReplicationMode::Logical),
+    );
+    check(
+        "replication=on",
+        Config::new().replication_mode(ReplicationMode::Physical),
+    );
+    check("replication=false", &Config::new());
 }
 
 #[test]
@@ -110,6 +155,15 @@ fn url() {
             .host_path("/var/lib/postgresql"),
     );
     #[cfg(unix)]
+    check(
+        "postgresql://%2Fvar%2Frun%2Fpostgresql:5433,localhost",
+        Config::new()
+            .host_path("/var/run/postgresql")
+            .port(5433)
+            .host("localhost")
+            .port(5432),
+    );
+    #[cfg(unix)]
     check(
         "postgresql://%2Fvar%2Flib%2Fpostgresql/dbname",
         Config::new()
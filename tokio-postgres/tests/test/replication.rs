@@ -0,0 +1,765 @@
+use bytes::{BufMut, Bytes};
+use futures::{SinkExt, TryStreamExt};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::timer;
+use tokio_postgres::replication::{
+    ChangeEvent, ReplicationClient, SnapshotAction, TestDecodingParser, TupleValue,
+};
+use tokio_postgres::types::PgLsn;
+use tokio_postgres::SimpleQueryMessage;
+
+use crate::connect;
+
+fn text(s: &str) -> TupleValue {
+    TupleValue::Text(s.to_string())
+}
+
+#[tokio::test]
+async fn logical_replication() {
+    let mut client = connect("user=postgres").await;
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS replication_logical;
+             CREATE TABLE replication_logical (id INT PRIMARY KEY, name TEXT);
+             DROP PUBLICATION IF EXISTS replication_logical_pub;
+             CREATE PUBLICATION replication_logical_pub FOR TABLE replication_logical;",
+        )
+        .await
+        .unwrap();
+
+    let mut replication =
+        ReplicationClient::new(connect("user=postgres replication=database").await);
+    let slot = replication
+        .create_logical_slot("replication_logical_slot", "pgoutput", true)
+        .await
+        .unwrap();
+    assert_eq!(slot.slot_name(), "replication_logical_slot");
+    assert_eq!(slot.output_plugin(), Some("pgoutput"));
+
+    client
+        .batch_execute(
+            "INSERT INTO replication_logical VALUES (1, 'alice');
+             UPDATE replication_logical SET name = 'bob' WHERE id = 1;
+             DELETE FROM replication_logical WHERE id = 1;",
+        )
+        .await
+        .unwrap();
+
+    let mut stream = replication
+        .start_logical_replication(
+            slot.slot_name(),
+            slot.consistent_point().unwrap(),
+            &["replication_logical_pub"],
+        )
+        .await
+        .unwrap();
+
+    match stream.try_next().await.unwrap().unwrap() {
+        ChangeEvent::Begin { xid, final_lsn, .. } => {
+            assert_ne!(xid.unwrap(), 0);
+            assert!(final_lsn.unwrap() >= slot.consistent_point().unwrap());
+        }
+        _ => panic!("expected begin"),
+    }
+
+    match stream.try_next().await.unwrap().unwrap() {
+        ChangeEvent::Insert { relation, new } => {
+            assert_eq!(relation.namespace(), "public");
+            assert_eq!(relation.name(), "replication_logical");
+            assert_eq!(relation.columns().len(), 2);
+            assert_eq!(relation.columns()[0].name(), "id");
+            assert!(relation.columns()[0].is_key());
+            assert!(!relation.columns()[1].is_key());
+            assert_eq!(relation.column_index("name"), Some(1));
+            assert_eq!(new, vec![text("1"), text("alice")]);
+        }
+        _ => panic!("expected insert"),
+    }
+
+    match stream.try_next().await.unwrap().unwrap() {
+        ChangeEvent::Update { relation, old, new } => {
+            assert_eq!(relation.name(), "replication_logical");
+            assert_eq!(old, None);
+            assert_eq!(new, vec![text("1"), text("bob")]);
+        }
+        _ => panic!("expected update"),
+    }
+
+    match stream.try_next().await.unwrap().unwrap() {
+        ChangeEvent::Delete { relation, old } => {
+            assert_eq!(relation.name(), "replication_logical");
+            assert_eq!(old, vec![text("1"), TupleValue::Null]);
+        }
+        _ => panic!("expected delete"),
+    }
+
+    match stream.try_next().await.unwrap().unwrap() {
+        ChangeEvent::Commit {
+            commit_lsn,
+            end_lsn,
+            ..
+        } => assert!(end_lsn.unwrap() > commit_lsn.unwrap()),
+        _ => panic!("expected commit"),
+    }
+}
+
+#[tokio::test]
+async fn logical_replication_messages() {
+    let mut client = connect("user=postgres").await;
+
+    // pgoutput only streams messages in Postgres 14 and newer
+    let rows = client
+        .simple_query("SHOW server_version_num")
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let version = match &rows[0] {
+        SimpleQueryMessage::Row(row) => row.get(0).unwrap().parse::<i32>().unwrap(),
+        _ => panic!("unexpected message"),
+    };
+    if version < 140_000 {
+        return;
+    }
+
+    client
+        .batch_execute(
+            "DROP PUBLICATION IF EXISTS replication_messages_pub;
+             CREATE PUBLICATION replication_messages_pub;",
+        )
+        .await
+        .unwrap();
+
+    let mut replication =
+        ReplicationClient::new(connect("user=postgres replication=database").await);
+    let slot = replication
+        .create_logical_slot("replication_messages_slot", "pgoutput", true)
+        .await
+        .unwrap();
+
+    client
+        .batch_execute(
+            "SELECT pg_logical_emit_message(false, 'outbox', 'hello');
+             BEGIN;
+             SELECT pg_logical_emit_message(true, 'outbox', 'world');
+             COMMIT;",
+        )
+        .await
+        .unwrap();
+
+    let mut stream = replication
+        .start_logical_replication_with_messages(
+            slot.slot_name(),
+            slot.consistent_point().unwrap(),
+            &["replication_messages_pub"],
+        )
+        .await
+        .unwrap();
+
+    let mut messages = vec![];
+    while messages.len() < 2 {
+        if let ChangeEvent::Message {
+            transactional,
+            lsn,
+            prefix,
+            content,
+        } = stream.try_next().await.unwrap().unwrap()
+        {
+            assert!(lsn >= slot.consistent_point().unwrap());
+            messages.push((transactional, prefix, content));
+        }
+    }
+
+    assert_eq!(
+        messages,
+        [
+            (false, "outbox".to_string(), Bytes::from("hello")),
+            (true, "outbox".to_string(), Bytes::from("world")),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_decoding_replication() {
+    let mut client = connect("user=postgres").await;
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS replication_test_decoding;
+             CREATE TABLE replication_test_decoding (id INT PRIMARY KEY, name TEXT)",
+        )
+        .await
+        .unwrap();
+
+    let mut replication =
+        ReplicationClient::new(connect("user=postgres replication=database").await);
+    let slot = replication
+        .create_logical_slot("replication_test_decoding_slot", "test_decoding", true)
+        .await
+        .unwrap();
+
+    client
+        .batch_execute("INSERT INTO replication_test_decoding VALUES (1, 'it''s')")
+        .await
+        .unwrap();
+
+    let mut stream = replication
+        .start_test_decoding_replication(slot.slot_name(), slot.consistent_point().unwrap())
+        .await
+        .unwrap();
+
+    match stream.try_next().await.unwrap().unwrap() {
+        ChangeEvent::Begin { xid, .. } => assert!(xid.is_some()),
+        _ => panic!("expected begin"),
+    }
+
+    match stream.try_next().await.unwrap().unwrap() {
+        ChangeEvent::Insert { relation, new } => {
+            assert_eq!(relation.namespace(), "public");
+            assert_eq!(relation.name(), "replication_test_decoding");
+            assert_eq!(relation.columns()[0].type_name(), Some("integer"));
+            assert_eq!(new, vec![text("1"), text("it's")]);
+        }
+        _ => panic!("expected insert"),
+    }
+
+    match stream.try_next().await.unwrap().unwrap() {
+        ChangeEvent::Commit { end_lsn, .. } => {
+            assert!(end_lsn.unwrap() > slot.consistent_point().unwrap())
+        }
+        _ => panic!("expected commit"),
+    }
+}
+
+#[test]
+fn test_decoding_parser() {
+    let mut parser = TestDecodingParser::new();
+
+    match parser.parse("BEGIN 529").unwrap().unwrap() {
+        ChangeEvent::Begin { xid, .. } => assert_eq!(xid, Some(529)),
+        _ => panic!("expected begin"),
+    }
+
+    let line = "table public.\"Foo Bar\": INSERT: id[integer]:1 \"Name\"[text]:'a ''b''' \
+                tags[text[]]:'{x,y}' note[character varying(10)]:null";
+    let relation = match parser.parse(line).unwrap().unwrap() {
+        ChangeEvent::Insert { relation, new } => {
+            assert_eq!(relation.name(), "Foo Bar");
+            let columns = relation
+                .columns()
+                .iter()
+                .map(|c| (c.name(), c.type_name().unwrap()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                columns,
+                [
+                    ("id", "integer"),
+                    ("Name", "text"),
+                    ("tags", "text[]"),
+                    ("note", "character varying(10)"),
+                ]
+            );
+            assert_eq!(
+                new,
+                vec![text("1"), text("a 'b'"), text("{x,y}"), TupleValue::Null]
+            );
+            relation
+        }
+        _ => panic!("expected insert"),
+    };
+
+    let line = "table public.\"Foo Bar\": UPDATE: old-key: id[integer]:1 new-tuple: id[integer]:2 \
+                \"Name\"[text]:unchanged-toast-datum tags[text[]]:null \
+                note[character varying(10)]:'x'";
+    match parser.parse(line).unwrap().unwrap() {
+        ChangeEvent::Update {
+            relation: update_relation,
+            old,
+            new,
+        } => {
+            assert!(Arc::ptr_eq(&relation, &update_relation));
+            assert_eq!(
+                old,
+                Some(vec![
+                    text("1"),
+                    TupleValue::Null,
+                    TupleValue::Null,
+                    TupleValue::Null,
+                ])
+            );
+            assert_eq!(
+                new,
+                vec![
+                    text("2"),
+                    TupleValue::UnchangedToast,
+                    TupleValue::Null,
+                    text("x"),
+                ]
+            );
+        }
+        _ => panic!("expected update"),
+    }
+
+    match parser
+        .parse("table public.\"Foo Bar\": DELETE: id[integer]:2")
+        .unwrap()
+        .unwrap()
+    {
+        ChangeEvent::Delete { relation, old } => {
+            assert_eq!(relation.columns().len(), 1);
+            assert_eq!(old, vec![text("2")]);
+        }
+        _ => panic!("expected delete"),
+    }
+
+    match parser
+        .parse("table public.a, public.b: TRUNCATE: restart_seqs cascade")
+        .unwrap()
+        .unwrap()
+    {
+        ChangeEvent::Truncate {
+            relations,
+            cascade,
+            restart_identity,
+        } => {
+            let names = relations.iter().map(|r| r.name()).collect::<Vec<_>>();
+            assert_eq!(names, ["a", "b"]);
+            assert!(cascade);
+            assert!(restart_identity);
+        }
+        _ => panic!("expected truncate"),
+    }
+
+    assert!(parser
+        .parse("message: transactional: 1 prefix: p, sz: 1 content:x")
+        .unwrap()
+        .is_none());
+
+    match parser.parse("COMMIT 529").unwrap().unwrap() {
+        ChangeEvent::Commit { xid, end_lsn, .. } => {
+            assert_eq!(xid, Some(529));
+            assert_eq!(end_lsn, None);
+        }
+        _ => panic!("expected commit"),
+    }
+
+    assert!(parser
+        .parse("table public.a: UPSERT: id[integer]:1")
+        .is_err());
+}
+
+#[cfg(feature = "with-serde_json-1")]
+#[test]
+fn wal2json_parser() {
+    use tokio_postgres::replication::Wal2JsonParser;
+
+    let mut parser = Wal2JsonParser::new();
+
+    match parser
+        .parse(r#"{"action":"B","xid":529}"#)
+        .unwrap()
+        .unwrap()
+    {
+        ChangeEvent::Begin { xid, .. } => assert_eq!(xid, Some(529)),
+        _ => panic!("expected begin"),
+    }
+
+    let json = r#"{"action":"I","schema":"public","table":"foo",
+        "columns":[{"name":"id","type":"integer","typeoid":23,"value":1},
+            {"name":"name","type":"text","typeoid":25,"value":"alice"},
+            {"name":"active","type":"boolean","typeoid":16,"value":true},
+            {"name":"note","type":"text","typeoid":25,"value":null}],
+        "pk":[{"name":"id","type":"integer","typeoid":23}]}"#;
+    let relation = match parser.parse(json).unwrap().unwrap() {
+        ChangeEvent::Insert { relation, new } => {
+            assert_eq!(relation.namespace(), "public");
+            assert_eq!(relation.name(), "foo");
+            assert_eq!(relation.columns()[1].type_name(), Some("text"));
+            assert_eq!(relation.columns()[1].type_oid(), Some(25));
+            assert!(relation.columns()[0].is_key());
+            assert!(!relation.columns()[1].is_key());
+            assert_eq!(
+                new,
+                vec![text("1"), text("alice"), text("t"), TupleValue::Null]
+            );
+            relation
+        }
+        _ => panic!("expected insert"),
+    };
+
+    let json = r#"{"action":"U","schema":"public","table":"foo",
+        "columns":[{"name":"id","type":"integer","typeoid":23,"value":2},
+            {"name":"name","type":"text","typeoid":25,"value":"bob"},
+            {"name":"active","type":"boolean","typeoid":16,"value":false},
+            {"name":"note","type":"text","typeoid":25,"value":"x"}],
+        "identity":[{"name":"id","type":"integer","typeoid":23,"value":1}],
+        "pk":[{"name":"id","type":"integer","typeoid":23}]}"#;
+    match parser.parse(json).unwrap().unwrap() {
+        ChangeEvent::Update {
+            relation: update_relation,
+            old,
+            new,
+        } => {
+            assert!(Arc::ptr_eq(&relation, &update_relation));
+            assert_eq!(
+                old,
+                Some(vec![
+                    text("1"),
+                    TupleValue::Null,
+                    TupleValue::Null,
+                    TupleValue::Null,
+                ])
+            );
+            assert_eq!(new, vec![text("2"), text("bob"), text("f"), text("x")]);
+        }
+        _ => panic!("expected update"),
+    }
+
+    let json = r#"{"action":"D","schema":"public","table":"foo",
+        "identity":[{"name":"id","type":"integer","typeoid":23,"value":2}]}"#;
+    match parser.parse(json).unwrap().unwrap() {
+        ChangeEvent::Delete { relation, old } => {
+            assert_eq!(relation.columns().len(), 1);
+            assert_eq!(old, vec![text("2")]);
+        }
+        _ => panic!("expected delete"),
+    }
+
+    match parser
+        .parse(r#"{"action":"T","schema":"public","table":"bar"}"#)
+        .unwrap()
+        .unwrap()
+    {
+        ChangeEvent::Truncate { relations, .. } => {
+            assert_eq!(relations.len(), 1);
+            assert_eq!(relations[0].name(), "bar");
+        }
+        _ => panic!("expected truncate"),
+    }
+
+    assert!(parser
+        .parse(r#"{"action":"M","transactional":false,"prefix":"p","content":"x"}"#)
+        .unwrap()
+        .is_none());
+
+    match parser
+        .parse(r#"{"action":"C","xid":529,"lsn":"0/16D3B70","nextlsn":"0/16D3BA8"}"#)
+        .unwrap()
+        .unwrap()
+    {
+        ChangeEvent::Commit {
+            xid,
+            commit_lsn,
+            end_lsn,
+            ..
+        } => {
+            assert_eq!(xid, Some(529));
+            assert_eq!(commit_lsn, Some("0/16D3B70".parse().unwrap()));
+            assert_eq!(end_lsn, Some("0/16D3BA8".parse().unwrap()));
+        }
+        _ => panic!("expected commit"),
+    }
+
+    assert!(parser.parse(r#"{"action":"X"}"#).is_err());
+    assert!(parser.parse("BEGIN 529").is_err());
+}
+
+#[tokio::test]
+async fn standby_status_update() {
+    let mut client = connect("user=postgres").await;
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS replication_status;
+             CREATE TABLE replication_status (id INT PRIMARY KEY);
+             DROP PUBLICATION IF EXISTS replication_status_pub;
+             CREATE PUBLICATION replication_status_pub FOR TABLE replication_status;",
+        )
+        .await
+        .unwrap();
+
+    let mut replication =
+        ReplicationClient::new(connect("user=postgres replication=database").await);
+    let slot = replication
+        .create_logical_slot("replication_status_slot", "pgoutput", true)
+        .await
+        .unwrap();
+
+    client
+        .batch_execute("INSERT INTO replication_status VALUES (1)")
+        .await
+        .unwrap();
+
+    let start = slot.consistent_point().unwrap();
+    let mut stream = replication
+        .start_logical_replication(slot.slot_name(), start, &["replication_status_pub"])
+        .await
+        .unwrap();
+    assert_eq!(stream.flushed_lsn(), start);
+    assert_eq!(stream.applied_lsn(), start);
+
+    let end_lsn = loop {
+        if let ChangeEvent::Commit { end_lsn, .. } = stream.try_next().await.unwrap().unwrap() {
+            break end_lsn.unwrap();
+        }
+    };
+    assert!(stream.received_lsn() > start);
+
+    stream.set_applied_lsn(end_lsn);
+    stream.set_flushed_lsn(end_lsn);
+    stream.send_status_update(true).await.unwrap();
+    assert_eq!(stream.flushed_lsn(), end_lsn);
+
+    // the server processes the update asynchronously
+    let mut confirmed = None;
+    for _ in 0..50 {
+        let rows = client
+            .simple_query(
+                "SELECT confirmed_flush_lsn FROM pg_replication_slots \
+                 WHERE slot_name = 'replication_status_slot'",
+            )
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        confirmed = rows.iter().find_map(|m| match m {
+            SimpleQueryMessage::Row(row) => row.get(0).map(|s| s.parse::<PgLsn>().unwrap()),
+            _ => None,
+        });
+        if confirmed == Some(end_lsn) {
+            break;
+        }
+        timer::delay(Instant::now() + Duration::from_millis(100)).await;
+    }
+    assert_eq!(confirmed, Some(end_lsn));
+}
+
+#[tokio::test]
+async fn physical_replication() {
+    let mut client = connect("user=postgres").await;
+    client
+        .batch_execute("CREATE TABLE IF NOT EXISTS replication_physical (id INT)")
+        .await
+        .unwrap();
+
+    let mut replication = ReplicationClient::new(connect("user=postgres replication=true").await);
+    let system = replication.identify_system().await.unwrap();
+    assert!(system.timeline() > 0);
+    assert_eq!(system.dbname(), None);
+
+    let mut stream = replication
+        .start_physical_replication(None, system.xlog_pos(), Some(system.timeline()))
+        .await
+        .unwrap();
+    assert_eq!(stream.received_lsn(), system.xlog_pos());
+
+    client
+        .batch_execute("INSERT INTO replication_physical VALUES (1)")
+        .await
+        .unwrap();
+
+    let first = stream.try_next().await.unwrap().unwrap();
+    assert_eq!(first.wal_start(), system.xlog_pos());
+    assert!(!first.data().is_empty());
+    assert!(first.wal_end() >= first.next_lsn());
+    assert_eq!(stream.received_lsn(), first.next_lsn());
+
+    client
+        .batch_execute("INSERT INTO replication_physical VALUES (2)")
+        .await
+        .unwrap();
+
+    let second = stream.try_next().await.unwrap().unwrap();
+    assert_eq!(second.wal_start(), first.next_lsn());
+}
+
+#[tokio::test]
+async fn copy_both() {
+    let mut client = connect("user=postgres").await;
+    client
+        .batch_execute("CREATE TABLE IF NOT EXISTS replication_physical (id INT)")
+        .await
+        .unwrap();
+
+    let mut replication = ReplicationClient::new(connect("user=postgres replication=true").await);
+    let system = replication.identify_system().await.unwrap();
+    let mut replication = replication.into_inner();
+
+    let mut duplex = replication
+        .copy_both_simple(&format!("START_REPLICATION PHYSICAL {}", system.xlog_pos()))
+        .await
+        .unwrap();
+
+    client
+        .batch_execute("INSERT INTO replication_physical VALUES (1)")
+        .await
+        .unwrap();
+
+    // the server sends XLogData ('w') and keepalive ('k') messages
+    let data = duplex.try_next().await.unwrap().unwrap();
+    assert!(data[0] == b'w' || data[0] == b'k');
+
+    // a standby status update reporting nothing as flushed or applied
+    let mut update = vec![b'r'];
+    update.put_u64_be(system.xlog_pos().into());
+    update.put_u64_be(0);
+    update.put_u64_be(0);
+    update.put_i64_be(0);
+    update.put_u8(0);
+    duplex.send(Bytes::from(update)).await.unwrap();
+
+    // ending the client's side ends the server's, after which the connection can be used again
+    SinkExt::<Bytes>::close(&mut duplex).await.unwrap();
+    while duplex.try_next().await.unwrap().is_some() {}
+    drop(duplex);
+
+    let rows = replication
+        .simple_query("IDENTIFY_SYSTEM")
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert!(rows.iter().any(|m| match m {
+        SimpleQueryMessage::Row(row) => row.get(0) == Some(system.system_id()),
+        _ => false,
+    }));
+}
+
+#[tokio::test]
+async fn bootstrap() {
+    let mut client = connect("user=postgres").await;
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS replication_bootstrap;
+             CREATE TABLE replication_bootstrap (id INT PRIMARY KEY, name TEXT);
+             INSERT INTO replication_bootstrap VALUES (1, 'alice'), (2, 'bob');
+             DROP PUBLICATION IF EXISTS replication_bootstrap_pub;
+             CREATE PUBLICATION replication_bootstrap_pub FOR TABLE replication_bootstrap;",
+        )
+        .await
+        .unwrap();
+
+    let mut replication =
+        ReplicationClient::new(connect("user=postgres replication=database").await);
+    let mut snapshot_client = connect("user=postgres").await;
+    let mut bootstrap = replication
+        .create_slot("replication_bootstrap_slot")
+        .temporary(true)
+        .bootstrap(&mut snapshot_client)
+        .await
+        .unwrap();
+    assert_eq!(bootstrap.slot().output_plugin(), Some("pgoutput"));
+
+    // made after the snapshot, so only seen by the stream
+    client
+        .batch_execute("INSERT INTO replication_bootstrap VALUES (3, 'carol')")
+        .await
+        .unwrap();
+
+    let data = bootstrap
+        .copy_table("replication_bootstrap")
+        .await
+        .unwrap()
+        .try_concat()
+        .await
+        .unwrap();
+    assert_eq!(&data[..], b"1\talice\n2\tbob\n");
+
+    let mut stream = bootstrap
+        .start_replication(&["replication_bootstrap_pub"])
+        .await
+        .unwrap();
+
+    match stream.try_next().await.unwrap().unwrap() {
+        ChangeEvent::Begin { .. } => {}
+        _ => panic!("expected begin"),
+    }
+
+    match stream.try_next().await.unwrap().unwrap() {
+        ChangeEvent::Insert { new, .. } => assert_eq!(new, vec![text("3"), text("carol")]),
+        _ => panic!("expected insert"),
+    }
+}
+
+#[tokio::test]
+async fn replication_slots() {
+    let mut client = connect("user=postgres").await;
+    client
+        .batch_execute(
+            "SELECT pg_drop_replication_slot(slot_name) FROM pg_replication_slots \
+             WHERE slot_name = 'replication_slots_logical'",
+        )
+        .await
+        .unwrap();
+    let mut replication =
+        ReplicationClient::new(connect("user=postgres replication=database").await);
+
+    let slot = replication
+        .create_slot("replication_slots_exported")
+        .temporary(true)
+        .snapshot(SnapshotAction::Export)
+        .logical("test_decoding")
+        .await
+        .unwrap();
+    assert_eq!(slot.slot_name(), "replication_slots_exported");
+    assert!(slot.consistent_point().is_some());
+    assert!(slot.snapshot_name().is_some());
+    assert_eq!(slot.output_plugin(), Some("test_decoding"));
+
+    let slot = replication
+        .create_slot("replication_slots_logical")
+        .snapshot(SnapshotAction::NoExport)
+        .logical("test_decoding")
+        .await
+        .unwrap();
+    assert_eq!(slot.snapshot_name(), None);
+
+    let slot = replication
+        .create_slot("replication_slots_physical")
+        .temporary(true)
+        .reserve_wal(true)
+        .physical()
+        .await
+        .unwrap();
+    assert_eq!(slot.slot_name(), "replication_slots_physical");
+    assert_eq!(slot.output_plugin(), None);
+
+    let slots = |client: &mut tokio_postgres::Client| {
+        client
+            .simple_query(
+                "SELECT slot_name, temporary FROM pg_replication_slots \
+                 WHERE slot_name LIKE 'replication\\_slots\\_%' ORDER BY slot_name",
+            )
+            .try_collect::<Vec<_>>()
+    };
+    let names = slots(&mut client)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter_map(|m| match m {
+            SimpleQueryMessage::Row(row) => {
+                Some(format!("{} {}", row.get(0).unwrap(), row.get(1).unwrap()))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "replication_slots_exported t",
+            "replication_slots_logical f",
+            "replication_slots_physical t",
+        ]
+    );
+
+    replication
+        .drop_slot("replication_slots_logical", false)
+        .await
+        .unwrap();
+    let rows = slots(&mut client).await.unwrap();
+    assert!(!rows.iter().any(|m| match m {
+        SimpleQueryMessage::Row(row) => row.get(0) == Some("replication_slots_logical"),
+        _ => false,
+    }));
+
+    assert!(replication
+        .drop_slot("replication_slots_logical", false)
+        .await
+        .is_err());
+}
@@ -2,7 +2,7 @@ use futures::{join, FutureExt, TryStreamExt};
 use std::time::{Duration, Instant};
 use tokio::timer;
 use tokio_postgres::error::SqlState;
-use tokio_postgres::{Client, NoTls};
+use tokio_postgres::{Client, Config, NoTls, PoolBuilder, ReconnectingClient};
 
 async fn connect(s: &str) -> Client {
     let (client, connection) = tokio_postgres::connect(s, NoTls).await.unwrap();
@@ -28,6 +28,7 @@ async fn smoke_test(s: &str) {
 #[ignore] // FIXME doesn't work with our docker-based tests :(
 async fn unix_socket() {
     smoke_test("host=/var/run/postgresql port=5433 user=postgres").await;
+    smoke_test("postgresql://postgres@%2Fvar%2Frun%2Fpostgresql:5433").await;
 }
 
 #[tokio::test]
@@ -45,6 +46,62 @@ async fn multiple_hosts_multiple_ports() {
     smoke_test("host=foobar.invalid,localhost port=5432,5433 user=postgres").await;
 }
 
+#[tokio::test]
+async fn hostaddr() {
+    smoke_test("hostaddr=127.0.0.1 port=5433 user=postgres").await;
+    // the hostname is only used for TLS, so it doesn't need to resolve
+    smoke_test("host=foobar.invalid hostaddr=127.0.0.1 port=5433 user=postgres").await;
+}
+
+#[tokio::test]
+async fn wrong_hostaddr_count() {
+    tokio_postgres::connect(
+        "host=localhost,localhost hostaddr=127.0.0.1 port=5433 user=postgres",
+        NoTls,
+    )
+    .await
+    .err()
+    .unwrap();
+}
+
+#[tokio::test]
+async fn load_balance_hosts_random() {
+    for _ in 0..5 {
+        smoke_test(
+            "host=foobar.invalid,localhost port=5433 user=postgres load_balance_hosts=random",
+        )
+        .await;
+    }
+
+    // the client must cancel queries on the server it's connected to, whichever it was
+    let mut client =
+        connect("host=localhost,127.0.0.1 port=5433 user=postgres load_balance_hosts=random").await;
+    let cancel = client.cancel_query(NoTls);
+    let cancel = timer::delay(Instant::now() + Duration::from_millis(100)).then(|()| cancel);
+
+    let sleep = client.batch_execute("SELECT pg_sleep(100)");
+
+    match join!(sleep, cancel) {
+        (Err(ref e), Ok(())) if e.code() == Some(&SqlState::QUERY_CANCELED) => {}
+        t => panic!("unexpected return: {:?}", t),
+    }
+}
+
+#[tokio::test]
+async fn connect_timeout_failover() {
+    // the listener's backlog completes TCP handshakes, but nothing ever responds to the startup message
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let start = Instant::now();
+    smoke_test(&format!(
+        "host=127.0.0.1,localhost port={},5433 user=postgres connect_timeout=1 tcp_user_timeout=10000",
+        port
+    ))
+    .await;
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
 #[tokio::test]
 async fn wrong_port_count() {
     tokio_postgres::connect("host=localhost port=5433,5433 user=postgres", NoTls)
@@ -70,6 +127,25 @@ async fn target_session_attrs_err() {
     .unwrap();
 }
 
+#[tokio::test]
+async fn target_session_attrs_primary() {
+    smoke_test("host=localhost port=5433 user=postgres target_session_attrs=primary").await;
+    smoke_test("host=localhost port=5433 user=postgres target_session_attrs=prefer-standby").await;
+
+    for attrs in &["read-only", "standby"] {
+        tokio_postgres::connect(
+            &format!(
+                "host=localhost port=5433 user=postgres target_session_attrs={}",
+                attrs
+            ),
+            NoTls,
+        )
+        .await
+        .err()
+        .unwrap();
+    }
+}
+
 #[tokio::test]
 async fn cancel_query() {
     let mut client = connect("host=localhost port=5433 user=postgres").await;
@@ -84,3 +160,147 @@ async fn cancel_query() {
         t => panic!("unexpected return: {:?}", t),
     }
 }
+
+#[tokio::test]
+async fn query_with_timeout() {
+    let mut client = connect("host=localhost port=5433 user=postgres").await;
+
+    let sleep = client.prepare("SELECT pg_sleep(100)").await.unwrap();
+    let start = Instant::now();
+    let err = client
+        .query_with_timeout(&sleep, &[], Duration::from_millis(100), NoTls)
+        .await
+        .unwrap_err();
+    assert!(err.is_timeout());
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    let stmt = client.prepare("SELECT 1::INT4").await.unwrap();
+    let rows = client
+        .query_with_timeout(&stmt, &[], Duration::from_secs(10), NoTls)
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+}
+
+#[tokio::test]
+async fn cancel_token() {
+    let mut client = connect("host=localhost port=5433 user=postgres").await;
+
+    let token = client.cancel_token();
+    let cancel = async move {
+        timer::delay(Instant::now() + Duration::from_millis(100)).await;
+        token.cancel_query(NoTls).await
+    };
+
+    let sleep = client.batch_execute("SELECT pg_sleep(100)");
+
+    match join!(sleep, cancel) {
+        (Err(ref e), Ok(())) if e.code() == Some(&SqlState::QUERY_CANCELED) => {}
+        t => panic!("unexpected return: {:?}", t),
+    }
+}
+
+#[tokio::test]
+async fn reconnecting_client() {
+    let config = "host=localhost port=5433 user=postgres"
+        .parse::<Config>()
+        .unwrap();
+    let mut client = ReconnectingClient::new(config, NoTls);
+    assert!(!client.is_connected());
+
+    let stmt = client.prepare_cached("SELECT $1::INT").await.unwrap();
+    let rows = client
+        .client()
+        .await
+        .unwrap()
+        .query(&stmt, &[&1i32])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+    assert!(client.is_connected());
+
+    let pid = client.client().await.unwrap().backend_pid();
+    let mut other = connect("host=localhost port=5433 user=postgres").await;
+    other
+        .batch_execute(&format!("SELECT pg_terminate_backend({})", pid))
+        .await
+        .unwrap();
+
+    // in-flight work fails once the backend exits, after which the client reconnects
+    loop {
+        let inner = client.client().await.unwrap();
+        if inner.backend_pid() != pid {
+            break;
+        }
+        if let Err(e) = inner.ping().await {
+            assert!(e.is_closed() || e.code().is_some());
+        }
+        timer::delay(Instant::now() + Duration::from_millis(50)).await;
+    }
+
+    let stmt = client.prepare_cached("SELECT $1::INT").await.unwrap();
+    let rows = client
+        .client()
+        .await
+        .unwrap()
+        .query(&stmt, &[&2i32])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 2);
+}
+
+#[tokio::test]
+async fn reconnecting_client_gives_up() {
+    let config = "host=localhost port=5433 user=postgres dbname=nonexistent"
+        .parse::<Config>()
+        .unwrap();
+    let mut client = ReconnectingClient::new(config, NoTls);
+    client
+        .max_attempts(2)
+        .backoff(Duration::from_millis(10), Duration::from_millis(10));
+
+    let err = client.client().await.err().unwrap();
+    assert_eq!(err.code(), Some(&SqlState::INVALID_CATALOG_NAME));
+}
+
+#[tokio::test]
+async fn pool() {
+    let config = "host=localhost port=5433 user=postgres"
+        .parse::<Config>()
+        .unwrap();
+    let pool = PoolBuilder::new()
+        .max_size(2)
+        .acquire_timeout(Duration::from_millis(200))
+        .build(config, NoTls);
+
+    let mut a = pool.get().await.unwrap();
+    a.batch_execute("SELECT 1").await.unwrap();
+    let a_pid = a.backend_pid();
+    let b = pool.get().await.unwrap();
+    let b_pid = b.backend_pid();
+    assert_eq!(pool.size(), 2);
+    assert_eq!(pool.idle(), 0);
+
+    let err = pool.get().await.err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "timed out waiting for a connection from the pool"
+    );
+
+    drop(a);
+    assert_eq!(pool.idle(), 1);
+    let a = pool.get().await.unwrap();
+    assert_eq!(a.backend_pid(), a_pid);
+
+    // a waiting request is handed the next connection returned to the pool
+    let release = async move {
+        timer::delay(Instant::now() + Duration::from_millis(50)).await;
+        drop(b);
+    };
+    let (c, ()) = join!(pool.get(), release);
+    assert_eq!(c.unwrap().backend_pid(), b_pid);
+    assert_eq!(pool.size(), 2);
+    drop(a);
+}
@@ -0,0 +1,81 @@
+use futures::{stream, TryStreamExt};
+use tokio_postgres::text_copy::{decode_field, decode_row, encode_row};
+
+use crate::connect;
+
+#[tokio::test]
+async fn round_trip() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT, bar TEXT)")
+        .await
+        .unwrap();
+
+    let values: [Option<&[u8]>; 6] = [
+        Some(b"tab\tand\nnewline"),
+        Some(b"back\\slash \\N"),
+        Some(b"\\."),
+        Some(b"\x08\x0b\x0c\r"),
+        Some(b""),
+        None,
+    ];
+
+    let mut data = vec![];
+    for (i, value) in values.iter().enumerate() {
+        encode_row(&[Some(i.to_string().as_bytes()), *value], &mut data);
+    }
+
+    let stmt = client
+        .prepare("COPY foo (id, bar) FROM STDIN")
+        .await
+        .unwrap();
+    let rows = client
+        .copy_in(&stmt, &[], stream::iter(vec![Ok::<_, String>(data)]))
+        .await
+        .unwrap();
+    assert_eq!(rows, values.len() as u64);
+
+    let stmt = client
+        .prepare("COPY (SELECT id, bar FROM foo ORDER BY id) TO STDOUT")
+        .await
+        .unwrap();
+    let rows = client
+        .copy_out(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), values.len());
+
+    for (i, (row, value)) in rows.iter().zip(&values).enumerate() {
+        let fields = decode_row(row).unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0], Some(i.to_string().into_bytes()));
+        assert_eq!(fields[1].as_ref().map(|v| &v[..]), *value);
+    }
+}
+
+#[test]
+fn decode_escapes() {
+    assert_eq!(decode_field(b"\\N").unwrap(), None);
+    assert_eq!(decode_field(b"\\\\N").unwrap(), Some(b"\\N".to_vec()));
+    assert_eq!(
+        decode_field(b"a\\tb\\nc\\rd").unwrap(),
+        Some(b"a\tb\nc\rd".to_vec())
+    );
+    assert_eq!(
+        decode_field(b"\\101\\0\\7\\1234").unwrap(),
+        Some(b"A\0\x07S4".to_vec())
+    );
+    assert_eq!(
+        decode_field(b"\\x41\\xa\\xg\\q").unwrap(),
+        Some(b"A\nxgq".to_vec())
+    );
+    assert!(decode_field(b"foo\\").is_err());
+
+    assert_eq!(
+        decode_row(b"1\ta\\\tb\t\\N\r\n").unwrap(),
+        [Some(b"1".to_vec()), Some(b"a\tb".to_vec()), None]
+    );
+    assert!(decode_row(b"1\tfoo\\").is_err());
+}
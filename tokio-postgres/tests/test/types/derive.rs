@@ -0,0 +1,304 @@
+use futures::TryStreamExt;
+use std::error::Error;
+use tokio_postgres::types::{FromSql, ToSql, WrongType};
+use tokio_postgres::FromRow;
+
+use crate::connect;
+
+#[derive(Debug, PartialEq, ToSql, FromSql)]
+#[postgres(name = "mood")]
+enum Mood {
+    #[postgres(name = "sad")]
+    Sad,
+    #[postgres(name = "ok")]
+    Ok,
+    #[postgres(name = "happy")]
+    Happy,
+}
+
+#[tokio::test]
+async fn enum_round_trip() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TYPE pg_temp.mood AS ENUM ('sad', 'ok', 'happy')")
+        .await
+        .unwrap();
+
+    let checks = &[
+        (Mood::Sad, "'sad'"),
+        (Mood::Ok, "'ok'"),
+        (Mood::Happy, "'happy'"),
+    ];
+    for (val, repr) in checks {
+        let stmt = client
+            .prepare(&format!("SELECT {}::mood", repr))
+            .await
+            .unwrap();
+        let rows = client
+            .query(&stmt, &[])
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(val, &rows[0].get::<_, Mood>(0));
+
+        let stmt = client.prepare("SELECT $1::mood").await.unwrap();
+        let rows = client
+            .query(&stmt, &[&val])
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(val, &rows[0].get::<_, Mood>(0));
+    }
+}
+
+#[tokio::test]
+async fn enum_unknown_label() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TYPE pg_temp.mood AS ENUM ('sad', 'ok', 'happy', 'ecstatic')")
+        .await
+        .unwrap();
+
+    let stmt = client.prepare("SELECT 'ecstatic'::mood").await.unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let err = rows[0].try_get::<_, Mood>(0).unwrap_err();
+    assert!(err.to_string().contains("ecstatic"));
+}
+
+#[derive(Debug, PartialEq, ToSql, FromSql)]
+#[postgres(name = "email", transparent)]
+struct Email(String);
+
+#[tokio::test]
+async fn transparent_domain() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "
+            CREATE DOMAIN pg_temp.email AS TEXT CHECK(VALUE LIKE '%@%');
+            CREATE TABLE pg_temp.users (email pg_temp.email);
+            ",
+        )
+        .await
+        .unwrap();
+
+    let email = Email("foo@example.com".to_string());
+
+    let stmt = client
+        .prepare("INSERT INTO pg_temp.users (email) VALUES ($1)")
+        .await
+        .unwrap();
+    client.execute(&stmt, &[&email]).await.unwrap();
+
+    let stmt = client
+        .prepare("SELECT email FROM pg_temp.users")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(email, rows[0].get::<_, Email>(0));
+}
+
+#[tokio::test]
+async fn transparent_wrong_domain() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE DOMAIN pg_temp.phone AS TEXT")
+        .await
+        .unwrap();
+
+    let stmt = client.prepare("SELECT $1::phone").await.unwrap();
+    let email = Email("foo@example.com".to_string());
+    let err = client
+        .query(&stmt, &[&email])
+        .try_collect::<Vec<_>>()
+        .await
+        .err()
+        .unwrap();
+    match err.source() {
+        Some(e) if e.is::<WrongType>() => {}
+        _ => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[derive(Debug, PartialEq, FromRow)]
+struct Address {
+    street: String,
+    #[postgres(name = "zip_code")]
+    zip: String,
+}
+
+#[derive(Debug, PartialEq, FromRow)]
+struct Customer {
+    id: i32,
+    #[postgres(name = "customer_name")]
+    name: String,
+    #[postgres(flatten)]
+    address: Address,
+}
+
+#[tokio::test]
+async fn from_row() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare(
+            "SELECT 1::INT4 AS id, 'alice' AS customer_name, 'Main St' AS street, '12345' AS zip_code",
+        )
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let customer = rows[0].try_into::<Customer>().unwrap();
+    assert_eq!(
+        customer,
+        Customer {
+            id: 1,
+            name: "alice".to_string(),
+            address: Address {
+                street: "Main St".to_string(),
+                zip: "12345".to_string(),
+            },
+        }
+    );
+}
+
+#[tokio::test]
+async fn from_row_missing_column() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client.prepare("SELECT 1::INT4 AS id").await.unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let err = rows[0].try_into::<Customer>().unwrap_err();
+    assert!(err.to_string().contains("customer_name"), "{}", err);
+}
+
+#[derive(Debug, PartialEq, ToSql, FromSql)]
+#[postgres(name = "derive_test.color")]
+enum Color {
+    #[postgres(name = "red")]
+    Red,
+    #[postgres(name = "green")]
+    Green,
+}
+
+#[tokio::test]
+async fn schema_qualified_enum() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "
+            DROP SCHEMA IF EXISTS derive_test CASCADE;
+            CREATE SCHEMA derive_test;
+            CREATE TYPE derive_test.color AS ENUM ('red', 'green');
+            CREATE TYPE pg_temp.color AS ENUM ('red', 'green');
+            ",
+        )
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare("SELECT $1::derive_test.color")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[&Color::Green])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, Color>(0), Color::Green);
+
+    let stmt = client.prepare("SELECT $1::pg_temp.color").await.unwrap();
+    let err = client
+        .query(&stmt, &[&Color::Green])
+        .try_collect::<Vec<_>>()
+        .await
+        .err()
+        .unwrap();
+    match err.source() {
+        Some(e) if e.is::<WrongType>() => {}
+        _ => panic!("unexpected error {:?}", err),
+    }
+
+    client
+        .batch_execute("DROP SCHEMA derive_test CASCADE")
+        .await
+        .unwrap();
+}
+
+#[derive(Debug, PartialEq, Clone, ToSql, FromSql)]
+#[postgres(name = "supplier")]
+struct Supplier {
+    id: i32,
+    #[postgres(name = "name")]
+    display_name: String,
+}
+
+#[derive(Debug, PartialEq, ToSql, FromSql)]
+#[postgres(name = "inventory_item")]
+struct InventoryItem {
+    name: String,
+    supplier: Supplier,
+    alternates: Vec<Supplier>,
+    price: Option<f64>,
+}
+
+#[tokio::test]
+async fn nested_composite() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "
+            CREATE TYPE pg_temp.supplier AS (id INT4, name TEXT);
+            CREATE TYPE pg_temp.inventory_item AS (
+                name TEXT,
+                supplier pg_temp.supplier,
+                alternates pg_temp.supplier[],
+                price DOUBLE PRECISION
+            );
+            ",
+        )
+        .await
+        .unwrap();
+
+    let supplier = Supplier {
+        id: 1,
+        display_name: "ACME".to_string(),
+    };
+    let item = InventoryItem {
+        name: "foobar".to_string(),
+        supplier: supplier.clone(),
+        alternates: vec![supplier.clone(), supplier],
+        price: None,
+    };
+
+    let stmt = client.prepare("SELECT $1::inventory_item").await.unwrap();
+    let rows = client
+        .query(&stmt, &[&item])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, InventoryItem>(0), item);
+}
@@ -1,4 +1,6 @@
+use bytes::Bytes;
 use futures::TryStreamExt;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::error::Error;
 use std::f32;
@@ -8,7 +10,11 @@ use std::net::IpAddr;
 use std::result;
 use std::time::{Duration, UNIX_EPOCH};
 use tokio_postgres::to_sql_checked;
-use tokio_postgres::types::{FromSql, FromSqlOwned, IsNull, Kind, ToSql, Type, WrongType};
+use tokio_postgres::types::{
+    AclItem, AclPrivilege, FromSql, FromSqlOwned, IsNull, Kind, Lossy, Numeric, Oid, PgLsn,
+    PgSnapshot, Privilege, Regclass, Regtype, TimeTz, Timestamp, ToSql, Trimmed, Type, Value,
+    WrongType,
+};
 
 use crate::connect;
 
@@ -16,10 +22,14 @@ use crate::connect;
 mod bit_vec_07;
 #[cfg(feature = "with-chrono-0_4")]
 mod chrono_04;
+#[cfg(feature = "derive")]
+mod derive;
 #[cfg(feature = "with-eui48-0_4")]
 mod eui48_04;
 #[cfg(feature = "with-geo-0_10")]
 mod geo_010;
+#[cfg(feature = "pgvector")]
+mod pgvector;
 #[cfg(feature = "with-serde_json-1")]
 mod serde_json_1;
 #[cfg(feature = "with-uuid-0_7")]
@@ -141,6 +151,223 @@ async fn test_i64_params() {
     .await;
 }
 
+#[tokio::test]
+async fn reg_types() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT 'pg_class'::REGCLASS, 'int4'::REGTYPE, $1::REGTYPE::TEXT")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[&Regtype(Type::TEXT.oid())])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(rows[0].get::<_, Regclass>(0), Regclass(1259));
+    assert_eq!(rows[0].get::<_, Regtype>(1), Regtype(Type::INT4.oid()));
+    assert_eq!(rows[0].get::<_, &str>(2), "text");
+    assert!(rows[0].try_get::<_, Regtype>(0).is_err());
+}
+
+#[tokio::test]
+async fn test_int2vector_params() {
+    test_type(
+        "INT2VECTOR",
+        &[(Some(vec![1i16, 2, 3]), "'1 2 3'"), (None, "NULL")],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_oidvector_params() {
+    test_type(
+        "OIDVECTOR",
+        &[(Some(vec![23u32, 25]), "'23 25'"), (None, "NULL")],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn empty_vectors() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT ''::INT2VECTOR, ''::OIDVECTOR")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, Vec<i16>>(0), Vec::<i16>::new());
+    assert_eq!(rows[0].get::<_, Vec<Oid>>(1), Vec::<Oid>::new());
+
+    // the server can't receive empty vectors, so they're rejected rather than sent
+    let stmt = client.prepare("SELECT $1::INT2VECTOR").await.unwrap();
+    let err = client
+        .query(&stmt, &[&Vec::<i16>::new()])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("int2vector"), "{}", err);
+}
+
+#[tokio::test]
+async fn catalog_vectors() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT proargtypes FROM pg_proc WHERE oid = 'int4pl'::REGPROC")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let types: Vec<Oid> = rows[0].get(0);
+    assert_eq!(types, vec![Type::INT4.oid(), Type::INT4.oid()]);
+}
+
+#[tokio::test]
+async fn test_i128_params() {
+    test_type(
+        "NUMERIC",
+        &[
+            (Some(0i128), "0"),
+            (Some(10_000i128), "10000"),
+            (Some(-12_345_678i128), "-12345678"),
+            (
+                Some(i128::max_value()),
+                "170141183460469231731687303715884105727",
+            ),
+            (
+                Some(i128::min_value()),
+                "-170141183460469231731687303715884105728",
+            ),
+            (None, "NULL"),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_u128_params() {
+    test_type(
+        "NUMERIC",
+        &[
+            (Some(0u128), "0"),
+            (Some(100_000_000u128), "100000000"),
+            (
+                Some(u128::max_value()),
+                "340282366920938463463374607431768211455",
+            ),
+            (None, "NULL"),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn numeric_integer_errors() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT 1.5::NUMERIC, 'NaN'::NUMERIC, -1::NUMERIC, 1e40::NUMERIC, 2.000::NUMERIC")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert!(rows[0].try_get::<_, i128>(0).is_err());
+    assert!(rows[0].try_get::<_, i128>(1).is_err());
+    assert!(rows[0].try_get::<_, u128>(2).is_err());
+    assert!(rows[0].try_get::<_, i128>(3).is_err());
+    assert_eq!(rows[0].get::<_, i128>(4), 2);
+}
+
+#[tokio::test]
+async fn test_special_numeric_params() {
+    test_type(
+        "NUMERIC",
+        &[
+            (Numeric::NaN, "'NaN'"),
+            (Numeric::Value(-12i128), "-12"),
+            (
+                Numeric::Value(i128::max_value()),
+                "170141183460469231731687303715884105727",
+            ),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_u16_params() {
+    test_type(
+        "INT",
+        &[
+            (Some(0u16), "0"),
+            (Some(65_535u16), "65535"),
+            (None, "NULL"),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_u32_params() {
+    test_type(
+        "BIGINT",
+        &[
+            (Some(0u32), "0"),
+            (Some(4_294_967_295u32), "4294967295"),
+            (None, "NULL"),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_u64_params() {
+    test_type(
+        "NUMERIC",
+        &[
+            (Some(0u64), "0"),
+            (Some(18_446_744_073_709_551_615u64), "18446744073709551615"),
+            (None, "NULL"),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn unsigned_out_of_range() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT -1::INT, 65536::INT, -1::BIGINT, 4294967296::BIGINT, 18446744073709551616::NUMERIC")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert!(rows[0].try_get::<_, u16>(0).is_err());
+    assert!(rows[0].try_get::<_, u16>(1).is_err());
+    assert!(rows[0].try_get::<_, u32>(2).is_err());
+    assert!(rows[0].try_get::<_, u32>(3).is_err());
+    assert!(rows[0].try_get::<_, u64>(4).is_err());
+}
+
 #[tokio::test]
 async fn test_f32_params() {
     test_type(
@@ -215,6 +442,23 @@ async fn test_borrowed_text() {
     assert_eq!(s, "foo");
 }
 
+#[tokio::test]
+async fn test_cow_text() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client.prepare("SELECT 'foo'").await.unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let s: Cow<'_, str> = rows[0].get(0);
+    match s {
+        Cow::Borrowed(s) => assert_eq!(s, "foo"),
+        Cow::Owned(_) => panic!("expected a borrowed value"),
+    }
+}
+
 #[tokio::test]
 async fn test_bpchar_params() {
     let mut client = connect("user=postgres").await;
@@ -255,6 +499,190 @@ async fn test_bpchar_params() {
     );
 }
 
+#[tokio::test]
+async fn test_trimmed_bpchar_params() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT '123'::CHAR(5), '  '::CHAR(2), 'a  '::TEXT, NULL::CHAR(5)")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(rows[0].get::<_, Trimmed<&str>>(0), Trimmed("123"));
+    assert_eq!(rows[0].get::<_, Trimmed<String>>(1), Trimmed(String::new()));
+    assert_eq!(rows[0].get::<_, Trimmed<&str>>(2), Trimmed("a  "));
+    assert_eq!(rows[0].get::<_, Option<Trimmed<&str>>>(3), None);
+}
+
+#[tokio::test]
+async fn lossy() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT 'café'::TEXT, NULL::TEXT")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(rows[0].get::<_, Lossy<'_>>(0), Lossy(Cow::Borrowed("café")));
+    assert_eq!(rows[0].get::<_, Option<Lossy<'_>>>(1), None);
+
+    // a LATIN1 value, as a SQL_ASCII database would return it
+    let value = Lossy::from_sql(&Type::TEXT, b"caf\xe9").unwrap();
+    assert_eq!(value, Lossy(Cow::Owned("caf\u{fffd}".to_string())));
+}
+
+#[tokio::test]
+async fn timetz() {
+    test_type(
+        "TIMETZ",
+        &[
+            (
+                Some(TimeTz {
+                    microseconds: 14_706_000_000,
+                    offset: 2 * 60 * 60,
+                }),
+                "'04:05:06+02'",
+            ),
+            (
+                Some(TimeTz {
+                    microseconds: 0,
+                    offset: -(5 * 60 * 60 + 30 * 60),
+                }),
+                "'00:00:00-05:30'",
+            ),
+            (None, "NULL"),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn aclitem() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare(
+            "SELECT ARRAY[
+                '=r/postgres'::ACLITEM,
+                'postgres=a*w/postgres'::ACLITEM
+            ]::TEXT[]",
+        )
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        rows[0].get::<_, Vec<AclItem>>(0),
+        vec![
+            AclItem {
+                grantee: None,
+                grantor: "postgres".to_string(),
+                privileges: vec![AclPrivilege {
+                    privilege: Privilege::Select,
+                    grant_option: false,
+                }],
+            },
+            AclItem {
+                grantee: Some("postgres".to_string()),
+                grantor: "postgres".to_string(),
+                privileges: vec![
+                    AclPrivilege {
+                        privilege: Privilege::Insert,
+                        grant_option: true,
+                    },
+                    AclPrivilege {
+                        privilege: Privilege::Update,
+                        grant_option: false,
+                    },
+                ],
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn pg_lsn() {
+    test_type(
+        "PG_LSN",
+        &[
+            (Some(PgLsn(0x0000_0016_B374_D848)), "'16/B374D848'"),
+            (Some(PgLsn(0)), "'0/0'"),
+            (None, "NULL"),
+        ],
+    )
+    .await;
+}
+
+#[test]
+fn pg_lsn_display() {
+    let lsn = "16/B374D848".parse::<PgLsn>().unwrap();
+    assert_eq!(lsn, PgLsn(0x0000_0016_B374_D848));
+    assert_eq!(lsn.to_string(), "16/B374D848");
+    assert!("16".parse::<PgLsn>().is_err());
+    assert!("16/".parse::<PgLsn>().is_err());
+    assert!("123456789/0".parse::<PgLsn>().is_err());
+}
+
+#[tokio::test]
+async fn txid_snapshot() {
+    test_type(
+        "TXID_SNAPSHOT",
+        &[
+            (
+                Some(PgSnapshot {
+                    xmin: 10,
+                    xmax: 20,
+                    xip_list: vec![10, 14, 15],
+                }),
+                "'10:20:10,14,15'",
+            ),
+            (
+                Some(PgSnapshot {
+                    xmin: 10,
+                    xmax: 10,
+                    xip_list: vec![],
+                }),
+                "'10:10:'",
+            ),
+            (None, "NULL"),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn current_snapshot() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT txid_current_snapshot()")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let snapshot = rows[0].get::<_, PgSnapshot>(0);
+    assert!(snapshot.xmin <= snapshot.xmax);
+    assert!(!snapshot.is_visible(snapshot.xmax));
+}
+
 #[tokio::test]
 async fn test_citext_params() {
     let mut client = connect("user=postgres").await;
@@ -317,6 +745,75 @@ async fn test_borrowed_bytea() {
     assert_eq!(s, b"foo");
 }
 
+#[tokio::test]
+async fn test_borrowed_bytea_large() {
+    let mut client = connect("user=postgres").await;
+    let stmt = client
+        .prepare("SELECT decode(repeat('ab', 1024 * 1024), 'hex'), NULL::BYTEA")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let s: &[u8] = rows[0].get(0);
+    assert_eq!(s.len(), 1024 * 1024);
+    assert!(s.iter().all(|b| *b == 0xab));
+    let s: Option<&[u8]> = rows[0].get(1);
+    assert_eq!(s, None);
+}
+
+#[tokio::test]
+async fn test_bytes_bytea() {
+    let mut client = connect("user=postgres").await;
+    let stmt = client
+        .prepare("SELECT 'foo'::BYTEA, NULL::BYTEA, 'foo'::TEXT")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(rows[0].get::<_, Bytes>(0), Bytes::from_static(b"foo"));
+    assert_eq!(
+        rows[0].try_get_bytes(0).unwrap(),
+        Some(Bytes::from_static(b"foo"))
+    );
+    assert_eq!(rows[0].try_get_bytes(1).unwrap(), None);
+    rows[0].try_get_bytes(2).unwrap_err();
+}
+
+#[tokio::test]
+async fn borrowed_values_share_row_buffer() {
+    let mut client = connect("user=postgres").await;
+    let stmt = client
+        .prepare("SELECT 'foo'::BYTEA, 'bar'::BYTEA")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let first = rows[0].get::<_, &[u8]>(0);
+    let second = rows[0].get::<_, &[u8]>(1);
+    assert_eq!(first, b"foo");
+    assert_eq!(second, b"bar");
+    assert_eq!(
+        first.as_ptr(),
+        rows[0].try_get_bytes(0).unwrap().unwrap().as_ptr()
+    );
+    // the second value follows the first's data and the length of the second
+    assert_eq!(
+        second.as_ptr(),
+        first[first.len()..].as_ptr().wrapping_add(4)
+    );
+}
+
 macro_rules! make_map {
     ($($k:expr => $v:expr),+) => ({
         let mut map = HashMap::new();
@@ -580,6 +1077,55 @@ async fn composite() {
     }
 }
 
+#[tokio::test]
+async fn record() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT (1::INT4, 'hello'::TEXT, NULL::INT8)")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let value: (i32, String, Option<i64>) = rows[0].get(0);
+    assert_eq!(value, (1, "hello".to_string(), None));
+
+    assert!(rows[0].try_get::<_, (i32, String)>(0).is_err());
+}
+
+#[tokio::test]
+async fn composite_tuple() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TYPE pg_temp.pair AS (name TEXT, value INT4)")
+        .await
+        .unwrap();
+
+    let stmt = client.prepare("SELECT $1::pair").await.unwrap();
+    let rows = client
+        .query(&stmt, &[&("foo", Some(1i32))])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let value: (String, Option<i32>) = rows[0].get(0);
+    assert_eq!(value, ("foo".to_string(), Some(1)));
+
+    let err = client
+        .query(&stmt, &[&(1i32, 2i32)])
+        .try_collect::<Vec<_>>()
+        .await
+        .err()
+        .unwrap();
+    match err.source() {
+        Some(e) if e.is::<WrongType>() => {}
+        _ => panic!("Unexpected error {:?}", err),
+    };
+}
+
 #[tokio::test]
 async fn enum_() {
     let mut client = connect("user=postgres").await;
@@ -603,6 +1149,118 @@ async fn enum_() {
     }
 }
 
+#[tokio::test]
+async fn dynamic_value() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TYPE pg_temp.mood AS ENUM ('sad', 'ok', 'happy');
+             CREATE TYPE pg_temp.pair AS (name TEXT, value INT4)",
+        )
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare(
+            "SELECT NULL::INT4, true, 1::INT2, 2::INT4, 3::INT8, 1.5::FLOAT8, 'foo'::TEXT,
+                    '\\x01'::BYTEA, 'ok'::mood, ARRAY[1, NULL]::INT4[], ('a', 1)::pair,
+                    (1, 'b'::TEXT, NULL::mood), '(1,2)'::POINT",
+        )
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let values = (0..rows[0].len())
+        .map(|i| rows[0].get::<_, Value>(i))
+        .collect::<Vec<_>>();
+    assert_eq!(values[0], Value::Null);
+    assert_eq!(values[1], Value::Bool(true));
+    assert_eq!(values[2], Value::Int2(1));
+    assert_eq!(values[3], Value::Int4(2));
+    assert_eq!(values[4], Value::Int8(3));
+    assert_eq!(values[5], Value::Float(1.5));
+    assert_eq!(values[6], Value::Text("foo".to_string()));
+    assert_eq!(values[7], Value::Bytea(vec![1]));
+    assert_eq!(values[8], Value::Text("ok".to_string()));
+    assert_eq!(values[9], Value::Array(vec![Value::Int4(1), Value::Null]));
+    assert_eq!(
+        values[10],
+        Value::Composite(vec![
+            ("name".to_string(), Value::Text("a".to_string())),
+            ("value".to_string(), Value::Int4(1)),
+        ])
+    );
+    assert_eq!(
+        values[11],
+        Value::Composite(vec![
+            ("f1".to_string(), Value::Int4(1)),
+            ("f2".to_string(), Value::Text("b".to_string())),
+            ("f3".to_string(), Value::Null),
+        ])
+    );
+    match values[12] {
+        Value::Other(ref raw) => assert_eq!(raw.len(), 16),
+        ref v => panic!("unexpected value {:?}", v),
+    }
+
+    let stmt = client
+        .prepare("SELECT $1::INT8, $2::mood, $3::pair, $4::TEXT[]")
+        .await
+        .unwrap();
+    let params = vec![
+        Value::Int8(1),
+        Value::Text("happy".to_string()),
+        values[10].clone(),
+        Value::Array(vec![Value::Text("x".to_string()), Value::Null]),
+    ];
+    let rows = client
+        .query_iter(&stmt, params.iter().map(|v| v as _))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    for (i, param) in params.iter().enumerate() {
+        assert_eq!(&rows[0].get::<_, Value>(i), param);
+    }
+
+    let stmt = client.prepare("SELECT $1::INT4").await.unwrap();
+    let err = client
+        .query(&stmt, &[&Value::Text("1".to_string())])
+        .try_collect::<Vec<_>>()
+        .await
+        .err()
+        .unwrap();
+    match err.source() {
+        Some(e) if e.is::<WrongType>() => {}
+        _ => panic!("Unexpected error {:?}", err),
+    };
+}
+
+#[tokio::test]
+async fn row_to_map() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT 1::INT4 AS id, 'foo'::TEXT AS name, NULL::BYTEA AS data, 2::INT8 AS id")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let mut expected = HashMap::new();
+    expected.insert("id".to_string(), Value::Int8(2));
+    expected.insert("name".to_string(), Value::Text("foo".to_string()));
+    expected.insert("data".to_string(), Value::Null);
+    assert_eq!(rows[0].to_map().unwrap(), expected);
+}
+
 #[tokio::test]
 async fn system_time() {
     test_type(
@@ -626,6 +1284,22 @@ async fn system_time() {
     .await;
 }
 
+#[tokio::test]
+async fn system_time_infinity() {
+    test_type(
+        "TIMESTAMP",
+        &[
+            (Timestamp::PosInfinity, "'infinity'"),
+            (Timestamp::NegInfinity, "'-infinity'"),
+            (
+                Timestamp::Value(UNIX_EPOCH + Duration::from_millis(1_010)),
+                "'1970-01-01 00:00:01.01'",
+            ),
+        ],
+    )
+    .await;
+}
+
 #[tokio::test]
 async fn inet() {
     test_type(
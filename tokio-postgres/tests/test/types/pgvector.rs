@@ -0,0 +1,65 @@
+use futures::TryStreamExt;
+use tokio_postgres::types::{SparseVector, Vector};
+
+use crate::connect;
+
+#[tokio::test]
+async fn vector() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE EXTENSION IF NOT EXISTS vector")
+        .await
+        .unwrap();
+
+    let value = Vector(vec![1., -2.5, 3.]);
+    let stmt = client
+        .prepare("SELECT $1::vector, '[1,-2.5,3]'::vector, $1::vector <-> '[1,-2.5,4]'::vector")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[&value])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(rows[0].get::<_, Vector>(0), value);
+    assert_eq!(rows[0].get::<_, Vector>(1), value);
+    assert_eq!(rows[0].get::<_, f64>(2), 1.);
+}
+
+#[tokio::test]
+async fn sparsevec() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE EXTENSION IF NOT EXISTS vector")
+        .await
+        .unwrap();
+
+    let value = SparseVector {
+        dim: 5,
+        indices: vec![0, 3],
+        values: vec![1.5, -2.],
+    };
+    let stmt = client
+        .prepare("SELECT $1::sparsevec, '{1:1.5,4:-2}/5'::sparsevec")
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[&value])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(rows[0].get::<_, SparseVector>(0), value);
+    assert_eq!(rows[0].get::<_, SparseVector>(1), value);
+    assert_eq!(value.to_dense(), Some(Vector(vec![1.5, 0., 0., -2., 0.])));
+
+    let invalid = SparseVector {
+        dim: 3,
+        indices: vec![3],
+        values: vec![1.],
+    };
+    assert_eq!(invalid.to_dense(), None);
+}
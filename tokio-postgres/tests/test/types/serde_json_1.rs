@@ -1,5 +1,7 @@
+use futures::TryStreamExt;
 use serde_json_1::Value;
 
+use crate::connect;
 use crate::types::test_type;
 
 #[tokio::test]
@@ -39,3 +41,49 @@ async fn test_jsonb_params() {
     )
     .await
 }
+
+#[tokio::test]
+async fn row_to_json() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare(
+            "SELECT 1::INT4 AS a, 'foo'::TEXT AS b, NULL::INT8 AS c, 1.50::NUMERIC AS d,
+                    '\\x0102'::BYTEA AS e, '{\"x\": true}'::JSONB AS f, ARRAY[1, NULL]::INT2[] AS g,
+                    'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11'::UUID AS h, 'NaN'::FLOAT8 AS i",
+        )
+        .await
+        .unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let expected = serde_json_1::json!({
+        "a": 1,
+        "b": "foo",
+        "c": null,
+        "d": "1.50",
+        "e": "\\x0102",
+        "f": {"x": true},
+        "g": [1, null],
+        "h": "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+        "i": null,
+    });
+    assert_eq!(rows[0].to_json().unwrap(), expected);
+}
+
+#[tokio::test]
+async fn row_to_json_unsupported() {
+    let mut client = connect("user=postgres").await;
+
+    let stmt = client.prepare("SELECT '(1,2)'::POINT").await.unwrap();
+    let rows = client
+        .query(&stmt, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    rows[0].to_json().unwrap_err();
+}